@@ -0,0 +1,348 @@
+use crate::content_filter::to_image_rect;
+use crate::PlayerEvent;
+use anyhow::Result;
+use egui::{ColorImage, Pos2, Rect, Vec2};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+// the cells the frames are compared in. 64x36 (up from 32x18) so that regions smaller than a tenth
+// of the frame still cover a few cells, which raises the scores of small moving objects.
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 36;
+// only every nth pixel in each direction is sampled when building the luma grid.
+const SAMPLE_STEP: usize = 4;
+
+/// Configures the frame analysis of a [`crate::Player`], see [`crate::Player::enable_frame_analysis`].
+#[derive(Clone, Debug)]
+pub struct FrameAnalysisOptions {
+    /// The mean luma difference between consecutive frames (in `0.0..=1.0`) above which motion is
+    /// reported. The difference is averaged over the cells of a 64x36 grid over the frame.
+    pub motion_threshold: f32,
+    /// Scales how easily motion is reported: the effective threshold is `motion_threshold / sensitivity`.
+    /// Can be overridden per region with [`MotionRegion::sensitivity`].
+    pub sensitivity: f32,
+    /// Only detect motion inside these regions. When empty, the whole frame is used as a single region.
+    pub include_regions: Vec<MotionRegion>,
+    /// Never detect motion inside these rects, in video coordinates (e.g. timestamps burnt into the video).
+    pub exclude_regions: Vec<Rect>,
+    /// How long a region has to be still before [`PlayerEvent::MotionStopped`] is emitted, in milliseconds.
+    pub motion_stop_delay_ms: i64,
+    /// The mean luma difference between consecutive frames (in `0.0..=1.0`) above which a scene change
+    /// is reported instead of motion.
+    pub scene_change_threshold: f32,
+    /// The minimum time between two captures of [`crate::Player::capture_on`], in milliseconds.
+    pub capture_cooldown_ms: i64,
+    /// The minimum time between two [`PlayerEvent::Motion`] while motion goes on, in milliseconds.
+    /// `0` reports every frame with motion.
+    pub motion_event_interval_ms: i64,
+}
+
+impl Default for FrameAnalysisOptions {
+    fn default() -> Self {
+        Self {
+            motion_threshold: 0.02,
+            sensitivity: 1.,
+            include_regions: Vec::new(),
+            exclude_regions: Vec::new(),
+            motion_stop_delay_ms: 1000,
+            scene_change_threshold: 0.3,
+            capture_cooldown_ms: 1000,
+            motion_event_interval_ms: 500,
+        }
+    }
+}
+
+/// A named region of the video in which motion is detected, see [`FrameAnalysisOptions::include_regions`].
+#[derive(Clone, Debug)]
+pub struct MotionRegion {
+    /// The name reported in [`PlayerEvent::MotionStarted`] and [`PlayerEvent::MotionStopped`].
+    pub name: String,
+    /// The region, in video coordinates.
+    pub rect: Rect,
+    /// Overrides [`FrameAnalysisOptions::sensitivity`] for this region.
+    pub sensitivity: Option<f32>,
+}
+
+impl MotionRegion {
+    /// Create a new region with the default sensitivity.
+    pub fn new(name: impl Into<String>, rect: Rect) -> Self {
+        Self {
+            name: name.into(),
+            rect,
+            sensitivity: None,
+        }
+    }
+}
+
+/// Which events trigger a capture in [`crate::Player::capture_on`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureFilter {
+    /// Capture when motion is detected.
+    Motion,
+    /// Capture when a scene change is detected.
+    SceneChange,
+    /// Capture on either motion or a scene change.
+    Any,
+}
+
+impl CaptureFilter {
+    fn matches(&self, event: &PlayerEvent) -> bool {
+        match event {
+            PlayerEvent::Motion { .. } => matches!(self, Self::Motion | Self::Any),
+            PlayerEvent::SceneChange { .. } => matches!(self, Self::SceneChange | Self::Any),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct RegionState {
+    moving: bool,
+    last_motion_ms: i64,
+}
+
+// which grid cells are used for detection, computed once per video size.
+struct CellMasks {
+    size: [usize; 2],
+    // cells that are in an include region (or anywhere, without include regions) and not excluded.
+    active: Vec<usize>,
+    // the active cells of each include region.
+    regions: Vec<Vec<usize>>,
+}
+
+pub(crate) struct FrameAnalyzer {
+    options: FrameAnalysisOptions,
+    // the size of the video the regions are in the coordinates of
+    video_size: Vec2,
+    pub(crate) capture: Option<(CaptureFilter, PathBuf)>,
+    event_sender: mpsc::Sender<PlayerEvent>,
+    previous_grid: Option<Vec<f32>>,
+    last_capture_ms: Option<i64>,
+    last_motion_event_ms: Option<i64>,
+    cell_masks: Option<CellMasks>,
+    region_states: Vec<RegionState>,
+}
+
+impl FrameAnalyzer {
+    pub(crate) fn new(
+        options: FrameAnalysisOptions,
+        video_size: Vec2,
+        event_sender: mpsc::Sender<PlayerEvent>,
+    ) -> Self {
+        let mut slf = Self {
+            options: FrameAnalysisOptions::default(),
+            video_size,
+            capture: None,
+            event_sender,
+            previous_grid: None,
+            last_capture_ms: None,
+            last_motion_event_ms: None,
+            cell_masks: None,
+            region_states: Vec::new(),
+        };
+        slf.set_options(options);
+        slf
+    }
+
+    pub(crate) fn set_options(&mut self, options: FrameAnalysisOptions) {
+        self.region_states = (0..options.include_regions.len().max(1))
+            .map(|_| RegionState::default())
+            .collect();
+        self.cell_masks = None;
+        self.options = options;
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.previous_grid = None;
+        self.last_motion_event_ms = None;
+    }
+
+    fn cell_masks(&mut self, size: [usize; 2]) -> &CellMasks {
+        if !matches!(self.cell_masks.as_ref(), Some(masks) if masks.size == size) {
+            let cell_size = egui::vec2(
+                size[0] as f32 / GRID_WIDTH as f32,
+                size[1] as f32 / GRID_HEIGHT as f32,
+            );
+            let cell_center = |cell: usize| {
+                Pos2::new(
+                    ((cell % GRID_WIDTH) as f32 + 0.5) * cell_size.x,
+                    ((cell / GRID_WIDTH) as f32 + 0.5) * cell_size.y,
+                )
+            };
+            let cell_at = |pos: Pos2| {
+                let x = ((pos.x / cell_size.x) as usize).min(GRID_WIDTH - 1);
+                let y = ((pos.y / cell_size.y) as usize).min(GRID_HEIGHT - 1);
+                y * GRID_WIDTH + x
+            };
+            let in_image = |rect: Rect| to_image_rect(rect, self.video_size, size);
+            let is_excluded = |cell: usize| {
+                self.options
+                    .exclude_regions
+                    .iter()
+                    .any(|r| in_image(*r).contains(cell_center(cell)))
+            };
+            let regions: Vec<Vec<usize>> = self
+                .options
+                .include_regions
+                .iter()
+                .map(|region| {
+                    let rect = in_image(region.rect);
+                    let mut cells: Vec<usize> = (0..GRID_WIDTH * GRID_HEIGHT)
+                        .filter(|c| rect.contains(cell_center(*c)))
+                        .collect();
+                    // regions smaller than a cell still get the cell they're in
+                    if cells.is_empty() {
+                        cells.push(cell_at(rect.center()));
+                    }
+                    cells.retain(|c| !is_excluded(*c));
+                    cells
+                })
+                .collect();
+            let mut active: Vec<usize> = if regions.is_empty() {
+                (0..GRID_WIDTH * GRID_HEIGHT)
+                    .filter(|c| !is_excluded(*c))
+                    .collect()
+            } else {
+                regions.iter().flatten().copied().collect()
+            };
+            active.sort_unstable();
+            active.dedup();
+            self.cell_masks = Some(CellMasks {
+                size,
+                active,
+                regions,
+            });
+        }
+        self.cell_masks.as_ref().unwrap()
+    }
+
+    pub(crate) fn analyze(&mut self, image: &ColorImage, elapsed_ms: i64) {
+        let grid = luma_grid(image);
+        let Some(previous_grid) = self.previous_grid.replace(grid) else {
+            return;
+        };
+        let grid = self.previous_grid.as_ref().unwrap();
+        let differences: Vec<f32> = previous_grid
+            .iter()
+            .zip(grid)
+            .map(|(a, b)| (a - b).abs() / 255.)
+            .collect();
+        let masks = self.cell_masks(image.size);
+        let score = mean_of(&differences, &masks.active);
+        let region_scores: Vec<f32> = if masks.regions.is_empty() {
+            vec![score]
+        } else {
+            masks
+                .regions
+                .iter()
+                .map(|cells| mean_of(&differences, cells))
+                .collect()
+        };
+
+        for (index, region_score) in region_scores.into_iter().enumerate() {
+            let region = self.options.include_regions.get(index);
+            let sensitivity = region
+                .and_then(|r| r.sensitivity)
+                .unwrap_or(self.options.sensitivity);
+            let threshold = self.options.motion_threshold / sensitivity;
+            let state = &mut self.region_states[index];
+            let region_name = region.map(|r| r.name.clone());
+            if region_score > threshold {
+                state.last_motion_ms = elapsed_ms;
+                if !state.moving {
+                    state.moving = true;
+                    let _ = self.event_sender.send(PlayerEvent::MotionStarted {
+                        region: region_name,
+                        elapsed_ms,
+                        score: region_score,
+                    });
+                }
+            } else if state.moving
+                && (elapsed_ms - state.last_motion_ms).abs() >= self.options.motion_stop_delay_ms
+            {
+                state.moving = false;
+                let _ = self.event_sender.send(PlayerEvent::MotionStopped {
+                    region: region_name,
+                    elapsed_ms,
+                });
+            }
+        }
+
+        let event = if score > self.options.scene_change_threshold {
+            PlayerEvent::SceneChange { elapsed_ms, score }
+        } else if score > self.options.motion_threshold / self.options.sensitivity {
+            PlayerEvent::Motion { elapsed_ms, score }
+        } else {
+            return;
+        };
+
+        if let Some((filter, dir)) = self.capture.as_ref() {
+            let cooled_down = match self.last_capture_ms {
+                Some(last) => (elapsed_ms - last).abs() >= self.options.capture_cooldown_ms,
+                None => true,
+            };
+            if filter.matches(&event) && cooled_down {
+                self.last_capture_ms = Some(elapsed_ms);
+                let path = dir.join(format!("{}_{elapsed_ms}.ppm", event.name()));
+                let image = image.clone();
+                let event_sender = self.event_sender.clone();
+                std::thread::spawn(move || {
+                    if write_ppm(&path, &image).is_ok() {
+                        let _ = event_sender.send(PlayerEvent::FrameCaptured { path, elapsed_ms });
+                    }
+                });
+            }
+        }
+
+        if let PlayerEvent::Motion { .. } = event {
+            let interval = self.options.motion_event_interval_ms;
+            if matches!(self.last_motion_event_ms, Some(last) if (elapsed_ms - last).abs() < interval)
+            {
+                return;
+            }
+            self.last_motion_event_ms = Some(elapsed_ms);
+        }
+        let _ = self.event_sender.send(event);
+    }
+}
+
+// the mean luma (in `0.0..=255.0`) of the cells of a coarse grid over `image`, shared with the
+// freeze detection of the health monitoring.
+pub(crate) fn luma_grid(image: &ColorImage) -> Vec<f32> {
+    let [width, height] = image.size;
+    let mut sums = vec![0f32; GRID_WIDTH * GRID_HEIGHT];
+    let mut counts = vec![0u32; GRID_WIDTH * GRID_HEIGHT];
+    for y in (0..height).step_by(SAMPLE_STEP) {
+        let cell_y = y * GRID_HEIGHT / height;
+        for x in (0..width).step_by(SAMPLE_STEP) {
+            let cell = cell_y * GRID_WIDTH + x * GRID_WIDTH / width;
+            let pixel = image.pixels[y * width + x];
+            sums[cell] +=
+                0.299 * pixel.r() as f32 + 0.587 * pixel.g() as f32 + 0.114 * pixel.b() as f32;
+            counts[cell] += 1;
+        }
+    }
+    sums.iter()
+        .zip(counts)
+        .map(|(sum, count)| if count > 0 { sum / count as f32 } else { 0. })
+        .collect()
+}
+
+fn mean_of(values: &[f32], cells: &[usize]) -> f32 {
+    if cells.is_empty() {
+        return 0.;
+    }
+    cells.iter().map(|c| values[*c]).sum::<f32>() / cells.len() as f32
+}
+
+fn write_ppm(path: &Path, image: &ColorImage) -> Result<()> {
+    let [width, height] = image.size;
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    write!(file, "P6\n{width} {height}\n255\n")?;
+    for pixel in image.pixels.iter() {
+        file.write_all(&[pixel.r(), pixel.g(), pixel.b()])?;
+    }
+    file.flush()?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::CuePointId;
+use std::time::{Duration, Instant};
+
+/// Where playback continues after a [`BranchChoice`] was chosen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BranchTarget {
+    /// Continue in the same input, at this stream time in milliseconds.
+    Time(i64),
+    /// Continue in another input, at this stream time in milliseconds. The app opens it, as it
+    /// owns the audio device, see [`crate::Player::choose_branch`].
+    Source {
+        /// The path or url of the input.
+        path: String,
+        /// Where to start in it.
+        time_ms: i64,
+    },
+}
+
+/// One of the choices of a [`BranchPoint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchChoice {
+    /// What the app shows for the choice.
+    pub label: String,
+    /// Where playback continues when it is chosen.
+    pub target: BranchTarget,
+}
+
+impl BranchChoice {
+    /// A choice continuing at `time_ms` of the same input.
+    pub fn new(label: impl Into<String>, time_ms: i64) -> Self {
+        Self {
+            label: label.into(),
+            target: BranchTarget::Time(time_ms),
+        }
+    }
+
+    /// A choice continuing at `time_ms` of the input at `path`.
+    pub fn with_source(label: impl Into<String>, path: impl Into<String>, time_ms: i64) -> Self {
+        Self {
+            label: label.into(),
+            target: BranchTarget::Source {
+                path: path.into(),
+                time_ms,
+            },
+        }
+    }
+}
+
+/// A point in an interactive video where playback pauses until one of its choices is picked, see
+/// [`crate::Player::add_branch_point`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchPoint {
+    /// The stream time playback pauses at, in milliseconds.
+    pub time_ms: i64,
+    /// The choices, sent with [`crate::PlayerEvent::BranchReached`].
+    pub choices: Vec<BranchChoice>,
+    /// Choose [`BranchPoint::default_choice`] after waiting this long for a choice.
+    pub timeout: Option<Duration>,
+    /// The index of the choice taken when the timeout passes.
+    pub default_choice: usize,
+}
+
+impl BranchPoint {
+    /// A branch point at `time_ms`, waiting for a choice without a timeout.
+    pub fn new(time_ms: i64, choices: Vec<BranchChoice>) -> Self {
+        Self {
+            time_ms,
+            choices,
+            timeout: None,
+            default_choice: 0,
+        }
+    }
+
+    /// Choose the choice at `default_choice` after `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration, default_choice: usize) -> Self {
+        self.timeout = Some(timeout);
+        self.default_choice = default_choice;
+        self
+    }
+}
+
+/// A branch point playback paused at, waiting for a choice.
+pub(crate) struct PendingBranch {
+    pub(crate) id: CuePointId,
+    pub(crate) point: BranchPoint,
+    pub(crate) reached: Instant,
+}
+
+impl PendingBranch {
+    /// The default choice, once the timeout passed.
+    pub(crate) fn timed_out_choice(&self) -> Option<usize> {
+        let timeout = self.point.timeout?;
+        (self.reached.elapsed() >= timeout).then_some(self.point.default_choice)
+    }
+}
@@ -0,0 +1,689 @@
+use crate::{
+    AmbientGlow, Clock, Component, ContentFilter, DamageOverlay, Denoise, DisplayColor, FfmpegInfo,
+    FrameAnalysisOptions, HealthOptions, HoverToPlay, HwAccel, InputOptions, Interpolation,
+    LetterboxFill, PerformanceProfile, Player, PlayerOptions, PlayerPool, PreviewProfile,
+    RegionFilter, ReviewCacheSize, SettingsStore, Shuttle, SkipMode, SyncMaster, TimeRange,
+    TimeshiftOptions, Transitions, UnfocusedBackoff, UpscaleFilter, UserData,
+};
+#[cfg(feature = "audio")]
+use crate::{ChannelMap, CpalAudioDevice, ResamplerOptions, SkipSilence, TimeStretch};
+#[cfg(feature = "subtitles")]
+use crate::{SubtitleEncoding, SubtitleOptions};
+use anyhow::Result;
+use egui::{Color32, CornerRadius, Key, TextureOptions};
+use std::any::Any;
+#[cfg(not(feature = "audio"))]
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Builds a [`Player`] with all of its options set before any stream is opened or started.
+/// Create one with [`Player::builder`].
+pub struct PlayerBuilder<'a> {
+    input_path: String,
+    input_options: InputOptions,
+    options: PlayerOptions,
+    #[cfg(feature = "audio")]
+    audio_device: Option<&'a mut CpalAudioDevice>,
+    // the lifetime is that of the audio device, kept so the type is the same without audio
+    #[cfg(not(feature = "audio"))]
+    audio_device: PhantomData<&'a mut ()>,
+    #[cfg(feature = "subtitles")]
+    subtitles: bool,
+    #[cfg(feature = "subtitles")]
+    subtitle_files: Vec<(String, SubtitleEncoding)>,
+    timeshift: Option<TimeshiftOptions>,
+    frame_analysis: Option<FrameAnalysisOptions>,
+    health_monitoring: Option<HealthOptions>,
+    damage_overlay: Option<DamageOverlay>,
+    frame_hashing: bool,
+    playback_speed: f32,
+    #[cfg(feature = "audio")]
+    skip_silence: Option<SkipSilence>,
+    #[cfg(feature = "audio")]
+    time_stretch: TimeStretch,
+    #[cfg(feature = "audio")]
+    channel_map: Option<ChannelMap>,
+    #[cfg(feature = "audio")]
+    resampler_options: ResamplerOptions,
+    clock: Option<Arc<dyn Clock>>,
+    offline: bool,
+    skip_ranges: Vec<TimeRange>,
+    content_filters: Vec<ContentFilter>,
+    region_filters: Vec<RegionFilter>,
+    settings_store: Option<Box<dyn SettingsStore>>,
+    autoplay: bool,
+    preview_profile: Option<PreviewProfile>,
+    proxy_path: Option<String>,
+    denoise: Option<Denoise>,
+    interpolation: Option<Interpolation>,
+    display_color: Option<DisplayColor>,
+    required_components: Vec<Component>,
+    performance_profile: Option<PerformanceProfile>,
+    frame_buffer: Option<usize>,
+    pool: Option<PlayerPool>,
+    user_data: Option<UserData>,
+}
+
+impl<'a> PlayerBuilder<'a> {
+    pub(crate) fn new(input_path: impl Into<String>) -> Self {
+        Self {
+            input_path: input_path.into(),
+            input_options: InputOptions::default(),
+            options: PlayerOptions::default(),
+            #[cfg(feature = "audio")]
+            audio_device: None,
+            #[cfg(not(feature = "audio"))]
+            audio_device: PhantomData,
+            #[cfg(feature = "subtitles")]
+            subtitles: false,
+            #[cfg(feature = "subtitles")]
+            subtitle_files: Vec::new(),
+            timeshift: None,
+            frame_analysis: None,
+            health_monitoring: None,
+            damage_overlay: None,
+            frame_hashing: false,
+            playback_speed: 1.,
+            #[cfg(feature = "audio")]
+            skip_silence: None,
+            #[cfg(feature = "audio")]
+            time_stretch: TimeStretch::default(),
+            #[cfg(feature = "audio")]
+            channel_map: None,
+            #[cfg(feature = "audio")]
+            resampler_options: ResamplerOptions::default(),
+            clock: None,
+            offline: false,
+            skip_ranges: Vec::new(),
+            content_filters: Vec::new(),
+            region_filters: Vec::new(),
+            settings_store: None,
+            autoplay: false,
+            preview_profile: None,
+            proxy_path: None,
+            denoise: None,
+            interpolation: None,
+            display_color: None,
+            required_components: Vec::new(),
+            performance_profile: None,
+            frame_buffer: None,
+            pool: None,
+            user_data: None,
+        }
+    }
+
+    /// Pass [`InputOptions`] to ffmpeg when opening the input.
+    pub fn with_input_options(mut self, input_options: InputOptions) -> Self {
+        self.input_options = input_options;
+        self
+    }
+
+    /// Replace all [`PlayerOptions`] at once.
+    pub fn with_options(mut self, options: PlayerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Output audio to `audio_device`, see [`Player::add_audio`].
+    #[cfg(feature = "audio")]
+    pub fn with_audio(mut self, audio_device: &'a mut CpalAudioDevice) -> Self {
+        self.audio_device = Some(audio_device);
+        self
+    }
+
+    /// Show subtitles, see [`Player::add_subtitles`].
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitles(mut self, subtitles: bool) -> Self {
+        self.subtitles = subtitles;
+        self
+    }
+
+    /// Show the subtitles of an external file, see [`Player::add_subtitle_file`]. Can be called
+    /// for several files, of which the last one is shown.
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitle_file(
+        mut self,
+        path: impl Into<String>,
+        encoding: SubtitleEncoding,
+    ) -> Self {
+        self.subtitle_files.push((path.into(), encoding));
+        self
+    }
+
+    /// See [`PlayerOptions::reload_subtitle_files`].
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitle_file_reloading(mut self, reload: bool) -> Self {
+        self.options.reload_subtitle_files = reload;
+        self
+    }
+
+    /// Start playing as soon as the player is built.
+    pub fn with_autoplay(mut self, autoplay: bool) -> Self {
+        self.autoplay = autoplay;
+        self
+    }
+
+    /// See [`PlayerOptions::looping`].
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.options.looping = looping;
+        self
+    }
+
+    /// See [`PlayerOptions::set_max_audio_volume`].
+    pub fn with_max_audio_volume(mut self, volume: f32) -> Self {
+        self.options.set_max_audio_volume(volume);
+        self
+    }
+
+    /// See [`PlayerOptions::set_audio_volume`].
+    pub fn with_audio_volume(mut self, volume: f32) -> Self {
+        self.options.set_audio_volume(volume);
+        self
+    }
+
+    /// See [`PlayerOptions::audio_gain_db`].
+    pub fn with_audio_gain_db(mut self, gain_db: f32) -> Self {
+        self.options.set_audio_gain_db(gain_db);
+        self
+    }
+
+    /// See [`PlayerOptions::audio_limiter`].
+    pub fn with_audio_limiter(self, audio_limiter: bool) -> Self {
+        self.options.audio_limiter.set(audio_limiter);
+        self
+    }
+
+    /// See [`PlayerOptions::audio_fade_ms`].
+    pub fn with_audio_fade_ms(mut self, fade_ms: u32) -> Self {
+        self.options.set_audio_fade_ms(fade_ms);
+        self
+    }
+
+    /// See [`PlayerOptions::paused_audio_reserve_ms`].
+    pub fn with_paused_audio_reserve_ms(self, reserve_ms: u32) -> Self {
+        self.options.paused_audio_reserve_ms.set(reserve_ms);
+        self
+    }
+
+    /// See [`PlayerOptions::texture_options`].
+    pub fn with_texture_options(mut self, texture_options: TextureOptions) -> Self {
+        self.options.texture_options = texture_options;
+        self
+    }
+
+    /// See [`PlayerOptions::tint`].
+    pub fn with_tint(mut self, tint: Color32) -> Self {
+        self.options.tint = tint;
+        self
+    }
+
+    /// See [`PlayerOptions::opacity`].
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.options.opacity = opacity.clamp(0., 1.);
+        self
+    }
+
+    /// See [`PlayerOptions::corner_radius`].
+    pub fn with_corner_radius(mut self, corner_radius: impl Into<CornerRadius>) -> Self {
+        self.options.corner_radius = corner_radius.into();
+        self
+    }
+
+    /// See [`PlayerOptions::letterbox`].
+    pub fn with_letterbox(mut self, fill: LetterboxFill) -> Self {
+        self.options.letterbox = Some(fill);
+        self
+    }
+
+    /// See [`PlayerOptions::ambient_glow`].
+    pub fn with_ambient_glow(mut self, ambient_glow: AmbientGlow) -> Self {
+        self.options.ambient_glow = Some(ambient_glow);
+        self
+    }
+
+    /// See [`PlayerOptions::upscale`].
+    pub fn with_upscale(mut self, upscale: UpscaleFilter) -> Self {
+        self.options.upscale = Some(upscale);
+        self
+    }
+
+    /// See [`PlayerOptions::sync_master`].
+    pub fn with_sync_master(mut self, sync_master: SyncMaster) -> Self {
+        self.options.sync_master = sync_master;
+        self
+    }
+
+    /// See [`PlayerOptions::compensate_audio_latency`].
+    pub fn with_audio_latency_compensation(mut self, compensate: bool) -> Self {
+        self.options.compensate_audio_latency = compensate;
+        self
+    }
+
+    /// See [`PlayerOptions::audio_delay_ms`].
+    pub fn with_audio_delay_ms(mut self, delay_ms: i64) -> Self {
+        self.options.audio_delay_ms = delay_ms;
+        self
+    }
+
+    /// See [`PlayerOptions::subtitle_delay_ms`].
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitle_delay_ms(mut self, delay_ms: i64) -> Self {
+        self.options.set_subtitle_delay_ms(delay_ms);
+        self
+    }
+
+    /// See [`PlayerOptions::subtitle_seek_preroll_ms`].
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitle_seek_preroll_ms(self, preroll_ms: i64) -> Self {
+        self.options.subtitle_seek_preroll_ms.set(preroll_ms);
+        self
+    }
+
+    /// See [`InputOptions::with_hwaccel`].
+    pub fn with_hwaccel(mut self, hwaccel: HwAccel) -> Self {
+        self.input_options = self.input_options.with_hwaccel(hwaccel);
+        self
+    }
+
+    /// See [`InputOptions::with_decode_threads`].
+    pub fn with_decode_threads(mut self, count: usize) -> Self {
+        self.input_options = self.input_options.with_decode_threads(count);
+        self
+    }
+
+    /// Buffer `frames` decoded frames ahead of the one shown (`50` by default), see
+    /// [`crate::PerformanceSettings::frame_buffer`].
+    pub fn with_frame_buffer(mut self, frames: usize) -> Self {
+        self.frame_buffer = Some(frames);
+        self
+    }
+
+    /// See [`PlayerOptions::subtitles`].
+    #[cfg(feature = "subtitles")]
+    pub fn with_subtitle_options(mut self, subtitle_options: SubtitleOptions) -> Self {
+        self.options.subtitles = subtitle_options;
+        self
+    }
+
+    /// See [`PlayerOptions::review_cache`].
+    pub fn with_review_cache(mut self, review_cache: ReviewCacheSize) -> Self {
+        self.options.review_cache = review_cache;
+        self
+    }
+
+    /// See [`PlayerOptions::show_controls`].
+    pub fn with_controls(mut self, show_controls: bool) -> Self {
+        self.options.show_controls = show_controls;
+        self
+    }
+
+    /// See [`PlayerOptions::cadence_pacing`].
+    pub fn with_cadence_pacing(mut self, cadence_pacing: bool) -> Self {
+        self.options.cadence_pacing = cadence_pacing;
+        self
+    }
+
+    /// See [`PlayerOptions::bypass_filters_key`].
+    pub fn with_bypass_filters_key(mut self, key: Option<Key>) -> Self {
+        self.options.bypass_filters_key = key;
+        self
+    }
+
+    /// See [`Player::set_skip_ranges`].
+    pub fn with_skip_ranges(mut self, skip_ranges: Vec<TimeRange>) -> Self {
+        self.skip_ranges = skip_ranges;
+        self
+    }
+
+    /// See [`PlayerOptions::skip_mode`].
+    pub fn with_skip_mode(mut self, skip_mode: SkipMode) -> Self {
+        self.options.skip_mode = skip_mode;
+        self
+    }
+
+    /// See [`Player::set_content_filters`].
+    pub fn with_content_filters(mut self, content_filters: Vec<ContentFilter>) -> Self {
+        self.content_filters = content_filters;
+        self
+    }
+
+    /// See [`Player::set_region_filters`].
+    pub fn with_region_filters(mut self, region_filters: Vec<RegionFilter>) -> Self {
+        self.region_filters = region_filters;
+        self
+    }
+
+    /// See [`Player::set_settings_store`]. Saved settings are applied once the streams are open.
+    pub fn with_settings_store(mut self, store: impl SettingsStore + 'static) -> Self {
+        self.settings_store = Some(Box::new(store));
+        self
+    }
+
+    /// See [`PlayerOptions::fast_start`].
+    pub fn with_fast_start(mut self, fast_start: bool) -> Self {
+        self.options.fast_start = fast_start;
+        self
+    }
+
+    /// See [`PlayerOptions::hover_to_play`].
+    pub fn with_hover_to_play(mut self, hover_to_play: HoverToPlay) -> Self {
+        self.options.hover_to_play = Some(hover_to_play);
+        self
+    }
+
+    /// See [`PlayerOptions::autoplay_visible`].
+    pub fn with_visibility_autoplay(mut self, visible_fraction: f32) -> Self {
+        self.options.autoplay_visible = Some(visible_fraction);
+        self
+    }
+
+    /// See [`PlayerOptions::transitions`].
+    pub fn with_transitions(mut self, transitions: Transitions) -> Self {
+        self.options.transitions = transitions;
+        self
+    }
+
+    /// Normalize the loudness to `target_lufs`, see [`PlayerOptions::loudness_target_lufs`]. Starts
+    /// [`Player::scan_loudness`] right away.
+    #[cfg(feature = "audio")]
+    pub fn with_loudness_normalization(mut self, target_lufs: f32) -> Self {
+        self.options.loudness_target_lufs = Some(target_lufs);
+        self
+    }
+
+    /// See [`PlayerOptions::unfocused_backoff`].
+    pub fn with_unfocused_backoff(mut self, backoff: UnfocusedBackoff) -> Self {
+        self.options.unfocused_backoff = Some(backoff);
+        self
+    }
+
+    /// See [`PlayerOptions::max_video_lead_ms`].
+    pub fn with_max_video_lead_ms(mut self, max_lead_ms: i64) -> Self {
+        self.options.max_video_lead_ms = Some(max_lead_ms);
+        self
+    }
+
+    /// See [`PlayerOptions::resize_settle_ms`].
+    pub fn with_resize_settle_ms(mut self, settle_ms: u64) -> Self {
+        self.options.resize_settle_ms = settle_ms;
+        self
+    }
+
+    /// See [`PlayerOptions::fit_to_view`].
+    pub fn with_fit_to_view(mut self, fit_to_view: bool) -> Self {
+        self.options.fit_to_view = fit_to_view;
+        self
+    }
+
+    /// See [`Player::add_timeshift`].
+    pub fn with_timeshift(mut self, options: TimeshiftOptions) -> Self {
+        self.timeshift = Some(options);
+        self
+    }
+
+    /// See [`Player::enable_frame_analysis`].
+    pub fn with_frame_analysis(mut self, options: FrameAnalysisOptions) -> Self {
+        self.frame_analysis = Some(options);
+        self
+    }
+
+    /// See [`Player::enable_health_monitoring`].
+    pub fn with_health_monitoring(mut self, options: HealthOptions) -> Self {
+        self.health_monitoring = Some(options);
+        self
+    }
+
+    /// See [`Player::set_playback_speed`].
+    pub fn with_playback_speed(mut self, speed: f32) -> Self {
+        self.playback_speed = speed;
+        self
+    }
+
+    /// See [`Player::set_damage_overlay`].
+    pub fn with_damage_overlay(mut self, overlay: DamageOverlay) -> Self {
+        self.damage_overlay = Some(overlay);
+        self
+    }
+
+    /// See [`Player::set_frame_hashing`].
+    pub fn with_frame_hashing(mut self, frame_hashing: bool) -> Self {
+        self.frame_hashing = frame_hashing;
+        self
+    }
+
+    /// See [`Player::set_skip_silence`].
+    #[cfg(feature = "audio")]
+    pub fn with_skip_silence(mut self, skip_silence: SkipSilence) -> Self {
+        self.skip_silence = Some(skip_silence);
+        self
+    }
+
+    /// See [`Player::set_resampler_options`].
+    #[cfg(feature = "audio")]
+    pub fn with_resampler_options(mut self, resampler_options: ResamplerOptions) -> Self {
+        self.resampler_options = resampler_options;
+        self
+    }
+
+    /// See [`Player::set_denoise`].
+    pub fn with_denoise(mut self, denoise: Denoise) -> Self {
+        self.denoise = Some(denoise);
+        self
+    }
+
+    /// See [`Player::set_interpolation`].
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = Some(interpolation);
+        self
+    }
+
+    /// See [`Player::set_display_color`].
+    pub fn with_display_color(mut self, display_color: DisplayColor) -> Self {
+        self.display_color = Some(display_color);
+        self
+    }
+
+    /// Fail [`PlayerBuilder::build`] with [`MissingComponents`] before opening anything, if the
+    /// linked ffmpeg lacks any of `components`, see [`FfmpegInfo::require`].
+    pub fn with_required_components(mut self, components: Vec<Component>) -> Self {
+        self.required_components = components;
+        self
+    }
+
+    /// See [`Player::set_channel_map`].
+    #[cfg(feature = "audio")]
+    pub fn with_channel_map(mut self, channel_map: ChannelMap) -> Self {
+        self.channel_map = Some(channel_map);
+        self
+    }
+
+    /// See [`Player::set_time_stretch`].
+    #[cfg(feature = "audio")]
+    pub fn with_time_stretch(mut self, time_stretch: TimeStretch) -> Self {
+        self.time_stretch = time_stretch;
+        self
+    }
+
+    /// See [`Player::set_clock`].
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// See [`Player::enable_offline_mode`].
+    pub fn with_offline_mode(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Play a small, muted preview: frames are scaled down and thinned out according to `profile`,
+    /// and the audio, subtitles, controls and review cache are left out.
+    pub fn with_preview_profile(mut self, profile: PreviewProfile) -> Self {
+        self.preview_profile = Some(profile);
+        self
+    }
+
+    /// Apply the settings of `profile`, including its decode threads, see
+    /// [`Player::set_performance_profile`]. The profile is applied first, so the texture options,
+    /// upscaling, review cache, frame buffer and decode threads set on the builder (or in
+    /// [`PlayerBuilder::with_options`] and [`PlayerBuilder::with_input_options`]) win over it,
+    /// whatever order they are set in.
+    pub fn with_performance_profile(mut self, profile: PerformanceProfile) -> Self {
+        self.performance_profile = Some(profile);
+        self
+    }
+
+    /// See [`PlayerOptions::inhibit_sleep`].
+    pub fn with_sleep_inhibition(mut self, inhibit_sleep: bool) -> Self {
+        self.options.inhibit_sleep = inhibit_sleep;
+        self
+    }
+
+    /// See [`PlayerOptions::reclaim_idle_after`].
+    pub fn with_idle_reclaim(mut self, after: std::time::Duration) -> Self {
+        self.options.reclaim_idle_after = Some(after);
+        self
+    }
+
+    /// See [`PlayerOptions::shuttle`].
+    pub fn with_shuttle(mut self, shuttle: Shuttle) -> Self {
+        self.options.shuttle = Some(shuttle);
+        self
+    }
+
+    /// See [`Player::set_user_data`].
+    pub fn with_user_data(mut self, data: impl Any + Send + Sync) -> Self {
+        self.user_data = Some(UserData::new(data));
+        self
+    }
+
+    /// See [`Player::join_pool`].
+    pub fn with_pool(mut self, pool: &PlayerPool) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    /// Start on the video of `proxy_path`, see [`Player::set_proxy`] and [`Player::use_proxy`].
+    pub fn with_proxy(mut self, proxy_path: impl Into<String>) -> Self {
+        self.proxy_path = Some(proxy_path.into());
+        self
+    }
+
+    /// Open the input and create the [`Player`].
+    pub fn build(mut self, ctx: &egui::Context) -> Result<Player> {
+        FfmpegInfo::require(&self.required_components)?;
+        // the profile goes first: the settings set on the builder itself, which differ from the
+        // defaults, replace the ones of the profile
+        let settings = self.performance_profile.map(|profile| {
+            let defaults = PlayerOptions::default();
+            let mut settings = profile.settings();
+            if self.options.texture_options != defaults.texture_options {
+                settings.texture_options = self.options.texture_options;
+            }
+            if self.options.upscale != defaults.upscale {
+                settings.upscale = self.options.upscale;
+            }
+            if self.options.review_cache != defaults.review_cache {
+                settings.review_cache = self.options.review_cache;
+            }
+            if let Some(threads) = self.input_options.decoder_options().threads {
+                settings.decode_threads = threads;
+            }
+            if let Some(frames) = self.frame_buffer {
+                settings.frame_buffer = frames;
+            }
+            settings
+        });
+        if let Some(settings) = settings {
+            self.input_options = self
+                .input_options
+                .with_decode_threads(settings.decode_threads);
+        }
+        let mut player = Player::new_with_input_options(ctx, &self.input_path, self.input_options)?;
+        player.options = self.options;
+        if let (Some(profile), Some(settings)) = (self.performance_profile, settings) {
+            player.set_performance_settings(settings);
+            player.performance_profile = Some(profile);
+        } else if let Some(frames) = self.frame_buffer {
+            player.video_streamer.lock().frame_buffer_size = frames.max(1);
+        }
+        if let Some(profile) = self.preview_profile {
+            player.set_max_frame_size(Some(profile.max_frame_size));
+            player.set_frame_step(profile.frame_step);
+            player.options.show_controls = false;
+            player.options.review_cache = ReviewCacheSize::Frames(0);
+            #[cfg(feature = "audio")]
+            {
+                self.audio_device = None;
+            }
+            #[cfg(feature = "subtitles")]
+            {
+                self.subtitles = false;
+                self.subtitle_files.clear();
+            }
+        }
+        #[cfg(feature = "audio")]
+        {
+            player.set_time_stretch(self.time_stretch);
+            player.set_channel_map(self.channel_map);
+            player.set_resampler_options(self.resampler_options)?;
+            player.set_skip_silence(self.skip_silence);
+        }
+        player.set_denoise(self.denoise);
+        player.set_interpolation(self.interpolation);
+        player.set_display_color(self.display_color);
+        player.set_playback_speed(self.playback_speed);
+        player.set_damage_overlay(self.damage_overlay);
+        player.set_frame_hashing(self.frame_hashing);
+        if let Some(clock) = self.clock {
+            player.video_clock.set_source(clock);
+        }
+        if self.offline {
+            player.enable_offline_mode();
+        }
+        player.set_skip_ranges(self.skip_ranges);
+        player.set_content_filters(self.content_filters);
+        player.set_region_filters(self.region_filters);
+        // the timeshift buffers are created with the streamers, so it has to be set up first
+        if let Some(timeshift) = self.timeshift {
+            player.add_timeshift(timeshift);
+        }
+        #[cfg(feature = "audio")]
+        if let Some(audio_device) = self.audio_device {
+            player.add_audio(audio_device)?;
+        }
+        #[cfg(feature = "subtitles")]
+        if !self.subtitle_files.is_empty() {
+            for (path, encoding) in self.subtitle_files {
+                player.add_subtitle_file(&path, encoding)?;
+            }
+        } else if self.subtitles {
+            player.add_subtitles()?;
+        }
+        if let Some(proxy_path) = self.proxy_path {
+            player.set_proxy(Some(proxy_path))?;
+            player.use_proxy(true)?;
+        }
+        if let Some(frame_analysis) = self.frame_analysis {
+            player.enable_frame_analysis(frame_analysis);
+        }
+        if let Some(health_monitoring) = self.health_monitoring {
+            player.enable_health_monitoring(health_monitoring);
+        }
+        #[cfg(feature = "audio")]
+        if player.options.loudness_target_lufs.is_some() {
+            player.scan_loudness();
+        }
+        if let Some(store) = self.settings_store {
+            player.use_settings_store(store);
+        }
+        if let Some(pool) = self.pool {
+            player.join_pool(&pool);
+        }
+        player.set_shared_user_data(self.user_data);
+        if self.autoplay {
+            player.start();
+        }
+        Ok(player)
+    }
+}
@@ -0,0 +1,381 @@
+use egui::{Align, Align2, Color32};
+use ffmpeg::Rational;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Text burned into every frame of a transcode, like the timecode and file name on review copies,
+/// see [`crate::TranscodeOptions::burn_in`]. Drawn with ffmpeg's `drawtext` filter (see
+/// [`BurnIn::is_available`]) at the size of the output, from the timestamp of each frame.
+///
+/// These placeholders in [`BurnIn::text`] are replaced on each frame:
+/// - `{timecode}`: the `HH:MM:SS:FF` timecode, counting from the timecode of the source if it has
+///   one (like camera files and edits), or from `00:00:00:00` at the first frame. Drop frame
+///   timecodes are counted as non drop frame.
+/// - `{time}`: the time of the frame in the source, as `H:MM:SS.mmm`.
+/// - `{frame}`: the number of the frame, counting from `0`.
+/// - `{filename}`: the file name of the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BurnIn {
+    /// The text, with the placeholders above.
+    pub text: String,
+    /// Where the text goes in the frame, inset by a margin.
+    pub align: Align2,
+    /// The height of the text, as a fraction of the height of the output.
+    pub font_size: f32,
+    /// The color of the text.
+    pub color: Color32,
+    /// The color of a box behind the text, keeping it readable on any footage.
+    pub background: Option<Color32>,
+    /// The font, or `None` for the default one of fontconfig.
+    pub font_file: Option<PathBuf>,
+}
+
+impl BurnIn {
+    /// Burn `text` (see the placeholders above) into the bottom center.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            align: Align2::CENTER_BOTTOM,
+            font_size: 1. / 20.,
+            color: Color32::WHITE,
+            background: Some(Color32::from_black_alpha(160)),
+            font_file: None,
+        }
+    }
+
+    /// The timecode in the bottom center, the usual burn-in of dailies.
+    pub fn timecode() -> Self {
+        Self::new("{timecode}")
+    }
+
+    /// The file name in the top left.
+    pub fn filename() -> Self {
+        Self::new("{filename}").with_align(Align2::LEFT_TOP)
+    }
+
+    /// Set [`BurnIn::align`].
+    pub fn with_align(mut self, align: Align2) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Set [`BurnIn::font_size`].
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Set [`BurnIn::color`].
+    pub fn with_color(mut self, color: Color32) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set [`BurnIn::background`].
+    pub fn with_background(mut self, background: Option<Color32>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Set [`BurnIn::font_file`].
+    pub fn with_font_file(mut self, font_file: impl Into<PathBuf>) -> Self {
+        self.font_file = Some(font_file.into());
+        self
+    }
+
+    /// Whether the linked ffmpeg provides the `drawtext` filter. Transcodes with burn-ins fail
+    /// without it.
+    pub fn is_available() -> bool {
+        ffmpeg::filter::find("drawtext").is_some()
+    }
+
+    /// The `drawtext` filter drawing this into frames `height` lines high.
+    pub(crate) fn filter_spec(&self, height: u32, source: &BurnInSource) -> String {
+        let font_size = (height as f32 * self.font_size).round().max(1.);
+        let margin = (height as f32 / 30.).round();
+        let x = match self.align.x() {
+            Align::Min => format!("{margin}"),
+            Align::Center => "(w-tw)/2".to_string(),
+            Align::Max => format!("w-tw-{margin}"),
+        };
+        let y = match self.align.y() {
+            Align::Min => format!("{margin}"),
+            Align::Center => "(h-th)/2".to_string(),
+            Align::Max => format!("h-th-{margin}"),
+        };
+        let mut options = vec![
+            ("text".to_string(), self.expanded_text(source)),
+            ("fontsize".to_string(), font_size.to_string()),
+            ("fontcolor".to_string(), hex_color(self.color)),
+            ("x".to_string(), x),
+            ("y".to_string(), y),
+        ];
+        if let Some(background) = self.background {
+            options.push(("box".to_string(), "1".to_string()));
+            options.push(("boxcolor".to_string(), hex_color(background)));
+            let border = (font_size / 4.).round();
+            options.push(("boxborderw".to_string(), border.to_string()));
+        }
+        if let Some(font_file) = &self.font_file {
+            let font_file = font_file.to_string_lossy().into_owned();
+            options.push(("fontfile".to_string(), font_file));
+        }
+        let options = options
+            .iter()
+            .map(|(key, value)| format!("{key}={}", escape_value(value)))
+            .collect::<Vec<_>>();
+        format!("drawtext={}", options.join(":"))
+    }
+
+    // the text with the placeholders turned into the expansions of `drawtext`
+    fn expanded_text(&self, source: &BurnInSource) -> String {
+        let mut expanded = String::new();
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find('{') {
+            expanded += &escape_text(&rest[..start]);
+            rest = &rest[start..];
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+            match &rest[1..end] {
+                "timecode" => expanded += &source.timecode_expansion(),
+                "time" => expanded += "%{pts:hms}",
+                "frame" => expanded += "%{n}",
+                "filename" => expanded += &escape_text(&source.filename),
+                _ => expanded += &escape_text(&rest[..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        expanded + &escape_text(rest)
+    }
+}
+
+/// What the placeholders of a [`BurnIn`] are filled in from.
+pub(crate) struct BurnInSource {
+    pub(crate) filename: String,
+    // the frame rate counted in, rounded for the frames of the timecode
+    frame_rate: f64,
+    timecode_rate: i64,
+    // the number of the frame at `start_s`
+    start_frame: i64,
+    start_s: f64,
+}
+
+impl BurnInSource {
+    /// `timecode` is the one of the source (`HH:MM:SS:FF`) for its first frame at
+    /// `start_time` in `time_base`.
+    pub(crate) fn new(
+        filename: String,
+        frame_rate: Rational,
+        timecode: Option<&str>,
+        start_time: i64,
+        time_base: Rational,
+    ) -> Self {
+        let frame_rate = match f64::from(frame_rate) {
+            rate if rate.is_finite() && rate > 0. => rate,
+            _ => 25.,
+        };
+        let timecode_rate = (frame_rate.round() as i64).max(1);
+        let start_s = if start_time == ffmpeg::ffi::AV_NOPTS_VALUE {
+            0.
+        } else {
+            start_time as f64 * f64::from(time_base)
+        };
+        let start_frame = timecode
+            .and_then(|timecode| parse_timecode(timecode, timecode_rate))
+            .unwrap_or(0);
+        Self {
+            filename,
+            frame_rate,
+            timecode_rate,
+            start_frame,
+            start_s,
+        }
+    }
+
+    // `HH:MM:SS:FF` from the time of the frame
+    fn timecode_expansion(&self) -> String {
+        let frames = format!(
+            "({}+floor((t-({}))*{}+0.5))",
+            self.start_frame, self.start_s, self.frame_rate
+        );
+        let rate = self.timecode_rate;
+        let fields = [
+            format!("mod(floor({frames}/{}),24)", rate * 3600),
+            format!("mod(floor({frames}/{}),60)", rate * 60),
+            format!("mod(floor({frames}/{rate}),60)"),
+            format!("mod({frames},{rate})"),
+        ];
+        let mut expansion = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                expansion.push(':');
+            }
+            let _ = write!(expansion, "%{{eif:{field}:d:2}}");
+        }
+        expansion
+    }
+}
+
+// the number of frames into the day of `HH:MM:SS:FF` (or `;` before the frames of drop frame)
+fn parse_timecode(timecode: &str, rate: i64) -> Option<i64> {
+    let fields = timecode
+        .split([':', ';', '.'])
+        .map(|field| field.trim().parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let [hours, minutes, seconds, frames] = fields[..] else {
+        return None;
+    };
+    Some(((hours * 60 + minutes) * 60 + seconds) * rate + frames)
+}
+
+// `0xRRGGBBAA`
+fn hex_color(color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    format!("0x{r:02x}{g:02x}{b:02x}{a:02x}")
+}
+
+// literal text for the expansion of `drawtext`
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '%') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// escape `value` for the options of the filter, then for the filter graph it is parsed from
+fn escape_value(value: &str) -> String {
+    let mut option = String::new();
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+    let mut graph = String::new();
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            graph.push('\\');
+        }
+        graph.push(c);
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(filename: &str, timecode: Option<&str>, start_time: i64) -> BurnInSource {
+        BurnInSource::new(
+            filename.to_string(),
+            Rational(24, 1),
+            timecode,
+            start_time,
+            Rational(1, 1000),
+        )
+    }
+
+    #[test]
+    fn filter_specs() {
+        let source = source("clip.mov", None, 0);
+        let cases = [
+            (
+                BurnIn::new("{frame}").with_background(None),
+                "drawtext=text=%{n}:fontsize=54:fontcolor=0xffffffff:x=(w-tw)/2:y=h-th-36",
+            ),
+            (
+                BurnIn::filename().with_color(Color32::RED),
+                "drawtext=text=clip.mov:fontsize=54:fontcolor=0xff0000ff:x=36:y=36:box=1\
+                 :boxcolor=0x000000a0:boxborderw=14",
+            ),
+        ];
+        for (burn_in, spec) in cases {
+            assert_eq!(burn_in.filter_spec(1080, &source), spec);
+        }
+        // escaped for `drawtext`, for its options and for the graph, in that order
+        let source = self::source("it's 100%: final.mov", None, 0);
+        let burn_in = BurnIn::filename().with_background(None);
+        assert_eq!(
+            burn_in.filter_spec(720, &source),
+            r"drawtext=text=it\\\'s 100\\\\%\\: final.mov:fontsize=36:fontcolor=0xffffffff:x=24:y=24"
+        );
+    }
+
+    #[test]
+    fn placeholders_are_expanded() {
+        let source = source("it's 100%: final.mov", None, 0);
+        let cases = [
+            ("{time}", "%{pts:hms}"),
+            ("{filename}", r"it's 100\%: final.mov"),
+            ("50% at {frame}", r"50\% at %{n}"),
+            ("{unknown} {", r"{unknown} {"),
+        ];
+        for (text, expanded) in cases {
+            assert_eq!(BurnIn::new(text).expanded_text(&source), expanded, "{text}");
+        }
+    }
+
+    #[test]
+    fn timecodes_count_from_the_source() {
+        let field = |frames: &str, divisor: &str, modulo: i64| {
+            format!("%{{eif:mod(floor({frames}/{divisor}),{modulo}):d:2}}")
+        };
+        // one hour in, with the first frame a second into the stream
+        let source = source("clip.mov", Some("01:00:00:00"), 1_000);
+        let frames = "(86400+floor((t-(1))*24+0.5))";
+        let expected = [
+            field(frames, "86400", 24),
+            field(frames, "1440", 60),
+            field(frames, "24", 60),
+            format!("%{{eif:mod({frames},24):d:2}}"),
+        ]
+        .join(":");
+        assert_eq!(BurnIn::timecode().expanded_text(&source), expected);
+        // without a timecode, counting from the start of the stream
+        let source = BurnInSource::new(
+            String::new(),
+            Rational(24, 1),
+            None,
+            ffmpeg::ffi::AV_NOPTS_VALUE,
+            Rational(1, 1000),
+        );
+        assert!(source
+            .timecode_expansion()
+            .starts_with("%{eif:mod(floor((0+floor((t-(0))*24"));
+    }
+
+    #[test]
+    fn timecodes_are_parsed() {
+        let cases = [
+            ("01:00:00:00", 24, Some(86_400)),
+            ("00:01:02:03", 25, Some(1_553)),
+            ("00:00:01;05", 30, Some(35)),
+            ("00:00:01", 30, None),
+            ("00:xx:01:00", 30, None),
+        ];
+        for (timecode, rate, frames) in cases {
+            assert_eq!(parse_timecode(timecode, rate), frames, "{timecode}");
+        }
+    }
+
+    #[test]
+    fn values_are_escaped_for_the_option_and_the_graph() {
+        let cases = [
+            ("plain", "plain"),
+            ("a:b", r"a\\:b"),
+            ("it's", r"it\\\'s"),
+            ("100%", "100%"),
+            (r"c:\fonts\a.ttf", r"c\\:\\\\fonts\\\\a.ttf"),
+            ("mod(a,b)", r"mod(a\,b)"),
+            ("[x];", r"\[x\]\;"),
+        ];
+        for (value, escaped) in cases {
+            assert_eq!(escape_value(value), escaped, "{value}");
+        }
+    }
+}
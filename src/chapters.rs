@@ -0,0 +1,216 @@
+use crate::input::{open_input, InputOptions};
+use crate::{millisec_to_timestamp, timestamp_to_millisec, video_frame_to_image};
+use anyhow::{Context as _, Result};
+#[cfg(feature = "chapters")]
+use chardetng::EncodingDetector;
+use egui::ColorImage;
+use ffmpeg::ffi::AV_DISPOSITION_TIMED_THUMBNAILS;
+use ffmpeg::format::context::input::Input;
+use ffmpeg::format::Pixel;
+use ffmpeg::media::Type;
+use ffmpeg::rescale;
+use ffmpeg::software::scaling::{context::Context, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+#[cfg(feature = "chapters")]
+use std::path::Path;
+use std::sync::Arc;
+
+// cue sheet times count frames of audio CDs, 75 per second.
+#[cfg(feature = "chapters")]
+const CUE_FRAMES_PER_SECOND: i64 = 75;
+
+/// A chapter of the input, see [`crate::Player::chapters`].
+#[derive(Clone, Debug)]
+pub struct Chapter {
+    /// The title of the chapter, if it has one.
+    pub title: Option<String>,
+    /// The performer or author of the chapter, if known (as in cue sheets).
+    pub performer: Option<String>,
+    /// Where the chapter starts, in milliseconds.
+    pub start_ms: i64,
+    /// Where the chapter ends, in milliseconds.
+    pub end_ms: i64,
+    /// The picture shown during the chapter, as in the chapter images of M4B audiobooks.
+    pub image: Option<Arc<ColorImage>>,
+}
+
+/// The chapters the demuxer found in `input` (as in matroska, mp4 and M4B files), with their
+/// timed thumbnails if it has any.
+pub(crate) fn read_chapters(
+    input: &Input,
+    input_path: &str,
+    options: &InputOptions,
+) -> Vec<Chapter> {
+    let mut chapters = input
+        .chapters()
+        .map(|chapter| {
+            let metadata = chapter.metadata();
+            Chapter {
+                title: metadata.get("title").map(str::to_string),
+                performer: metadata.get("artist").map(str::to_string),
+                start_ms: timestamp_to_millisec(chapter.start(), chapter.time_base()),
+                end_ms: timestamp_to_millisec(chapter.end(), chapter.time_base()),
+                image: None,
+            }
+        })
+        .collect::<Vec<_>>();
+    let thumbnails = input.streams().find(|stream| {
+        stream.parameters().medium() == Type::Video
+            && stream.disposition().bits() & AV_DISPOSITION_TIMED_THUMBNAILS != 0
+    });
+    if let Some(thumbnails) = thumbnails {
+        // missing images only lose the picture, not the chapter
+        let _ = load_chapter_images(input_path, options, thumbnails.index(), &mut chapters);
+    }
+    chapters
+}
+
+// decode the first thumbnail at or after the start of each chapter.
+fn load_chapter_images(
+    input_path: &str,
+    options: &InputOptions,
+    stream_index: usize,
+    chapters: &mut [Chapter],
+) -> Result<()> {
+    let mut input = open_input(input_path, options)?;
+    let stream = input.stream(stream_index).context("no thumbnail stream")?;
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+        .decoder()
+        .video()?;
+    for chapter in chapters.iter_mut() {
+        let target_ts = millisec_to_timestamp(chapter.start_ms, rescale::TIME_BASE);
+        input.seek(target_ts, ..target_ts)?;
+        decoder.flush();
+        let packet = input
+            .packets()
+            .filter_map(Result::ok)
+            .find(|(stream, _)| stream.index() == stream_index)
+            .map(|(_, packet)| packet);
+        let Some(packet) = packet else {
+            continue;
+        };
+        decoder.send_packet(&packet)?;
+        let mut decoded = Video::empty();
+        if decoder.receive_frame(&mut decoded).is_err() {
+            continue;
+        }
+        let mut rgb_frame = Video::empty();
+        let mut scaler = Context::get(
+            decoded.format(),
+            decoded.width(),
+            decoded.height(),
+            Pixel::RGBA,
+            decoded.width(),
+            decoded.height(),
+            Flags::BILINEAR,
+        )?;
+        scaler.run(&decoded, &mut rgb_frame)?;
+        chapter.image = Some(Arc::new(video_frame_to_image(rgb_frame)));
+    }
+    Ok(())
+}
+
+/// Parse the tracks of a cue sheet (as ripped from audio CDs) as chapters. Only the first `FILE`
+/// of the sheet is used; the last chapter ends at `duration_ms`.
+#[cfg(feature = "chapters")]
+pub(crate) fn read_cue_sheet(path: &Path, duration_ms: i64) -> Result<Vec<Chapter>> {
+    let bytes = std::fs::read(path)?;
+    // cue sheets predate unicode, so they are often in a legacy codepage
+    let mut detector = EncodingDetector::new();
+    detector.feed(&bytes, true);
+    let (text, _, _) = detector.guess(None, true).decode(&bytes);
+    parse_cue_sheet(&text, duration_ms)
+}
+
+#[cfg(feature = "chapters")]
+fn parse_cue_sheet(text: &str, duration_ms: i64) -> Result<Vec<Chapter>> {
+    let mut chapters: Vec<Chapter> = vec![];
+    let mut files = 0;
+    let mut album_performer = None;
+    for line in text.lines() {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => files += 1,
+            _ if files > 1 => break,
+            "TRACK" => chapters.push(Chapter {
+                title: None,
+                performer: album_performer.clone(),
+                start_ms: 0,
+                end_ms: duration_ms,
+                image: None,
+            }),
+            "TITLE" => {
+                if let Some(chapter) = chapters.last_mut() {
+                    chapter.title = Some(unquote(argument));
+                }
+            }
+            "PERFORMER" => match chapters.last_mut() {
+                Some(chapter) => chapter.performer = Some(unquote(argument)),
+                None => album_performer = Some(unquote(argument)),
+            },
+            "INDEX" => {
+                // index 01 is where the track starts, 00 is the pregap before it
+                let (number, time) = argument.split_once(' ').unwrap_or((argument, ""));
+                if number == "01" {
+                    let chapter = chapters.last_mut().context("INDEX outside of a TRACK")?;
+                    chapter.start_ms = parse_cue_time(time.trim())
+                        .with_context(|| format!("invalid cue time: {time}"))?;
+                }
+            }
+            _ => (),
+        }
+    }
+    for i in 1..chapters.len() {
+        chapters[i - 1].end_ms = chapters[i].start_ms;
+    }
+    Ok(chapters)
+}
+
+#[cfg(feature = "chapters")]
+fn unquote(argument: &str) -> String {
+    argument.trim_matches('"').to_string()
+}
+
+// `mm:ss:ff`, where minutes can exceed 59.
+#[cfg(feature = "chapters")]
+fn parse_cue_time(time: &str) -> Option<i64> {
+    let mut parts = time.split(':').map(|part| part.parse::<i64>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    Some((minutes * 60 + seconds) * 1000 + frames * 1000 / CUE_FRAMES_PER_SECOND)
+}
+
+#[cfg(all(test, feature = "chapters"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cue_sheet_tracks_are_chapters() {
+        let sheet = r#"PERFORMER "Album Artist"
+FILE "side a.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Intro"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Song"
+    PERFORMER "Guest"
+    INDEX 00 02:58:00
+    INDEX 01 03:00:37
+FILE "side b.flac" WAVE
+  TRACK 03 AUDIO
+    INDEX 01 00:00:00
+"#;
+        let chapters = parse_cue_sheet(sheet, 300_000).unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title.as_deref(), Some("Intro"));
+        assert_eq!(chapters[0].performer.as_deref(), Some("Album Artist"));
+        assert_eq!((chapters[0].start_ms, chapters[0].end_ms), (0, 180_493));
+        assert_eq!(chapters[1].performer.as_deref(), Some("Guest"));
+        assert_eq!(
+            (chapters[1].start_ms, chapters[1].end_ms),
+            (180_493, 300_000)
+        );
+        assert!(parse_cue_sheet("INDEX 01 00:00:00", 0).is_err());
+    }
+}
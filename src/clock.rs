@@ -0,0 +1,132 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A source of time for a [`crate::Player`], see [`crate::Player::set_clock`].
+pub trait Clock: Send + Sync {
+    /// The time elapsed since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+}
+
+/// The default [`Clock`], following wall time.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    /// Create a clock starting at zero now.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// A [`Clock`] that only moves when told to, for driving a player deterministically.
+/// Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock at zero.
+    pub fn new() -> Self {
+        Self {
+            nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Move the clock forward by `duration`. Clones advancing it at once all add up.
+    pub fn advance(&self, duration: Duration) {
+        self.nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Set the time of the clock.
+    pub fn set(&self, time: Duration) {
+        self.nanos.store(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// A stream time clock that advances with its [`Clock`] while running.
+pub(crate) struct PlaybackClock {
+    source: Arc<dyn Clock>,
+    base_ms: i64,
+    resumed_at: Option<Duration>,
+    rate: f32,
+}
+
+impl PlaybackClock {
+    pub(crate) fn new(source: Arc<dyn Clock>) -> Self {
+        Self {
+            source,
+            base_ms: 0,
+            resumed_at: None,
+            rate: 1.,
+        }
+    }
+    /// Switch to another source, keeping the current time.
+    pub(crate) fn set_source(&mut self, source: Arc<dyn Clock>) {
+        let time_ms = self.time_ms();
+        self.source = source;
+        self.set(time_ms);
+    }
+    pub(crate) fn time_ms(&self) -> i64 {
+        self.base_ms
+            + self
+                .resumed_at
+                .map(|resumed_at| {
+                    let elapsed = self.source.now().saturating_sub(resumed_at);
+                    (elapsed.as_millis() as f64 * self.rate as f64) as i64
+                })
+                .unwrap_or(0)
+    }
+    pub(crate) fn set_rate(&mut self, rate: f32) {
+        if rate != self.rate {
+            self.set(self.time_ms());
+            self.rate = rate;
+        }
+    }
+    pub(crate) fn set_running(&mut self, running: bool) {
+        match (running, self.resumed_at.is_some()) {
+            (true, false) => self.resumed_at = Some(self.source.now()),
+            (false, true) => {
+                self.base_ms = self.time_ms();
+                self.resumed_at = None;
+            }
+            _ => (),
+        }
+    }
+    pub(crate) fn set(&mut self, time_ms: i64) {
+        self.base_ms = time_ms;
+        if self.resumed_at.is_some() {
+            self.resumed_at = Some(self.source.now());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::input::{open_input, InputOptions};
+use crate::{millisec_to_timestamp, timestamp_to_millisec};
+use anyhow::Result;
+use ffmpeg::rescale;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// frames this far into a cut can't be ones reordered past the out point of the previous file.
+const REORDER_MS: i64 = 500;
+
+/// A part of a file in an edit decision list, see [`crate::Player::new_edit`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cut {
+    /// The path or url of the file.
+    pub path: String,
+    /// Where the cut starts, in milliseconds of file time.
+    pub in_ms: i64,
+    /// Where the cut ends (exclusive), in milliseconds of file time.
+    pub out_ms: i64,
+}
+
+impl Cut {
+    /// The part of `path` from `in_ms` up to `out_ms`.
+    pub fn new(path: impl Into<String>, in_ms: i64, out_ms: i64) -> Self {
+        Self {
+            path: path.into(),
+            in_ms,
+            out_ms,
+        }
+    }
+}
+
+/// A file played as part of a concatenated input.
+pub(crate) struct Segment {
+    pub(crate) path: String,
+    /// Where playback of the file starts, in milliseconds of file time.
+    pub(crate) in_ms: Option<i64>,
+    /// Where playback of the file ends, in milliseconds of file time.
+    pub(crate) out_ms: Option<i64>,
+}
+
+impl Segment {
+    pub(crate) fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            in_ms: None,
+            out_ms: None,
+        }
+    }
+}
+
+impl From<&Cut> for Segment {
+    fn from(cut: &Cut) -> Self {
+        Self {
+            path: cut.path.clone(),
+            in_ms: Some(cut.in_ms),
+            out_ms: Some(cut.out_ms),
+        }
+    }
+}
+
+/// An input joining several files, made by [`concat_input`].
+pub(crate) struct ConcatInput {
+    pub(crate) input_path: String,
+    pub(crate) input_options: InputOptions,
+    /// Where every segment starts on the joined timeline, followed by where the last one ends.
+    pub(crate) bounds_ms: Vec<i64>,
+}
+
+/// The input for playing `segments` back to back as one timeline, using ffmpeg's
+/// concat demuxer. The script is passed inline as a `data:` url, so opening it again for the audio
+/// and subtitle streams needs no temporary file. Every segment gets its duration written out, which
+/// lets the demuxer compute the total duration and seek across segments.
+pub(crate) fn concat_input(segments: &[Segment]) -> Result<ConcatInput> {
+    if segments.is_empty() {
+        anyhow::bail!("no files to concatenate");
+    }
+    let mut script = String::from("ffconcat version 1.0\n");
+    let mut bounds_ms = vec![0];
+    for segment in segments {
+        let path = absolute_path(&segment.path)?;
+        let duration_ms = match segment.out_ms {
+            Some(out_ms) => out_ms - segment.in_ms.unwrap_or(0),
+            None => {
+                let input = open_input(&path, &InputOptions::default())?;
+                let file_ms = timestamp_to_millisec(input.duration(), rescale::TIME_BASE);
+                file_ms - segment.in_ms.unwrap_or(0)
+            }
+        };
+        if duration_ms <= 0 {
+            anyhow::bail!("{} has nothing to play", segment.path);
+        }
+        bounds_ms.push(bounds_ms[bounds_ms.len() - 1] + duration_ms);
+        writeln!(script, "file '{}'", path.replace('\'', r"'\''"))?;
+        writeln!(script, "duration {}", seconds(duration_ms))?;
+        if let Some(in_ms) = segment.in_ms {
+            writeln!(script, "inpoint {}", seconds(in_ms))?;
+        }
+        if let Some(out_ms) = segment.out_ms {
+            writeln!(script, "outpoint {}", seconds(out_ms))?;
+        }
+    }
+    let input_path = format!("data:text/plain;base64,{}", base64(script.as_bytes()));
+    let input_options = InputOptions::new()
+        .with_format("concat")
+        // allow absolute paths and urls in the script
+        .set("safe", 0)
+        .set("protocol_whitelist", "data,file,crypto,http,https,tcp,tls");
+    Ok(ConcatInput {
+        input_path,
+        input_options,
+        bounds_ms,
+    })
+}
+
+/// Drops the frames the concat demuxer lets through around cut points: the ones decoded from the
+/// keyframe before an in point, and the ones reordered past an out point. The demuxer only cuts
+/// whole packets, so these end up outside of their cut on the joined timeline.
+pub(crate) struct CutFilter {
+    bounds_ms: Vec<i64>,
+    // the cut being decoded, and whether decoding is known to have reached its file (instead of
+    // running past the out point of the previous one).
+    cut: Option<(usize, bool)>,
+    last_ms: i64,
+}
+
+impl CutFilter {
+    pub(crate) fn new(bounds_ms: Vec<i64>) -> Self {
+        Self {
+            bounds_ms,
+            cut: None,
+            last_ms: 0,
+        }
+    }
+
+    /// Forget the decoding position, after seeking.
+    pub(crate) fn reset(&mut self) {
+        self.cut = None;
+    }
+
+    /// Whether the frame at `time_ms` belongs to the edit. Frames of `decoded` that turn out to be
+    /// from past the out point of the previous file are removed.
+    pub(crate) fn accept<T>(
+        &mut self,
+        time_ms: i64,
+        decoded: &mut VecDeque<(T, i64, i64)>,
+    ) -> bool {
+        let last_cut = self.bounds_ms.len() - 2;
+        let (cut, confirmed) = match self.cut {
+            None => (self.cut_at(time_ms), true),
+            // decoding went back in time, so the next file started
+            Some((cut, confirmed)) if time_ms <= self.last_ms => {
+                let cut = if confirmed {
+                    (cut + 1).min(last_cut)
+                } else {
+                    cut
+                };
+                let start_ms = self.bounds_ms[cut];
+                decoded.retain(|frame| frame.1 < start_ms);
+                (cut, true)
+            }
+            // either the next file starting right at its in point, or this one running past its
+            // out point, in which case the next file goes back in time soon
+            Some((cut, true)) if cut < last_cut && time_ms >= self.bounds_ms[cut + 1] => {
+                (cut + 1, false)
+            }
+            Some((cut, confirmed)) => (
+                cut,
+                confirmed || time_ms >= self.bounds_ms[cut] + REORDER_MS,
+            ),
+        };
+        self.cut = Some((cut, confirmed));
+        self.last_ms = time_ms;
+        (self.bounds_ms[cut]..self.bounds_ms[cut + 1]).contains(&time_ms)
+    }
+
+    fn cut_at(&self, time_ms: i64) -> usize {
+        let starts = &self.bounds_ms[1..self.bounds_ms.len() - 1];
+        starts.partition_point(|&start_ms| start_ms <= time_ms)
+    }
+}
+
+// the script has no location of its own, so relative paths have to be resolved here.
+fn absolute_path(path: &str) -> Result<String> {
+    if path.contains("://") {
+        return Ok(path.to_string());
+    }
+    Ok(std::path::absolute(Path::new(path))?
+        .to_string_lossy()
+        .to_string())
+}
+
+fn seconds(ms: i64) -> String {
+    // in microseconds, the unit the demuxer parses durations to.
+    let us = millisec_to_timestamp(ms, rescale::TIME_BASE);
+    format!("{}.{:06}", us / 1_000_000, us % 1_000_000)
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_outside_of_their_cut_are_dropped() {
+        let mut filter = CutFilter::new(vec![0, 1000, 3000]);
+        let mut decoded = VecDeque::new();
+        // the first file runs past its out point, then the second one starts from the keyframe
+        // before its in point
+        for time_ms in [0, 500, 900, 1000, 1100, 800, 950, 1000, 1500] {
+            if filter.accept(time_ms, &mut decoded) {
+                decoded.push_back(((), time_ms, 0));
+            }
+        }
+        let times_ms = decoded.iter().map(|frame| frame.1).collect::<Vec<_>>();
+        assert_eq!(times_ms, [0, 500, 900, 1000, 1500]);
+    }
+
+    #[test]
+    fn base64_is_padded() {
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+    }
+}
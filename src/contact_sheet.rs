@@ -0,0 +1,76 @@
+use crate::format_duration;
+use crate::frame_server::{Accuracy, FrameServer};
+use crate::subtitle::{paint_shape, MAX_TEXTURE_SIDE};
+use anyhow::{Context as _, Result};
+use chrono::Duration;
+use egui::epaint::text::Fonts;
+use egui::{Align2, Color32, ColorImage, FontDefinitions, FontId, Pos2, Shape, Vec2};
+
+// the space around and between the thumbnails, in pixels
+const GAP: usize = 4;
+const LABEL_FONT_SIZE: f32 = 12.;
+
+/// A grid of `columns` by `rows` thumbnails of the video of `input_path`, evenly spread over its
+/// duration, each labeled with its time. The thumbnails fit in `thumbnail_size` pixels on their
+/// longer side and are taken at keyframes, so a sheet only seeks once per thumbnail. Enabled with
+/// the `export` and `experimental` features.
+pub fn generate_contact_sheet(
+    input_path: &str,
+    columns: usize,
+    rows: usize,
+    thumbnail_size: u32,
+) -> Result<ColorImage> {
+    let mut frame_server = FrameServer::new(input_path)?;
+    frame_server.set_max_frame_size(Some(thumbnail_size));
+    let count = columns * rows;
+    let thumbnails = (0..count)
+        .map(|i| {
+            let time_ms = frame_server.duration_ms * (2 * i as i64 + 1) / (2 * count as i64);
+            frame_server.decoded_frame_at(time_ms, Accuracy::Keyframe)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let [cell_width, cell_height] = thumbnails
+        .first()
+        .map(|(_, image)| image.size)
+        .with_context(|| format!("no thumbnails in a {columns}x{rows} contact sheet"))?;
+
+    let mut sheet = ColorImage::new(
+        [
+            columns * cell_width + (columns + 1) * GAP,
+            rows * cell_height + (rows + 1) * GAP,
+        ],
+        Color32::BLACK,
+    );
+    let fonts = Fonts::new(1., MAX_TEXTURE_SIDE, FontDefinitions::default());
+    let mut labels = Vec::new();
+    for (i, (time_ms, image)) in thumbnails.iter().enumerate() {
+        let left = GAP + (i % columns) * (cell_width + GAP);
+        let top = GAP + (i / columns) * (cell_height + GAP);
+        // frames of the same video all have the same size, but are cut to the cell to be sure
+        let [width, height] = [
+            image.size[0].min(cell_width),
+            image.size[1].min(cell_height),
+        ];
+        for y in 0..height {
+            let row = &image.pixels[y * image.size[0]..y * image.size[0] + width];
+            let start = (top + y) * sheet.size[0] + left;
+            sheet.pixels[start..start + width].copy_from_slice(row);
+        }
+
+        let galley = fonts.layout_no_wrap(
+            format_duration(Duration::milliseconds(*time_ms)),
+            FontId::monospace(LABEL_FONT_SIZE),
+            Color32::WHITE,
+        );
+        let corner = Pos2::new((left + cell_width) as f32, (top + cell_height) as f32);
+        let text_rect = Align2::RIGHT_BOTTOM.anchor_size(corner - Vec2::splat(3.), galley.size());
+        labels.push(Shape::rect_filled(
+            text_rect.expand(2.),
+            2.,
+            Color32::from_black_alpha(160),
+        ));
+        labels.push(Shape::galley(text_rect.min, galley, Color32::WHITE));
+    }
+    paint_shape(&mut sheet, &fonts, Shape::Vec(labels));
+    Ok(sheet)
+}
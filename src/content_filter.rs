@@ -0,0 +1,282 @@
+use crate::TimeRange;
+use egui::{vec2, Color32, ColorImage, Pos2, Rect, Vec2};
+
+/// What a [`ContentFilter`] does to playback inside of its range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentAction {
+    /// Silence the audio.
+    Mute,
+    /// Show black frames instead of the video.
+    Blank,
+    /// Blur the video beyond recognition.
+    Blur,
+    /// Seek past the range. Frames and audio inside of it are never shown or heard, even while the
+    /// seek is in progress.
+    Skip,
+}
+
+/// A range of the stream filtered for family viewing, see [`crate::Player::set_content_filters`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentFilter {
+    /// When the filter applies.
+    pub range: TimeRange,
+    /// What the filter does.
+    pub action: ContentAction,
+}
+
+impl ContentFilter {
+    /// Apply `action` inside of `range`.
+    pub fn new(range: TimeRange, action: ContentAction) -> Self {
+        Self { range, action }
+    }
+
+    #[cfg(feature = "audio")]
+    pub(crate) fn mutes(&self) -> bool {
+        matches!(self.action, ContentAction::Mute | ContentAction::Skip)
+    }
+
+    pub(crate) fn changes_frames(&self) -> bool {
+        self.action != ContentAction::Mute
+    }
+
+    #[cfg(feature = "audio")]
+    /// Whether the filter applies to any of `start_ms..end_ms`.
+    pub(crate) fn overlaps(&self, start_ms: i64, end_ms: i64) -> bool {
+        start_ms < self.range.end_ms && self.range.start_ms < end_ms.max(start_ms + 1)
+    }
+}
+
+/// Apply the video actions of the `filters` containing `time_ms` to `image`.
+pub(crate) fn filter_image(filters: &[ContentFilter], time_ms: i64, image: &mut ColorImage) {
+    let active = filters.iter().filter(|f| f.range.contains(time_ms));
+    let mut blurred = false;
+    for filter in active {
+        match filter.action {
+            ContentAction::Blank | ContentAction::Skip => {
+                image.pixels.fill(Color32::BLACK);
+                return;
+            }
+            ContentAction::Blur => blurred = true,
+            ContentAction::Mute => (),
+        }
+    }
+    if blurred {
+        let [width, height] = image.size;
+        blur(image, [0, 0, width, height], width.max(height) / 40);
+    }
+}
+
+/// How a [`RegionFilter`] hides its region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionEffect {
+    /// Blur the region.
+    Blur,
+    /// Replace the region with blocks of its average color, each this many pixels wide.
+    Pixelate(usize),
+}
+
+/// A region of the video that is blurred or pixelated during some time ranges, like a face or a
+/// license plate. See [`crate::Player::set_region_filters`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionFilter {
+    /// The region, in video coordinates. It is scaled along with the frames, like with
+    /// [`crate::Player::set_max_frame_size`].
+    pub rect: Rect,
+    /// When the region is hidden. Always, if this is empty.
+    pub ranges: Vec<TimeRange>,
+    /// How the region is hidden.
+    pub effect: RegionEffect,
+}
+
+impl RegionFilter {
+    /// Hide `rect` with `effect` for the whole stream.
+    pub fn new(rect: Rect, effect: RegionEffect) -> Self {
+        Self {
+            rect,
+            ranges: vec![],
+            effect,
+        }
+    }
+
+    /// Only hide the region during `range`. Can be called repeatedly for several ranges.
+    pub fn add_range(mut self, range: TimeRange) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    pub(crate) fn is_active(&self, time_ms: i64) -> bool {
+        self.ranges.is_empty() || self.ranges.iter().any(|r| r.contains(time_ms))
+    }
+}
+
+/// `rect` in video coordinates of a video `video_size` large, in the pixels of a frame of it scaled
+/// to `image_size`, like with [`crate::Player::set_max_frame_size`]. Unchanged for an unknown size.
+pub(crate) fn to_image_rect(rect: Rect, video_size: Vec2, image_size: [usize; 2]) -> Rect {
+    if video_size.x <= 0. || video_size.y <= 0. {
+        return rect;
+    }
+    let scale = vec2(image_size[0] as f32, image_size[1] as f32) / video_size;
+    Rect::from_min_max(
+        (rect.min.to_vec2() * scale).to_pos2(),
+        (rect.max.to_vec2() * scale).to_pos2(),
+    )
+}
+
+/// Apply the `filters` active at `time_ms` to `image`, a frame of a video `video_size` large.
+pub(crate) fn filter_regions(
+    filters: &[RegionFilter],
+    time_ms: i64,
+    video_size: Vec2,
+    image: &mut ColorImage,
+) {
+    let [width, height] = image.size;
+    for filter in filters.iter().filter(|f| f.is_active(time_ms)) {
+        let rect = to_image_rect(filter.rect, video_size, image.size);
+        let rect = rect.intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            vec2(width as f32, height as f32),
+        ));
+        if !rect.is_positive() {
+            continue;
+        }
+        let region = [
+            rect.min.x as usize,
+            rect.min.y as usize,
+            rect.max.x.ceil() as usize,
+            rect.max.y.ceil() as usize,
+        ];
+        match filter.effect {
+            RegionEffect::Blur => {
+                let radius = (rect.width().max(rect.height()) / 8.) as usize;
+                blur(image, region, radius.max(1));
+            }
+            RegionEffect::Pixelate(block_size) => pixelate(image, region, block_size.max(1)),
+        }
+    }
+}
+
+// `region` is `[left, top, right, bottom]`, in pixels.
+fn blur(image: &mut ColorImage, region: [usize; 4], radius: usize) {
+    // two box blurs come close enough to a gaussian
+    for _ in 0..2 {
+        box_blur(image, region, radius);
+    }
+}
+
+// blur rows, then columns, with running sums so the radius doesn't affect the cost.
+fn box_blur(image: &mut ColorImage, region: [usize; 4], radius: usize) {
+    let width = image.size[0];
+    let [left, top, right, bottom] = region;
+    if radius == 0 || left >= right || top >= bottom {
+        return;
+    }
+    let mut line = vec![];
+    for y in top..bottom {
+        line.clear();
+        line.extend_from_slice(&image.pixels[y * width + left..y * width + right]);
+        blur_line(&line, radius, |x, color| {
+            image.pixels[y * width + left + x] = color
+        });
+    }
+    for x in left..right {
+        line.clear();
+        line.extend((top..bottom).map(|y| image.pixels[y * width + x]));
+        blur_line(&line, radius, |y, color| {
+            image.pixels[(top + y) * width + x] = color
+        });
+    }
+}
+
+fn blur_line(line: &[Color32], radius: usize, mut set: impl FnMut(usize, Color32)) {
+    let len = line.len();
+    let mut sum = [0u32; 4];
+    let mut count = 0;
+    for &color in &line[..radius.min(len)] {
+        add(&mut sum, color);
+        count += 1;
+    }
+    for i in 0..len {
+        if i + radius < len {
+            add(&mut sum, line[i + radius]);
+            count += 1;
+        }
+        if i > radius {
+            subtract(&mut sum, line[i - radius - 1]);
+            count -= 1;
+        }
+        let [r, g, b, a] = sum.map(|channel| (channel / count) as u8);
+        set(i, Color32::from_rgba_premultiplied(r, g, b, a));
+    }
+}
+
+fn add(sum: &mut [u32; 4], color: Color32) {
+    for (channel, value) in sum.iter_mut().zip(color.to_array()) {
+        *channel += value as u32;
+    }
+}
+
+fn subtract(sum: &mut [u32; 4], color: Color32) {
+    for (channel, value) in sum.iter_mut().zip(color.to_array()) {
+        *channel -= value as u32;
+    }
+}
+
+fn pixelate(image: &mut ColorImage, region: [usize; 4], block_size: usize) {
+    let width = image.size[0];
+    let [left, top, right, bottom] = region;
+    for block_top in (top..bottom).step_by(block_size) {
+        for block_left in (left..right).step_by(block_size) {
+            let xs = block_left..(block_left + block_size).min(right);
+            let ys = block_top..(block_top + block_size).min(bottom);
+            let mut sum = [0u32; 4];
+            for y in ys.clone() {
+                for x in xs.clone() {
+                    add(&mut sum, image.pixels[y * width + x]);
+                }
+            }
+            let count = (xs.len() * ys.len()) as u32;
+            let [r, g, b, a] = sum.map(|channel| (channel / count) as u8);
+            let color = Color32::from_rgba_premultiplied(r, g, b, a);
+            for y in ys {
+                image.pixels[y * width + xs.start..y * width + xs.end].fill(color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::pos2;
+
+    fn gradient(size: [usize; 2]) -> ColorImage {
+        let pixels = (0..size[0] * size[1])
+            .map(|i| Color32::from_gray((i % size[0]) as u8))
+            .collect();
+        ColorImage { size, pixels }
+    }
+
+    #[test]
+    fn regions_scale_with_the_frame() {
+        let rect = Rect::from_min_max(pos2(100., 0.), pos2(200., 100.));
+        let filters = [RegionFilter::new(rect, RegionEffect::Pixelate(64))];
+        // a 200x100 video served at half its size
+        let mut image = gradient([100, 50]);
+        filter_regions(&filters, 0, vec2(200., 100.), &mut image);
+        let original = gradient([100, 50]);
+        for y in 0..50 {
+            let row = &image.pixels[y * 100..(y + 1) * 100];
+            assert_eq!(row[..50], original.pixels[y * 100..y * 100 + 50]);
+            assert!(row[50..].iter().all(|c| *c == row[50]));
+            assert_ne!(row[50], original.pixels[y * 100 + 50]);
+        }
+    }
+
+    #[test]
+    fn image_rect_of_unknown_size() {
+        let rect = Rect::from_min_max(pos2(10., 20.), pos2(30., 40.));
+        assert_eq!(to_image_rect(rect, Vec2::ZERO, [100, 50]), rect);
+        let scaled = to_image_rect(rect, vec2(200., 100.), [400, 200]);
+        assert_eq!(scaled, Rect::from_min_max(pos2(20., 40.), pos2(60., 80.)));
+    }
+}
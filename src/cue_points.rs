@@ -0,0 +1,95 @@
+use crate::Player;
+
+type CueCallback = Box<dyn FnMut(&mut Player) + Send>;
+
+/// Identifies a cue point added with [`Player::add_cue_point`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CuePointId(u64);
+
+struct CuePoint {
+    id: CuePointId,
+    time_ms: i64,
+    // taken out while it runs, so it can get the player
+    callback: Option<CueCallback>,
+}
+
+/// The cue points of a [`Player`], fired as the presented frames cross them.
+#[derive(Default)]
+pub(crate) struct CuePoints {
+    next_id: u64,
+    points: Vec<CuePoint>,
+    // the time of the last presented frame, `None` after a seek so jumping over a cue point doesn't
+    // count as playing through it
+    last_ms: Option<i64>,
+}
+
+impl CuePoints {
+    pub(crate) fn add(&mut self, time_ms: i64, callback: CueCallback) -> CuePointId {
+        let id = CuePointId(self.next_id);
+        self.next_id += 1;
+        let index = self
+            .points
+            .partition_point(|point| point.time_ms <= time_ms);
+        self.points.insert(
+            index,
+            CuePoint {
+                id,
+                time_ms,
+                callback: Some(callback),
+            },
+        );
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: CuePointId) -> bool {
+        let len = self.points.len();
+        self.points.retain(|point| point.id != id);
+        self.points.len() != len
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Playback starts from the beginning, so a cue point at the first frame fires.
+    pub(crate) fn on_start(&mut self) {
+        self.last_ms = Some(i64::MIN);
+    }
+
+    pub(crate) fn on_seek(&mut self) {
+        self.last_ms = None;
+    }
+
+    /// Whether playback went on from the last presented frame, and not from a seek.
+    pub(crate) fn is_tracking(&self) -> bool {
+        self.last_ms.is_some()
+    }
+
+    /// The cue points crossed by presenting the frame at `time_ms`, in order: after the last
+    /// presented frame, up to and including `time_ms`.
+    pub(crate) fn crossed(&mut self, time_ms: i64) -> Vec<CuePointId> {
+        let Some(last_ms) = self.last_ms.replace(time_ms) else {
+            return Vec::new();
+        };
+        self.points
+            .iter()
+            .filter(|point| point.time_ms > last_ms && point.time_ms <= time_ms)
+            .map(|point| point.id)
+            .collect()
+    }
+
+    pub(crate) fn take_callback(&mut self, id: CuePointId) -> Option<CueCallback> {
+        self.points
+            .iter_mut()
+            .find(|point| point.id == id)?
+            .callback
+            .take()
+    }
+
+    // put back the callback from `take_callback`, unless it removed its cue point
+    pub(crate) fn restore_callback(&mut self, id: CuePointId, callback: CueCallback) {
+        if let Some(point) = self.points.iter_mut().find(|point| point.id == id) {
+            point.callback = Some(callback);
+        }
+    }
+}
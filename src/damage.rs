@@ -0,0 +1,76 @@
+use egui::Color32;
+use ffmpeg::ffi::{
+    FF_DECODE_ERROR_CONCEALMENT_ACTIVE, FF_DECODE_ERROR_DECODE_SLICES,
+    FF_DECODE_ERROR_INVALID_BITSTREAM, FF_DECODE_ERROR_MISSING_REFERENCE,
+};
+use std::time::Duration;
+
+/// The damage the decoder reported for a video frame, see [`crate::Player::frame_damage`]. ffmpeg
+/// reports it for the frame as a whole, not for the macroblocks it concealed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameDamage {
+    /// Parts of the frame couldn't be parsed.
+    pub invalid_bitstream: bool,
+    /// A frame it is predicted from is missing, like after a lost keyframe.
+    pub missing_reference: bool,
+    /// Lost parts were filled in from their surroundings or the previous frame.
+    pub concealed: bool,
+    /// Some slices of the frame weren't decoded.
+    pub lost_slices: bool,
+    /// The frame is flagged as corrupt.
+    pub corrupt: bool,
+}
+
+impl FrameDamage {
+    /// The damage of `frame`, `None` if it was decoded intact.
+    pub(crate) fn of(frame: &ffmpeg::Frame) -> Option<Self> {
+        let flags = unsafe { (*frame.as_ptr()).decode_error_flags } as u32;
+        let damage = Self {
+            invalid_bitstream: flags & FF_DECODE_ERROR_INVALID_BITSTREAM != 0,
+            missing_reference: flags & FF_DECODE_ERROR_MISSING_REFERENCE != 0,
+            concealed: flags & FF_DECODE_ERROR_CONCEALMENT_ACTIVE != 0,
+            lost_slices: flags & FF_DECODE_ERROR_DECODE_SLICES != 0,
+            corrupt: frame.is_corrupt(),
+        };
+        (damage != Self::default()).then_some(damage)
+    }
+
+    /// The kinds of damage, like `concealed, missing reference`.
+    pub fn describe(&self) -> String {
+        [
+            (self.corrupt, "corrupt"),
+            (self.invalid_bitstream, "invalid bitstream"),
+            (self.missing_reference, "missing reference"),
+            (self.concealed, "concealed"),
+            (self.lost_slices, "lost slices"),
+        ]
+        .iter()
+        .filter(|(damaged, _)| *damaged)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// How damaged frames are highlighted for quality control, like when reviewing ingested
+/// archive footage, see [`crate::Player::set_damage_overlay`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamageOverlay {
+    /// The color drawn over damaged frames.
+    pub tint: Color32,
+    /// How long the highlight stays after a damaged frame, so a single one is noticed at full
+    /// speed.
+    pub hold: Duration,
+    /// Name the kinds of damage in the top left corner of the video.
+    pub show_label: bool,
+}
+
+impl Default for DamageOverlay {
+    fn default() -> Self {
+        Self {
+            tint: Color32::from_rgba_unmultiplied(255, 0, 160, 90),
+            hold: Duration::from_millis(500),
+            show_label: true,
+        }
+    }
+}
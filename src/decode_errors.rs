@@ -0,0 +1,56 @@
+use crate::stats::SessionStats;
+use crate::{PlayerEvent, Shared};
+use std::sync::mpsc;
+
+/// Reports the errors of one decoder as [`PlayerEvent::DecodeError`], while playback goes on.
+pub(crate) struct DecodeErrors {
+    count: Shared<u64>,
+    stats: SessionStats,
+    event_sender: mpsc::Sender<PlayerEvent>,
+}
+
+impl DecodeErrors {
+    pub(crate) fn new(
+        count: Shared<u64>,
+        stats: SessionStats,
+        event_sender: mpsc::Sender<PlayerEvent>,
+    ) -> Self {
+        Self {
+            count,
+            stats,
+            event_sender,
+        }
+    }
+
+    /// Count the error of the packet or frame of `stream_index` at `elapsed_ms`, and report it.
+    pub(crate) fn report(
+        &self,
+        stream_index: usize,
+        elapsed_ms: i64,
+        error: String,
+        dropped: bool,
+    ) {
+        let count = self.count.get() + 1;
+        self.count.set(count);
+        self.stats
+            .lock()
+            .error(stream_index, elapsed_ms, error.clone());
+        let _ = self.event_sender.send(PlayerEvent::DecodeError {
+            stream_index,
+            elapsed_ms,
+            error,
+            dropped,
+            count,
+        });
+    }
+}
+
+/// Whether an error of the decoder is about damaged data, rather than it needing more input or
+/// being at the end of the stream.
+pub(crate) fn is_data_error(error: &ffmpeg::Error) -> bool {
+    match error {
+        ffmpeg::Error::Eof => false,
+        ffmpeg::Error::Other { errno } => *errno != ffmpeg::error::EAGAIN,
+        _ => true,
+    }
+}
@@ -0,0 +1,44 @@
+use parking_lot::Mutex;
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+/// The first panic in the pipeline of a player, shared with its threads. Reported as
+/// [`crate::PlayerEvent::Fatal`].
+#[derive(Clone, Default)]
+pub(crate) struct FatalError(Arc<Mutex<Option<(&'static str, String)>>>);
+
+impl FatalError {
+    /// Run `f`, catching a panic in it instead of unwinding the thread. The first panic is kept,
+    /// with the `thread` it happened on, and `None` is returned.
+    pub(crate) fn contain<R>(&self, thread: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+        match catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                self.0
+                    .lock()
+                    .get_or_insert_with(|| (thread, panic_message(payload.as_ref())));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.lock().is_some()
+    }
+
+    /// The thread that panicked and the message of the panic.
+    pub(crate) fn get(&self) -> Option<(&'static str, String)> {
+        self.0.lock().clone()
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic without a message".to_string()
+    }
+}
@@ -0,0 +1,187 @@
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+/// The version of one of the ffmpeg libraries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LibraryVersion {
+    /// Changes break compatibility.
+    pub major: u32,
+    /// Changes add features.
+    pub minor: u32,
+    /// Changes fix bugs.
+    pub micro: u32,
+}
+
+impl From<u32> for LibraryVersion {
+    // unpacks `AV_VERSION_INT`
+    fn from(version: u32) -> Self {
+        Self {
+            major: version >> 16,
+            minor: (version >> 8) & 0xff,
+            micro: version & 0xff,
+        }
+    }
+}
+
+impl fmt::Display for LibraryVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.micro)
+    }
+}
+
+/// A component of ffmpeg, by the name `ffmpeg -decoders`, `-demuxers` etc. list it under, see
+/// [`FfmpegInfo::require`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Component {
+    /// A decoder, like `ass` for ASS subtitles.
+    Decoder(String),
+    /// An encoder, like `libx264`.
+    Encoder(String),
+    /// A demuxer, like `hls`.
+    Demuxer(String),
+    /// A muxer, like `mp4`.
+    Muxer(String),
+    /// A filter, like `rubberband`.
+    Filter(String),
+    /// An input protocol, like `srt` or `https`.
+    Protocol(String),
+}
+
+impl Component {
+    /// Whether the linked ffmpeg was built with this component.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Decoder(name) => ffmpeg::decoder::find_by_name(name).is_some(),
+            Self::Encoder(name) => ffmpeg::encoder::find_by_name(name).is_some(),
+            Self::Demuxer(name) => CString::new(name.as_str()).is_ok_and(|name| unsafe {
+                !ffmpeg::ffi::av_find_input_format(name.as_ptr()).is_null()
+            }),
+            Self::Muxer(name) => CString::new(name.as_str()).is_ok_and(|name| unsafe {
+                !ffmpeg::ffi::av_guess_format(name.as_ptr(), std::ptr::null(), std::ptr::null())
+                    .is_null()
+            }),
+            Self::Filter(name) => ffmpeg::filter::find(name).is_some(),
+            Self::Protocol(name) => input_protocols().any(|protocol| protocol == *name),
+        }
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, name) = match self {
+            Self::Decoder(name) => ("decoder", name),
+            Self::Encoder(name) => ("encoder", name),
+            Self::Demuxer(name) => ("demuxer", name),
+            Self::Muxer(name) => ("muxer", name),
+            Self::Filter(name) => ("filter", name),
+            Self::Protocol(name) => ("protocol", name),
+        };
+        write!(f, "{kind} `{name}`")
+    }
+}
+
+/// The error of [`FfmpegInfo::require`]: the linked ffmpeg lacks these components.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingComponents {
+    /// The missing components, in the order they were required.
+    pub components: Vec<Component>,
+    /// The version of the linked ffmpeg.
+    pub version: String,
+}
+
+impl fmt::Display for MissingComponents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ffmpeg {} was built without the ", self.version)?;
+        for (index, component) in self.components.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{component}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingComponents {}
+
+/// The ffmpeg the program is linked against, see [`FfmpegInfo::detect`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FfmpegInfo {
+    /// The version of the ffmpeg release, like `7.0.2`, or its git revision for builds from source.
+    pub version: String,
+    /// The version of `libavutil`.
+    pub avutil: LibraryVersion,
+    /// The version of `libavcodec`.
+    pub avcodec: LibraryVersion,
+    /// The version of `libavformat`.
+    pub avformat: LibraryVersion,
+    /// The version of `libavfilter`.
+    pub avfilter: LibraryVersion,
+    /// The version of `libswscale`.
+    pub swscale: LibraryVersion,
+    /// The version of `libswresample`, which is only linked with the `audio` feature.
+    #[cfg(feature = "audio")]
+    pub swresample: LibraryVersion,
+    /// The arguments ffmpeg was configured with, like the external libraries it was built with.
+    pub configuration: String,
+    /// The license of the build, like `LGPL version 2.1 or later`.
+    pub license: String,
+}
+
+impl FfmpegInfo {
+    /// Find out which ffmpeg is linked. Meant for logging at startup and bug reports.
+    pub fn detect() -> Self {
+        Self {
+            version: version_info(),
+            avutil: ffmpeg::util::version().into(),
+            avcodec: ffmpeg::codec::version().into(),
+            avformat: ffmpeg::format::version().into(),
+            avfilter: ffmpeg::filter::version().into(),
+            swscale: ffmpeg::software::scaling::version().into(),
+            #[cfg(feature = "audio")]
+            swresample: ffmpeg::software::resampling::version().into(),
+            configuration: ffmpeg::util::configuration().to_string(),
+            license: ffmpeg::util::license().to_string(),
+        }
+    }
+
+    /// Check that the linked ffmpeg has all of `components`, failing with the ones it doesn't
+    /// have. Meant for failing at startup with a clear error, instead of when a file needs them.
+    pub fn require(components: &[Component]) -> Result<(), MissingComponents> {
+        let missing: Vec<Component> = components
+            .iter()
+            .filter(|component| !component.is_available())
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(MissingComponents {
+            components: missing,
+            version: version_info(),
+        })
+    }
+
+    /// The names of the input protocols the linked ffmpeg was built with.
+    pub fn input_protocols() -> Vec<String> {
+        input_protocols().collect()
+    }
+}
+
+fn version_info() -> String {
+    unsafe { CStr::from_ptr(ffmpeg::ffi::av_version_info()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn input_protocols() -> impl Iterator<Item = String> {
+    let mut opaque = std::ptr::null_mut();
+    std::iter::from_fn(move || {
+        let name = unsafe { ffmpeg::ffi::avio_enum_protocols(&mut opaque, 0) };
+        (!name.is_null()).then(|| {
+            unsafe { CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    })
+}
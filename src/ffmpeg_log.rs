@@ -0,0 +1,122 @@
+use ffmpeg::ffi::{
+    av_log_format_line2, av_log_set_callback, va_list, AV_LOG_DEBUG, AV_LOG_ERROR, AV_LOG_INFO,
+    AV_LOG_WARNING,
+};
+use ffmpeg::util::log::Level;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Once};
+
+// the `log::LevelFilter` messages are forwarded up to, as a `usize`
+static MAX_LEVEL: AtomicUsize = AtomicUsize::new(0);
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    // the input the player on this thread plays, see `set_thread_source`
+    static SOURCE: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+    // the start of a line ffmpeg logged in parts, and whether the next part starts a new line
+    static PENDING: RefCell<(String, c_int)> = const { RefCell::new((String::new(), 1)) };
+}
+
+/// Forward the log of ffmpeg to the [`log`] crate instead of printing it to stderr, up to `level`.
+/// The messages have the target `ffmpeg`, and start with the input of the player (or the
+/// file being opened) they came from, like `[rtsp://camera/stream] [h264 @ 0x5581..] error while
+/// decoding MB 12 4`. Messages logged from ffmpeg's own threads (like those of frame threaded
+/// decoding) have no input. Apps using `tracing` receive them through `tracing-log`.
+///
+/// Can be called again to change the level, see also [`set_ffmpeg_log_level`].
+pub fn capture_ffmpeg_log(level: log::LevelFilter) {
+    set_ffmpeg_log_level(level);
+    INSTALL.call_once(|| unsafe { av_log_set_callback(Some(log_callback)) });
+}
+
+/// Change the level the log of ffmpeg is forwarded up to, after [`capture_ffmpeg_log`].
+/// ffmpeg skips formatting the messages above it.
+pub fn set_ffmpeg_log_level(level: log::LevelFilter) {
+    MAX_LEVEL.store(level as usize, Ordering::Relaxed);
+    ffmpeg::util::log::set_level(match level {
+        log::LevelFilter::Off => Level::Quiet,
+        log::LevelFilter::Error => Level::Error,
+        log::LevelFilter::Warn => Level::Warning,
+        log::LevelFilter::Info => Level::Info,
+        log::LevelFilter::Debug => Level::Debug,
+        log::LevelFilter::Trace => Level::Trace,
+    });
+}
+
+/// Attribute what ffmpeg logs on this thread to `source`, for the timer threads of a player.
+pub(crate) fn set_thread_source(source: &Arc<str>) {
+    SOURCE.with_borrow_mut(|current| {
+        if !current
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, source))
+        {
+            *current = Some(source.clone());
+        }
+    });
+}
+
+/// Attributes what ffmpeg logs on this thread to a source until dropped, restoring the one before.
+pub(crate) struct SourceScope(Option<Arc<str>>);
+
+impl SourceScope {
+    pub(crate) fn new(source: impl Into<Arc<str>>) -> Self {
+        Self(SOURCE.with_borrow_mut(|current| current.replace(source.into())))
+    }
+}
+
+impl Drop for SourceScope {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SOURCE.with_borrow_mut(|current| *current = previous);
+    }
+}
+
+fn log_level(level: c_int) -> log::Level {
+    match level {
+        level if level <= AV_LOG_ERROR => log::Level::Error,
+        level if level <= AV_LOG_WARNING => log::Level::Warn,
+        level if level <= AV_LOG_INFO => log::Level::Info,
+        level if level <= AV_LOG_DEBUG => log::Level::Debug,
+        _ => log::Level::Trace,
+    }
+}
+
+unsafe extern "C" fn log_callback(
+    avcl: *mut c_void,
+    level: c_int,
+    fmt: *const c_char,
+    args: va_list,
+) {
+    let log_level = log_level(level);
+    if log_level as usize > MAX_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut buffer = [0 as c_char; 1024];
+    PENDING.with_borrow_mut(|(pending, print_prefix)| {
+        av_log_format_line2(
+            avcl,
+            level,
+            fmt,
+            args,
+            buffer.as_mut_ptr(),
+            buffer.len() as c_int,
+            print_prefix,
+        );
+        pending.push_str(&CStr::from_ptr(buffer.as_ptr()).to_string_lossy());
+        // ffmpeg ends its messages with a newline, the parts before it are kept until then
+        if !pending.ends_with('\n') {
+            return;
+        }
+        let line = std::mem::take(pending);
+        let line = line.trim_end();
+        if line.is_empty() {
+            return;
+        }
+        SOURCE.with_borrow(|source| match source {
+            Some(source) => log::log!(target: "ffmpeg", log_level, "[{source}] {line}"),
+            None => log::log!(target: "ffmpeg", log_level, "{line}"),
+        });
+    });
+}
@@ -0,0 +1,548 @@
+use anyhow::Result;
+use ffmpeg::color::{Primaries, Range, Space, TransferCharacteristic};
+use ffmpeg::format::Pixel;
+#[cfg(feature = "audio")]
+use ffmpeg::frame::Audio;
+use ffmpeg::frame::Video;
+#[cfg(feature = "audio")]
+use ffmpeg::ChannelLayout;
+use ffmpeg::Rational;
+
+/// The algorithm used to change the speed of audio without changing its pitch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "audio")]
+pub enum TimeStretchAlgorithm {
+    /// ffmpeg's built-in `atempo` filter. Always available, but has audible artifacts on speech at high speeds.
+    #[default]
+    Atempo,
+    /// The `rubberband` filter. Only available if ffmpeg was built with `librubberband`, otherwise
+    /// [`TimeStretchAlgorithm::Atempo`] is used instead.
+    Rubberband,
+}
+
+#[cfg(feature = "audio")]
+impl TimeStretchAlgorithm {
+    /// Whether the linked ffmpeg provides this algorithm.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Atempo => true,
+            Self::Rubberband => ffmpeg::filter::find("rubberband").is_some(),
+        }
+    }
+}
+
+/// The quality of the time stretching. Only affects [`TimeStretchAlgorithm::Rubberband`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "audio")]
+pub enum TimeStretchQuality {
+    /// Lowest cpu usage.
+    Fast,
+    /// A balance between quality and cpu usage.
+    #[default]
+    Balanced,
+    /// Highest quality, preserving formants (best for speech).
+    High,
+}
+
+/// Configures how audio is stretched when the playback speed isn't `1.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "audio")]
+pub struct TimeStretch {
+    /// The time stretching algorithm.
+    pub algorithm: TimeStretchAlgorithm,
+    /// The quality level of the algorithm.
+    pub quality: TimeStretchQuality,
+}
+
+#[cfg(feature = "audio")]
+impl TimeStretch {
+    /// The filter chain changing the tempo by `speed`.
+    pub(crate) fn filter_spec(&self, speed: f32) -> String {
+        let algorithm = if self.algorithm.is_available() {
+            self.algorithm
+        } else {
+            TimeStretchAlgorithm::Atempo
+        };
+        match algorithm {
+            TimeStretchAlgorithm::Atempo => {
+                // atempo sounds best within 0.5..=2.0, so chain multiple instances outside of that
+                let mut remaining = speed as f64;
+                let mut filters = vec![];
+                while remaining > 2. {
+                    filters.push("atempo=2.0".to_string());
+                    remaining /= 2.;
+                }
+                while remaining < 0.5 {
+                    filters.push("atempo=0.5".to_string());
+                    remaining /= 0.5;
+                }
+                filters.push(format!("atempo={remaining}"));
+                filters.join(",")
+            }
+            TimeStretchAlgorithm::Rubberband => {
+                let quality = match self.quality {
+                    TimeStretchQuality::Fast => "pitchq=speed:window=short",
+                    TimeStretchQuality::Balanced => "pitchq=consistency",
+                    TimeStretchQuality::High => "pitchq=quality:formant=preserved",
+                };
+                format!("rubberband=tempo={speed}:{quality}")
+            }
+        }
+    }
+}
+
+/// Remaps the channels of the audio before it is mixed down for the audio device, like swapping
+/// left and right of miswired capture hardware, see [`crate::Player::set_channel_map`]. The
+/// channels are numbered in the order of the channel layout of the stream.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg(feature = "audio")]
+pub struct ChannelMap {
+    /// The gain of each input channel in each output channel: `matrix[out][in]`. Output channels
+    /// without a row keep their input, and input channels past the end of a row are left out.
+    pub matrix: Vec<Vec<f32>>,
+}
+
+#[cfg(feature = "audio")]
+impl ChannelMap {
+    /// Map the channels with `matrix`, see [`ChannelMap::matrix`]. For example
+    /// `vec![vec![1., 0., 0.7], vec![0., 1., 0.7], vec![0., 0., 0.]]` routes the center of a
+    /// `3.0` stream to both left and right.
+    pub fn new(matrix: Vec<Vec<f32>>) -> Self {
+        Self { matrix }
+    }
+
+    /// Swap the first two channels, left and right of most layouts.
+    pub fn swap_left_right() -> Self {
+        Self::new(vec![vec![0., 1.], vec![1., 0.]])
+    }
+
+    /// The `pan` filter mapping the `channels` of `layout` (its ffmpeg description) onto the same
+    /// layout, `None` if it keeps every channel as it is.
+    pub(crate) fn filter_spec(&self, layout: &str, channels: usize) -> Option<String> {
+        let mut identity = true;
+        let outputs: Vec<String> = (0..channels)
+            .map(|out| {
+                let Some(row) = self.matrix.get(out) else {
+                    return format!("c{out}=c{out}");
+                };
+                let terms: Vec<(usize, f32)> = row
+                    .iter()
+                    .copied()
+                    .take(channels)
+                    .enumerate()
+                    .filter(|(_, gain)| *gain != 0.)
+                    .collect();
+                identity &= terms == [(out, 1.)];
+                if terms.is_empty() {
+                    return format!("c{out}=0*c{out}");
+                }
+                // the sign of each gain joins it to the terms before
+                let mut sum = String::new();
+                for (index, (input, gain)) in terms.into_iter().enumerate() {
+                    match (index, gain < 0.) {
+                        (_, true) => sum.push_str(&format!("-{}*c{input}", -gain)),
+                        (0, false) => sum.push_str(&format!("{gain}*c{input}")),
+                        (_, false) => sum.push_str(&format!("+{gain}*c{input}")),
+                    }
+                }
+                format!("c{out}={sum}")
+            })
+            .collect();
+        (!identity).then(|| format!("pan={layout}|{}", outputs.join("|")))
+    }
+}
+
+/// The ffmpeg filter removing noise from the video, see [`Denoise`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DenoiseFilter {
+    /// `hqdn3d`, a fast spatial and temporal filter. Light enough for live sources.
+    #[default]
+    Hqdn3d,
+    /// `nlmeans`, non-local means. Keeps more detail, but is a lot slower.
+    Nlmeans,
+}
+
+impl DenoiseFilter {
+    /// Whether the linked ffmpeg provides this filter.
+    pub fn is_available(&self) -> bool {
+        let name = match self {
+            Self::Hqdn3d => "hqdn3d",
+            Self::Nlmeans => "nlmeans",
+        };
+        ffmpeg::filter::find(name).is_some()
+    }
+}
+
+/// How strongly [`Denoise`] filters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DenoiseStrength {
+    /// Removes fine grain, keeping all detail.
+    Light,
+    /// ffmpeg's defaults.
+    #[default]
+    Medium,
+    /// For very noisy sources like low light cameras, smearing some detail.
+    Strong,
+}
+
+/// Removes noise from decoded frames before they are shown, see [`crate::Player::set_denoise`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Denoise {
+    /// The filter.
+    pub filter: DenoiseFilter,
+    /// The strength of the filter.
+    pub strength: DenoiseStrength,
+}
+
+impl Denoise {
+    /// The filter chain removing the noise.
+    pub(crate) fn filter_spec(&self) -> String {
+        match (self.filter, self.strength) {
+            (DenoiseFilter::Hqdn3d, DenoiseStrength::Light) => "hqdn3d=2:1.5:3:2.25",
+            (DenoiseFilter::Hqdn3d, DenoiseStrength::Medium) => "hqdn3d=4:3:6:4.5",
+            (DenoiseFilter::Hqdn3d, DenoiseStrength::Strong) => "hqdn3d=8:6:12:9",
+            (DenoiseFilter::Nlmeans, DenoiseStrength::Light) => "nlmeans=s=1.5",
+            (DenoiseFilter::Nlmeans, DenoiseStrength::Medium) => "nlmeans=s=3",
+            (DenoiseFilter::Nlmeans, DenoiseStrength::Strong) => "nlmeans=s=6",
+        }
+        .to_string()
+    }
+}
+
+/// How [`Interpolation`] makes up the frames in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InterpolationMode {
+    /// Blend neighbouring frames. Cheap, but moving objects look doubled.
+    Blend,
+    /// Estimate the motion between frames and move the pixels along it. Smooth, but very
+    /// expensive and with artifacts around fast motion.
+    #[default]
+    MotionCompensated,
+}
+
+/// Converts the video to a higher frame rate (like 24fps films to the 60Hz of a display) by making
+/// up the frames in between with ffmpeg's `minterpolate` filter, see
+/// [`crate::Player::set_interpolation`]. This takes a lot of cpu, so it is off by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interpolation {
+    /// The frame rate of the output, usually the refresh rate of the display.
+    pub fps: u32,
+    /// How the frames in between are made.
+    pub mode: InterpolationMode,
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Self {
+            fps: 60,
+            mode: InterpolationMode::default(),
+        }
+    }
+}
+
+impl Interpolation {
+    /// Whether the linked ffmpeg provides the `minterpolate` filter.
+    pub fn is_available() -> bool {
+        ffmpeg::filter::find("minterpolate").is_some()
+    }
+
+    /// The filter making up the frames.
+    pub(crate) fn filter_spec(&self) -> String {
+        let mode = match self.mode {
+            InterpolationMode::Blend => "blend",
+            InterpolationMode::MotionCompensated => "mci",
+        };
+        format!("minterpolate=fps={}:mi_mode={mode}", self.fps.max(1))
+    }
+}
+
+/// The color primaries of a display, see [`DisplayColor`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayPrimaries {
+    /// sRGB, the same as BT.709. What video is made for and most displays cover.
+    #[default]
+    Srgb,
+    /// Display P3, the wide gamut of Apple displays and many newer monitors and laptops.
+    DisplayP3,
+    /// DCI-P3 with its white point, of digital cinema projectors.
+    DciP3,
+    /// BT.2020, of some HDR displays in their wide gamut mode.
+    Bt2020,
+}
+
+impl DisplayPrimaries {
+    fn primaries(&self) -> Primaries {
+        match self {
+            Self::Srgb => Primaries::BT709,
+            Self::DisplayP3 => Primaries::SMPTE432,
+            Self::DciP3 => Primaries::SMPTE431,
+            Self::Bt2020 => Primaries::BT2020,
+        }
+    }
+}
+
+/// The transfer function (gamma) of a display, see [`DisplayColor`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayTransfer {
+    /// The sRGB curve, also used by Display P3.
+    #[default]
+    Srgb,
+    /// A pure 2.2 gamma.
+    Gamma22,
+    /// The BT.709 curve of video.
+    Bt709,
+}
+
+impl DisplayTransfer {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Srgb => "srgb",
+            Self::Gamma22 => "gamma22",
+            Self::Bt709 => "bt709",
+        }
+    }
+
+    // whether video with `transfer` is shown as is, like BT.709 video has always been shown on sRGB
+    fn matches(&self, transfer: TransferCharacteristic) -> bool {
+        use TransferCharacteristic::*;
+        match self {
+            Self::Srgb => matches!(transfer, IEC61966_2_1 | BT709 | SMPTE170M),
+            Self::Gamma22 => transfer == GAMMA22,
+            Self::Bt709 => matches!(transfer, BT709 | SMPTE170M),
+        }
+    }
+}
+
+/// Converts the colors of the video to the primaries and transfer function of the display with
+/// ffmpeg's `colorspace` filter, see [`crate::Player::set_display_color`]. Without it, the video is
+/// shown as if the display was sRGB, which looks oversaturated on wide gamut displays that don't
+/// manage the colors of the window. Use the primaries of the display's ICC profile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisplayColor {
+    /// The primaries of the display.
+    pub primaries: DisplayPrimaries,
+    /// The transfer function of the display.
+    pub transfer: DisplayTransfer,
+}
+
+impl DisplayColor {
+    /// A Display P3 display.
+    pub const DISPLAY_P3: Self = Self {
+        primaries: DisplayPrimaries::DisplayP3,
+        transfer: DisplayTransfer::Srgb,
+    };
+
+    /// Whether the linked ffmpeg provides the `colorspace` filter.
+    pub fn is_available() -> bool {
+        ffmpeg::filter::find("colorspace").is_some()
+    }
+
+    /// The filter converting `frame` to the display, `None` when it already matches it or its colors
+    /// can't be converted (like HDR video). Properties the frame doesn't have are guessed from its
+    /// height, like ffmpeg does.
+    pub(crate) fn filter_spec(&self, frame: &Video) -> Option<String> {
+        let hd = frame.height() >= 720;
+        let primaries = match frame.color_primaries() {
+            Primaries::Unspecified | Primaries::Reserved0 | Primaries::Reserved if hd => {
+                Primaries::BT709
+            }
+            Primaries::Unspecified | Primaries::Reserved0 | Primaries::Reserved => {
+                Primaries::SMPTE170M
+            }
+            primaries => primaries,
+        };
+        let transfer = match frame.color_transfer_characteristic() {
+            TransferCharacteristic::Unspecified
+            | TransferCharacteristic::Reserved0
+            | TransferCharacteristic::Reserved => TransferCharacteristic::BT709,
+            transfer => transfer,
+        };
+        if primaries == self.primaries.primaries() && self.transfer.matches(transfer) {
+            return None;
+        }
+        // the input transfer functions the filter supports, not the ones of HDR
+        use TransferCharacteristic::*;
+        if !matches!(
+            transfer,
+            BT709
+                | GAMMA22
+                | GAMMA28
+                | SMPTE170M
+                | SMPTE240M
+                | Linear
+                | IEC61966_2_1
+                | IEC61966_2_4
+                | BT2020_10
+                | BT2020_12
+        ) {
+            return None;
+        }
+        let space = match frame.color_space() {
+            Space::BT709 => "bt709",
+            Space::FCC => "fcc",
+            Space::BT470BG => "bt470bg",
+            Space::SMPTE170M => "smpte170m",
+            Space::SMPTE240M => "smpte240m",
+            Space::YCGCO => "ycgco",
+            Space::BT2020NCL => "bt2020ncl",
+            Space::Unspecified | Space::Reserved if hd => "bt709",
+            Space::Unspecified | Space::Reserved => "smpte170m",
+            _ => return None,
+        };
+        let range = match frame.color_range() {
+            Range::JPEG => "pc",
+            _ => "tv",
+        };
+        Some(format!(
+            // the filter only takes planar yuv
+            "format=yuv420p|yuv422p|yuv444p|yuv420p10le|yuv422p10le|yuv444p10le,\
+             colorspace=primaries={}:trc={}:iprimaries={}:itrc={}:ispace={space}:irange={range}",
+            self.primaries.primaries().name()?,
+            self.transfer.name(),
+            primaries.name()?,
+            transfer.name()?,
+        ))
+    }
+}
+
+/// An ffmpeg filter graph processing decoded video frames. The timestamps of the output are in
+/// [`VideoFilter::time_base`], as filters changing the frame rate also change it.
+pub(crate) struct VideoFilter {
+    graph: ffmpeg::filter::Graph,
+    time_base: Rational,
+}
+
+impl VideoFilter {
+    pub(crate) fn new(
+        spec: &str,
+        format: Pixel,
+        width: u32,
+        height: u32,
+        time_base: Rational,
+    ) -> Result<Self> {
+        let mut graph = ffmpeg::filter::Graph::new();
+        let format_name = format
+            .descriptor()
+            .ok_or(ffmpeg::Error::InvalidData)?
+            .name();
+        let args = format!(
+            "video_size={width}x{height}:pix_fmt={format_name}:time_base={time_base}:pixel_aspect=1/1"
+        );
+        graph.add(&ffmpeg::filter::find("buffer").unwrap(), "in", &args)?;
+        graph.add(&ffmpeg::filter::find("buffersink").unwrap(), "out", "")?;
+        graph.output("in", 0)?.input("out", 0)?.parse(spec)?;
+        graph.validate()?;
+        let time_base = unsafe {
+            ffmpeg::ffi::av_buffersink_get_time_base(graph.get("out").unwrap().as_ptr()).into()
+        };
+        Ok(Self { graph, time_base })
+    }
+
+    /// The time base of the filtered frames.
+    pub(crate) fn time_base(&self) -> Rational {
+        self.time_base
+    }
+
+    /// Push a frame through the filter and collect every frame that is ready.
+    pub(crate) fn run(&mut self, frame: &Video) -> Result<Vec<Video>> {
+        self.graph.get("in").unwrap().source().add(frame)?;
+        let mut filtered_frames = vec![];
+        loop {
+            let mut filtered = Video::empty();
+            if self
+                .graph
+                .get("out")
+                .unwrap()
+                .sink()
+                .frame(&mut filtered)
+                .is_err()
+            {
+                break;
+            }
+            filtered_frames.push(filtered);
+        }
+        Ok(filtered_frames)
+    }
+}
+
+/// An ffmpeg filter graph processing decoded audio frames. The output has the same format, rate and
+/// channel layout as the input.
+#[cfg(feature = "audio")]
+pub(crate) struct AudioFilter {
+    graph: ffmpeg::filter::Graph,
+}
+
+#[cfg(feature = "audio")]
+impl AudioFilter {
+    pub(crate) fn new(
+        spec: &str,
+        format: ffmpeg::format::Sample,
+        rate: u32,
+        channel_layout: &ChannelLayout,
+        time_base: Rational,
+    ) -> Result<Self> {
+        let mut graph = ffmpeg::filter::Graph::new();
+        let layout = channel_layout.description();
+        let args = format!(
+            "time_base={time_base}:sample_rate={rate}:sample_fmt={}:channel_layout={layout}",
+            format.name()
+        );
+        graph.add(&ffmpeg::filter::find("abuffer").unwrap(), "in", &args)?;
+        graph.add(&ffmpeg::filter::find("abuffersink").unwrap(), "out", "")?;
+        let spec = format!(
+            "{spec},aformat=sample_fmts={}:sample_rates={rate}:channel_layouts={layout}",
+            format.name()
+        );
+        graph.output("in", 0)?.input("out", 0)?.parse(&spec)?;
+        graph.validate()?;
+        Ok(Self { graph })
+    }
+
+    /// Push a frame through the filter and collect every frame that is ready.
+    pub(crate) fn run(&mut self, frame: &Audio) -> Result<Vec<Audio>> {
+        self.graph.get("in").unwrap().source().add(frame)?;
+        let mut filtered_frames = vec![];
+        loop {
+            let mut filtered = Audio::empty();
+            if self
+                .graph
+                .get("out")
+                .unwrap()
+                .sink()
+                .frame(&mut filtered)
+                .is_err()
+            {
+                break;
+            }
+            filtered_frames.push(filtered);
+        }
+        Ok(filtered_frames)
+    }
+}
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_map_filter_specs() {
+        let cases = [
+            (ChannelMap::new(vec![vec![1., 0.], vec![0., 1.]]), None),
+            (
+                ChannelMap::swap_left_right(),
+                Some("pan=stereo|c0=1*c1|c1=1*c0"),
+            ),
+            (
+                ChannelMap::new(vec![vec![0.5, -0.5]]),
+                Some("pan=stereo|c0=0.5*c0-0.5*c1|c1=c1"),
+            ),
+            (
+                ChannelMap::new(vec![vec![-1.], vec![0., 0.]]),
+                Some("pan=stereo|c0=-1*c0|c1=0*c1"),
+            ),
+        ];
+        for (map, spec) in cases {
+            assert_eq!(map.filter_spec("stereo", 2).as_deref(), spec, "{map:?}");
+        }
+    }
+}
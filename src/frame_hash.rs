@@ -0,0 +1,29 @@
+use ffmpeg::ffi::av_image_get_linesize;
+use ffmpeg::util::frame::Video;
+use xxhash_rust::xxh3::Xxh3;
+
+/// The xxh3 hash of the planes of `frame`, together with its size and pixel format. The padding
+/// at the end of the rows is left out, so frames with the same pixels hash the same however the
+/// decoder aligned them.
+pub(crate) fn hash_frame(frame: &Video) -> u64 {
+    let mut hasher = Xxh3::new();
+    let format = frame.format();
+    for value in [
+        frame.width(),
+        frame.height(),
+        ffmpeg::ffi::AVPixelFormat::from(format) as u32,
+    ] {
+        hasher.update(&value.to_le_bytes());
+    }
+    for plane in 0..frame.planes() {
+        let row_len =
+            unsafe { av_image_get_linesize(format.into(), frame.width() as i32, plane as i32) }
+                .max(0) as usize;
+        let stride = frame.stride(plane);
+        let data = frame.data(plane);
+        for row in 0..frame.plane_height(plane) as usize {
+            hasher.update(&data[row * stride..row * stride + row_len]);
+        }
+    }
+    hasher.digest()
+}
@@ -0,0 +1,335 @@
+use crate::content_filter::filter_regions;
+use crate::input::{open_input, InputOptions};
+use crate::keyframes::scan_keyframes;
+use crate::{
+    fit_frame_size, millisec_to_timestamp, timestamp_to_millisec, video_frame_to_image, MediaInfo,
+    RegionFilter, TimeRange,
+};
+use anyhow::{Context as _, Result};
+use egui::ColorImage;
+use ffmpeg::error::EAGAIN;
+use ffmpeg::format::context::input::Input;
+use ffmpeg::format::Pixel;
+use ffmpeg::media::Type;
+use ffmpeg::software::scaling::{context::Context, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+use ffmpeg::{rescale, Rational};
+use std::collections::VecDeque;
+
+// how many of the most recently decoded frames are kept for repeated or nearby requests.
+const CACHED_FRAMES: usize = 8;
+// requests this far ahead of the last decoded frame are reached by decoding instead of seeking.
+const FORWARD_DECODE_MS: i64 = 2000;
+
+/// How closely a frame returned by [`FrameServer::frame_at`] matches the requested time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Accuracy {
+    /// The keyframe at or before the requested time. Fast, since only one frame is decoded after seeking.
+    Keyframe,
+    /// The frame that is shown at the requested time, decoded forward from the preceding keyframe.
+    #[default]
+    Exact,
+}
+
+/// A frame decoded by a [`FrameServer`], see [`FrameServer::frames`].
+#[derive(Clone)]
+pub struct DecodedFrame {
+    /// The presentation time of the frame, in milliseconds.
+    pub time_ms: i64,
+    /// The frame's image, cropped and rotated like it is displayed.
+    pub image: ColorImage,
+}
+
+/// Serves single frames at arbitrary times, for random access like scrubbing in an editor. Opens its
+/// own input and decoder, so it is independent of the realtime playback of a [`crate::Player`]
+/// (see [`crate::Player::frame_server`]). Sequential and nearby requests reuse the decoder position
+/// and recently decoded frames instead of seeking each time.
+pub struct FrameServer {
+    input_path: String,
+    input_options: InputOptions,
+    input_context: Input,
+    decoder: ffmpeg::decoder::Video,
+    stream_index: usize,
+    time_base: Rational,
+    /// The total duration of the stream, in milliseconds.
+    pub duration_ms: i64,
+    /// Properties of the video stream. Served frames are cropped and rotated like they are displayed.
+    pub media_info: MediaInfo,
+    // the frames decoded since the last seek, oldest first.
+    frames: VecDeque<DecodedFrame>,
+    pub(crate) keyframes: Option<Vec<i64>>,
+    region_filters: Vec<RegionFilter>,
+    // the longer side frames are scaled down to, `0` for the full size
+    max_frame_size: u32,
+    // whether the end of the input was sent to the decoder, and whether the decoder is drained.
+    draining: bool,
+    eof: bool,
+}
+
+impl FrameServer {
+    /// Open the video stream of `input_path`.
+    pub fn new(input_path: &str) -> Result<Self> {
+        Self::new_with_input_options(input_path, &InputOptions::default())
+    }
+
+    /// Open the video stream of `input_path`, passing `input_options` to ffmpeg.
+    pub fn new_with_input_options(input_path: &str, input_options: &InputOptions) -> Result<Self> {
+        let input_context = open_input(input_path, input_options)?;
+        let video_stream = input_context
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let stream_index = video_stream.index();
+        let time_base = video_stream.time_base();
+        let video_context =
+            ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
+        let decoder = video_context.decoder().video()?;
+        let media_info = MediaInfo::new(input_path, &video_stream, &decoder);
+        let duration_ms = timestamp_to_millisec(input_context.duration(), rescale::TIME_BASE);
+        Ok(Self {
+            input_path: input_path.to_string(),
+            input_options: input_options.clone(),
+            input_context,
+            decoder,
+            stream_index,
+            time_base,
+            duration_ms,
+            media_info,
+            frames: VecDeque::new(),
+            keyframes: None,
+            region_filters: Vec::new(),
+            max_frame_size: 0,
+            draining: false,
+            eof: false,
+        })
+    }
+
+    /// The presentation times of the keyframes of the video stream, in milliseconds. The index is
+    /// built on the first call by reading the whole input, and lets exact requests skip ahead by
+    /// seeking whenever a keyframe is closer than the current decoder position.
+    pub fn keyframes(&mut self) -> Result<&[i64]> {
+        if self.keyframes.is_none() {
+            self.keyframes = Some(scan_keyframes(&self.input_path, &self.input_options)?);
+        }
+        Ok(self.keyframes.as_deref().unwrap_or_default())
+    }
+
+    /// Blur or pixelate parts of the served frames, see [`crate::Player::set_region_filters`].
+    pub fn set_region_filters(&mut self, region_filters: Vec<RegionFilter>) {
+        self.region_filters = region_filters;
+    }
+
+    /// Scale the served frames down to fit in `max_size` pixels on their longer side, see
+    /// [`crate::Player::set_max_frame_size`]. Frames decoded before keep their size.
+    pub fn set_max_frame_size(&mut self, max_size: Option<u32>) {
+        self.max_frame_size = max_size.unwrap_or(0);
+    }
+
+    /// The frame at `time_ms` milliseconds of stream time, with the given [`Accuracy`].
+    pub fn frame_at(&mut self, time_ms: i64, accuracy: Accuracy) -> Result<ColorImage> {
+        let (frame_ms, mut image) = self.decoded_frame_at(time_ms, accuracy)?;
+        let video_size = self.media_info.display_size;
+        filter_regions(&self.region_filters, frame_ms, video_size, &mut image);
+        Ok(image)
+    }
+
+    /// Decode the frames inside `range` in order, keeping every `stride`th of them (`1` keeps all),
+    /// for batch processing like extracting a dataset or laying out a contact sheet. The frames are
+    /// decoded as fast as possible, none are skipped by seeking, and the region filters are
+    /// applied like for [`FrameServer::frame_at`]. The iteration ends after the first error.
+    pub fn frames(&mut self, range: TimeRange, stride: usize) -> Frames<'_> {
+        Frames {
+            server: self,
+            range,
+            stride: stride.max(1),
+            index: 0,
+            started: false,
+            done: false,
+        }
+    }
+
+    // the frame at `time_ms` as decoded, with its own time.
+    pub(crate) fn decoded_frame_at(
+        &mut self,
+        time_ms: i64,
+        accuracy: Accuracy,
+    ) -> Result<(i64, ColorImage)> {
+        if accuracy == Accuracy::Keyframe {
+            self.seek(time_ms)?;
+            self.decode_next()?;
+            return self
+                .frames
+                .back()
+                .map(|frame| (frame.time_ms, frame.image.clone()))
+                .with_context(|| format!("no frame at {time_ms}ms"));
+        }
+
+        if let Some(frame) = self.cached_frame_at(time_ms) {
+            return Ok((frame.time_ms, frame.image.clone()));
+        }
+        let reachable = self
+            .frames
+            .front()
+            .is_some_and(|first| first.time_ms <= time_ms)
+            && self.frames.back().is_some_and(|last| {
+                let keyframe_between = self.keyframes.as_ref().is_some_and(|keyframes| {
+                    keyframes
+                        .iter()
+                        .any(|k| (last.time_ms + 1..=time_ms).contains(k))
+                });
+                time_ms - last.time_ms < FORWARD_DECODE_MS && !keyframe_between
+            });
+        if !reachable {
+            self.seek(time_ms)?;
+        }
+        loop {
+            if let Some(frame) = self.cached_frame_at(time_ms) {
+                return Ok((frame.time_ms, frame.image.clone()));
+            }
+            // seeking can land after the target, so the earliest frame there is the closest one
+            if let Some(first) = self.frames.front().filter(|f| f.time_ms > time_ms) {
+                return Ok((first.time_ms, first.image.clone()));
+            }
+            if !self.decode_next()? {
+                anyhow::bail!("no frame at {time_ms}ms");
+            }
+        }
+    }
+
+    // the decoded frame shown at `time_ms`, which is only known once the frame after it is decoded.
+    fn cached_frame_at(&self, time_ms: i64) -> Option<&DecodedFrame> {
+        let next = self
+            .frames
+            .iter()
+            .position(|frame| frame.time_ms > time_ms)
+            .or(self.eof.then_some(self.frames.len()))?;
+        (next > 0).then(|| &self.frames[next - 1])
+    }
+
+    fn seek(&mut self, time_ms: i64) -> Result<()> {
+        let target_ts = millisec_to_timestamp(time_ms, rescale::TIME_BASE);
+        self.input_context.seek(target_ts, ..target_ts)?;
+        self.decoder.flush();
+        self.frames.clear();
+        self.draining = false;
+        self.eof = false;
+        Ok(())
+    }
+
+    // decode the next frame into the cache, returning `false` at the end of the stream.
+    fn decode_next(&mut self) -> Result<bool> {
+        let mut decoded_frame = Video::empty();
+        loop {
+            match self.decoder.receive_frame(&mut decoded_frame) {
+                Ok(()) => break,
+                Err(ffmpeg::Error::Eof) => {
+                    self.eof = true;
+                    return Ok(false);
+                }
+                Err(ffmpeg::Error::Other { errno }) if errno == EAGAIN => (),
+                Err(e) => return Err(e.into()),
+            }
+            match self.input_context.packets().next() {
+                Some(Ok((stream, packet))) if stream.index() == self.stream_index => {
+                    self.decoder.send_packet(&packet)?;
+                }
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(e.into()),
+                None if !self.draining => {
+                    self.decoder.send_eof()?;
+                    self.draining = true;
+                }
+                None => (),
+            }
+        }
+
+        let mut rgb_frame = Video::empty();
+        let (width, height) = fit_frame_size(
+            decoded_frame.width(),
+            decoded_frame.height(),
+            self.max_frame_size,
+        );
+        let mut scaler = Context::get(
+            decoded_frame.format(),
+            decoded_frame.width(),
+            decoded_frame.height(),
+            Pixel::RGBA,
+            width,
+            height,
+            Flags::BILINEAR,
+        )?;
+        scaler.run(&decoded_frame, &mut rgb_frame)?;
+        let time_ms = decoded_frame
+            .timestamp()
+            .or(decoded_frame.pts())
+            .map(|ts| timestamp_to_millisec(ts, self.time_base))
+            .unwrap_or_else(|| self.frames.back().map_or(0, |f| f.time_ms));
+
+        self.frames.push_back(DecodedFrame {
+            time_ms,
+            image: self.media_info.to_display(video_frame_to_image(rgb_frame)),
+        });
+        if self.frames.len() > CACHED_FRAMES {
+            self.frames.pop_front();
+        }
+        Ok(true)
+    }
+}
+
+/// The frames of a range of a video, see [`FrameServer::frames`].
+pub struct Frames<'a> {
+    server: &'a mut FrameServer,
+    range: TimeRange,
+    stride: usize,
+    // how many frames inside the range were decoded
+    index: usize,
+    started: bool,
+    done: bool,
+}
+
+impl Frames<'_> {
+    fn next_frame(&mut self) -> Result<Option<DecodedFrame>> {
+        if !self.started {
+            self.started = true;
+            self.server.seek(self.range.start_ms)?;
+        }
+        loop {
+            if !self.server.decode_next()? {
+                return Ok(None);
+            }
+            let Some(frame) = self.server.frames.back() else {
+                return Ok(None);
+            };
+            if frame.time_ms >= self.range.end_ms {
+                return Ok(None);
+            }
+            // seeking lands on the keyframe before the range
+            if frame.time_ms < self.range.start_ms {
+                continue;
+            }
+            self.index += 1;
+            if (self.index - 1).is_multiple_of(self.stride) {
+                let mut frame = frame.clone();
+                let (filters, video_size) = (
+                    &self.server.region_filters,
+                    self.server.media_info.display_size,
+                );
+                filter_regions(filters, frame.time_ms, video_size, &mut frame.image);
+                return Ok(Some(frame));
+            }
+        }
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<DecodedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.next_frame().transpose();
+        self.done = !matches!(frame, Some(Ok(_)));
+        frame
+    }
+}
@@ -0,0 +1,193 @@
+use crate::analysis::luma_grid;
+use crate::{timestamp_to_millisec, PlayerEvent};
+use egui::ColorImage;
+use ffmpeg::{Packet, Rational};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+// packets further apart than this on the wall clock are taken as a pause, not jitter.
+const MAX_ARRIVAL_GAP: Duration = Duration::from_secs(1);
+
+/// Configures the stream health monitoring of a [`crate::Player`], for watching live broadcasts,
+/// see [`crate::Player::enable_health_monitoring`].
+#[derive(Clone, Debug)]
+pub struct HealthOptions {
+    /// Report packets the demuxer flagged as corrupt with [`PlayerEvent::ContinuityError`].
+    /// MPEG-TS inputs flag them on continuity counter errors.
+    pub continuity_errors: bool,
+    /// Report [`PlayerEvent::JitterExceeded`] when packets arrive off their timestamps by more than
+    /// this, in milliseconds. Only meaningful for live inputs, files are read as fast as they decode.
+    pub jitter_threshold_ms: Option<f32>,
+    /// Report [`PlayerEvent::SilenceStarted`] when the audio has been silent for this long, in
+    /// milliseconds of stream time.
+    pub silence_after_ms: Option<i64>,
+    /// The level below which audio counts as silent, in dBFS.
+    pub silence_level_db: f32,
+    /// Report [`PlayerEvent::VideoFrozen`] when the video has shown the same picture for this long,
+    /// in milliseconds of stream time.
+    pub freeze_after_ms: Option<i64>,
+    /// The mean luma difference between consecutive frames (in `0.0..=1.0`) below which they count
+    /// as the same picture. Above `0.0`, as a frozen source encoded again isn't decoded bit-exact.
+    pub freeze_threshold: f32,
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        Self {
+            continuity_errors: true,
+            jitter_threshold_ms: None,
+            silence_after_ms: Some(10_000),
+            silence_level_db: -60.,
+            freeze_after_ms: Some(5_000),
+            freeze_threshold: 0.002,
+        }
+    }
+}
+
+// a condition (like silence) reported once it held for a while, and again when it ends
+#[derive(Default)]
+struct Condition {
+    since_ms: Option<i64>,
+    reported: bool,
+}
+
+enum ConditionChange {
+    Started { since_ms: i64 },
+    Ended { since_ms: i64 },
+}
+
+impl Condition {
+    fn update(&mut self, holds: bool, elapsed_ms: i64, after_ms: i64) -> Option<ConditionChange> {
+        if !holds {
+            let since_ms = self.since_ms.take()?;
+            return std::mem::take(&mut self.reported)
+                .then_some(ConditionChange::Ended { since_ms });
+        }
+        let since_ms = *self.since_ms.get_or_insert(elapsed_ms);
+        if !self.reported && elapsed_ms - since_ms >= after_ms {
+            self.reported = true;
+            return Some(ConditionChange::Started { since_ms });
+        }
+        None
+    }
+}
+
+/// Watches the packets and frames of one stream for anomalies, emitting them as [`PlayerEvent`]s.
+pub(crate) struct HealthMonitor {
+    options: HealthOptions,
+    event_sender: mpsc::Sender<PlayerEvent>,
+    // when the last packet arrived, with its timestamp in milliseconds
+    last_arrival: Option<(Instant, i64)>,
+    // the smoothed interarrival jitter, like that of rtp (rfc 3550)
+    jitter_ms: f32,
+    jitter_exceeded: bool,
+    previous_grid: Option<Vec<f32>>,
+    freeze: Condition,
+    #[cfg(feature = "audio")]
+    silence: Condition,
+}
+
+impl HealthMonitor {
+    pub(crate) fn new(options: HealthOptions, event_sender: mpsc::Sender<PlayerEvent>) -> Self {
+        Self {
+            options,
+            event_sender,
+            last_arrival: None,
+            jitter_ms: 0.,
+            jitter_exceeded: false,
+            previous_grid: None,
+            freeze: Condition::default(),
+            #[cfg(feature = "audio")]
+            silence: Condition::default(),
+        }
+    }
+
+    /// Forget the state from before a seek, which would otherwise be reported as an anomaly.
+    pub(crate) fn reset(&mut self) {
+        self.last_arrival = None;
+        self.jitter_ms = 0.;
+        self.previous_grid = None;
+        self.freeze = Condition::default();
+        #[cfg(feature = "audio")]
+        {
+            self.silence = Condition::default();
+        }
+    }
+
+    pub(crate) fn on_packet(&mut self, packet: &Packet, stream_index: usize, time_base: Rational) {
+        let Some(timestamp) = packet.dts().or(packet.pts()) else {
+            return;
+        };
+        let elapsed_ms = timestamp_to_millisec(timestamp, time_base);
+        if self.options.continuity_errors && packet.is_corrupt() {
+            let _ = self.event_sender.send(PlayerEvent::ContinuityError {
+                stream_index,
+                elapsed_ms,
+            });
+        }
+        let Some(threshold_ms) = self.options.jitter_threshold_ms else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some((last_instant, last_ms)) = self.last_arrival {
+            let arrival_gap = now.duration_since(last_instant);
+            if arrival_gap < MAX_ARRIVAL_GAP {
+                let deviation = arrival_gap.as_secs_f32() * 1000. - (elapsed_ms - last_ms) as f32;
+                self.jitter_ms += (deviation.abs() - self.jitter_ms) / 16.;
+            }
+        }
+        self.last_arrival = Some((now, elapsed_ms));
+        let exceeded = self.jitter_ms > threshold_ms;
+        if exceeded && !self.jitter_exceeded {
+            let _ = self.event_sender.send(PlayerEvent::JitterExceeded {
+                stream_index,
+                jitter_ms: self.jitter_ms,
+            });
+        }
+        self.jitter_exceeded = exceeded;
+    }
+
+    pub(crate) fn on_video_frame(&mut self, image: &ColorImage, elapsed_ms: i64) {
+        let Some(freeze_after_ms) = self.options.freeze_after_ms else {
+            return;
+        };
+        let grid = luma_grid(image);
+        let frozen = self.previous_grid.as_ref().is_some_and(|previous| {
+            let difference: f32 = previous.iter().zip(&grid).map(|(a, b)| (a - b).abs()).sum();
+            difference / 255. / grid.len() as f32 <= self.options.freeze_threshold
+        });
+        self.previous_grid = Some(grid);
+        let event = match self.freeze.update(frozen, elapsed_ms, freeze_after_ms) {
+            Some(ConditionChange::Started { since_ms }) => PlayerEvent::VideoFrozen {
+                elapsed_ms: since_ms,
+            },
+            Some(ConditionChange::Ended { since_ms }) => PlayerEvent::VideoResumed {
+                elapsed_ms,
+                duration_ms: elapsed_ms - since_ms,
+            },
+            None => return,
+        };
+        let _ = self.event_sender.send(event);
+    }
+
+    #[cfg(feature = "audio")]
+    pub(crate) fn on_audio(&mut self, samples: &[f32], elapsed_ms: i64) {
+        let Some(silence_after_ms) = self.options.silence_after_ms else {
+            return;
+        };
+        let mean_square = samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32;
+        let level_db = 10. * mean_square.max(1e-12).log10();
+        let silent = level_db < self.options.silence_level_db;
+        let event = match self.silence.update(silent, elapsed_ms, silence_after_ms) {
+            Some(ConditionChange::Started { since_ms }) => PlayerEvent::SilenceStarted {
+                elapsed_ms: since_ms,
+            },
+            Some(ConditionChange::Ended { since_ms }) => PlayerEvent::SilenceEnded {
+                elapsed_ms,
+                duration_ms: elapsed_ms - since_ms,
+            },
+            None => return,
+        };
+        let _ = self.event_sender.send(event);
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Result;
+use ffmpeg::codec::context::Context;
+use ffmpeg::codec::Parameters;
+use ffmpeg::error::{EAGAIN, EIO, ENOMEM, ENOSYS};
+use ffmpeg::ffi::*;
+use ffmpeg::format::Pixel;
+use ffmpeg::frame::Video;
+use parking_lot::Mutex;
+use std::fmt;
+
+/// A hardware decoding api, see [`crate::InputOptions::with_hwaccel`]. Only the ones the linked
+/// ffmpeg was built with and the machine has drivers for work, the video is decoded in software
+/// otherwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HwAccel {
+    /// VA-API, for Intel and AMD gpus on Linux.
+    Vaapi,
+    /// VDPAU, on Linux.
+    Vdpau,
+    /// NVDEC, for Nvidia gpus.
+    Cuda,
+    /// Intel Quick Sync Video.
+    Qsv,
+    /// Direct3D 11, on Windows.
+    D3d11va,
+    /// DXVA2, on older Windows.
+    Dxva2,
+    /// VideoToolbox, on macOS.
+    VideoToolbox,
+}
+
+impl HwAccel {
+    fn device_type(&self) -> AVHWDeviceType {
+        match self {
+            Self::Vaapi => AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+            Self::Vdpau => AVHWDeviceType::AV_HWDEVICE_TYPE_VDPAU,
+            Self::Cuda => AVHWDeviceType::AV_HWDEVICE_TYPE_CUDA,
+            Self::Qsv => AVHWDeviceType::AV_HWDEVICE_TYPE_QSV,
+            Self::D3d11va => AVHWDeviceType::AV_HWDEVICE_TYPE_D3D11VA,
+            Self::Dxva2 => AVHWDeviceType::AV_HWDEVICE_TYPE_DXVA2,
+            Self::VideoToolbox => AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+        }
+    }
+}
+
+/// Why a [`crate::Player`] decodes in software although hardware decoding was requested, see
+/// [`crate::Player::hw_fallback_reason`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HwFallbackReason {
+    /// The api can't decode the codec of the video, or ffmpeg was built without it.
+    UnsupportedCodec,
+    /// The hardware supports the codec, but not the profile of the video (like 10-bit H.264) or
+    /// its size.
+    UnsupportedProfile {
+        /// The ffmpeg profile of the video.
+        profile: i32,
+    },
+    /// The hardware ran out of surfaces to decode into, like when other applications use them.
+    OutOfSurfaces,
+    /// The device couldn't be opened (like without a driver), or failed while decoding.
+    DriverError {
+        /// The error ffmpeg reported.
+        error: String,
+    },
+}
+
+impl HwFallbackReason {
+    fn from_error(error: ffmpeg::Error) -> Self {
+        match error {
+            ffmpeg::Error::Other { errno } if errno == ENOMEM => Self::OutOfSurfaces,
+            error => Self::DriverError {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HwFallbackReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedCodec => write!(f, "the codec isn't supported"),
+            Self::UnsupportedProfile { profile } => {
+                write!(f, "profile {profile} of the codec isn't supported")
+            }
+            Self::OutOfSurfaces => write!(f, "out of decode surfaces"),
+            Self::DriverError { error } => write!(f, "driver error: {error}"),
+        }
+    }
+}
+
+// shared with the `get_format` callback of the decoder through its `opaque` pointer
+struct HwState {
+    pix_fmt: Pixel,
+    fallback: Mutex<Option<HwFallbackReason>>,
+}
+
+// the errors in a row, not from the hardware itself, after which decoding moves to software
+const MAX_FAILURES: u32 = 16;
+
+/// The hardware side of a decoder opened by [`open_video_decoder`]. The decoder points to it, so it
+/// has to live as long as the decoder.
+pub(crate) struct HwDecoding {
+    state: Box<HwState>,
+    // the errors of the decoder since the last decoded frame
+    failures: u32,
+}
+
+impl HwDecoding {
+    // set up `context`, before it is opened, to decode with `hwaccel`
+    fn new(context: &mut Context, hwaccel: HwAccel) -> Result<Self, HwFallbackReason> {
+        let codec =
+            ffmpeg::decoder::find(context.id()).ok_or(HwFallbackReason::UnsupportedCodec)?;
+        let device_type = hwaccel.device_type();
+        let pix_fmt = unsafe { hw_pixel_format(codec.as_ptr(), device_type) }
+            .ok_or(HwFallbackReason::UnsupportedCodec)?;
+        let mut device = std::ptr::null_mut();
+        let ret = unsafe {
+            av_hwdevice_ctx_create(
+                &mut device,
+                device_type,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if ret < 0 {
+            return Err(HwFallbackReason::from_error(ffmpeg::Error::from(ret)));
+        }
+        let state = Box::new(HwState {
+            pix_fmt,
+            fallback: Mutex::new(None),
+        });
+        unsafe {
+            let context = context.as_mut_ptr();
+            // the context takes over the reference to the device
+            (*context).hw_device_ctx = device;
+            (*context).opaque = &*state as *const HwState as *mut _;
+            (*context).get_format = Some(get_format);
+        }
+        Ok(Self { state, failures: 0 })
+    }
+
+    /// Why the decoder moved to software on its own, like for a profile the hardware can't decode.
+    pub(crate) fn fallback(&self) -> Option<HwFallbackReason> {
+        self.state.fallback.lock().clone()
+    }
+
+    /// Copy `frame` into main memory, if it was decoded on the hardware.
+    pub(crate) fn transfer(&self, frame: Video) -> Result<Video, HwFallbackReason> {
+        if frame.format() != self.state.pix_fmt {
+            return Ok(frame);
+        }
+        let mut transferred = Video::empty();
+        let ret = unsafe {
+            let ret = av_hwframe_transfer_data(transferred.as_mut_ptr(), frame.as_ptr(), 0);
+            if ret < 0 {
+                ret
+            } else {
+                av_frame_copy_props(transferred.as_mut_ptr(), frame.as_ptr())
+            }
+        };
+        if ret < 0 {
+            return Err(HwFallbackReason::from_error(ffmpeg::Error::from(ret)));
+        }
+        Ok(transferred)
+    }
+
+    /// The reason to move to software for an error of the decoder. Errors of the hardware (like
+    /// running out of surfaces or a failing driver) move at once. Others, like damaged packets, are
+    /// left to be reported as decode errors, unless `MAX_FAILURES` come in a row without a frame.
+    /// `None` for needing more input or the end of the stream.
+    pub(crate) fn fallback_for(&mut self, error: &ffmpeg::Error) -> Option<HwFallbackReason> {
+        match error {
+            ffmpeg::Error::Eof => None,
+            ffmpeg::Error::Other { errno } if *errno == EAGAIN => None,
+            ffmpeg::Error::Other { errno } if matches!(*errno, ENOMEM | EIO | ENOSYS) => {
+                Some(HwFallbackReason::from_error(*error))
+            }
+            error => {
+                self.failures += 1;
+                (self.failures >= MAX_FAILURES).then(|| HwFallbackReason::from_error(*error))
+            }
+        }
+    }
+
+    /// A frame was decoded, so the errors before it didn't keep the hardware from decoding.
+    pub(crate) fn decoded(&mut self) {
+        self.failures = 0;
+    }
+}
+
+// the pixel format of frames decoded by `codec` on a device of `device_type`
+unsafe fn hw_pixel_format(codec: *const AVCodec, device_type: AVHWDeviceType) -> Option<Pixel> {
+    for index in 0.. {
+        let config = avcodec_get_hw_config(codec, index);
+        if config.is_null() {
+            return None;
+        }
+        let methods = (*config).methods;
+        if methods & AV_CODEC_HW_CONFIG_METHOD_HW_DEVICE_CTX as i32 != 0
+            && (*config).device_type == device_type
+        {
+            return Some(Pixel::from((*config).pix_fmt));
+        }
+    }
+    None
+}
+
+unsafe extern "C" fn get_format(
+    context: *mut AVCodecContext,
+    formats: *const AVPixelFormat,
+) -> AVPixelFormat {
+    let state = &*((*context).opaque as *const HwState);
+    let mut format = formats;
+    while Pixel::from(*format) != Pixel::None {
+        if Pixel::from(*format) == state.pix_fmt {
+            return *format;
+        }
+        format = format.add(1);
+    }
+    // ffmpeg leaves out the hardware format when the hardware can't decode the stream, so the
+    // first format is decoded in software
+    *state.fallback.lock() = Some(HwFallbackReason::UnsupportedProfile {
+        profile: (*context).profile,
+    });
+    *formats
+}
+
+/// How [`open_video_decoder`] sets up the decoder, from [`crate::InputOptions`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct DecoderOptions {
+    pub(crate) hwaccel: Option<HwAccel>,
+    // `0` for one per cpu core, `None` for ffmpeg's default of one
+    pub(crate) threads: Option<usize>,
+    pub(crate) skip_non_reference_frames: bool,
+    // see `InputOptions::with_error_concealment`
+    pub(crate) drop_corrupt_frames: bool,
+}
+
+impl DecoderOptions {
+    fn context(&self, parameters: Parameters) -> Result<Context> {
+        let mut context = Context::from_parameters(parameters)?;
+        if self.drop_corrupt_frames {
+            unsafe { (*context.as_mut_ptr()).error_concealment = 0 };
+        }
+        if let Some(count) = self.threads {
+            context.set_threading(ffmpeg::threading::Config {
+                kind: ffmpeg::threading::Type::Frame,
+                count,
+            });
+        }
+        Ok(context)
+    }
+
+    fn open(&self, context: Context) -> Result<ffmpeg::decoder::Video, ffmpeg::Error> {
+        let mut decoder = context.decoder();
+        decoder.skip_frame(skip_frame(self.skip_non_reference_frames));
+        decoder.video()
+    }
+}
+
+/// The frames the decoder skips, see [`crate::PerformanceSettings::skip_non_reference_frames`].
+pub(crate) fn skip_frame(skip_non_reference_frames: bool) -> ffmpeg::Discard {
+    if skip_non_reference_frames {
+        ffmpeg::Discard::NonReference
+    } else {
+        ffmpeg::Discard::Default
+    }
+}
+
+/// Open a decoder for the video stream with `parameters`, on the hardware if `options` ask for it
+/// and it can, otherwise in software with the reason why.
+pub(crate) fn open_video_decoder(
+    parameters: Parameters,
+    options: &DecoderOptions,
+) -> Result<(
+    ffmpeg::decoder::Video,
+    Option<HwDecoding>,
+    Option<HwFallbackReason>,
+)> {
+    let Some(hwaccel) = options.hwaccel else {
+        let decoder = options.open(options.context(parameters)?)?;
+        return Ok((decoder, None, None));
+    };
+    let mut context = options.context(parameters.clone())?;
+    let reason = match HwDecoding::new(&mut context, hwaccel) {
+        Ok(hw_decoding) => match options.open(context) {
+            Ok(decoder) => return Ok((decoder, Some(hw_decoding), None)),
+            Err(error) => HwFallbackReason::from_error(error),
+        },
+        Err(reason) => reason,
+    };
+    let decoder = options.open(options.context(parameters)?)?;
+    Ok((decoder, None, Some(reason)))
+}
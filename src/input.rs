@@ -0,0 +1,511 @@
+use crate::ffmpeg_log::SourceScope;
+use crate::hwaccel::DecoderOptions;
+use crate::HwAccel;
+use anyhow::Result;
+use ffmpeg::error::{
+    EAGAIN, ECONNREFUSED, ECONNRESET, EHOSTUNREACH, EIO, ENETDOWN, ENETUNREACH, ETIMEDOUT,
+};
+use ffmpeg::format::context::input::Input;
+use parking_lot::Mutex;
+use std::ffi::{c_int, c_void, CString};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Options passed to ffmpeg when opening the input of a [`crate::Player`]. These are handed
+/// to the demuxer and protocol layers, so anything `ffmpeg -i` accepts before the input works here.
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+    entries: Vec<(String, String)>,
+    format: Option<String>,
+    trust_header: bool,
+    hwaccel: Option<HwAccel>,
+    decode_threads: Option<usize>,
+    drop_corrupt_frames: bool,
+    io_policies: Vec<(Protocol, IoPolicy)>,
+}
+
+impl InputOptions {
+    /// Create an empty set of options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a raw ffmpeg option, replacing any previous value for `key`.
+    pub fn set(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        let key = key.into();
+        let value = value.to_string();
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key, value)),
+        }
+        self
+    }
+
+    /// Get the value of a previously set option.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Force the input format instead of probing it, like `ffmpeg -f`. This also allows opening
+    /// input devices, such as `lavfi` filter graphs.
+    pub fn with_format(mut self, format: impl Into<String>) -> Self {
+        self.format = Some(format.into());
+        self
+    }
+
+    /// Probe less of the input before playing it (see [`crate::PlayerOptions::fast_start`]), which
+    /// shortens opening short clips and network streams. Streams that only appear later in the input
+    /// may be missed.
+    pub fn with_fast_start(self) -> Self {
+        self.with_probe_size(64 * 1024)
+            .with_analyze_duration(std::time::Duration::from_millis(100))
+    }
+
+    /// Read at most `bytes` of the input to detect its streams and their codecs, like
+    /// `ffmpeg -probesize`. ffmpeg reads 5 MB by default; broken or sparse streams (like MPEG-TS
+    /// with late subtitles) may need more.
+    pub fn with_probe_size(self, bytes: u64) -> Self {
+        self.set("probesize", bytes)
+    }
+
+    /// Read at most `duration` of the input to detect its streams and their codecs, like
+    /// `ffmpeg -analyzeduration`. ffmpeg analyzes 5 seconds by default.
+    pub fn with_analyze_duration(self, duration: std::time::Duration) -> Self {
+        self.set("analyzeduration", duration.as_micros())
+    }
+
+    /// Take the streams described by the header of the container as they are, without decoding
+    /// the start of the input to complete them. Makes opening well-formed files with complete
+    /// headers (like MP4 and MKV) much faster. Inputs without a header describing any stream are
+    /// still probed.
+    pub fn with_trusted_header(mut self) -> Self {
+        self.trust_header = true;
+        self
+    }
+
+    /// Decode the video on the hardware with `hwaccel`, like `ffmpeg -hwaccel`. When it can't, the
+    /// video is decoded in software and [`crate::Player::hw_fallback_reason`] tells why.
+    pub fn with_hwaccel(mut self, hwaccel: HwAccel) -> Self {
+        self.hwaccel = Some(hwaccel);
+        self
+    }
+
+    /// Decode the video with `count` threads, `0` for one per cpu core. ffmpeg uses a single
+    /// thread by default.
+    pub fn with_decode_threads(mut self, count: usize) -> Self {
+        self.decode_threads = Some(count);
+        self
+    }
+
+    /// Repair damaged video frames (guessing the motion of the lost parts and smoothing them) and
+    /// show them, `true` by default. Without, damaged frames are dropped, so playback stutters over
+    /// the damage instead of showing it. Either way, they are reported as
+    /// [`crate::PlayerEvent::DecodeError`].
+    pub fn with_error_concealment(mut self, conceal: bool) -> Self {
+        self.drop_corrupt_frames = !conceal;
+        self
+    }
+
+    /// Use `policy` for the inputs of `protocol`, instead of waiting indefinitely and not trying
+    /// again, see [`IoPolicy`].
+    pub fn with_io_policy(mut self, protocol: Protocol, policy: IoPolicy) -> Self {
+        match self.io_policies.iter_mut().find(|(p, _)| *p == protocol) {
+            Some(entry) => entry.1 = policy,
+            None => self.io_policies.push((protocol, policy)),
+        }
+        self
+    }
+
+    /// The [`IoPolicy`] inputs of `protocol` are opened and read with.
+    pub fn io_policy(&self, protocol: Protocol) -> IoPolicy {
+        self.io_policies
+            .iter()
+            .find(|(p, _)| *p == protocol)
+            .map_or(IoPolicy::default(), |(_, policy)| *policy)
+    }
+
+    /// Use [`IoPolicy::recommended_for`] each protocol, for the protocols without a policy yet.
+    pub fn with_recommended_io_policies(mut self) -> Self {
+        for protocol in [
+            Protocol::File,
+            Protocol::Http,
+            Protocol::Rtsp,
+            Protocol::Other,
+        ] {
+            if !self.io_policies.iter().any(|(p, _)| *p == protocol) {
+                self.io_policies
+                    .push((protocol, IoPolicy::recommended_for(protocol)));
+            }
+        }
+        self
+    }
+
+    pub(crate) fn decoder_options(&self) -> DecoderOptions {
+        DecoderOptions {
+            hwaccel: self.hwaccel,
+            threads: self.decode_threads,
+            skip_non_reference_frames: false,
+            drop_corrupt_frames: self.drop_corrupt_frames,
+        }
+    }
+
+    /// Apply [`SrtOptions`], used when the input is an `srt://` url. Enabled with the `network`
+    /// feature.
+    #[cfg(feature = "network")]
+    pub fn with_srt(mut self, srt: SrtOptions) -> Self {
+        if let Some(passphrase) = srt.passphrase {
+            self = self.set("passphrase", passphrase);
+        }
+        if let Some(latency_ms) = srt.latency_ms {
+            // ffmpeg's srt protocol takes latency in microseconds.
+            self = self.set("latency", latency_ms as u64 * 1000);
+        }
+        if let Some(stream_id) = srt.stream_id {
+            self = self.set("streamid", stream_id);
+        }
+        self
+    }
+
+    /// Apply [`UdpOptions`], used when the input is a `udp://` url. ffmpeg's `rtp://` protocol
+    /// only takes `buffer_size` and `local_address` of them, see [`UdpOptions`]. Enabled with the
+    /// `network` feature.
+    #[cfg(feature = "network")]
+    pub fn with_udp(mut self, udp: UdpOptions) -> Self {
+        if let Some(buffer_size) = udp.buffer_size {
+            self = self.set("buffer_size", buffer_size);
+        }
+        if let Some(reuse) = udp.reuse {
+            self = self.set("reuse", reuse as u8);
+        }
+        if let Some(local_address) = udp.local_address {
+            self = self.set("localaddr", local_address);
+        }
+        if let Some(fifo_size) = udp.fifo_size {
+            self = self.set("fifo_size", fifo_size);
+        }
+        if let Some(overrun_nonfatal) = udp.overrun_nonfatal {
+            self = self.set("overrun_nonfatal", overrun_nonfatal as u8);
+        }
+        self
+    }
+
+    fn to_dictionary(&self) -> ffmpeg::Dictionary<'static> {
+        let mut dictionary = ffmpeg::Dictionary::new();
+        for (key, value) in &self.entries {
+            dictionary.set(key, value);
+        }
+        dictionary
+    }
+
+    // the options with the protocol options of the `IoPolicy` of `path` added, unless they are set
+    fn with_io_options(&self, path: &str) -> Self {
+        let protocol = Protocol::of(path);
+        let policy = self.io_policy(protocol);
+        let mut entries: Vec<(&str, String)> = Vec::new();
+        if let Some(read_timeout) = policy.read_timeout {
+            // the rtsp demuxer has its own socket timeout, the other protocols (and http's
+            // reconnects) use the generic one
+            let key = match protocol {
+                Protocol::Rtsp => "timeout",
+                _ => "rw_timeout",
+            };
+            entries.push((key, read_timeout.as_micros().to_string()));
+        }
+        if protocol == Protocol::Http && policy.retries > 0 {
+            entries.extend([
+                ("reconnect", "1".to_string()),
+                ("reconnect_streamed", "1".to_string()),
+                ("reconnect_on_network_error", "1".to_string()),
+                ("reconnect_max_retries", policy.retries.to_string()),
+                (
+                    "reconnect_delay_max",
+                    policy.retry_delay.as_secs().max(1).to_string(),
+                ),
+            ]);
+        }
+        let mut options = self.clone();
+        for (key, value) in entries {
+            if options.get(key).is_none() {
+                options = options.set(key, value);
+            }
+        }
+        options
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(passphrase) = self.get("passphrase") {
+            if !(10..=79).contains(&passphrase.len()) {
+                anyhow::bail!("srt passphrase must be between 10 and 79 characters long");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Options for the SRT (Secure Reliable Transport) protocol.
+#[cfg(feature = "network")]
+#[derive(Clone, Debug, Default)]
+pub struct SrtOptions {
+    /// The passphrase used to decrypt the stream. Must be 10 to 79 characters long.
+    pub passphrase: Option<String>,
+    /// The receiver latency, in milliseconds.
+    pub latency_ms: Option<u32>,
+    /// The stream id, used by servers to select which stream to send.
+    pub stream_id: Option<String>,
+}
+
+/// The kind of protocol an input is read with, which picks its [`IoPolicy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// Local and network file systems, for paths and `file:` urls.
+    File,
+    /// `http://` and `https://` urls, including HLS and DASH.
+    Http,
+    /// `rtsp://` and `rtsps://` urls.
+    Rtsp,
+    /// Any other url, like `udp://`, `srt://` or a device.
+    Other,
+}
+
+impl Protocol {
+    /// The protocol of the input at `path`, by the scheme of its url.
+    pub fn of(path: &str) -> Self {
+        let Some((scheme, _)) = path.split_once("://") else {
+            return Self::File;
+        };
+        match scheme.to_ascii_lowercase().as_str() {
+            "file" => Self::File,
+            "http" | "https" => Self::Http,
+            "rtsp" | "rtsps" => Self::Rtsp,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// How long opening and reading an input may take, and how often it is tried again, see
+/// [`InputOptions::with_io_policy`]. Without a timeout, an input that stops answering (like a
+/// server going away) blocks its player until it answers again. The default waits indefinitely
+/// and doesn't try again, like ffmpeg.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoPolicy {
+    /// How long opening the input (including probing its streams) may take before it fails,
+    /// `None` to wait indefinitely. The open is interrupted through the interrupt callback of
+    /// ffmpeg, which protocols check while they wait.
+    pub open_timeout: Option<Duration>,
+    /// How long a single read may wait for data before it fails, `None` to wait indefinitely.
+    /// Reads of a file that are stuck in the kernel (like on a hung hard-mounted NFS share) can't
+    /// be interrupted, and only fail once the system call returns; mount such shares `soft`.
+    pub read_timeout: Option<Duration>,
+    /// How often an open that failed for a reason that may pass (like a timeout, a refused
+    /// connection or a server error) is tried again. Inputs that don't exist, are forbidden or
+    /// can't be read are not tried again. HTTP inputs also reconnect this often when the
+    /// connection drops while reading.
+    pub retries: u32,
+    /// How long to wait before trying again.
+    pub retry_delay: Duration,
+}
+
+impl Default for IoPolicy {
+    fn default() -> Self {
+        Self {
+            open_timeout: None,
+            read_timeout: None,
+            retries: 0,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl IoPolicy {
+    /// A policy that suits most inputs of `protocol`, see
+    /// [`InputOptions::with_recommended_io_policies`]: network protocols time out and retry,
+    /// files time out, and other inputs (like live udp streams, which may be quiet for a while)
+    /// wait indefinitely.
+    pub fn recommended_for(protocol: Protocol) -> Self {
+        let (timeout, retries) = match protocol {
+            Protocol::File => (Some(Duration::from_secs(10)), 0),
+            Protocol::Http => (Some(Duration::from_secs(15)), 3),
+            Protocol::Rtsp => (Some(Duration::from_secs(10)), 3),
+            Protocol::Other => (None, 0),
+        };
+        Self {
+            open_timeout: timeout,
+            read_timeout: timeout,
+            retries,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+pub(crate) fn open_input(path: &str, options: &InputOptions) -> Result<Input> {
+    options.validate()?;
+    let protocol = Protocol::of(path);
+    #[cfg(not(feature = "network"))]
+    if protocol != Protocol::File {
+        anyhow::bail!("{path} is a url, which needs the `network` feature");
+    }
+    let policy = options.io_policy(protocol);
+    let options = options.with_io_options(path);
+    let mut attempt = 0;
+    loop {
+        match open_input_once(path, &options, policy.open_timeout) {
+            Ok(input) => return Ok(input),
+            Err(e) if attempt >= policy.retries || !is_transient(&e) => return Err(e),
+            Err(_) => {
+                attempt += 1;
+                std::thread::sleep(policy.retry_delay);
+            }
+        }
+    }
+}
+
+// whether opening may succeed when tried again, unlike for inputs that don't exist or are
+// forbidden
+fn is_transient(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<ffmpeg::Error>() {
+        Some(ffmpeg::Error::Exit | ffmpeg::Error::HttpServerError) => true,
+        Some(ffmpeg::Error::Other { errno }) => matches!(
+            *errno,
+            EAGAIN
+                | ETIMEDOUT
+                | ECONNREFUSED
+                | ECONNRESET
+                | EHOSTUNREACH
+                | ENETUNREACH
+                | ENETDOWN
+                | EIO
+        ),
+        _ => false,
+    }
+}
+
+fn open_input_once(path: &str, options: &InputOptions, timeout: Option<Duration>) -> Result<Input> {
+    let _log_source = SourceScope::new(path);
+    let input_format = match options.format.as_ref() {
+        Some(format) => {
+            // input devices aren't registered with the demuxers
+            ffmpeg::device::register_all();
+            let name = CString::new(format.as_str())?;
+            let input_format = unsafe { ffmpeg::ffi::av_find_input_format(name.as_ptr()) };
+            if input_format.is_null() {
+                anyhow::bail!("unknown input format: {format}");
+            }
+            input_format
+        }
+        None => std::ptr::null(),
+    };
+    let deadline = timeout.map(|timeout| Deadline::new(Instant::now() + timeout));
+    let opened = open_context(path, input_format, options, deadline.as_ref());
+    match (opened, timeout) {
+        (Err(error), Some(timeout))
+            if error.downcast_ref::<ffmpeg::Error>() == Some(&ffmpeg::Error::Exit) =>
+        {
+            Err(error.context(format!(
+                "opening {path} timed out after {}ms",
+                timeout.as_millis()
+            )))
+        }
+        (opened, _) => opened,
+    }
+}
+
+// the deadlines of the opens in progress, by their id. Protocols keep a copy of the interrupt
+// callback for as long as the input is open, so it looks the deadline up rather than pointing
+// to it, and once the open is done it finds none
+static DEADLINES: Mutex<Vec<(usize, Instant)>> = Mutex::new(Vec::new());
+static NEXT_DEADLINE_ID: AtomicUsize = AtomicUsize::new(0);
+
+// a deadline interrupting an open, until it is dropped
+struct Deadline {
+    id: usize,
+}
+
+impl Deadline {
+    fn new(at: Instant) -> Self {
+        let id = NEXT_DEADLINE_ID.fetch_add(1, Ordering::Relaxed);
+        DEADLINES.lock().push((id, at));
+        Self { id }
+    }
+
+    fn interrupt_callback(&self) -> ffmpeg::ffi::AVIOInterruptCB {
+        ffmpeg::ffi::AVIOInterruptCB {
+            callback: Some(past_deadline),
+            opaque: self.id as *mut c_void,
+        }
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        DEADLINES.lock().retain(|(id, _)| *id != self.id);
+    }
+}
+
+unsafe extern "C" fn past_deadline(opaque: *mut c_void) -> c_int {
+    let id = opaque as usize;
+    let deadlines = DEADLINES.lock();
+    let deadline = deadlines.iter().find(|(other, _)| *other == id);
+    deadline.is_some_and(|(_, at)| Instant::now() >= *at) as c_int
+}
+
+// open the input like `input_with_dictionary`, interrupted at `deadline`. With a trusted header,
+// streams are only looked for if the header has none.
+fn open_context(
+    path: &str,
+    input_format: *const ffmpeg::ffi::AVInputFormat,
+    options: &InputOptions,
+    deadline: Option<&Deadline>,
+) -> Result<Input> {
+    let path = CString::new(path)?;
+    unsafe {
+        let mut context = ffmpeg::ffi::avformat_alloc_context();
+        if context.is_null() {
+            return Err(ffmpeg::Error::from(ffmpeg::ffi::AVERROR(ffmpeg::error::ENOMEM)).into());
+        }
+        if let Some(deadline) = deadline {
+            (*context).interrupt_callback = deadline.interrupt_callback();
+        }
+        let mut dictionary = options.to_dictionary().disown();
+        let result = ffmpeg::ffi::avformat_open_input(
+            &mut context,
+            path.as_ptr(),
+            input_format,
+            &mut dictionary,
+        );
+        ffmpeg::Dictionary::own(dictionary);
+        // the context is freed when the open fails
+        if result < 0 {
+            return Err(ffmpeg::Error::from(result).into());
+        }
+        if !options.trust_header || (*context).nb_streams == 0 {
+            let result = ffmpeg::ffi::avformat_find_stream_info(context, std::ptr::null_mut());
+            if result < 0 {
+                ffmpeg::ffi::avformat_close_input(&mut context);
+                return Err(ffmpeg::Error::from(result).into());
+            }
+        }
+        Ok(Input::wrap(context))
+    }
+}
+
+/// Socket options for the UDP protocol. The defaults of ffmpeg are quite small for multicast
+/// MPEG-TS, so raising `buffer_size` and `fifo_size` prevents dropped packets. For `rtp://` inputs,
+/// only `buffer_size` and `local_address` apply: the rtp protocol has no circular buffer and
+/// always reuses its sockets, so the other options are ignored there.
+#[cfg(feature = "network")]
+#[derive(Clone, Debug, Default)]
+pub struct UdpOptions {
+    /// The size of the system socket receive buffer, in bytes.
+    pub buffer_size: Option<usize>,
+    /// Allow other sockets to bind to the same address (needed for multiple receivers of a multicast group).
+    pub reuse: Option<bool>,
+    /// The local IP address of the interface to receive (and join multicast groups) on.
+    pub local_address: Option<String>,
+    /// The size of ffmpeg's circular receive buffer, in units of 188-byte packets.
+    pub fifo_size: Option<usize>,
+    /// Keep receiving when the circular buffer overruns, instead of failing the stream.
+    pub overrun_nonfatal: Option<bool>,
+}
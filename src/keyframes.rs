@@ -0,0 +1,29 @@
+use crate::input::{open_input, InputOptions};
+use crate::timestamp_to_millisec;
+use anyhow::Result;
+use ffmpeg::media::Type;
+
+/// Read every packet of the video stream of `input_path` (without decoding) and collect the
+/// presentation times of its keyframes, in milliseconds and ascending order.
+pub(crate) fn scan_keyframes(input_path: &str, input_options: &InputOptions) -> Result<Vec<i64>> {
+    let mut input_context = open_input(input_path, input_options)?;
+    let stream_index = input_context
+        .streams()
+        .best(Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?
+        .index();
+    let mut keyframes = vec![];
+    for packet in input_context.packets() {
+        let (stream, packet) = packet?;
+        if stream.index() != stream_index || !packet.is_key() {
+            continue;
+        }
+        match packet.pts().or(packet.dts()) {
+            Some(ffmpeg::ffi::AV_NOPTS_VALUE) | None => (),
+            Some(ts) => keyframes.push(timestamp_to_millisec(ts, stream.time_base())),
+        }
+    }
+    keyframes.sort_unstable();
+    keyframes.dedup();
+    Ok(keyframes)
+}
@@ -0,0 +1,122 @@
+use egui::{Color32, ColorImage, Rect, Vec2};
+use std::time::Duration;
+
+/// How the bars around a video fitted into its rect are filled, see
+/// [`crate::PlayerOptions::letterbox`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LetterboxFill {
+    /// A solid color.
+    Color(Color32),
+    /// Nothing, so whatever is behind the player shows through.
+    Transparent,
+    /// A blurred copy of the video stretched over the bars, darkened by `dim` (in `0.0..=1.0`),
+    /// like the ambient mode of modern players.
+    Ambient {
+        /// How much darker the fill is than the video.
+        dim: f32,
+    },
+}
+
+impl Default for LetterboxFill {
+    fn default() -> Self {
+        Self::Color(Color32::BLACK)
+    }
+}
+
+/// A blurred copy of the video glowing around it, see [`crate::PlayerOptions::ambient_glow`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientGlow {
+    /// How far the glow reaches past each side of the video, as a fraction of its size.
+    pub spread: f32,
+    /// The brightness of the glow, in `0.0..=1.0`.
+    pub intensity: f32,
+    /// How often the glow follows the video. It is blurred, so a few times a second is enough.
+    pub updates_per_second: f32,
+}
+
+impl Default for AmbientGlow {
+    fn default() -> Self {
+        Self {
+            spread: 0.15,
+            intensity: 0.6,
+            updates_per_second: 4.,
+        }
+    }
+}
+
+impl AmbientGlow {
+    pub(crate) fn update_interval(&self) -> Duration {
+        Duration::from_secs_f32(1. / self.updates_per_second.max(0.1))
+    }
+}
+
+/// How often the fill of [`LetterboxFill::Ambient`] follows the video without an [`AmbientGlow`].
+pub(crate) const AMBIENT_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+// the transparent pixels around the image of the glow, so its edges fade out when stretched
+const GLOW_PADDING: usize = 2;
+
+// the longer side of the image stretched over the bars for `LetterboxFill::Ambient`. Stretching
+// it with linear filtering blurs it.
+const AMBIENT_IMAGE_SIZE: usize = 16;
+
+/// The largest rect with the aspect ratio of `size` centered in `area`.
+pub(crate) fn fit_rect(area: Rect, size: Vec2) -> Rect {
+    if size.x <= 0. || size.y <= 0. {
+        return area;
+    }
+    let scale = (area.width() / size.x).min(area.height() / size.y);
+    Rect::from_center_size(area.center(), size * scale)
+}
+
+/// `image` scaled down by averaging, to stretch over the bars for [`LetterboxFill::Ambient`].
+pub(crate) fn ambient_image(image: &ColorImage) -> ColorImage {
+    scale_by_averaging(image, AMBIENT_IMAGE_SIZE)
+}
+
+/// `image` scaled to `longer_side` pixels on its longer side, each pixel the average of the ones
+/// it covers.
+pub(crate) fn scale_by_averaging(image: &ColorImage, longer_side: usize) -> ColorImage {
+    let [width, height] = image.size;
+    let image_longer_side = width.max(height).max(1);
+    let scaled_width = (width * longer_side / image_longer_side).max(1);
+    let scaled_height = (height * longer_side / image_longer_side).max(1);
+    let mut pixels = Vec::with_capacity(scaled_width * scaled_height);
+    for y in 0..scaled_height {
+        let (top, bottom) = (y * height / scaled_height, (y + 1) * height / scaled_height);
+        for x in 0..scaled_width {
+            let (left, right) = (x * width / scaled_width, (x + 1) * width / scaled_width);
+            let mut sum = [0u32; 4];
+            let mut count = 0;
+            for row in top..bottom.max(top + 1).min(height) {
+                for pixel in &image.pixels[row * width + left..row * width + right.max(left + 1)] {
+                    for (channel, value) in sum.iter_mut().zip(pixel.to_array()) {
+                        *channel += value as u32;
+                    }
+                    count += 1;
+                }
+            }
+            let [r, g, b, a] = sum.map(|channel| (channel / count.max(1)) as u8);
+            pixels.push(Color32::from_rgba_premultiplied(r, g, b, a));
+        }
+    }
+    ColorImage {
+        size: [scaled_width, scaled_height],
+        pixels,
+    }
+}
+
+/// `ambient`, from [`ambient_image`], with a transparent border for [`AmbientGlow`].
+pub(crate) fn glow_image(ambient: &ColorImage) -> ColorImage {
+    let [width, height] = ambient.size;
+    let padded_width = width + 2 * GLOW_PADDING;
+    let mut pixels = vec![Color32::TRANSPARENT; padded_width * (height + 2 * GLOW_PADDING)];
+    for (y, row) in ambient.pixels.chunks(width).enumerate() {
+        let start = (y + GLOW_PADDING) * padded_width + GLOW_PADDING;
+        pixels[start..start + width].copy_from_slice(row);
+    }
+    ColorImage {
+        size: [padded_width, height + 2 * GLOW_PADDING],
+        pixels,
+    }
+}
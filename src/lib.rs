@@ -10,37 +10,202 @@ use anyhow::Result;
 use atomic::Atomic;
 use bytemuck::NoUninit;
 use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "audio")]
 use cpal::traits::{DeviceTrait, HostTrait};
+#[cfg(feature = "audio")]
 use cpal::BuildStreamError;
-use egui::emath::RectTransform;
+#[cfg(feature = "subtitles")]
+use egui::emath::{GuiRounding, RectTransform};
+#[cfg(feature = "subtitles")]
+use egui::epaint::text::{Fonts, LayoutJob};
 use egui::epaint::Shadow;
 use egui::load::SizedTexture;
 
+use analysis::FrameAnalyzer;
+#[cfg(feature = "audio")]
 use cpal::traits::StreamTrait;
+use decode_errors::DecodeErrors;
 use egui::{
-    vec2, Align2, Color32, ColorImage, CornerRadius, FontId, Image, Pos2, Rect, Response, Sense,
-    Spinner, TextureHandle, TextureOptions, Ui, Vec2,
+    vec2, Align, Align2, Color32, ColorImage, CornerRadius, FontId, Image, Key, Layout, Pos2, Rect,
+    Response, Sense, Spinner, TextureHandle, TextureId, TextureOptions, Ui, UiBuilder, Vec2,
 };
+#[cfg(feature = "subtitles")]
+use egui::{FontFamily, Galley};
+use fatal::FatalError;
 use ffmpeg::error::EAGAIN;
 use ffmpeg::ffi::{AVERROR, AV_TIME_BASE};
 use ffmpeg::format::context::input::Input;
-use ffmpeg::format::{input, Pixel};
+use ffmpeg::format::Pixel;
+#[cfg(feature = "audio")]
 use ffmpeg::frame::Audio;
 use ffmpeg::media::Type;
+#[cfg(feature = "audio")]
+use ffmpeg::software;
 use ffmpeg::software::scaling::{context::Context, flag::Flags};
 use ffmpeg::util::frame::video::Video;
 use ffmpeg::{rescale, Packet, Rational, Rescale};
-use ffmpeg::{software, ChannelLayout};
+#[cfg(feature = "audio")]
+use filter::AudioFilter;
+use filter::VideoFilter;
+use health::HealthMonitor;
+use hwaccel::{DecoderOptions, HwDecoding};
+use input::open_input;
 use parking_lot::Mutex;
+use pool::PoolMembership;
+use shuttle::ShuttleState;
+use sleep_inhibit::SleepInhibitor;
+use std::any::Any;
 use std::collections::VecDeque;
 use std::ops::Deref;
+#[cfg(feature = "audio")]
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+#[cfg(feature = "audio")]
 use std::sync::mpsc;
-use std::sync::{Arc, Weak};
-use std::time::UNIX_EPOCH;
-use subtitle::Subtitle;
+use std::sync::{Arc, OnceLock, Weak};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "subtitles")]
+use subtitle::{AssScript, CachedLayout, FontAttachment, Subtitle, DEFAULT_PLAY_RES};
 use timer::{Guard, Timer};
-
+use upload::{PreparedUpload, UploadStage};
+
+mod analysis;
+mod branch;
+mod builder;
+#[cfg(all(feature = "export", feature = "experimental"))]
+mod burn_in;
+mod chapters;
+mod clock;
+mod concat;
+#[cfg(all(feature = "export", feature = "experimental"))]
+mod contact_sheet;
+mod content_filter;
+mod cue_points;
+mod damage;
+mod decode_errors;
+mod fatal;
+mod ffmpeg_info;
+mod ffmpeg_log;
+mod filter;
+mod frame_hash;
+mod frame_server;
+mod health;
+mod hwaccel;
+mod input;
+mod keyframes;
+mod letterbox;
+#[cfg(feature = "audio")]
+mod loudness;
+mod mask;
+mod media_info;
+mod performance;
+mod pool;
+pub mod prelude;
+mod preview;
+mod profile;
+mod refresh;
+#[cfg(feature = "audio")]
+mod resampler;
+mod review_cache;
+mod settings;
+mod shuttle;
+#[cfg(feature = "audio")]
+mod silence;
+mod skip;
+mod sleep_inhibit;
+mod sleep_timer;
+mod stats;
+#[cfg(feature = "subtitles")]
 mod subtitle;
+mod sync_test;
+mod timeline;
+mod timeshift;
+mod tracks;
+#[cfg(all(feature = "export", feature = "experimental"))]
+mod transcode;
+mod transition;
+mod up_next;
+mod upload;
+mod upscale;
+mod user_data;
+#[cfg(feature = "widgets")]
+pub mod widgets;
+
+pub use analysis::{CaptureFilter, FrameAnalysisOptions, MotionRegion};
+use branch::PendingBranch;
+pub use branch::{BranchChoice, BranchPoint, BranchTarget};
+pub use builder::PlayerBuilder;
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub use burn_in::BurnIn;
+pub use chapters::Chapter;
+use clock::PlaybackClock;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use concat::Cut;
+use concat::{concat_input, CutFilter, Segment};
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub use contact_sheet::generate_contact_sheet;
+pub use content_filter::{ContentAction, ContentFilter, RegionEffect, RegionFilter};
+pub use cue_points::CuePointId;
+use cue_points::CuePoints;
+pub use damage::{DamageOverlay, FrameDamage};
+pub use ffmpeg_info::{Component, FfmpegInfo, LibraryVersion, MissingComponents};
+pub use ffmpeg_log::{capture_ffmpeg_log, set_ffmpeg_log_level};
+#[cfg(feature = "audio")]
+pub use filter::{ChannelMap, TimeStretch, TimeStretchAlgorithm, TimeStretchQuality};
+pub use filter::{
+    Denoise, DenoiseFilter, DenoiseStrength, DisplayColor, DisplayPrimaries, DisplayTransfer,
+    Interpolation, InterpolationMode,
+};
+pub use frame_server::{Accuracy, DecodedFrame, FrameServer, Frames};
+pub use health::HealthOptions;
+pub use hwaccel::{HwAccel, HwFallbackReason};
+pub use input::{InputOptions, IoPolicy, Protocol};
+#[cfg(feature = "network")]
+pub use input::{SrtOptions, UdpOptions};
+pub use letterbox::{AmbientGlow, LetterboxFill};
+pub use media_info::{FieldOrder, MediaInfo};
+pub use performance::{PerformanceProfile, PerformanceSettings, UnfocusedBackoff};
+pub use pool::PlayerPool;
+pub use preview::PreviewProfile;
+pub use profile::PlaybackProfile;
+use refresh::RefreshMeter;
+pub use refresh::{DisplayRefresh, FramePacing};
+#[cfg(feature = "audio")]
+pub use resampler::{DitherMethod, ResamplerEngine, ResamplerOptions};
+use review_cache::ReviewCache;
+pub use review_cache::ReviewCacheSize;
+pub use settings::{SettingsStore, TitleSettings};
+pub use shuttle::Shuttle;
+#[cfg(feature = "audio")]
+pub use silence::SkipSilence;
+#[cfg(feature = "audio")]
+use silence::{SilenceDetector, SilentSpans};
+pub use skip::{SkipMode, TimeRange};
+use sleep_timer::SleepTimer;
+pub use stats::{PlaybackStats, SessionEvent, SessionEventKind};
+use stats::{SessionStats, StatsRecorder};
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub use subtitle::{export_subtitle_tracks, CaptionFormat, ExportedTrack};
+#[cfg(feature = "subtitles")]
+pub use subtitle::{
+    render_subtitle_image, SafeArea, SubtitleCue, SubtitleEncoding, SubtitleOptions,
+    SubtitlePlacement, WrapStyle,
+};
+use sync_test::SyncTest;
+pub use sync_test::SyncTestResult;
+pub use timeline::TimelineModel;
+use timeshift::TimeshiftWindow;
+pub use timeshift::{TimeshiftBuffer, TimeshiftOptions};
+pub use tracks::{language_name, TrackInfo};
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub use transcode::{Transcode, TranscodeOptions};
+#[cfg(all(feature = "export", feature = "experimental"))]
+use transcode::{TranscodeFilters, TranscodeInput};
+pub use transition::{Easing, Transition, Transitions};
+pub use up_next::UpNext;
+use up_next::UpNextState;
+pub use upscale::UpscaleFilter;
+pub use user_data::{TaggedEvent, UserData};
 
 #[cfg(feature = "from_bytes")]
 use tempfile::NamedTempFile;
@@ -57,14 +222,42 @@ fn format_duration(dur: Duration) -> String {
     }
 }
 
+// the buffer size asked for by `AudioBufferSize::LowLatency`, in frames (about 5ms at 48kHz).
+// Some backends report a minimum far below what they can keep filled.
+#[cfg(feature = "audio")]
+const LOW_LATENCY_BUFFER_FRAMES: u32 = 256;
+
+/// How much audio a [`CpalAudioDevice`] buffers, trading latency for robustness against underruns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "audio")]
+pub enum AudioBufferSize {
+    /// The default buffer size of the audio backend.
+    #[default]
+    Default,
+    /// A small buffer, for the lowest latency the device keeps up with. The streams are still
+    /// shared with other applications, since cpal doesn't open exclusive mode streams.
+    LowLatency,
+    /// A buffer of this many frames, clamped to what the device supports.
+    Frames(u32),
+}
+
 /// The playback device. Needs to be initialized (and kept alive!) for use by a [`Player`].
+///
+/// Audio is always decoded and played as PCM. cpal only opens PCM streams, so compressed audio
+/// (AC-3, DTS) can't be passed through to an S/PDIF or HDMI receiver for decoding.
+///
+/// Enabled with the `audio` feature.
+#[cfg(feature = "audio")]
 pub struct CpalAudioDevice {
     sample_format: cpal::SampleFormat,
     sample_rate: u32,
     callback: Arc<Mutex<AudioDeviceCallback>>,
     stream: cpal::Stream,
+    // the frames written by the last callback
+    buffer_frames: Shared<u32>,
 }
 
+#[cfg(feature = "audio")]
 impl CpalAudioDevice {
     /// Get the sample format.
     pub fn get_sample_format(&self) -> cpal::SampleFormat {
@@ -76,12 +269,20 @@ impl CpalAudioDevice {
         self.sample_rate
     }
 
+    /// The buffer size the backend actually uses, in frames, once it has asked for audio. Can
+    /// differ from the [`AudioBufferSize`] requested, and from one callback to the next.
+    pub fn buffer_size(&self) -> Option<u32> {
+        let frames = self.buffer_frames.get();
+        (frames > 0).then_some(frames)
+    }
+
     fn play(&self) {
         let _ = self.stream.play();
     }
 
     fn run<T>(
         callback: Arc<Mutex<AudioDeviceCallback>>,
+        buffer_frames: Shared<u32>,
         device: &cpal::Device,
         config: &cpal::StreamConfig,
     ) -> Result<cpal::Stream, BuildStreamError>
@@ -89,35 +290,85 @@ impl CpalAudioDevice {
         T: cpal::SizedSample + cpal::FromSample<f32> + std::iter::Sum<f32>,
     {
         let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
+        let channels = config.channels.max(1) as usize;
         device.build_output_stream(
             config,
-            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                Self::write_data(callback.clone(), data)
+            move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+                buffer_frames.set((data.len() / channels) as u32);
+                Self::write_data(callback.clone(), data, info)
             },
             err_fn,
             None,
         )
     }
 
-    fn write_data<T>(callback: Arc<Mutex<AudioDeviceCallback>>, output: &mut [T])
-    where
+    fn write_data<T>(
+        callback: Arc<Mutex<AudioDeviceCallback>>,
+        output: &mut [T],
+        info: &cpal::OutputCallbackInfo,
+    ) where
         T: cpal::Sample + cpal::FromSample<f32> + std::iter::Sum<f32>,
     {
-        callback.lock().callback(output);
+        let mut callback = callback.lock();
+        if let Some(output_timing) = callback.output_timing.as_ref() {
+            let timestamp = info.timestamp();
+            let latency = timestamp
+                .playback
+                .duration_since(&timestamp.callback)
+                .unwrap_or_default();
+            *output_timing.lock() = Some((Instant::now(), latency));
+        }
+        // any other panic plays silence, rather than unwinding into the audio driver
+        if catch_unwind(AssertUnwindSafe(|| callback.callback(output))).is_err() {
+            output.fill(T::EQUILIBRIUM);
+        }
     }
 
     /// Create a new [`AudioDevice`]. Creates an [`sdl2::AudioSubsystem`]. An [`AudioDevice`] is required for using audio.
     pub fn new() -> Self {
+        Self::new_with_buffer_size(AudioBufferSize::Default)
+    }
+
+    /// Create a new [`CpalAudioDevice`] buffering `buffer_size`. Falls back to the default buffer
+    /// size if the device refuses the requested one, see [`CpalAudioDevice::buffer_size`] for the
+    /// buffer size that was achieved.
+    pub fn new_with_buffer_size(buffer_size: AudioBufferSize) -> Self {
         let host = cpal::default_host();
         let device = host.default_output_device().unwrap();
         let callback = Arc::new(Mutex::new(AudioDeviceCallback::default()));
+        let buffer_frames = Shared::new(0);
         let config = device.default_output_config().unwrap();
         let sample_format = config.sample_format();
         let sample_rate = config.sample_rate().0;
-        let config_uw: &cpal::StreamConfig = &config.into();
-        let stream = match sample_format {
-            cpal::SampleFormat::F32 => Self::run::<f32>(callback.clone(), &device, config_uw),
+        let fixed_frames = match (buffer_size, config.buffer_size()) {
+            (AudioBufferSize::Default, _) => None,
+            (AudioBufferSize::LowLatency, cpal::SupportedBufferSize::Range { min, max }) => {
+                Some(LOW_LATENCY_BUFFER_FRAMES.clamp(*min, *max))
+            }
+            (AudioBufferSize::LowLatency, cpal::SupportedBufferSize::Unknown) => {
+                Some(LOW_LATENCY_BUFFER_FRAMES)
+            }
+            (AudioBufferSize::Frames(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+                Some(frames.clamp(*min, *max))
+            }
+            (AudioBufferSize::Frames(frames), cpal::SupportedBufferSize::Unknown) => Some(frames),
+        };
+        let mut config_uw: cpal::StreamConfig = config.into();
+        let build = |config: &cpal::StreamConfig| match sample_format {
+            cpal::SampleFormat::F32 => {
+                Self::run::<f32>(callback.clone(), buffer_frames.clone(), &device, config)
+            }
             sample_format => panic!("Unsupported sample format '{sample_format}'"),
+        };
+        let stream = match fixed_frames {
+            Some(frames) => {
+                config_uw.buffer_size = cpal::BufferSize::Fixed(frames);
+                build(&config_uw).or_else(|_| {
+                    config_uw.buffer_size = cpal::BufferSize::Default;
+                    build(&config_uw)
+                })
+            }
+            None => build(&config_uw),
         }
         .unwrap();
         CpalAudioDevice {
@@ -125,26 +376,328 @@ impl CpalAudioDevice {
             sample_rate,
             callback: callback.clone(),
             stream,
+            buffer_frames,
         }
     }
 }
 
+#[cfg(any(feature = "audio", feature = "subtitles"))]
 enum PlayerMessage {
-    StreamCycled(Type),
+    // the type of the streams, and how many of them were skipped
+    StreamCycled(Type, usize),
 }
 
+#[cfg(any(feature = "audio", feature = "subtitles"))]
 type PlayerMessageSender = std::sync::mpsc::Sender<PlayerMessage>;
+#[cfg(any(feature = "audio", feature = "subtitles"))]
 type PlayerMessageReciever = std::sync::mpsc::Receiver<PlayerMessage>;
 
+/// Events emitted by a [`Player`], see [`Player::events`]. New events are added in minor releases,
+/// so matches need a wildcard arm.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PlayerEvent {
+    /// Frame analysis detected motion between two consecutive frames. `score` is the mean luma
+    /// difference, in `0.0..=1.0`. Sent at most every
+    /// [`FrameAnalysisOptions::motion_event_interval_ms`] while motion goes on.
+    Motion {
+        /// The stream time of the frame, in milliseconds.
+        elapsed_ms: i64,
+        /// The mean luma difference to the previous frame.
+        score: f32,
+    },
+    /// Frame analysis detected a scene change.
+    SceneChange {
+        /// The stream time of the frame, in milliseconds.
+        elapsed_ms: i64,
+        /// The mean luma difference to the previous frame.
+        score: f32,
+    },
+    /// Motion started inside a region. `region` is the name of the [`MotionRegion`], or `None` if
+    /// no include regions are configured.
+    MotionStarted {
+        /// The region the motion was detected in.
+        region: Option<String>,
+        /// The stream time of the frame, in milliseconds.
+        elapsed_ms: i64,
+        /// The mean luma difference inside the region.
+        score: f32,
+    },
+    /// A region has been still for [`FrameAnalysisOptions::motion_stop_delay_ms`].
+    MotionStopped {
+        /// The region the motion was detected in.
+        region: Option<String>,
+        /// The stream time of the frame, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// A frame was saved by [`Player::capture_on`].
+    FrameCaptured {
+        /// The file the frame was written to.
+        path: PathBuf,
+        /// The stream time of the frame, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// Playback entered a skip range with [`SkipMode::Prompt`], so the skip button is shown.
+    SkipRangeEntered {
+        /// The range that was entered.
+        range: TimeRange,
+    },
+    /// A skip range was skipped, see [`Player::set_skip_ranges`]. Also emitted for
+    /// [`ContentAction::Skip`] filters.
+    Skipped {
+        /// The range that was skipped.
+        range: TimeRange,
+        /// Whether it was skipped by [`SkipMode::Auto`], instead of the skip button or [`Player::skip_current_range`].
+        automatic: bool,
+    },
+    /// The sleep timer set with [`Player::stop_at`] or [`Player::stop_after`] paused playback.
+    SleepTimerFired {
+        /// The stream time playback was paused at, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// The demuxer flagged a packet as corrupt, like on a continuity counter error of an MPEG-TS
+    /// input, see [`Player::enable_health_monitoring`].
+    ContinuityError {
+        /// The input stream the packet belongs to.
+        stream_index: usize,
+        /// The stream time of the packet, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// The packets of a stream arrive off their timestamps by more than
+    /// [`HealthOptions::jitter_threshold_ms`]. ffmpeg doesn't expose the PCR of MPEG-TS inputs, so
+    /// this is the interarrival jitter of the packet timestamps (like that of RTP), which follows it.
+    JitterExceeded {
+        /// The input stream the packets belong to.
+        stream_index: usize,
+        /// The smoothed jitter, in milliseconds.
+        jitter_ms: f32,
+    },
+    /// The audio has been silent for [`HealthOptions::silence_after_ms`].
+    SilenceStarted {
+        /// The stream time the silence started at, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// The audio is no longer silent, after [`PlayerEvent::SilenceStarted`].
+    SilenceEnded {
+        /// The stream time the silence ended at, in milliseconds.
+        elapsed_ms: i64,
+        /// How long the silence lasted, in milliseconds.
+        duration_ms: i64,
+    },
+    /// The video has shown the same picture for [`HealthOptions::freeze_after_ms`].
+    VideoFrozen {
+        /// The stream time the picture froze at, in milliseconds.
+        elapsed_ms: i64,
+    },
+    /// The picture changes again, after [`PlayerEvent::VideoFrozen`].
+    VideoResumed {
+        /// The stream time the picture changed at, in milliseconds.
+        elapsed_ms: i64,
+        /// How long the picture was frozen, in milliseconds.
+        duration_ms: i64,
+    },
+    /// The video is decoded in software although [`InputOptions::with_hwaccel`] asked for the
+    /// hardware, see [`Player::hw_fallback_reason`].
+    HwDecodingFallback {
+        /// Why the hardware isn't used.
+        reason: HwFallbackReason,
+    },
+    /// A packet couldn't be decoded or a frame came out damaged, like in a corrupt file. Playback
+    /// goes on, see also [`InputOptions::with_error_concealment`].
+    DecodeError {
+        /// The index of the stream in the input.
+        stream_index: usize,
+        /// The time of the packet or frame, in milliseconds.
+        elapsed_ms: i64,
+        /// What went wrong.
+        error: String,
+        /// Whether the packet or frame was dropped, rather than shown repaired.
+        dropped: bool,
+        /// The errors of the input so far, this one included, see [`Player::decode_error_count`].
+        count: u64,
+    },
+    /// Playback paused at a [`BranchPoint`], waiting for [`Player::choose_branch`].
+    BranchReached {
+        /// The id [`Player::add_branch_point`] returned.
+        id: CuePointId,
+        /// The choices to present.
+        choices: Vec<BranchChoice>,
+    },
+    /// Playback entered the countdown of the [`UpNext`] prompt set with [`Player::set_up_next`].
+    UpNextStarted {
+        /// The title of the next item.
+        title: String,
+        /// How long until the next item is asked for, in milliseconds.
+        remaining_ms: i64,
+    },
+    /// The countdown of the [`UpNext`] prompt stopped, as it was cancelled or playback went back
+    /// before it.
+    UpNextCancelled,
+    /// The app should play the next item: the countdown of the [`UpNext`] prompt ran out or the
+    /// next item was chosen right away. The prompt, with the data of the next item (see
+    /// [`UpNext::with_user_data`]), stays in [`Player::up_next`].
+    PlayNext {
+        /// Whether the countdown ran out, rather than [`Player::play_up_next`] being called.
+        automatic: bool,
+    },
+    /// A choice of a [`BranchPoint`] was taken, by the app or after its timeout.
+    BranchChosen {
+        /// The id [`Player::add_branch_point`] returned.
+        id: CuePointId,
+        /// The index of the choice.
+        choice: usize,
+        /// Where playback continues. The app opens a [`BranchTarget::Source`] itself, as it owns
+        /// the audio device, like with [`PlayerBuilder`] and [`Player::dissolve_from`] this player.
+        target: BranchTarget,
+    },
+    /// Playback jumped over silence, see [`Player::set_skip_silence`].
+    SilenceSkipped {
+        /// Where playback was and where it jumped to.
+        range: TimeRange,
+    },
+    /// A thread of the pipeline panicked, like on a bug in a decoder. The player stopped and
+    /// freed its decoders, the rest of the app goes on. It can't be started again, create a new
+    /// one to retry, see [`Player::fatal_error`].
+    Fatal {
+        /// The thread that panicked, `video`, `audio` or `subtitles`.
+        thread: &'static str,
+        /// The message of the panic.
+        error: String,
+    },
+}
+
+impl PlayerEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Motion { .. } => "motion",
+            Self::SceneChange { .. } => "scene_change",
+            Self::MotionStarted { .. } => "motion_started",
+            Self::MotionStopped { .. } => "motion_stopped",
+            Self::FrameCaptured { .. } => "frame_captured",
+            Self::SkipRangeEntered { .. } => "skip_range_entered",
+            Self::Skipped { .. } => "skipped",
+            Self::SleepTimerFired { .. } => "sleep_timer_fired",
+            Self::ContinuityError { .. } => "continuity_error",
+            Self::JitterExceeded { .. } => "jitter_exceeded",
+            Self::SilenceStarted { .. } => "silence_started",
+            Self::SilenceEnded { .. } => "silence_ended",
+            Self::VideoFrozen { .. } => "video_frozen",
+            Self::VideoResumed { .. } => "video_resumed",
+            Self::HwDecodingFallback { .. } => "hw_decoding_fallback",
+            Self::DecodeError { .. } => "decode_error",
+            Self::UpNextStarted { .. } => "up_next_started",
+            Self::UpNextCancelled => "up_next_cancelled",
+            Self::PlayNext { .. } => "play_next",
+            Self::BranchReached { .. } => "branch_reached",
+            Self::BranchChosen { .. } => "branch_chosen",
+            Self::SilenceSkipped { .. } => "silence_skipped",
+            Self::Fatal { .. } => "fatal",
+        }
+    }
+}
+
+/// When a video frame passed through each stage of the pipeline, for measuring latency.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameTimestamps {
+    /// When the frame was captured, if the source reports it (e.g. RTSP with RTCP sender reports).
+    pub capture: Option<SystemTime>,
+    /// When the last packet of the frame was read from the input.
+    pub receive: Option<Instant>,
+    /// When the frame came out of the decoder.
+    pub decode: Instant,
+    /// When the frame was uploaded to the player's texture.
+    pub present: Instant,
+    /// The wall clock time of [`FrameTimestamps::present`], for comparing against [`FrameTimestamps::capture`].
+    pub present_system: SystemTime,
+}
+
+impl FrameTimestamps {
+    /// The time between capture and presentation (glass-to-glass), if the capture time is known.
+    pub fn capture_to_present(&self) -> Option<std::time::Duration> {
+        self.present_system.duration_since(self.capture?).ok()
+    }
+    /// The time between receiving and presenting the frame.
+    pub fn receive_to_present(&self) -> Option<std::time::Duration> {
+        Some(self.present.duration_since(self.receive?))
+    }
+    /// The time between decoding and presenting the frame, i.e. how long it was buffered.
+    pub fn decode_to_present(&self) -> std::time::Duration {
+        self.present.duration_since(self.decode)
+    }
+}
+
+/// A video frame passed to the callback of [`Player::set_frame_callback`] when it is presented.
+pub struct PresentedFrame<'a> {
+    /// The frame's image.
+    pub image: &'a ColorImage,
+    /// The presentation time of the frame, in milliseconds.
+    pub presentation_time_ms: i64,
+    /// The pipeline timestamps of the frame.
+    pub timestamps: FrameTimestamps,
+    /// The hash of the decoded frame, with [`Player::set_frame_hashing`].
+    pub hash: Option<u64>,
+    /// The data of the player, see [`Player::set_user_data`].
+    pub user_data: Option<&'a UserData>,
+}
+
+/// A texture holding a presented frame, see [`Player::frame_textures`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTexture {
+    /// The texture, for painting it or binding it in a custom shader.
+    pub id: TextureId,
+    /// The size of the texture, in pixels.
+    pub size: [usize; 2],
+    /// The presentation time of the frame, in milliseconds.
+    pub presentation_time_ms: i64,
+}
+
+/// The textures of the current and previous frame, see [`Player::frame_textures`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTextures {
+    /// The frame on screen, `None` before the first one was presented.
+    pub current: Option<FrameTexture>,
+    /// The frame before it, only kept with [`Player::set_double_buffering`].
+    pub previous: Option<FrameTexture>,
+}
+
+/// A decoded video frame, waiting to be presented.
+pub struct VideoFrame {
+    /// The frame's image.
+    pub image: ColorImage,
+    /// When the frame was captured, if reported by the source.
+    pub capture: Option<SystemTime>,
+    /// When the last packet of the frame was read from the input.
+    pub receive: Option<Instant>,
+    /// When the frame came out of the decoder.
+    pub decode: Instant,
+    /// The damage the decoder reported, `None` for frames decoded intact.
+    pub damage: Option<FrameDamage>,
+    /// The hash of the decoded frame, with [`Player::set_frame_hashing`].
+    pub hash: Option<u64>,
+    pub(crate) upload: PreparedUpload,
+}
+
+type FrameCallback = Box<dyn FnMut(&PresentedFrame) + Send>;
+
+type TrackNameFormatter = Box<dyn Fn(&TrackInfo) -> String + Send>;
+
+type PlayerEventSender = std::sync::mpsc::Sender<PlayerEvent>;
+type PlayerEventReciever = std::sync::mpsc::Receiver<PlayerEvent>;
+
 type ApplyVideoFrameFn = Box<dyn FnMut(ColorImage) + Send>;
+#[cfg(feature = "subtitles")]
 type SubtitleQueue = Arc<Mutex<VecDeque<Subtitle>>>;
+// when the audio device last asked for samples, and how long until they are played
+type AudioOutputTiming = Arc<Mutex<Option<(Instant, std::time::Duration)>>>;
 
+#[cfg(feature = "audio")]
 struct StreamingAudioChunk {
     data: Vec<f32>,
     presentation_time_ms: i64,
     duration: i64,
 }
 
+#[cfg(feature = "audio")]
 impl StreamingAudioChunk {
     fn new(data: Vec<f32>) -> Self {
         StreamingAudioChunk {
@@ -155,11 +708,75 @@ impl StreamingAudioChunk {
     }
 }
 
+#[cfg(feature = "audio")]
 type AudioSampleProducer = mpsc::Sender<StreamingAudioChunk>;
+#[cfg(feature = "audio")]
 type AudioSampleConsumer = mpsc::Receiver<StreamingAudioChunk>;
 
+/// What [`Player::advance`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OfflineStep {
+    /// The next frame was presented.
+    Presented,
+    /// The next frame isn't decoded yet, call [`Player::advance`] again.
+    Pending,
+    /// There is no next frame, as the player isn't playing or is at the end of the stream.
+    Finished,
+}
+
+/// The clock that video frames are synchronized to.
+#[derive(Clone, Debug, Default)]
+pub enum SyncMaster {
+    /// Follow the audio device. Falls back to [`SyncMaster::Video`] if the player has no audio.
+    #[default]
+    Audio,
+    /// Follow a wall clock that runs while the player is playing. Audio plays freely alongside it.
+    Video,
+    /// Follow an externally driven clock, in milliseconds of stream time.
+    External(Shared<i64>),
+}
+
+/// Plays the video while the pointer hovers it, like the previews of a file browser, see
+/// [`PlayerOptions::hover_to_play`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HoverToPlay {
+    /// How long the pointer has to stay on the video before playback starts, so moving across a
+    /// grid of previews doesn't start all of them.
+    pub start_delay: std::time::Duration,
+    /// What happens when the pointer leaves the video.
+    pub on_leave: HoverLeave,
+}
+
+impl Default for HoverToPlay {
+    fn default() -> Self {
+        Self {
+            start_delay: std::time::Duration::from_millis(300),
+            on_leave: HoverLeave::default(),
+        }
+    }
+}
+
+/// What a [`Player`] started by [`HoverToPlay`] does when the pointer leaves it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HoverLeave {
+    /// Pause, and continue from there on the next hover.
+    #[default]
+    Pause,
+    /// Go back to the first frame.
+    Reset,
+}
+
 /// Configurable aspects of a [`Player`].
+///
+/// All of these can be changed while the player is running, and take effect on the next frame (see
+/// the setters on [`Player`]). The input options, audio device, subtitle stream and timeshift buffer
+/// are part of the pipeline instead, and changing them with [`Player::add_audio`],
+/// [`Player::add_subtitles`] or [`Player::add_timeshift`] stops and resets the player.
+///
+/// New options are added in minor releases, so start from [`PlayerOptions::default`] and set the
+/// fields needed (or use [`PlayerBuilder`]) rather than listing them all.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct PlayerOptions {
     /// Should the stream loop if it finishes?
     pub looping: bool,
@@ -167,8 +784,134 @@ pub struct PlayerOptions {
     pub audio_volume: Shared<f32>,
     /// The maximum volume of the audio stream.
     pub max_audio_volume: f32,
+    /// Gain applied to the audio stream on top of the volume, in decibels.
+    pub audio_gain_db: Shared<f32>,
+    /// Soft limit the audio output of this player, so boosted audio doesn't clip.
+    pub audio_limiter: Shared<bool>,
+    /// Fade the audio out and in over this many milliseconds when playback is paused, resumed or
+    /// seeked, instead of cutting it off and starting it abruptly, which can be heard as a click.
+    /// `0` turns the fades off.
+    pub audio_fade_ms: Shared<u32>,
+    /// Keep this many milliseconds of audio decoded while paused, so sound starts as soon as
+    /// playback resumes instead of after the decoder catches up. `0` turns the reserve off.
+    pub paused_audio_reserve_ms: Shared<u32>,
     /// The texture options for the displayed video frame.
     pub texture_options: TextureOptions,
+    /// Multiplied with the colors of the video, like for tinting it to show a state. White leaves
+    /// it unchanged.
+    pub tint: Color32,
+    /// The opacity of the video (in `0.0..=1.0`), like for dimming it behind a dialog.
+    pub opacity: f32,
+    /// Round the corners of the video, like for matching the card it is shown in. See
+    /// [`Player::set_clip_mask`] for other shapes.
+    pub corner_radius: CornerRadius,
+    /// Fit the video into the rect it is drawn in, keeping its aspect ratio, and fill the bars
+    /// around it. `None` stretches the video over the whole rect.
+    pub letterbox: Option<LetterboxFill>,
+    /// Draw a blurred copy of the video glowing around the rect it is drawn in.
+    pub ambient_glow: Option<AmbientGlow>,
+    /// Upscale frames with this filter when the video is drawn much larger than it is (like SD
+    /// content in a large view), instead of stretching the texture. This converts the frames at up
+    /// to twice their size, at a higher cpu and memory cost.
+    pub upscale: Option<UpscaleFilter>,
+    /// The clock that video frames are synchronized to.
+    pub sync_master: SyncMaster,
+    /// Delay video by the output latency the audio backend reports (see
+    /// [`Player::audio_output_latency`]) when synchronized to audio, so it stays in sync with
+    /// high latency outputs like bluetooth headphones. Off by default, as some backends report
+    /// latencies the output doesn't have.
+    pub compensate_audio_latency: bool,
+    /// How late the audio device plays audio, in milliseconds, on top of the reported output
+    /// latency. Video is delayed by the same amount when synchronized to audio. See
+    /// [`Player::sync_test`] for measuring it.
+    pub audio_delay_ms: i64,
+    /// How text subtitles are laid out.
+    #[cfg(feature = "subtitles")]
+    pub subtitles: SubtitleOptions,
+    /// How far before the target a seek starts reading subtitles, in milliseconds, so a cue that
+    /// began before the target but is still showing at it appears right away. Every seek then
+    /// reads the packets of all streams over this time, so it is `0` by default, only showing the
+    /// cues starting after the target. A few seconds (like `5_000`) cover most dialogue.
+    #[cfg(feature = "subtitles")]
+    pub subtitle_seek_preroll_ms: Shared<i64>,
+    /// How much later than their time subtitle cues show, in milliseconds, negative to show them
+    /// earlier. Cues already read keep their time until the next seek. See
+    /// [`PlayerOptions::set_subtitle_delay_ms`].
+    #[cfg(feature = "subtitles")]
+    pub subtitle_delay_ms: Shared<i64>,
+    /// Reload the file added with [`Player::add_subtitle_file`] whenever it changes on disk, like
+    /// while editing it in a subtitle editor. The selected stream, encoding and
+    /// [`PlayerOptions::subtitle_delay_ms`] are kept.
+    #[cfg(feature = "subtitles")]
+    pub reload_subtitle_files: bool,
+    /// Show the first frame as soon as it is decoded when playback starts, instead of waiting for a
+    /// full frame interval and (when synchronized to audio) for the audio device to start. Use
+    /// [`InputOptions::with_fast_start`] as well to open the input quicker.
+    pub fast_start: bool,
+    /// Draw the player controls, see [`Player::render_controls`].
+    pub show_controls: bool,
+    /// Play while the pointer hovers the video drawn by [`Player::ui`] or [`Player::ui_at`].
+    pub hover_to_play: Option<HoverToPlay>,
+    /// Play while at least this fraction (in `0.0..=1.0`) of the video drawn by [`Player::ui`] or
+    /// [`Player::ui_at`] is inside the clip rect, like when scrolling a feed, and pause once less
+    /// of it is. Players that are no longer drawn at all have to be paused by the app.
+    pub autoplay_visible: Option<f32>,
+    /// Fade the video in on start and out at the end, and dissolve between players.
+    pub transitions: Transitions,
+    /// How many recently displayed frames are kept, so seeking back and forth over them while paused
+    /// shows them immediately instead of decoding them again. Off by default, as every player
+    /// keeping them adds up: `ReviewCacheSize::Megabytes(256)` costs up to 256 MB per player.
+    pub review_cache: ReviewCacheSize,
+    /// What happens when playback enters a range set with [`Player::set_skip_ranges`].
+    pub skip_mode: SkipMode,
+    /// Bypass the filters while this key is held and the player is hovered, see
+    /// [`Player::set_filters_bypassed`].
+    pub bypass_filters_key: Option<Key>,
+    /// Keep the system from sleeping while playing, releasing it when paused or stopped. On
+    /// windows and macos (with `caffeinate`) the display stays on and the screensaver doesn't
+    /// start either. On linux this takes a sleep and idle inhibitor of logind with
+    /// `systemd-inhibit`, which the screensavers of GNOME and KDE don't follow, so apps showing
+    /// video full screen there inhibit the screensaver through their windowing toolkit. Does
+    /// nothing where the tools aren't installed.
+    pub inhibit_sleep: bool,
+    /// Free the video texture and the decoded frames of a player paused, stopped or hidden (drawn
+    /// outside the clip rect) this long, keeping a small poster of the frame on screen. Its
+    /// pipeline stops like in a [`PlayerPool`], and everything is rebuilt from the frame on screen
+    /// once it is resumed, seeked or drawn visible again (stopped players keep the poster until
+    /// they are started or seeked). For long running apps with many players, which would hold on
+    /// to the memory of the gpu otherwise. `None` keeps them.
+    ///
+    /// Players that aren't drawn at all, like the ones scrolled out of a `ScrollArea::show_rows`,
+    /// count as hidden from the last pass they were drawn visible in. They are reclaimed by
+    /// [`Player::process_state`], which the app calls on them each frame instead of drawing them.
+    pub reclaim_idle_after: Option<std::time::Duration>,
+    /// J/K/L shuttle keys for review, working while the player is hovered, see [`Shuttle`].
+    pub shuttle: Option<Shuttle>,
+    /// Normalize the loudness of the input to this many LUFS (e.g. `-16.` for streaming, `-23.` for
+    /// broadcast) with a gain on top of [`PlayerOptions::audio_gain_db`] (see
+    /// [`Player::normalization_gain_db`]) once [`Player::scan_loudness`] has measured it. Gives
+    /// the items of a playlist a consistent volume.
+    pub loudness_target_lufs: Option<f32>,
+    /// Decode and show less of the video while the window is unfocused, to save power.
+    pub unfocused_backoff: Option<UnfocusedBackoff>,
+    /// Stop decoding once the queued frames reach this many milliseconds past the playback
+    /// position. It bounds the memory of the queue, and how many frames are still shown with the
+    /// previous filters after changing them, for apps that have to react right away. `None` only
+    /// limits the queue to its number of frames.
+    pub max_video_lead_ms: Option<i64>,
+    /// While the player is resized continuously (like dragging the splitter of a dock), frames
+    /// aren't upscaled for [`PlayerOptions::upscale`] and the gpu stretches them instead. Once the
+    /// size held still for this many milliseconds, they are upscaled to it again. `0` upscales to
+    /// every size in between. Frames keep their size for [`PlayerOptions::fit_to_view`] meanwhile.
+    pub resize_settle_ms: u64,
+    /// Scale frames down to the size the video is drawn at in physical pixels (its size in points
+    /// times `pixels_per_point`), rather than uploading the full resolution for a small player.
+    /// The size follows the display scale, so video stays sharp on HiDPI displays.
+    pub fit_to_view: bool,
+    /// Present frames that fit the display with a cadence (see [`FramePacing`]) on the refresh
+    /// nearest their time, rather than the first one after it, so timing noise doesn't break the
+    /// cadence up. Off by default, as frames then show up to half a refresh early.
+    pub cadence_pacing: bool,
 }
 
 impl Default for PlayerOptions {
@@ -177,7 +920,45 @@ impl Default for PlayerOptions {
             looping: false,
             max_audio_volume: 1.,
             audio_volume: Shared::new(0.5),
+            audio_gain_db: Shared::new(0.),
+            audio_limiter: Shared::new(false),
+            audio_fade_ms: Shared::new(0),
+            paused_audio_reserve_ms: Shared::new(200),
             texture_options: TextureOptions::default(),
+            tint: Color32::WHITE,
+            opacity: 1.,
+            corner_radius: CornerRadius::ZERO,
+            letterbox: None,
+            ambient_glow: None,
+            upscale: None,
+            sync_master: SyncMaster::default(),
+            compensate_audio_latency: false,
+            audio_delay_ms: 0,
+            #[cfg(feature = "subtitles")]
+            subtitles: SubtitleOptions::default(),
+            #[cfg(feature = "subtitles")]
+            subtitle_seek_preroll_ms: Shared::new(0),
+            #[cfg(feature = "subtitles")]
+            subtitle_delay_ms: Shared::new(0),
+            #[cfg(feature = "subtitles")]
+            reload_subtitle_files: false,
+            fast_start: false,
+            show_controls: true,
+            hover_to_play: None,
+            autoplay_visible: None,
+            transitions: Transitions::default(),
+            review_cache: ReviewCacheSize::default(),
+            skip_mode: SkipMode::default(),
+            bypass_filters_key: None,
+            inhibit_sleep: false,
+            reclaim_idle_after: None,
+            shuttle: None,
+            loudness_target_lufs: None,
+            unfocused_backoff: None,
+            max_video_lead_ms: None,
+            resize_settle_ms: 200,
+            fit_to_view: false,
+            cadence_pacing: false,
         }
     }
 }
@@ -196,6 +977,24 @@ impl PlayerOptions {
         self.audio_volume
             .set(volume.clamp(0., self.max_audio_volume));
     }
+
+    /// Set the player gain, in decibels. `0.0` leaves the audio unchanged.
+    pub fn set_audio_gain_db(&mut self, gain_db: f32) {
+        self.audio_gain_db.set(gain_db);
+    }
+
+    /// Set the fades of the audio when pausing, resuming and seeking, in milliseconds. `0` turns
+    /// them off.
+    pub fn set_audio_fade_ms(&mut self, fade_ms: u32) {
+        self.audio_fade_ms.set(fade_ms);
+    }
+
+    /// Set how much later than their time subtitle cues show, in milliseconds. Negative values
+    /// show them earlier.
+    #[cfg(feature = "subtitles")]
+    pub fn set_subtitle_delay_ms(&mut self, delay_ms: i64) {
+        self.subtitle_delay_ms.set(delay_ms);
+    }
 }
 
 /// The [`Player`] processes and controls streams of video/audio. This is what you use to show a video file.
@@ -205,15 +1004,17 @@ pub struct Player {
     pub video_streamer: Arc<Mutex<VideoStreamer>>,
     /// The audio streamer of the player. Won't exist unless [`Player::with_audio`] is called and there exists
     /// a valid audio stream in the file.
+    #[cfg(feature = "audio")]
     pub audio_streamer: Option<Arc<Mutex<AudioStreamer>>>,
     /// The subtitle streamer of the player. Won't exist unless [`Player::with_subtitles`] is called and there exists
     /// a valid subtitle stream in the file.
+    #[cfg(feature = "subtitles")]
     pub subtitle_streamer: Option<Arc<Mutex<SubtitleStreamer>>>,
     /// The state of the player.
     pub player_state: Shared<PlayerState>,
     /// The player's texture handle.
     pub texture_handle: TextureHandle,
-    /// The size of the video stream.
+    /// The display size of the video stream, see [`MediaInfo::display_size`].
     pub size: Vec2,
     /// The total duration of the stream, in milliseconds.
     pub duration_ms: i64,
@@ -221,18 +1022,42 @@ pub struct Player {
     pub framerate: f64,
     /// Configures certain aspects of this [`Player`].
     pub options: PlayerOptions,
+    media_info: MediaInfo,
     audio_stream_info: StreamInfo,
     subtitle_stream_info: StreamInfo,
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
     message_sender: PlayerMessageSender,
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
     message_reciever: PlayerMessageReciever,
+    // the stream switches whose `PlayerMessage::StreamCycled` hasn't arrived yet
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
+    pending_stream_cycles: usize,
+    event_sender: PlayerEventSender,
+    event_reciever: PlayerEventReciever,
+    // applied to the streamers of streams added later, like with `add_audio`
+    health_options: Option<HealthOptions>,
+    #[cfg(feature = "audio")]
+    skip_silence: Option<SkipSilence>,
+    #[cfg(feature = "audio")]
+    silent_spans: SilentSpans,
+    hw_fallback: Arc<Mutex<Option<HwFallbackReason>>>,
+    decode_error_count: Shared<u64>,
+    session_stats: SessionStats,
     video_timer: Timer,
+    #[cfg(feature = "audio")]
     audio_timer: Timer,
     synchro_timer: Timer,
+    #[cfg(feature = "subtitles")]
     subtitle_timer: Timer,
+    #[cfg(feature = "audio")]
     audio_thread: Option<Guard>,
     video_thread: Option<Guard>,
     synchro_thread: Option<Guard>,
+    #[cfg(feature = "subtitles")]
     subtitle_thread: Option<Guard>,
+    // a panic of the timer or seek threads, the pipeline is torn down once it is reported
+    fatal: FatalError,
+    fatal_reported: bool,
     ctx_ref: egui::Context,
     last_seek_ms: Option<i64>,
     preseek_player_state: Option<PlayerState>,
@@ -241,12 +1066,177 @@ pub struct Player {
     video_elapsed_ms: Shared<i64>,
     audio_elapsed_ms: Shared<i64>,
     audio_device_time_ms: Shared<i64>,
+    audio_output_timing: AudioOutputTiming,
+    sync_test: Option<SyncTest>,
+    #[cfg(feature = "subtitles")]
     subtitle_elapsed_ms: Shared<i64>,
     seeking_signal: Shared<bool>,
     video_elapsed_ms_override: Option<i64>,
+    #[cfg(feature = "subtitles")]
     subtitles_queue: SubtitleQueue,
+    #[cfg(feature = "subtitles")]
     current_subtitles: Vec<Subtitle>,
+    // pushed with `inject_subtitle`, kept apart so seeks and stream changes don't clear them
+    #[cfg(feature = "subtitles")]
+    injected_subtitles: Vec<Subtitle>,
+    // the files added with `add_subtitle_file` and the charset their text is converted from
+    #[cfg(feature = "subtitles")]
+    subtitle_files: Vec<(String, Option<String>)>,
+    // the index of the file shown, with its modification time when it was loaded and when that was
+    // last checked
+    #[cfg(feature = "subtitles")]
+    subtitle_file: Option<(usize, Option<SystemTime>)>,
+    #[cfg(feature = "subtitles")]
+    subtitle_file_checked: Option<Instant>,
+    #[cfg(feature = "subtitles")]
+    subtitles_enabled: bool,
+    // fonts attached to the input, registered with egui on the next call to `render_subtitles`
+    #[cfg(feature = "subtitles")]
+    pending_font_attachments: Vec<FontAttachment>,
+    frame_callback: Option<FrameCallback>,
+    user_data: Option<UserData>,
+    cue_points: CuePoints,
+    audio_tracks: Vec<TrackInfo>,
+    subtitle_tracks: Vec<TrackInfo>,
+    track_name_formatter: Option<TrackNameFormatter>,
+    up_next: Option<(UpNext, UpNextState)>,
+    performance_profile: Option<PerformanceProfile>,
+    // the texture of the previous frame with `Player::set_double_buffering`, swapped with
+    // `texture_handle` on every new frame
+    spare_texture: Option<TextureHandle>,
+    // the presentation times of the frames in `texture_handle` and `spare_texture`
+    texture_frame_ms: Option<i64>,
+    spare_frame_ms: Option<i64>,
+    pending_branch: Option<PendingBranch>,
+    // the last presented frame and its time, before the region filters. Kept to show it again when
+    // the texture options or filters change.
+    last_frame: Option<(i64, Arc<ColorImage>)>,
+    review_cache: ReviewCache,
+    // built in the background on the first call to `keyframes`
+    keyframe_index: Arc<OnceLock<Vec<i64>>>,
+    keyframe_index_requested: bool,
+    // measured in the background by `scan_loudness`, and the target the gain was last set for
+    #[cfg(feature = "audio")]
+    loudness_lufs: Arc<OnceLock<f32>>,
+    #[cfg(feature = "audio")]
+    loudness_scan_requested: bool,
+    #[cfg(feature = "audio")]
+    normalized_to_lufs: Option<f32>,
+    // applied on top of `PlayerOptions::audio_gain_db`, so normalizing doesn't replace the gain
+    #[cfg(feature = "audio")]
+    normalization_gain_db: Shared<f32>,
+    // a seek shown from the review cache while paused, done for real once playback resumes
+    deferred_seek_frac: Option<f32>,
+    video_clock: PlaybackClock,
+    // set by `enable_offline_mode`, with the time of the frame last presented by `advance`
+    offline: bool,
+    offline_presented_ms: Option<i64>,
+    // where the cuts of an edit start and end, see `Player::new_edit`
+    cut_bounds_ms: Option<Vec<i64>>,
+    skip_ranges: Vec<TimeRange>,
+    // the skip range playback is inside of, handled once when it was entered
+    active_skip_range: Option<usize>,
+    content_filters: Arc<Mutex<Vec<ContentFilter>>>,
+    // the region filters, clip mask and whether the filters are bypassed
+    upload_stage: UploadStage,
+    // a tiny copy of the frame, stretched over the bars for `LetterboxFill::Ambient`
+    ambient_texture: Option<TextureHandle>,
+    glow_texture: Option<TextureHandle>,
+    // when the ambient textures were last updated, and the frame to update them with next
+    ambient_updated: Option<Instant>,
+    ambient_pending: Option<Arc<ColorImage>>,
+    // whether the filters are bypassed because `PlayerOptions::bypass_filters_key` is held
+    bypass_key_held: bool,
+    // when the pointer entered the video, and whether `PlayerOptions::hover_to_play` started playback
+    hover_started: Option<Instant>,
+    hover_playing: bool,
+    // set when starting with `PlayerOptions::fast_start` until a frame was presented
+    first_frame_pending: bool,
+    // the `PlayerOptions::unfocused_backoff` in effect while the window is unfocused, and when a
+    // frame was last presented during it. The repaint timer pauses while backing off.
+    backoff: Option<UnfocusedBackoff>,
+    backoff_presented: Option<Instant>,
+    repaint_paused: Shared<bool>,
+    // the `PlayerOptions::max_video_lead_ms` the video streamer has
+    video_lead_limit: Option<i64>,
+    damage_overlay: Option<DamageOverlay>,
+    // the size the video was last drawn at in pixels (for `PlayerOptions::upscale`), and when it
+    // last changed while the player was being resized
+    drawn_size: u32,
+    resized_at: Option<Instant>,
+    // the damage of the frame on screen, and the last damaged frame with when it was presented
+    frame_damage: Option<FrameDamage>,
+    damage_highlighted: Option<(Instant, FrameDamage)>,
+    frame_hash: Option<u64>,
+    // when playback last started, for `Transitions::fade_in`
+    fade_in_started: Option<Instant>,
+    // the last frame of the player this one replaced, faded out over the video
+    crossfade: Option<Crossfade>,
+    // whether `PlayerOptions::autoplay_visible` started playback
+    visibility_playing: bool,
+    pool: Option<PoolMembership>,
+    shuttle: ShuttleState,
+    // held while playing with `PlayerOptions::inhibit_sleep`
+    sleep_inhibitor: Option<SleepInhibitor>,
+    // shuttling too fast for audio, so it isn't decoded and video follows its own clock
+    shuttle_silent: Shared<bool>,
+    // set by the pool to stop the timer threads, applied by `update_suspension`
+    suspend_requested: Shared<bool>,
+    // the state to return to once a suspended pipeline wakes
+    suspended: Option<PlayerState>,
+    // the pass of egui the player was last drawn visible in, so it wakes once it comes into view
+    pool_visible_pass: Option<u64>,
+    // since when the player is paused or hidden, for `PlayerOptions::reclaim_idle_after`
+    idle_since: Option<Instant>,
+    // the pass of egui and the time the player was last drawn visible, so players that aren't
+    // drawn at all count as hidden
+    drawn_visible: Option<(u64, Instant)>,
+    // whether `PlayerOptions::reclaim_idle_after` freed the frames, `Some(true)` while hidden
+    reclaimed: Option<bool>,
+    refresh_meter: RefreshMeter,
+    // the rate set with `Player::set_display_refresh_rate`
+    display_refresh_hz: Option<f32>,
+    crop: Option<Rect>,
+    chapters: Vec<Chapter>,
+    // taken once per call to `process_state`, see `Player::timeline`
+    timeline: TimelineModel,
+    sleep_timer: Option<SleepTimer>,
+    // set by `pause_at` while playing towards it
+    pause_at_ms: Option<i64>,
+    // kept by `pause_at`, so stepping through nearby frames reuses its decoder
+    exact_frame_server: Option<FrameServer>,
+    // lowered by the sleep timer fading out, on top of the volume and gain
+    audio_fade_gain: Shared<f32>,
+    // set by `enable_offline_mode`, without touching the volume
+    audio_muted: Shared<bool>,
+    fingerprint: String,
+    layout_fingerprint: String,
+    settings_store: Option<Box<dyn SettingsStore>>,
+    playback_speed: Shared<f32>,
+    #[cfg(feature = "audio")]
+    time_stretch: TimeStretch,
+    #[cfg(feature = "audio")]
+    channel_map: Option<ChannelMap>,
+    #[cfg(feature = "audio")]
+    resampler_options: ResamplerOptions,
+    max_frame_size: Shared<u32>,
+    frame_step: Shared<u32>,
+    upscale_filter: Shared<UpscaleFilter>,
+    upscale_to: Shared<u32>,
+    fit_to: Shared<u32>,
     input_path: String,
+    input_options: InputOptions,
+    // a low resolution copy of the input, played instead of it while `proxy_active`
+    proxy_path: Option<String>,
+    proxy_active: bool,
+    timeshift: Option<(TimeshiftOptions, TimeshiftWindow)>,
+}
+
+struct Crossfade {
+    texture_handle: TextureHandle,
+    uv: Rect,
+    started: Instant,
+    transition: Transition,
 }
 
 /// The possible states of a [`Player`].
@@ -280,33 +1270,99 @@ pub struct VideoStreamer {
     _audio_elapsed_ms: Shared<i64>,
     apply_video_frame_fn: Option<ApplyVideoFrameFn>,
     frame_cache: VecDeque<(<VideoStreamer as Streamer>::ProcessedFrame, i64, i64)>,
+    timeshift: Option<TimeshiftBuffer>,
+    frame_analyzer: Option<FrameAnalyzer>,
+    health_monitor: Option<HealthMonitor>,
+    content_filters: Arc<Mutex<Vec<ContentFilter>>>,
+    upload_stage: UploadStage,
+    decoder_options: DecoderOptions,
+    // how many decoded frames are buffered in `frame_cache`
+    frame_buffer_size: usize,
+    // how far the last frame in `frame_cache` may be past the first, see
+    // `PlayerOptions::max_video_lead_ms`
+    max_video_lead_ms: Option<i64>,
+    // after `video_decoder`, which points to it until it is dropped
+    hw_decoding: Option<HwDecoding>,
+    hw_fallback: Arc<Mutex<Option<HwFallbackReason>>>,
+    event_sender: PlayerEventSender,
+    decode_errors: DecodeErrors,
+    media_info: MediaInfo,
+    cut_filter: Option<CutFilter>,
+    // when the packets of recent frames were read, by pts.
+    packet_receive_times: VecDeque<(i64, Instant)>,
+    // the longer side frames are scaled down to, `0` for the full size
+    max_frame_size: Shared<u32>,
+    frame_step: Shared<u32>,
+    upscale_filter: Shared<UpscaleFilter>,
+    // the longer side the video is drawn at in pixels, when `PlayerOptions::upscale` is set
+    upscale_to: Shared<u32>,
+    // the longer side the video is drawn at in pixels, when `PlayerOptions::fit_to_view` is set
+    fit_to: Shared<u32>,
+    // decoded frames to drop before the next one is kept, counted for `frame_step`
+    frames_to_skip: u32,
+    denoise: Option<Denoise>,
+    interpolation: Option<Interpolation>,
+    display_color: Option<DisplayColor>,
+    // the filter graph for `denoise`, `display_color` and `interpolation`, with the spec and frame
+    // format it was built for
+    video_filter: Option<VideoFilter>,
+    video_filter_config: Option<(String, u32, u32, Pixel)>,
+    filtered_frames: VecDeque<Video>,
+    // whether `VideoFrame::hash` is computed, see `Player::set_frame_hashing`
+    hash_frames: bool,
 }
 
 /// Streams audio.
+#[cfg(feature = "audio")]
 pub struct AudioStreamer {
     video_elapsed_ms: Shared<i64>,
     audio_elapsed_ms: Shared<i64>,
     duration_ms: i64,
     audio_decoder: ffmpeg::decoder::Audio,
     resampler: software::resampling::Context,
+    resampler_options: ResamplerOptions,
     audio_sample_producer: AudioSampleProducer,
     input_context: Input,
     player_state: Shared<PlayerState>,
     audio_stream_indices: VecDeque<StreamIndex>,
+    timeshift: Option<TimeshiftBuffer>,
+    playback_speed: Shared<f32>,
+    time_stretch: TimeStretch,
+    channel_map: Option<ChannelMap>,
+    audio_filter: Option<AudioFilter>,
+    audio_filter_spec: Option<String>,
+    cut_filter: Option<CutFilter>,
+    content_filters: Arc<Mutex<Vec<ContentFilter>>>,
+    health_monitor: Option<HealthMonitor>,
+    silence_detector: Option<SilenceDetector>,
+    decode_errors: DecodeErrors,
+    paused_audio_reserve_ms: Shared<u32>,
+    shuttle_silent: Shared<bool>,
+    // the samples sent to the audio device, and those it has taken, for the paused reserve
+    sent_samples: Shared<u64>,
+    taken_samples: Shared<u64>,
 }
 
 /// Streams subtitles.
+#[cfg(feature = "subtitles")]
 pub struct SubtitleStreamer {
     video_elapsed_ms: Shared<i64>,
     _audio_elapsed_ms: Shared<i64>,
     subtitle_elapsed_ms: Shared<i64>,
     duration_ms: i64,
     subtitle_decoder: ffmpeg::decoder::Subtitle,
+    ass_script: AssScript,
     next_packet: Option<Packet>,
     subtitles_queue: SubtitleQueue,
     input_context: Input,
     player_state: Shared<PlayerState>,
     subtitle_stream_indices: VecDeque<StreamIndex>,
+    timeshift: Option<TimeshiftBuffer>,
+    // the encoding text subtitles are converted from, for external files
+    charset: Option<String>,
+    seek_preroll_ms: Shared<i64>,
+    // shifts the cues and the read position, so cues shown earlier are read ahead of time
+    delay_ms: Shared<i64>,
 }
 
 #[derive(Clone, Debug)]
@@ -333,6 +1389,17 @@ impl<T: Copy + bytemuck::NoUninit> Shared<T> {
 }
 
 const AV_TIME_BASE_RATIONAL: Rational = Rational(1, AV_TIME_BASE);
+// how long subtitle cues are kept after they ended, in milliseconds
+#[cfg(feature = "subtitles")]
+const KEPT_SUBTITLES_MS: i64 = 10_000;
+// the longer side of the poster kept by `PlayerOptions::reclaim_idle_after`, in pixels
+const POSTER_SIZE: usize = 160;
+// the height `Player::controls_only_ui` takes at least, so the volume slider opening above the
+// seek bar stays inside it
+const CONTROLS_ONLY_MIN_HEIGHT: f32 = 130.;
+// how often an external subtitle file is checked for changes
+#[cfg(feature = "subtitles")]
+const SUBTITLE_FILE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
 const MILLISEC_TIME_BASE: Rational = Rational(1, 1000);
 
 fn timestamp_to_millisec(timestamp: i64, time_base: Rational) -> i64 {
@@ -351,18 +1418,45 @@ fn millisec_approx_eq(a: i64, b: i64) -> bool {
 impl Player {
     /// A formatted string for displaying the duration of the video stream.
     pub fn duration_text(&mut self) -> String {
+        if let Some((_, window)) = self.timeshift.as_ref() {
+            let behind_live_ms = window.end_ms.get() - self.elapsed_ms();
+            return if behind_live_ms < 1000 {
+                "live".to_string()
+            } else {
+                format!(
+                    "-{}",
+                    format_duration(Duration::milliseconds(behind_live_ms))
+                )
+            };
+        }
         format!(
             "{} / {}",
             format_duration(Duration::milliseconds(self.elapsed_ms())),
             format_duration(Duration::milliseconds(self.duration_ms))
         )
     }
+    // whether the player plays audio, which it never does without the `audio` feature
+    fn has_audio(&self) -> bool {
+        #[cfg(feature = "audio")]
+        {
+            self.audio_streamer.is_some()
+        }
+        #[cfg(not(feature = "audio"))]
+        {
+            false
+        }
+    }
     fn reset(&mut self) {
         self.last_seek_ms = None;
         self.video_elapsed_ms_override = None;
         self.video_elapsed_ms.set(0);
         self.audio_elapsed_ms.set(0);
+        self.video_clock.set(0);
+        self.offline_presented_ms = None;
+        self.deferred_seek_frac = None;
+        self.pause_at_ms = None;
         self.video_streamer.lock().reset();
+        #[cfg(feature = "audio")]
         if let Some(audio_decoder) = self.audio_streamer.as_mut() {
             audio_decoder.lock().reset();
         }
@@ -375,30 +1469,108 @@ impl Player {
             .map(|i| *i)
             .unwrap_or(self.video_elapsed_ms.get())
     }
+    /// The time of the audio device, in milliseconds of stream time. Only exists if the player has audio.
+    pub fn audio_clock_ms(&self) -> Option<i64> {
+        self.has_audio().then(|| self.audio_device_time_ms.get())
+    }
+    /// How long audio takes from the player to the speakers, as last reported by the audio backend
+    /// (its buffers and the output device). Only exists once the player has played audio.
+    pub fn audio_output_latency(&self) -> Option<std::time::Duration> {
+        if !self.has_audio() {
+            return None;
+        }
+        self.audio_output_timing.lock().map(|(_, latency)| latency)
+    }
+    /// The time of the clock video frames are currently synchronized to, in milliseconds of stream time.
+    /// See [`PlayerOptions::sync_master`].
+    pub fn clock_ms(&self) -> i64 {
+        match &self.options.sync_master {
+            SyncMaster::Audio if self.has_audio() && !self.shuttle_silent.get() => {
+                let latency_ms = match self.audio_output_latency() {
+                    Some(latency) if self.options.compensate_audio_latency => {
+                        latency.as_millis() as i64
+                    }
+                    _ => 0,
+                };
+                self.audio_device_time_ms.get() - latency_ms - self.options.audio_delay_ms
+            }
+            SyncMaster::External(time_ms) => time_ms.get(),
+            _ => self.video_clock.time_ms(),
+        }
+    }
+    fn is_video_clock_master(&self) -> bool {
+        match self.options.sync_master {
+            SyncMaster::Audio => !self.has_audio() || self.shuttle_silent.get(),
+            SyncMaster::Video => true,
+            SyncMaster::External(_) => false,
+        }
+    }
     fn set_state(&mut self, new_state: PlayerState) {
         self.player_state.set(new_state)
     }
     /// Pause the stream.
     pub fn pause(&mut self) {
+        if let Some(state) = self.suspended.as_mut() {
+            *state = PlayerState::Paused;
+        }
         self.set_state(PlayerState::Paused)
     }
     /// Resume the stream from a paused state.
     pub fn resume(&mut self) {
-        self.set_state(PlayerState::Playing)
+        self.wake();
+        self.set_state(PlayerState::Playing);
+        self.rebuild_reclaimed();
     }
     /// Stop the stream.
     pub fn stop(&mut self) {
         self.set_state(PlayerState::Stopped);
         self.video_thread = None;
-        self.audio_thread = None;
+        #[cfg(feature = "audio")]
+        {
+            self.audio_thread = None;
+        }
+        self.suspended = None;
+        self.reclaimed = None;
+        if let Some(pool) = self.pool.as_ref() {
+            pool.deactivate();
+        }
         self.reset()
     }
     fn duration_frac(&mut self) -> f32 {
+        if let Some((_, window)) = self.timeshift.as_ref() {
+            return window.ms_to_frac(self.elapsed_ms());
+        }
         self.elapsed_ms() as f32 / self.duration_ms as f32
     }
     /// Seek to a location in the stream.
     pub fn seek(&mut self, seek_frac: f32) {
+        if self.fatal.is_set() {
+            return;
+        }
+        self.wake();
+        // the seek decodes the frames again
+        self.reclaimed = None;
         let current_state = self.player_state.get();
+        self.cue_points.on_seek();
+        // seeking away from a branch point leaves it
+        self.pending_branch = None;
+        let target_ms = match self.timeshift.as_ref() {
+            Some((_, window)) => window.frac_to_ms(seek_frac),
+            None => (seek_frac as f64 * self.duration_ms as f64) as i64,
+        };
+        if let Some(sleep_timer) = self.sleep_timer.as_mut() {
+            sleep_timer.on_seek();
+        }
+        self.pause_at_ms = None;
+        if current_state == PlayerState::Paused {
+            if let Some(image) = self.review_cache.get(target_ms) {
+                self.show_frame(target_ms, image);
+                self.video_elapsed_ms_override = Some(target_ms);
+                self.deferred_seek_frac = Some(seek_frac);
+                return;
+            }
+        }
+        self.deferred_seek_frac = None;
         if !matches!(current_state, PlayerState::SeekingInProgress) {
             match current_state {
                 PlayerState::Stopped | PlayerState::EndOfFile => {
@@ -412,27 +1584,49 @@ impl Player {
             }
 
             let video_streamer = self.video_streamer.clone();
-            let mut audio_streamer = self.audio_streamer.clone();
-            let mut subtitle_streamer = self.subtitle_streamer.clone();
-            let subtitle_queue = self.subtitles_queue.clone();
+            // what ffmpeg logs while seeking is attributed to the input
+            let log_source: Arc<str> = self.input_path.as_str().into();
 
-            self.last_seek_ms = Some((seek_frac as f64 * self.duration_ms as f64) as i64);
+            self.last_seek_ms = Some(target_ms);
             self.set_state(PlayerState::SeekingInProgress);
 
-            if let Some(audio_streamer) = audio_streamer.take() {
+            #[cfg(feature = "audio")]
+            if let Some(audio_streamer) = self.audio_streamer.clone() {
+                let log_source = log_source.clone();
+                let fatal = self.fatal.clone();
                 std::thread::spawn(move || {
-                    audio_streamer.lock().seek(seek_frac);
+                    ffmpeg_log::set_thread_source(&log_source);
+                    fatal.contain("audio", || {
+                        let mut audio_streamer = audio_streamer.lock();
+                        // drop audio still buffered in the filters from before the seek
+                        audio_streamer.reset_audio_filter();
+                        audio_streamer.seek(seek_frac);
+                    });
                 });
             };
-            if let Some(subtitle_streamer) = subtitle_streamer.take() {
+            #[cfg(feature = "subtitles")]
+            if let Some(subtitle_streamer) = self.subtitle_streamer.clone() {
+                let subtitle_queue = self.subtitles_queue.clone();
                 self.current_subtitles.clear();
+                let log_source = log_source.clone();
+                let fatal = self.fatal.clone();
                 std::thread::spawn(move || {
+                    ffmpeg_log::set_thread_source(&log_source);
                     subtitle_queue.lock().clear();
-                    subtitle_streamer.lock().seek(seek_frac);
+                    fatal.contain("subtitles", || subtitle_streamer.lock().seek(seek_frac));
                 });
             };
+            let fatal = self.fatal.clone();
             std::thread::spawn(move || {
-                video_streamer.lock().seek(seek_frac);
+                ffmpeg_log::set_thread_source(&log_source);
+                fatal.contain("video", || {
+                    let mut video_streamer = video_streamer.lock();
+                    // the jump would otherwise be reported as a scene change
+                    if let Some(frame_analyzer) = video_streamer.frame_analyzer.as_mut() {
+                        frame_analyzer.reset();
+                    }
+                    video_streamer.seek(seek_frac);
+                });
             });
         }
     }
@@ -442,11 +1636,25 @@ impl Player {
         let ctx = self.ctx_ref.clone();
         let nanos = 1e9 / self.framerate;
         let wait_duration = Duration::nanoseconds(nanos as i64);
+        // what ffmpeg logs on the timer threads is attributed to the input
+        let log_source: Arc<str> = self.input_path.as_str().into();
 
         fn play<T: Streamer>(streamer: &Weak<Mutex<T>>) -> bool {
             if let Some(streamer) = streamer.upgrade() {
                 if let Some(mut streamer) = streamer.try_lock() {
-                    if (streamer.player_state().get() == PlayerState::Playing)
+                    let player_state = streamer.player_state().get();
+                    if player_state == PlayerState::Paused && streamer.timeshift().is_some() {
+                        // keep recording the live source while paused
+                        let _ = streamer.buffer_live_packet();
+                    } else if player_state == PlayerState::Paused && streamer.needs_paused_reserve()
+                    {
+                        // decode ahead of the paused position, so resuming has sound at once
+                        if let Ok(frame) = streamer.recieve_next_packet_until_frame() {
+                            streamer.apply_frame(frame);
+                        }
+                    } else if player_state == PlayerState::Playing && streamer.is_buffered_ahead() {
+                        return true;
+                    } else if (player_state == PlayerState::Playing)
                         && streamer.primary_elapsed_ms().get() >= streamer.elapsed_ms().get()
                     {
                         match streamer.recieve_next_packet_until_frame() {
@@ -465,16 +1673,36 @@ impl Player {
             return false;
         }
 
+        // a panic stops the pipeline of this player, instead of the timer thread, see
+        // `update_fatal`. the threads also idle while a `PlayerPool` suspends the player
+        fn play_contained<T: Streamer>(
+            streamer: &Weak<Mutex<T>>,
+            fatal: &FatalError,
+            suspended: &Shared<bool>,
+            thread: &'static str,
+        ) -> bool {
+            if fatal.is_set() || suspended.get() {
+                // wait for the player to drop the timer
+                std::thread::sleep(core::time::Duration::from_millis(10));
+                return false;
+            }
+            fatal.contain(thread, || play(streamer)).unwrap_or(false)
+        }
+
         self.video_streamer.lock().apply_video_frame_fn = Some(Box::new(move |frame| {
             texture_handle.set(frame, texture_options)
         }));
 
         let video_streamer_ref = Arc::downgrade(&self.video_streamer);
 
+        let video_log_source = log_source.clone();
+        let fatal = self.fatal.clone();
+        let suspended = self.suspend_requested.clone();
         let video_timer_guard = self
             .video_timer
             .schedule_repeating(Duration::zero(), move || {
-                let sleep = play(&video_streamer_ref);
+                ffmpeg_log::set_thread_source(&video_log_source);
+                let sleep = play_contained(&video_streamer_ref, &fatal, &suspended, "video");
                 if sleep {
                     std::thread::sleep(core::time::Duration::from_nanos(nanos as u64));
                 }
@@ -482,37 +1710,59 @@ impl Player {
 
         self.video_thread = Some(video_timer_guard);
 
+        #[cfg(feature = "audio")]
         if let Some(audio_decoder) = self.audio_streamer.as_ref() {
             let audio_decoder_ref = Arc::downgrade(audio_decoder);
+            let log_source = log_source.clone();
+            let fatal = self.fatal.clone();
+            let suspended = self.suspend_requested.clone();
             let audio_timer_guard =
                 self.audio_timer
                     .schedule_repeating(Duration::zero(), move || {
-                        play(&audio_decoder_ref);
+                        ffmpeg_log::set_thread_source(&log_source);
+                        play_contained(&audio_decoder_ref, &fatal, &suspended, "audio");
                     });
             self.audio_thread = Some(audio_timer_guard);
         }
 
+        #[cfg(feature = "subtitles")]
         if let Some(subtitle_decoder) = self.subtitle_streamer.as_ref() {
             let subtitle_decoder_ref = Arc::downgrade(subtitle_decoder);
+            let log_source = log_source.clone();
+            let fatal = self.fatal.clone();
+            let suspended = self.suspend_requested.clone();
             let subtitle_timer_guard =
                 self.subtitle_timer
                     .schedule_repeating(Duration::zero(), move || {
-                        play(&subtitle_decoder_ref);
+                        ffmpeg_log::set_thread_source(&log_source);
+                        play_contained(&subtitle_decoder_ref, &fatal, &suspended, "subtitles");
                     });
             self.subtitle_thread = Some(subtitle_timer_guard);
         }
 
         // have a general timer to repaint egui that doesn't have to wait for ffmpeg
+        let repaint_paused = self.repaint_paused.clone();
         let synchro_timer_guard = self
             .synchro_timer
             .schedule_repeating(wait_duration, move || {
-                ctx.request_repaint();
+                if !repaint_paused.get() {
+                    ctx.request_repaint();
+                }
             });
         self.synchro_thread = Some(synchro_timer_guard);
+        if let Some(pool) = self.pool.as_ref() {
+            pool.activate();
+        }
     }
     /// Start the stream.
     pub fn start(&mut self) {
+        if self.fatal.is_set() {
+            return;
+        }
         self.stop();
+        self.first_frame_pending = self.options.fast_start;
+        self.fade_in_started = Some(Instant::now());
+        self.cue_points.on_start();
         self.spawn_timers();
         self.resume();
     }
@@ -521,7 +1771,45 @@ impl Player {
     /// of the player. This function is already included in  [`Player::ui`] or
     /// [`Player::ui_at`].
     pub fn process_state(&mut self) {
+        if self.update_fatal() {
+            return;
+        }
+        self.update_suspension();
+        self.update_hidden_reclaim();
         let mut reset_stream = false;
+        if self.player_state.get() == PlayerState::Playing {
+            if let Some(seek_frac) = self.deferred_seek_frac.take() {
+                self.seek(seek_frac);
+            }
+        }
+        // offline, the clock only moves with `advance`
+        self.video_clock
+            .set_running(self.player_state.get() == PlayerState::Playing && !self.offline);
+        self.video_clock.set_rate(self.playback_speed.get());
+        if matches!(
+            self.player_state.get(),
+            PlayerState::Playing | PlayerState::Paused
+        ) {
+            self.update_skip_range();
+            self.apply_skip_filters();
+            #[cfg(feature = "audio")]
+            self.update_silence_skip();
+            self.update_sleep_timer();
+            self.update_pending_branch();
+            #[cfg(feature = "subtitles")]
+            self.update_subtitles();
+            self.update_session_stats();
+        }
+        #[cfg(feature = "audio")]
+        self.apply_loudness_normalization();
+        self.update_unfocused_backoff();
+        self.update_video_lead_limit();
+        self.update_up_next();
+        self.update_shuttle();
+        self.update_sleep_inhibition();
+        #[cfg(feature = "subtitles")]
+        self.reload_changed_subtitle_file();
+        self.update_timeline();
 
         match self.player_state.get() {
             PlayerState::EndOfFile => {
@@ -531,33 +1819,6 @@ impl Player {
                     self.player_state.set(PlayerState::Stopped);
                 }
             }
-            PlayerState::Playing => {
-                let mut ticking_subs = 0;
-                let mut should_cull_overlapping_subs = false;
-                let time = self.audio_device_time_ms.get();
-                for subtitle in self.current_subtitles.iter_mut() {
-                    should_cull_overlapping_subs |= subtitle.presentation_time_ms.is_some();
-                    if should_cull_overlapping_subs && time < subtitle.presentation_time_ms.unwrap()
-                    {
-                        continue;
-                    }
-                    subtitle.showing = true;
-                    ticking_subs += 1;
-                    subtitle.remaining_duration_ms -=
-                        self.ctx_ref.input(|i| (i.stable_dt * 1000.) as i64);
-                }
-                while should_cull_overlapping_subs && ticking_subs > 1 {
-                    self.current_subtitles.remove(0);
-                    ticking_subs -= 1;
-                }
-                self.current_subtitles
-                    .retain(|s| s.remaining_duration_ms > 0);
-                if let Some(mut queue) = self.subtitles_queue.try_lock() {
-                    if queue.len() > 0 {
-                        self.current_subtitles.push(queue.pop_front().unwrap());
-                    }
-                }
-            }
             state @ (PlayerState::SeekingInProgress | PlayerState::SeekingFinished) => {
                 if self.last_seek_ms.is_some() {
                     let last_seek_ms = *self.last_seek_ms.as_ref().unwrap();
@@ -565,6 +1826,8 @@ impl Player {
                         if let Some(previeous_player_state) = self.preseek_player_state {
                             self.set_state(previeous_player_state)
                         }
+                        self.video_clock.set(last_seek_ms);
+                        self.offline_presented_ms = None;
                         self.video_elapsed_ms_override = None;
                         self.last_seek_ms = None;
                     } else {
@@ -580,16 +1843,28 @@ impl Player {
             PlayerState::Restarting => reset_stream = true,
             _ => (),
         }
+        #[cfg(any(feature = "audio", feature = "subtitles"))]
         if let Ok(message) = self.message_reciever.try_recv() {
             match message {
-                PlayerMessage::StreamCycled(stream_type) => match stream_type {
-                    Type::Audio => self.audio_stream_info.cycle(),
-                    Type::Subtitle => {
-                        self.current_subtitles.clear();
-                        self.subtitle_stream_info.cycle();
+                PlayerMessage::StreamCycled(stream_type, steps) => {
+                    let info = match stream_type {
+                        Type::Audio => &mut self.audio_stream_info,
+                        Type::Subtitle => {
+                            #[cfg(feature = "subtitles")]
+                            self.current_subtitles.clear();
+                            &mut self.subtitle_stream_info
+                        }
+                        _ => unreachable!(),
+                    };
+                    for _ in 0..steps {
+                        info.cycle();
                     }
-                    _ => unreachable!(),
-                },
+                    // saved once the audio and subtitle streams are both selected
+                    self.pending_stream_cycles -= 1;
+                    if self.pending_stream_cycles == 0 {
+                        self.save_settings();
+                    }
+                }
             }
         }
         if reset_stream {
@@ -598,16 +1873,267 @@ impl Player {
         }
     }
 
+    // follow `PlayerOptions::unfocused_backoff` as the window loses and regains focus
+    fn update_unfocused_backoff(&mut self) {
+        let focused = self.ctx_ref.input(|i| i.focused);
+        let backoff = self.options.unfocused_backoff.filter(|_| !focused);
+        if backoff != self.backoff {
+            self.backoff = backoff;
+            self.backoff_presented = None;
+            self.repaint_paused.set(backoff.is_some());
+            let mut video_streamer = self.video_streamer.lock();
+            let skip_frame = match backoff {
+                Some(backoff) => backoff.skip_frame(),
+                None => {
+                    hwaccel::skip_frame(video_streamer.decoder_options.skip_non_reference_frames)
+                }
+            };
+            video_streamer.video_decoder.skip_frame(skip_frame);
+        }
+        if let Some(backoff) = self.backoff {
+            self.ctx_ref
+                .request_repaint_after(backoff.repaint_interval());
+        }
+    }
+
+    fn update_video_lead_limit(&mut self) {
+        if self.options.max_video_lead_ms != self.video_lead_limit {
+            self.video_lead_limit = self.options.max_video_lead_ms;
+            self.video_streamer.lock().max_video_lead_ms = self.video_lead_limit;
+        }
+    }
+
+    // whether `PlayerOptions::unfocused_backoff` holds back the next frame
+    fn backoff_holds_frame(&self) -> bool {
+        match self.backoff {
+            Some(UnfocusedBackoff::AudioOnly) => true,
+            Some(backoff) => self
+                .backoff_presented
+                .is_some_and(|presented| presented.elapsed() < backoff.repaint_interval()),
+            None => false,
+        }
+    }
+
+    // show the cues belonging to the frame on screen, so pausing and stepping frames shows exactly
+    // the ones of that frame
+    #[cfg(feature = "subtitles")]
+    fn update_subtitles(&mut self) {
+        let time_ms = match self.last_frame.as_ref() {
+            Some((time_ms, _)) => *time_ms,
+            None => self.current_frame_ms(),
+        };
+        if let Some(mut queue) = self.subtitles_queue.try_lock() {
+            self.current_subtitles.extend(queue.drain(..));
+        }
+        // ended cues are kept for a while, for stepping back over frames from the review cache
+        self.current_subtitles
+            .retain(|s| !s.has_ended(time_ms - KEPT_SUBTITLES_MS));
+        // bitmap cues replace each other, so only the latest one shows, while text cues overlap
+        let latest_bitmap_ms = self
+            .current_subtitles
+            .iter()
+            .filter(|s| s.is_bitmap() && s.is_shown_at(time_ms))
+            .filter_map(|s| s.presentation_time_ms)
+            .max();
+        for subtitle in self.current_subtitles.iter_mut() {
+            subtitle.showing = subtitle.is_shown_at(time_ms)
+                && (!subtitle.is_bitmap()
+                    || subtitle
+                        .presentation_time_ms
+                        .is_none_or(|start_ms| Some(start_ms) == latest_bitmap_ms));
+        }
+        self.injected_subtitles
+            .retain(|s| !s.has_ended(time_ms - KEPT_SUBTITLES_MS));
+        for subtitle in self.injected_subtitles.iter_mut() {
+            subtitle.showing = subtitle.is_shown_at(time_ms);
+        }
+    }
+
+    /// Show `cue` along with the subtitles of the stream, even if there are none. Injected cues
+    /// stay through seeks and stream changes, until they have ended or
+    /// [`Player::clear_injected_subtitles`] is called.
+    #[cfg(feature = "subtitles")]
+    pub fn inject_subtitle(&mut self, cue: SubtitleCue) {
+        self.injected_subtitles.push(cue.into());
+    }
+
+    /// Remove the cues added with [`Player::inject_subtitle`].
+    #[cfg(feature = "subtitles")]
+    pub fn clear_injected_subtitles(&mut self) {
+        self.injected_subtitles.clear();
+    }
+
     /// Create the [`egui::Image`] for the video frame.
     pub fn generate_frame_image(&mut self, size: Vec2) -> Image {
+        self.present_frame();
+        let tint = self.frame_tint(self.frame_opacity());
+        Image::new(SizedTexture::new(self.texture_handle.id(), size))
+            .uv(self.frame_uv())
+            .tint(tint)
+            .corner_radius(self.options.corner_radius)
+            .sense(Sense::click())
+    }
+
+    // the color the video is multiplied with, at `opacity` on top of `PlayerOptions::opacity`.
+    fn frame_tint(&self, opacity: f32) -> Color32 {
+        self.options
+            .tint
+            .gamma_multiply(self.options.opacity.clamp(0., 1.) * opacity)
+    }
+
+    // the opacity of the video, following `PlayerOptions::transitions`.
+    fn frame_opacity(&self) -> f32 {
+        let transitions = self.options.transitions;
+        let mut opacity = 1.;
+        if let Some(fade_in) = transitions.fade_in {
+            match self.fade_in_started {
+                _ if self.player_state.get() == PlayerState::Stopped => opacity = 0.,
+                Some(started) if started.elapsed() < fade_in.duration => {
+                    opacity *= fade_in.progress(started.elapsed());
+                    self.ctx_ref.request_repaint();
+                }
+                _ => (),
+            }
+        }
+        // live inputs and ones of unknown duration have no end to fade out at
+        let has_end = self.timeshift.is_none() && self.duration_ms > 0;
+        if let Some(fade_out) = transitions.fade_out.filter(|_| has_end) {
+            let fade_ms = fade_out.duration.as_millis() as i64;
+            let remaining_ms = (self.duration_ms - self.current_frame_ms()).max(0);
+            if remaining_ms < fade_ms {
+                let faded = std::time::Duration::from_millis((fade_ms - remaining_ms) as u64);
+                opacity *= 1. - fade_out.progress(faded);
+            }
+        }
+        opacity
+    }
+
+    // the part of the texture that is shown, after cropping.
+    fn frame_uv(&self) -> Rect {
+        match self.crop {
+            Some(crop) => Rect::from_min_max(
+                (crop.min.to_vec2() / self.size).to_pos2(),
+                (crop.max.to_vec2() / self.size).to_pos2(),
+            ),
+            None => Rect::from_min_max(Pos2::ZERO, Pos2::new(1., 1.)),
+        }
+    }
+
+    /// Replace `previous` on screen without a gap: its last frame is drawn over the first frame of
+    /// this player and faded out over `duration`. `previous` can be dropped right away. Until then
+    /// this player shows its first frame, already decoded when it was created, and seeks keep the
+    /// last frame on screen until the frame at the target is decoded.
+    pub fn crossfade_from(&mut self, previous: &Player, duration: std::time::Duration) {
+        self.crossfade = Some(Crossfade {
+            texture_handle: previous.texture_handle.clone(),
+            uv: previous.frame_uv(),
+            started: Instant::now(),
+            transition: Transition {
+                duration,
+                easing: Easing::Linear,
+            },
+        });
+    }
+
+    /// Like [`Player::crossfade_from`], with the duration and easing of [`Transitions::dissolve`].
+    /// Without one, this player replaces `previous` right away.
+    pub fn dissolve_from(&mut self, previous: &Player) {
+        if let Some(dissolve) = self.options.transitions.dissolve {
+            self.crossfade_from(previous, dissolve.duration);
+            if let Some(crossfade) = self.crossfade.as_mut() {
+                crossfade.transition.easing = dissolve.easing;
+            }
+        }
+    }
+
+    // draw the last frame of the player set with `crossfade_from` over the video.
+    fn paint_crossfade(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(crossfade) = self.crossfade.as_ref() else {
+            return;
+        };
+        let elapsed = crossfade.started.elapsed();
+        if elapsed >= crossfade.transition.duration {
+            self.crossfade = None;
+            return;
+        }
+        let opacity = 1. - crossfade.transition.progress(elapsed);
+        let corner_radius = if self.subtitle_band_fraction() > 0. {
+            self.split_corner_radius().0
+        } else {
+            self.options.corner_radius
+        };
+        Image::new(SizedTexture::new(
+            crossfade.texture_handle.id(),
+            frame_response.rect.size(),
+        ))
+        .uv(crossfade.uv)
+        .tint(self.frame_tint(opacity))
+        .corner_radius(corner_radius)
+        .paint_at(ui, frame_response.rect);
+        ui.ctx().request_repaint();
+    }
+
+    // upload the frame due at the current clock to the texture, returning whether there was one.
+    fn present_frame(&mut self) -> bool {
+        if self.deferred_seek_frac.is_some() {
+            return false;
+        }
         let mut vs = self.video_streamer.lock();
-        let frame_cache: &mut VecDeque<(ColorImage, i64, i64)> = &mut vs.frame_cache;
+        let clock_ms = self.clock_ms();
+        if let Some(pause_ms) = self.pause_at_ms.filter(|&pause_ms| clock_ms >= pause_ms) {
+            // the frame shown at `pause_ms` is on screen once the next one is after it
+            if vs
+                .frame_cache
+                .front()
+                .is_some_and(|frame| frame.1 > pause_ms)
+            {
+                drop(vs);
+                self.pause_at_ms = None;
+                self.pause();
+                if self.is_video_clock_master() {
+                    self.video_clock.set(pause_ms);
+                }
+                return false;
+            }
+        }
+        if self.backoff_holds_frame() {
+            // drop the frames that are due without showing them, so they don't pile up
+            while vs
+                .frame_cache
+                .get(1)
+                .is_some_and(|frame| frame.1 <= clock_ms)
+            {
+                vs.frame_cache.pop_front();
+            }
+            return false;
+        }
+        let frame_cache = &mut vs.frame_cache;
 
         let mut found = false;
 
+        // the video clock follows the frames when it drifts too far from them (e.g. live streams
+        // that don't start at zero)
+        if self.is_video_clock_master() {
+            if let (Some(first), Some(last)) = (frame_cache.front(), frame_cache.back()) {
+                let time = self.video_clock.time_ms();
+                if time < first.1 - 1000 || time > last.1 + 1000 {
+                    self.video_clock.set(first.1);
+                }
+            }
+        }
+
         // get the closest
         let mut closest = 0;
-        let dtime = self.audio_device_time_ms.get();
+        let lead_ms = match self.player_state.get() {
+            PlayerState::Playing if self.options.cadence_pacing => self
+                .display_refresh()
+                .map_or(0, |refresh| refresh.pacing_lead_ms()),
+            _ => 0,
+        };
+        let dtime = match self.pause_at_ms {
+            Some(pause_ms) => (clock_ms + lead_ms).min(pause_ms),
+            None => clock_ms + lead_ms,
+        };
         for (i, frame) in frame_cache.iter().enumerate() {
             if i >= frame_cache.len() - 1 {
                 continue;
@@ -618,54 +2144,962 @@ impl Player {
                 break;
             }
         }
+        // the first frame after starting doesn't wait for the clock (or for the audio device to start
+        // it), see `PlayerOptions::fast_start`
+        if self.first_frame_pending && !frame_cache.is_empty() {
+            self.first_frame_pending = false;
+            found = true;
+        }
         // remove any frames that are before the closest (if needed)
         if closest > 0 {
             frame_cache.drain(0..closest);
+            if self.player_state.get() == PlayerState::Playing {
+                self.session_stats
+                    .lock()
+                    .frames_dropped(dtime, closest as u64);
+            }
         }
 
         if !frame_cache.is_empty() && found {
-            let frame = frame_cache.pop_front().unwrap().0;
-            let texture_options = self.options.texture_options;
-            self.texture_handle.set(frame, texture_options);
+            let (mut frame, presentation_time_ms, duration_ms) = frame_cache.pop_front().unwrap();
+            drop(vs);
+            if self.backoff.is_some() {
+                self.backoff_presented = Some(Instant::now());
+            }
+            self.session_stats.lock().frame_presented();
+            self.frame_damage = frame.damage;
+            self.frame_hash = frame.hash;
+            if let Some(damage) = frame.damage {
+                self.damage_highlighted = Some((Instant::now(), damage));
+            }
+            let upload = &frame.upload;
+            let (source, image) = if upload.generation == self.upload_stage.generation {
+                let (source, image) = (upload.source.clone(), upload.image.clone());
+                (
+                    source.clone(),
+                    self.upload_frame(presentation_time_ms, source, image),
+                )
+            } else {
+                // prepared before the filters changed, so they are applied again to the decoded frame
+                let mut image = std::mem::take(&mut frame.image);
+                content_filter::filter_image(
+                    &self.content_filters.lock(),
+                    presentation_time_ms,
+                    &mut image,
+                );
+                let source = Arc::new(image);
+                (
+                    source.clone(),
+                    self.show_frame(presentation_time_ms, source),
+                )
+            };
+            if let Some(sync_test) = self.sync_test.as_mut() {
+                let output_timing = *self.audio_output_timing.lock();
+                if let Some((callback_at, latency)) = output_timing {
+                    // the stream time coming out of the speakers right now
+                    let audio_heard_ms = self.audio_device_time_ms.get() as f64
+                        + callback_at.elapsed().as_secs_f64() * 1000.
+                        - latency.as_secs_f64() * 1000.;
+                    sync_test.frame_presented(presentation_time_ms, audio_heard_ms);
+                }
+            }
+            if let Some(frame_callback) = self.frame_callback.as_mut() {
+                frame_callback(&PresentedFrame {
+                    image: &image,
+                    presentation_time_ms,
+                    timestamps: FrameTimestamps {
+                        capture: frame.capture,
+                        receive: frame.receive,
+                        decode: frame.decode,
+                        present: Instant::now(),
+                        present_system: SystemTime::now(),
+                    },
+                    hash: frame.hash,
+                    user_data: self.user_data.as_ref(),
+                });
+            }
+            let duration_ms = if duration_ms > 0 {
+                duration_ms
+            } else {
+                (1000. / self.framerate) as i64
+            };
+            self.review_cache.insert(
+                presentation_time_ms,
+                duration_ms,
+                source,
+                self.options.review_cache,
+            );
+            self.fire_cue_points(presentation_time_ms);
         }
+        found
+    }
 
-        Image::new(SizedTexture::new(self.texture_handle.id(), size)).sense(Sense::click())
+    // upload `source` to the texture with the region filters applied (unless bypassed), returning
+    // the uploaded image.
+    fn show_frame(&mut self, time_ms: i64, source: Arc<ColorImage>) -> Arc<ColorImage> {
+        let image = self.upload_stage.apply(time_ms, &source);
+        self.upload_frame(time_ms, source, image)
     }
 
-    /// Draw the video frame with a specific rect (without controls). Make sure to call [`Player::process_state`].
-    pub fn render_frame(&mut self, ui: &mut Ui, size: Vec2) -> Response {
-        ui.add(self.generate_frame_image(size))
+    // upload `image`, made from `source` by the upload stage, to the texture.
+    fn upload_frame(
+        &mut self,
+        time_ms: i64,
+        source: Arc<ColorImage>,
+        image: Arc<ColorImage>,
+    ) -> Arc<ColorImage> {
+        // showing the same frame again (like for new texture options) keeps the previous one
+        if self.texture_frame_ms != Some(time_ms) {
+            if let Some(spare_texture) = self.spare_texture.as_mut() {
+                std::mem::swap(spare_texture, &mut self.texture_handle);
+                self.spare_frame_ms = self.texture_frame_ms;
+            }
+        }
+        self.texture_handle
+            .set(image.clone(), self.options.texture_options);
+        self.texture_frame_ms = Some(time_ms);
+        self.update_ambient_textures(image.clone());
+        self.last_frame = Some((time_ms, source));
+        image
     }
 
-    /// Draw the video frame (without controls). Make sure to call [`Player::process_state`].
-    pub fn render_frame_at(&mut self, ui: &mut Ui, rect: Rect) -> Response {
-        ui.put(rect, self.generate_frame_image(rect.size()))
+    // update the textures of `LetterboxFill::Ambient` and `PlayerOptions::ambient_glow` with
+    // `image`, at most once per update interval. Frames in between are held back until it passed.
+    fn update_ambient_textures(&mut self, image: Arc<ColorImage>) {
+        let letterbox = matches!(self.options.letterbox, Some(LetterboxFill::Ambient { .. }));
+        let glow = self.options.ambient_glow;
+        if !letterbox && glow.is_none() {
+            return;
+        }
+        let interval = glow.map_or(letterbox::AMBIENT_UPDATE_INTERVAL, |glow| {
+            glow.update_interval()
+        });
+        if self
+            .ambient_updated
+            .is_some_and(|updated| updated.elapsed() < interval)
+        {
+            self.ambient_pending = Some(image);
+            self.ctx_ref.request_repaint_after(interval);
+            return;
+        }
+        self.ambient_updated = Some(Instant::now());
+        self.ambient_pending = None;
+        let ambient = letterbox::ambient_image(&image);
+        if glow.is_some() {
+            let glow_image = letterbox::glow_image(&ambient);
+            set_linear_texture(
+                &self.ctx_ref,
+                &mut self.glow_texture,
+                "vidstream-glow",
+                glow_image,
+            );
+        }
+        if letterbox {
+            set_linear_texture(
+                &self.ctx_ref,
+                &mut self.ambient_texture,
+                "vidstream-ambient",
+                ambient,
+            );
+        }
     }
 
-    /// Draw the video frame and player controls and process state changes.
-    pub fn ui(&mut self, ui: &mut Ui, size: Vec2) -> egui::Response {
-        let frame_response = self.render_frame(ui, size);
-        self.render_controls(ui, &frame_response);
-        self.render_subtitles(ui, &frame_response);
-        self.process_state();
-        frame_response
+    // draw `PlayerOptions::ambient_glow` around `rect`.
+    fn paint_ambient_glow(&mut self, ui: &Ui, rect: Rect) {
+        if let Some(image) = self.ambient_pending.take() {
+            self.update_ambient_textures(image);
+        }
+        let (Some(glow), Some(glow_texture)) =
+            (self.options.ambient_glow, self.glow_texture.as_ref())
+        else {
+            return;
+        };
+        let glow_rect =
+            Rect::from_center_size(rect.center(), rect.size() * (1. + 2. * glow.spread));
+        ui.painter().image(
+            glow_texture.id(),
+            glow_rect,
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1., 1.)),
+            Color32::WHITE.gamma_multiply(glow.intensity.clamp(0., 1.) * self.options.opacity),
+        );
     }
 
-    /// Draw the video frame and player controls with a specific rect, and process state changes.
-    pub fn ui_at(&mut self, ui: &mut Ui, rect: Rect) -> egui::Response {
-        let frame_response = self.render_frame_at(ui, rect);
-        self.render_controls(ui, &frame_response);
-        self.render_subtitles(ui, &frame_response);
-        self.process_state();
+    // show the last presented frame again, after something changed how it is shown
+    fn refresh_frame(&mut self) {
+        if let Some((time_ms, source)) = self.last_frame.clone() {
+            self.show_frame(time_ms, source);
+        }
+    }
+
+    // hand the changed upload stage to the decoding thread, and show the current frame with it
+    fn update_upload_stage(&mut self) {
+        self.upload_stage.generation += 1;
+        self.video_streamer.lock().upload_stage = self.upload_stage.clone();
+        self.refresh_frame();
+    }
+
+    /// Draw the video frame with a specific rect (without controls). Make sure to call [`Player::process_state`].
+    pub fn render_frame(&mut self, ui: &mut Ui, size: Vec2) -> Response {
+        if self.options.letterbox.is_some() || self.options.ambient_glow.is_some() {
+            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            let mut frame_ui = ui.new_child(UiBuilder::new().max_rect(rect));
+            return self.render_frame_at(&mut frame_ui, rect);
+        }
+        let band_height = self.subtitle_band_fraction() * size.y;
+        if band_height <= 0. {
+            let frame_response = ui.add(self.generate_frame_image(size));
+            self.paint_crossfade(ui, &frame_response);
+            self.update_drawn_size(ui, &frame_response);
+            return frame_response;
+        }
+        ui.allocate_ui_with_layout(size, Layout::top_down(Align::Center), |ui| {
+            ui.spacing_mut().item_spacing.y = 0.;
+            let (top, bottom) = self.split_corner_radius();
+            let frame_image = self
+                .generate_frame_image(size - vec2(0., band_height))
+                .corner_radius(top);
+            let frame_response = ui.add(frame_image);
+            self.paint_crossfade(ui, &frame_response);
+            self.update_drawn_size(ui, &frame_response);
+            let (band_rect, _) = ui.allocate_exact_size(vec2(size.x, band_height), Sense::hover());
+            ui.painter().rect_filled(band_rect, bottom, Color32::BLACK);
+            frame_response
+        })
+        .inner
+    }
+
+    /// Draw the video frame (without controls). Make sure to call [`Player::process_state`].
+    pub fn render_frame_at(&mut self, ui: &mut Ui, rect: Rect) -> Response {
+        self.paint_ambient_glow(ui, rect);
+        let band_height = self.subtitle_band_fraction() * rect.height();
+        let mut video_rect = rect;
+        let mut corner_radius = self.options.corner_radius;
+        if band_height > 0. {
+            video_rect.set_bottom(rect.bottom() - band_height);
+            let band_rect = Rect::from_min_max(video_rect.left_bottom(), rect.max);
+            let (top, bottom) = self.split_corner_radius();
+            ui.painter().rect_filled(band_rect, bottom, Color32::BLACK);
+            corner_radius = top;
+        }
+        if let Some(fill) = self.options.letterbox {
+            self.paint_letterbox(ui, video_rect, fill, corner_radius);
+            video_rect = letterbox::fit_rect(video_rect, self.displayed_size());
+        }
+        let frame_image = self
+            .generate_frame_image(video_rect.size())
+            .corner_radius(corner_radius);
+        let frame_response = ui.put(video_rect, frame_image);
+        self.paint_crossfade(ui, &frame_response);
+        self.update_drawn_size(ui, &frame_response);
         frame_response
     }
 
+    // tell the video streamer the size the video is drawn at, for `PlayerOptions::upscale` and
+    // `PlayerOptions::fit_to_view`.
+    fn update_drawn_size(&mut self, ui: &Ui, frame_response: &Response) {
+        if self.options.upscale.is_none() {
+            self.upscale_to.set(0);
+        }
+        if !self.options.fit_to_view {
+            self.fit_to.set(0);
+        }
+        if self.options.upscale.is_none() && !self.options.fit_to_view {
+            return;
+        }
+        // the whole frame is drawn larger than the rect when it is cropped
+        let scale = frame_response.rect.size() / self.displayed_size();
+        let drawn_size = ((self.size * scale).max_elem() * ui.ctx().pixels_per_point()) as u32;
+        if drawn_size != self.drawn_size {
+            if self.drawn_size != 0 {
+                self.resized_at = Some(Instant::now());
+            }
+            self.drawn_size = drawn_size;
+        }
+        // frames scaled to every size in between would each reallocate the texture
+        let settle = std::time::Duration::from_millis(self.options.resize_settle_ms);
+        if let Some(remaining) = self
+            .resized_at
+            .and_then(|resized_at| settle.checked_sub(resized_at.elapsed()))
+        {
+            self.upscale_to.set(0);
+            ui.ctx().request_repaint_after(remaining);
+            return;
+        }
+        self.resized_at = None;
+        if let Some(upscale) = self.options.upscale {
+            self.upscale_filter.set(upscale);
+            self.upscale_to.set(drawn_size);
+        }
+        if self.options.fit_to_view {
+            self.fit_to.set(drawn_size);
+        }
+    }
+
+    /// Whether the player is being resized, see [`PlayerOptions::resize_settle_ms`].
+    pub fn is_resizing(&self) -> bool {
+        self.resized_at.is_some()
+    }
+
+    // the size the video is shown at, after cropping.
+    fn displayed_size(&self) -> Vec2 {
+        self.crop.map_or(self.size, |crop| crop.size())
+    }
+
+    // fill `area` behind the video, following `PlayerOptions::letterbox`.
+    fn paint_letterbox(
+        &self,
+        ui: &Ui,
+        area: Rect,
+        fill: LetterboxFill,
+        corner_radius: CornerRadius,
+    ) {
+        match fill {
+            LetterboxFill::Color(color) => {
+                ui.painter().rect_filled(area, corner_radius, color);
+            }
+            LetterboxFill::Transparent => (),
+            LetterboxFill::Ambient { dim } => {
+                let Some(ambient_texture) = self.ambient_texture.as_ref() else {
+                    return;
+                };
+                let brightness = ((1. - dim.clamp(0., 1.)) * 255.) as u8;
+                Image::new(SizedTexture::new(ambient_texture.id(), area.size()))
+                    .tint(Color32::from_gray(brightness).gamma_multiply(self.options.opacity))
+                    .corner_radius(corner_radius)
+                    .paint_at(ui, area);
+            }
+        }
+    }
+
+    // the corners of `PlayerOptions::corner_radius` above and below a subtitle band.
+    fn split_corner_radius(&self) -> (CornerRadius, CornerRadius) {
+        let radius = self.options.corner_radius;
+        let top = CornerRadius {
+            sw: 0,
+            se: 0,
+            ..radius
+        };
+        let bottom = CornerRadius {
+            nw: 0,
+            ne: 0,
+            ..radius
+        };
+        (top, bottom)
+    }
+
+    fn subtitle_band_fraction(&self) -> f32 {
+        #[cfg(feature = "subtitles")]
+        if let SubtitlePlacement::BelowVideo { band_height } = self.options.subtitles.placement {
+            return band_height.clamp(0., 0.9);
+        }
+        0.
+    }
+
+    // the band below the video that subtitles are drawn in, if subtitles are placed below the video.
+    #[cfg(feature = "subtitles")]
+    fn subtitle_band(&self, video_rect: Rect) -> Option<Rect> {
+        let fraction = self.subtitle_band_fraction();
+        (fraction > 0.).then(|| {
+            let band_height = video_rect.height() * fraction / (1. - fraction);
+            Rect::from_min_size(
+                video_rect.left_bottom(),
+                vec2(video_rect.width(), band_height),
+            )
+        })
+    }
+
+    /// Draw the video frame and player controls and process state changes.
+    pub fn ui(&mut self, ui: &mut Ui, size: Vec2) -> egui::Response {
+        let frame_response = self.render_frame(ui, size);
+        self.update_bypass_key(ui, &frame_response);
+        self.update_shuttle_keys(ui, &frame_response);
+        self.update_hover_to_play(ui, &frame_response);
+        self.update_visibility_autoplay(ui, &frame_response);
+        self.update_pool_visibility(ui, &frame_response);
+        self.update_refresh_meter(ui);
+        self.update_idle_reclaim(ui, &frame_response);
+        self.render_controls(ui, &frame_response);
+        self.render_skip_button(ui, &frame_response);
+        self.render_damage_overlay(ui, &frame_response);
+        self.render_up_next(ui, &frame_response);
+        #[cfg(feature = "subtitles")]
+        self.render_subtitles(ui, &frame_response);
+        self.process_state();
+        frame_response
+    }
+
+    /// Draw the video frame and player controls with a specific rect, and process state changes.
+    pub fn ui_at(&mut self, ui: &mut Ui, rect: Rect) -> egui::Response {
+        let frame_response = self.render_frame_at(ui, rect);
+        self.update_bypass_key(ui, &frame_response);
+        self.update_shuttle_keys(ui, &frame_response);
+        self.update_hover_to_play(ui, &frame_response);
+        self.update_visibility_autoplay(ui, &frame_response);
+        self.update_pool_visibility(ui, &frame_response);
+        self.update_refresh_meter(ui);
+        self.update_idle_reclaim(ui, &frame_response);
+        self.render_controls(ui, &frame_response);
+        self.render_skip_button(ui, &frame_response);
+        self.render_damage_overlay(ui, &frame_response);
+        self.render_up_next(ui, &frame_response);
+        #[cfg(feature = "subtitles")]
+        self.render_subtitles(ui, &frame_response);
+        self.process_state();
+        frame_response
+    }
+
+    // follow `PlayerOptions::bypass_filters_key`, leaving bypasses set by the app alone
+    fn update_bypass_key(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(key) = self.options.bypass_filters_key else {
+            return;
+        };
+        let held = ui.rect_contains_pointer(frame_response.rect) && ui.input(|i| i.key_down(key));
+        if held != self.bypass_key_held {
+            self.bypass_key_held = held;
+            self.set_filters_bypassed(held);
+        }
+    }
+
+    // start playing from any state, returning `false` if the player already is. Seeks still in
+    // progress restore the state from before them, so that state is changed instead.
+    fn play_automatically(&mut self) -> bool {
+        match self.player_state.get() {
+            PlayerState::Stopped => self.start(),
+            PlayerState::EndOfFile => {
+                self.reset();
+                self.resume();
+            }
+            PlayerState::Paused => self.resume(),
+            PlayerState::SeekingInProgress | PlayerState::SeekingFinished => {
+                self.preseek_player_state = Some(PlayerState::Playing)
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // the counterpart of `play_automatically`.
+    fn pause_automatically(&mut self) {
+        match self.player_state.get() {
+            PlayerState::SeekingInProgress | PlayerState::SeekingFinished => {
+                self.preseek_player_state = Some(PlayerState::Paused)
+            }
+            _ => self.pause(),
+        }
+    }
+
+    // follow `PlayerOptions::hover_to_play`
+    fn update_hover_to_play(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(hover_to_play) = self.options.hover_to_play else {
+            return;
+        };
+        if !ui.rect_contains_pointer(frame_response.rect) {
+            self.hover_started = None;
+            if std::mem::take(&mut self.hover_playing) {
+                match hover_to_play.on_leave {
+                    HoverLeave::Pause => self.pause_automatically(),
+                    HoverLeave::Reset => {
+                        self.stop();
+                        self.seek(0.);
+                    }
+                }
+            }
+            return;
+        }
+        let hovered_for = self
+            .hover_started
+            .get_or_insert_with(Instant::now)
+            .elapsed();
+        if self.hover_playing {
+            return;
+        }
+        if hovered_for < hover_to_play.start_delay {
+            ui.ctx()
+                .request_repaint_after(hover_to_play.start_delay - hovered_for);
+            return;
+        }
+        // when it was already playing, leaving is up to the app
+        self.hover_playing = self.play_automatically();
+    }
+
+    // tell the pool the player is seen, waking it once it comes back into view
+    fn update_pool_visibility(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(pool) = self.pool.as_ref() else {
+            return;
+        };
+        let visible = frame_response.rect.intersect(ui.clip_rect()).is_positive();
+        let pass = ui.ctx().cumulative_pass_nr();
+        // players scrolled out of a `ScrollArea::show_rows` aren't drawn at all, so not being
+        // drawn visible on the pass before counts as hidden too
+        let visible_before = matches!(self.pool_visible_pass, Some(last) if last + 1 >= pass);
+        if visible {
+            pool.visible();
+            if !visible_before {
+                self.wake();
+            }
+        }
+        self.pool_visible_pass = visible.then_some(pass);
+    }
+
+    // frames follow the refreshes while playing, as the player repaints continuously
+    fn update_refresh_meter(&mut self, ui: &Ui) {
+        if self.player_state.get() == PlayerState::Playing {
+            let (dt, monitor_size) = ui
+                .ctx()
+                .input(|i| (i.unstable_dt, i.viewport().monitor_size));
+            self.refresh_meter.record(dt, monitor_size);
+        }
+    }
+
+    /// The refresh rate of the display the player is drawn on and how the frames of the video
+    /// fit it, like whether motion judders (see [`DisplayRefresh::judder_risk`]). egui doesn't
+    /// know the rate, so it is measured from the frames drawn while playing (with vsync), unless
+    /// the app set it with [`Player::set_display_refresh_rate`]. `None` until then.
+    pub fn display_refresh(&self) -> Option<DisplayRefresh> {
+        let (hz, measured) = match self.display_refresh_hz {
+            Some(hz) => (hz, false),
+            None => (self.refresh_meter.hz()?, true),
+        };
+        Some(DisplayRefresh::new(hz, measured, self.framerate))
+    }
+
+    /// Set the refresh rate of the display the player is drawn on, in Hz, for backends that
+    /// report it (like `MonitorHandle::refresh_rate_millihertz` of winit). Set it again when the
+    /// window moves to another monitor. `None` measures it, see [`Player::display_refresh`].
+    pub fn set_display_refresh_rate(&mut self, hz: Option<f32>) {
+        self.display_refresh_hz = hz.filter(|hz| *hz > 0.);
+    }
+
+    /// Join `pool`, which caps how many players run their pipeline at once, see [`PlayerPool`].
+    pub fn join_pool(&mut self, pool: &PlayerPool) {
+        let membership = pool.join(self.suspend_requested.clone());
+        if self.video_thread.is_some() {
+            membership.activate();
+        }
+        self.pool = Some(membership);
+    }
+
+    /// Whether the [`PlayerPool`] of the player suspended its pipeline, showing the last frame until
+    /// it comes back into view or is resumed, seeked or started.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.is_some()
+    }
+
+    /// Whether [`PlayerOptions::reclaim_idle_after`] freed the texture and decoded frames of the
+    /// player, showing a small poster until it is rebuilt.
+    pub fn is_reclaimed(&self) -> bool {
+        self.reclaimed.is_some()
+    }
+
+    // stop the timer threads once the pool asked for it, keeping the last frame as the poster
+    fn update_suspension(&mut self) {
+        if !self.suspend_requested.get() || self.suspended.is_some() {
+            return;
+        }
+        if self.video_thread.is_none() {
+            self.suspend_requested.set(false);
+            return;
+        }
+        self.suspend();
+    }
+
+    // stop the timer threads until `wake`, returning whether they were stopped
+    fn suspend(&mut self) -> bool {
+        if self.suspended.is_some() {
+            return true;
+        }
+        let state = self.player_state.get();
+        // a seek finishes first, as its threads don't stop
+        if self.video_thread.is_none()
+            || !matches!(state, PlayerState::Playing | PlayerState::Paused)
+        {
+            return false;
+        }
+        self.suspended = Some(state);
+        self.set_state(PlayerState::Paused);
+        self.video_thread = None;
+        #[cfg(feature = "audio")]
+        {
+            self.audio_thread = None;
+        }
+        #[cfg(feature = "subtitles")]
+        {
+            self.subtitle_thread = None;
+        }
+        true
+    }
+
+    // follow `PlayerOptions::reclaim_idle_after`
+    fn update_idle_reclaim(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(reclaim_after) = self.options.reclaim_idle_after else {
+            return;
+        };
+        let visible = frame_response.rect.intersect(ui.clip_rect()).is_positive();
+        let pass = ui.ctx().cumulative_pass_nr();
+        let visible_before = matches!(self.drawn_visible, Some((last, _)) if last + 1 >= pass);
+        if visible {
+            self.drawn_visible = Some((pass, Instant::now()));
+            // the time it wasn't drawn at all doesn't count as paused on screen
+            if !visible_before {
+                self.idle_since = None;
+            }
+        }
+        if self.reclaimed == Some(true) && visible {
+            self.wake();
+            self.rebuild_reclaimed();
+        }
+        let state = self.suspended.unwrap_or(self.player_state.get());
+        let idle = !visible
+            || matches!(
+                state,
+                PlayerState::Paused | PlayerState::Stopped | PlayerState::EndOfFile
+            );
+        if !idle {
+            self.idle_since = None;
+        } else if self.reclaimed.is_none() {
+            let idle_since = *self.idle_since.get_or_insert_with(Instant::now);
+            if idle_since.elapsed() >= reclaim_after {
+                self.reclaim(!visible);
+            } else {
+                ui.ctx()
+                    .request_repaint_after(reclaim_after - idle_since.elapsed());
+            }
+        }
+    }
+
+    // reclaim players that weren't drawn visible on the last pass, like the ones scrolled out of a
+    // `ScrollArea::show_rows`, which `update_idle_reclaim` doesn't see
+    fn update_hidden_reclaim(&mut self) {
+        let Some(reclaim_after) = self.options.reclaim_idle_after else {
+            return;
+        };
+        let Some((last, drawn_at)) = self.drawn_visible else {
+            return;
+        };
+        let hidden = last + 1 < self.ctx_ref.cumulative_pass_nr();
+        if hidden && self.reclaimed.is_none() && drawn_at.elapsed() >= reclaim_after {
+            self.reclaim(true);
+        }
+    }
+
+    // free the texture and the decoded frames, keeping a poster of the frame on screen
+    fn reclaim(&mut self, hidden: bool) {
+        let stopped = matches!(
+            self.player_state.get(),
+            PlayerState::Stopped | PlayerState::EndOfFile
+        );
+        // stopped players have no pipeline to stop, but still hold the frames
+        if !stopped && !self.suspend() {
+            return;
+        }
+        if let Some(pool) = self.pool.as_ref() {
+            pool.deactivate();
+        }
+        {
+            let mut video_streamer = self.video_streamer.lock();
+            video_streamer.frame_cache.clear();
+            video_streamer.filtered_frames.clear();
+        }
+        self.review_cache.evict(ReviewCacheSize::Frames(0));
+        if let Some((time_ms, source)) = self.last_frame.take() {
+            let poster = Arc::new(letterbox::scale_by_averaging(&source, POSTER_SIZE));
+            self.texture_handle
+                .set(poster.clone(), self.options.texture_options);
+            if let Some(spare_texture) = self.spare_texture.as_mut() {
+                spare_texture.set(poster.clone(), self.options.texture_options);
+            }
+            self.last_frame = Some((time_ms, poster));
+        }
+        self.reclaimed = Some(hidden);
+    }
+
+    // decode the frame on screen again after `reclaim`, with the pipeline running
+    fn rebuild_reclaimed(&mut self) {
+        if self.reclaimed.is_none() {
+            return;
+        }
+        // stopped players keep the poster until they are started or seeked
+        let state = self.suspended.unwrap_or(self.player_state.get());
+        if matches!(state, PlayerState::Stopped | PlayerState::EndOfFile) {
+            self.reclaimed = Some(false);
+            return;
+        }
+        self.reclaimed = None;
+        self.idle_since = None;
+        match self.last_frame.as_ref() {
+            Some(&(time_ms, _)) => self.seek_to_ms(time_ms),
+            None => {
+                let seek_frac = self.duration_frac();
+                self.seek(seek_frac);
+            }
+        }
+    }
+
+    // restart the pipeline a pool suspended, where it stopped
+    fn wake(&mut self) {
+        let Some(state) = self.suspended.take() else {
+            return;
+        };
+        self.spawn_timers();
+        self.set_state(state);
+    }
+
+    // follow `PlayerOptions::autoplay_visible`
+    fn update_visibility_autoplay(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(visible_fraction) = self.options.autoplay_visible else {
+            return;
+        };
+        let rect = frame_response.rect;
+        let visible_rect = rect.intersect(ui.clip_rect());
+        let visible =
+            visible_rect.is_positive() && visible_rect.area() / rect.area() >= visible_fraction;
+        if visible && !self.visibility_playing {
+            self.visibility_playing = self.play_automatically();
+        } else if !visible && std::mem::take(&mut self.visibility_playing) {
+            self.pause_automatically();
+        }
+    }
+
+    /// Highlight damaged frames for quality control, see [`DamageOverlay`]. `None` stops it. The
+    /// damage is also reported as [`PlayerEvent::DecodeError`].
+    pub fn set_damage_overlay(&mut self, overlay: Option<DamageOverlay>) {
+        self.damage_overlay = overlay;
+    }
+
+    /// The damage the decoder reported for the frame on screen, `None` if it is intact.
+    pub fn frame_damage(&self) -> Option<FrameDamage> {
+        self.frame_damage
+    }
+
+    /// Tint the video and name the damage for a while after a damaged frame was presented, see
+    /// [`Player::set_damage_overlay`]. Already included in [`Player::ui`] and [`Player::ui_at`].
+    pub fn render_damage_overlay(&mut self, ui: &mut Ui, frame_response: &Response) {
+        let Some(overlay) = self.damage_overlay else {
+            return;
+        };
+        let Some((presented, damage)) = self.damage_highlighted else {
+            return;
+        };
+        let elapsed = presented.elapsed();
+        if elapsed >= overlay.hold {
+            return;
+        }
+        let rect = frame_response.rect;
+        ui.painter()
+            .rect_filled(rect, self.options.corner_radius, overlay.tint);
+        if overlay.show_label {
+            ui.painter().text(
+                rect.left_top() + vec2(10., 10.),
+                Align2::LEFT_TOP,
+                format!("damaged: {}", damage.describe()),
+                FontId::proportional(14.),
+                Color32::WHITE,
+            );
+        }
+        self.ctx_ref.request_repaint_after(overlay.hold - elapsed);
+    }
+
+    /// Draw the skip button in the bottom right corner of the video while playback is inside a skip
+    /// range with [`SkipMode::Prompt`], see [`Player::set_skip_ranges`]. Already included in
+    /// [`Player::ui`] and [`Player::ui_at`].
+    pub fn render_skip_button(&mut self, ui: &mut Ui, frame_response: &Response) {
+        if self.options.skip_mode != SkipMode::Prompt {
+            return;
+        }
+        let Some(range) = self.current_skip_range() else {
+            return;
+        };
+        let button = egui::Button::new(
+            egui::RichText::new(range.button_text())
+                .size(16.)
+                .color(Color32::WHITE),
+        )
+        .fill(Color32::from_black_alpha(160))
+        .stroke(egui::Stroke::new(1., Color32::WHITE));
+        let button_size = vec2(140., 36.);
+        // above the seekbar
+        let button_rect = Rect::from_min_size(
+            frame_response.rect.right_bottom() - button_size - vec2(20., 50.),
+            button_size,
+        );
+        if ui.put(button_rect, button).clicked() {
+            self.skip_current_range();
+        }
+    }
+
+    /// Prompt for the next item over the final seconds of this one, with a countdown after which
+    /// [`PlayerEvent::PlayNext`] asks the app to play it. `None` removes the prompt. Not shown while
+    /// looping or for inputs without a known duration, like live ones.
+    pub fn set_up_next(&mut self, up_next: Option<UpNext>) {
+        if self
+            .up_next
+            .as_ref()
+            .is_some_and(|(_, state)| *state == UpNextState::Counting)
+        {
+            let _ = self.event_sender.send(PlayerEvent::UpNextCancelled);
+        }
+        self.up_next = up_next.map(|up_next| (up_next, UpNextState::Waiting));
+    }
+
+    /// The prompt set with [`Player::set_up_next`].
+    pub fn up_next(&self) -> Option<&UpNext> {
+        self.up_next.as_ref().map(|(up_next, _)| up_next)
+    }
+
+    /// How long until the countdown of the [`UpNext`] prompt runs out, while it counts down.
+    pub fn up_next_remaining(&self) -> Option<std::time::Duration> {
+        let (_, state) = self.up_next.as_ref()?;
+        (*state == UpNextState::Counting).then(|| {
+            let remaining_ms = (self.duration_ms - self.current_frame_ms()).max(0);
+            std::time::Duration::from_millis(remaining_ms as u64)
+        })
+    }
+
+    /// Stop the countdown of the [`UpNext`] prompt, so this item plays to its end. It comes back
+    /// when playback goes back before the countdown.
+    pub fn cancel_up_next(&mut self) {
+        if let Some((_, state @ UpNextState::Counting)) = self.up_next.as_mut() {
+            *state = UpNextState::Cancelled;
+            let _ = self.event_sender.send(PlayerEvent::UpNextCancelled);
+        }
+    }
+
+    /// Ask for the next item right away, emitting [`PlayerEvent::PlayNext`].
+    pub fn play_up_next(&mut self) {
+        if let Some((_, state)) = self.up_next.as_mut() {
+            *state = UpNextState::Done;
+            let _ = self
+                .event_sender
+                .send(PlayerEvent::PlayNext { automatic: false });
+        }
+    }
+
+    fn update_up_next(&mut self) {
+        let player_state = self.player_state.get();
+        let remaining_ms = self.duration_ms - self.current_frame_ms();
+        // live inputs have no end to count down to, and report no duration
+        let live = self.timeshift.is_some() || self.duration_ms <= 0;
+        let looping = self.options.looping;
+        let Some((up_next, state)) = self.up_next.as_mut() else {
+            return;
+        };
+        if live || looping {
+            return;
+        }
+        let in_countdown = remaining_ms <= up_next.countdown.as_millis() as i64;
+        let event = match *state {
+            UpNextState::Waiting if in_countdown && player_state == PlayerState::Playing => {
+                *state = UpNextState::Counting;
+                PlayerEvent::UpNextStarted {
+                    title: up_next.title.clone(),
+                    remaining_ms,
+                }
+            }
+            UpNextState::Counting
+                if player_state == PlayerState::EndOfFile || remaining_ms <= 0 =>
+            {
+                *state = UpNextState::Done;
+                PlayerEvent::PlayNext { automatic: true }
+            }
+            UpNextState::Counting if !in_countdown => {
+                *state = UpNextState::Waiting;
+                PlayerEvent::UpNextCancelled
+            }
+            UpNextState::Cancelled | UpNextState::Done if !in_countdown => {
+                *state = UpNextState::Waiting;
+                return;
+            }
+            _ => return,
+        };
+        let _ = self.event_sender.send(event);
+    }
+
+    /// Draw the [`UpNext`] prompt set with [`Player::set_up_next`] in the bottom right corner of
+    /// the video while it counts down, with buttons to play the next item now or cancel. Already
+    /// included in [`Player::ui`] and [`Player::ui_at`].
+    pub fn render_up_next(&mut self, ui: &mut Ui, frame_response: &Response) {
+        let Some(remaining) = self.up_next_remaining() else {
+            return;
+        };
+        let Some((up_next, _)) = self
+            .up_next
+            .as_ref()
+            .filter(|(up_next, _)| up_next.show_overlay)
+        else {
+            return;
+        };
+        let title = up_next.title.clone();
+        let progress = 1. - remaining.as_secs_f32() / up_next.countdown.as_secs_f32().max(0.001);
+        let card_size = vec2(260., 96.);
+        // above the seekbar and the skip button
+        let card_rect = Rect::from_min_size(
+            frame_response.rect.right_bottom() - card_size - vec2(20., 100.),
+            card_size,
+        );
+        let mut card_ui = ui.new_child(UiBuilder::new().max_rect(card_rect));
+        let (mut play_now, mut cancel) = (false, false);
+        egui::Frame::new()
+            .fill(Color32::from_black_alpha(200))
+            .corner_radius(CornerRadius::same(6))
+            .inner_margin(10.)
+            .show(&mut card_ui, |ui| {
+                ui.set_min_size(card_size - vec2(20., 20.));
+                ui.label(
+                    egui::RichText::new(format!("up next in {}s", remaining.as_secs_f32().ceil()))
+                        .size(12.)
+                        .color(Color32::LIGHT_GRAY),
+                );
+                ui.label(egui::RichText::new(title).size(16.).color(Color32::WHITE));
+                ui.add(egui::ProgressBar::new(progress.clamp(0., 1.)).desired_height(3.));
+                ui.horizontal(|ui| {
+                    play_now = ui.button("play now").clicked();
+                    cancel = ui.button("cancel").clicked();
+                });
+            });
+        if play_now {
+            self.play_up_next();
+        } else if cancel {
+            self.cancel_up_next();
+        }
+    }
+
     /// Draw the subtitles, if any. Only works when a subtitle streamer has been already created with
     /// [`Player::add_subtitles`] or [`Player::with_subtitles`] and a valid subtitle stream exists.
+    /// Text is drawn with the fonts attached to the input, falling back to the fonts of egui. Emoji
+    /// come out in egui's monochrome emoji font, as egui can't draw color glyphs, and images in
+    /// cues (like the `[Graphics]` of ASS scripts) aren't drawn at all.
+    #[cfg(feature = "subtitles")]
     pub fn render_subtitles(&mut self, ui: &mut Ui, frame_response: &Response) {
-        let original_rect_center_bottom = Pos2::new(self.size.x / 2., self.size.y);
-        let mut last_bottom = self.size.y;
-        for subtitle in self.current_subtitles.iter_mut() {
+        self.update_subtitles();
+        if !self.pending_font_attachments.is_empty() {
+            let mut definitions = ui.fonts(|f| f.lock().fonts.definitions().clone());
+            FontAttachment::register(
+                std::mem::take(&mut self.pending_font_attachments),
+                &mut definitions,
+            );
+            ui.ctx().set_fonts(definitions);
+        }
+        let band = self.subtitle_band(frame_response.rect);
+        let safe_area = self
+            .options
+            .subtitles
+            .safe_area
+            .apply(band.unwrap_or(frame_response.rect));
+        // subtitles of higher layers are drawn above lower ones, and only collide within a layer
+        let stream_subtitles = match self.subtitles_enabled {
+            true => &mut self.current_subtitles[..],
+            false => &mut [],
+        };
+        let mut subtitles: Vec<&mut Subtitle> = stream_subtitles
+            .iter_mut()
+            .chain(self.injected_subtitles.iter_mut())
+            .collect();
+        subtitles.sort_by_key(|s| s.layer);
+        let mut placed: Vec<(i32, Rect)> = Vec::new();
+        for subtitle in subtitles {
+            if !subtitle.showing {
+                continue;
+            }
             if !subtitle.bitmap.data.is_empty() && subtitle.bitmap.tex_handle == None {
                 let mut image = ColorImage::default();
                 image.size = [subtitle.bitmap.w as usize, subtitle.bitmap.h as usize];
@@ -676,24 +3110,30 @@ impl Player {
                     TextureOptions::default(),
                 ))
             }
+            // bitmaps are positioned on the decoded frame, of which only the clean aperture is shown
+            let [coded_width, coded_height] = self.media_info.coded_size;
             let transform = RectTransform::from_to(
-                Rect::from_min_size(Pos2::ZERO, self.size),
+                self.media_info
+                    .clean_aperture
+                    .unwrap_or(Rect::from_min_size(
+                        Pos2::ZERO,
+                        vec2(coded_width as f32, coded_height as f32),
+                    )),
                 frame_response.rect,
             );
             if subtitle.bitmap.tex_handle != None {
-                if !subtitle.showing {
-                    continue;
-                }
                 let min = egui::pos2(subtitle.bitmap.x as f32, subtitle.bitmap.y as f32);
                 let max = egui::pos2(
                     min.x + subtitle.bitmap.w as f32,
                     min.y + subtitle.bitmap.h as f32,
                 );
 
+                // snapped to physical pixels, so the bitmap isn't resampled across two of them
                 let rect = Rect {
                     min: transform.transform_pos(min),
                     max: transform.transform_pos(max),
-                };
+                }
+                .round_to_pixels(ui.pixels_per_point());
                 ui.painter().image(
                     subtitle.bitmap.tex_handle.as_ref().unwrap().id(),
                     rect,
@@ -702,23 +3142,23 @@ impl Player {
                 );
                 continue;
             }
-            let text_rect = ui.painter().text(
-                subtitle
-                    .position
-                    .map(|p| transform.transform_pos(p))
-                    .unwrap_or_else(|| {
-                        //TODO incorporate left/right margin
-                        let mut center_bottom = original_rect_center_bottom;
-                        center_bottom.y =
-                            center_bottom.y.min(last_bottom) - subtitle.margin.bottom as f32;
-                        transform.transform_pos(center_bottom)
-                    }),
-                subtitle.alignment,
-                &subtitle.text,
-                FontId::proportional(transform.transform_pos(Pos2::new(subtitle.font_size, 0.)).x),
+            let (text_rect, galley) = ui.fonts(|fonts| {
+                layout_text_subtitle(
+                    fonts,
+                    subtitle,
+                    &self.options.subtitles,
+                    frame_response.rect,
+                    safe_area,
+                    band.is_some(),
+                    &placed,
+                )
+            });
+            placed.push((subtitle.layer, text_rect));
+            ui.painter().galley(
+                text_rect.min - galley.rect.min.to_vec2(),
+                galley,
                 subtitle.primary_fill,
             );
-            last_bottom = transform.inverse().transform_pos(text_rect.center_top()).y;
         }
     }
 
@@ -726,6 +3166,29 @@ impl Player {
     /// drawing something in between the video frames and controls, it is probably better to use
     /// [`Player::ui`] or [`Player::ui_at`].
     pub fn render_controls(&mut self, ui: &mut Ui, frame_response: &Response) {
+        if !self.options.show_controls {
+            return;
+        }
+        self.draw_controls(ui, frame_response, false);
+    }
+
+    /// Draw the player controls (seek bar, transport, volume and stream menus) without the video
+    /// frame, and process state changes. For remote control panels and layouts where the video is
+    /// drawn elsewhere, from [`Player::texture_handle`]. Takes at least 130 points of height, so
+    /// the volume slider above the seek bar fits, and paints nothing outside of what it takes.
+    pub fn controls_only_ui(&mut self, ui: &mut Ui, size: Vec2) -> egui::Response {
+        let size = vec2(size.x, size.y.max(CONTROLS_ONLY_MIN_HEIGHT));
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+        let mut controls_ui = ui.new_child(UiBuilder::new().max_rect(rect));
+        controls_ui.shrink_clip_rect(rect);
+        controls_ui.painter().rect_filled(rect, 0., Color32::BLACK);
+        self.draw_controls(&mut controls_ui, &response, true);
+        self.process_state();
+        response
+    }
+
+    // `always_shown` keeps the controls up when nothing is hovered, as there is no video to reveal.
+    fn draw_controls(&mut self, ui: &mut Ui, frame_response: &Response, always_shown: bool) {
         let hovered = ui.rect_contains_pointer(frame_response.rect);
         let player_state = self.player_state.get();
         let currently_seeking = matches!(
@@ -737,7 +3200,7 @@ impl Player {
         let animation_time = 0.2;
         let seekbar_anim_frac = ui.ctx().animate_bool_with_time(
             frame_response.id.with("seekbar_anim"),
-            hovered || currently_seeking || is_paused || is_stopped,
+            always_shown || hovered || currently_seeking || is_paused || is_stopped,
             animation_time,
         );
 
@@ -960,8 +3423,10 @@ impl Player {
 
             let mut draw_row = |stream_type: Type| {
                 let text = match stream_type {
-                    Type::Audio => format!("{} {}", sound_icon, self.audio_stream_info),
-                    Type::Subtitle => format!("{} {}", subtitle_icon, self.subtitle_stream_info),
+                    Type::Audio => format!("{} {}", sound_icon, self.stream_label(Type::Audio)),
+                    Type::Subtitle => {
+                        format!("{} {}", subtitle_icon, self.stream_label(Type::Subtitle))
+                    }
                     _ => unreachable!(),
                 };
 
@@ -993,7 +3458,9 @@ impl Player {
                     .clicked()
                 {
                     match stream_type {
+                        #[cfg(feature = "audio")]
                         Type::Audio => self.cycle_audio_stream(),
+                        #[cfg(feature = "subtitles")]
                         Type::Subtitle => self.cycle_subtitle_stream(),
                         _ => unreachable!(),
                     };
@@ -1029,7 +3496,7 @@ impl Player {
                 .memory_mut(|m| m.data.insert_temp(stream_anim_id, stream_anim_frac));
         }
 
-        if self.audio_streamer.is_some() {
+        if self.has_audio() {
             let sound_icon_rect = ui.painter().text(
                 sound_icon_pos,
                 Align2::RIGHT_BOTTOM,
@@ -1121,10 +3588,12 @@ impl Player {
     }
 
     /// Initializes the audio stream (if there is one), required for making a [`Player`] output audio.
-    /// Will stop and reset the player's state.
+    /// Will stop and reset the player's state. Enabled with the `audio` feature.
+    #[cfg(feature = "audio")]
     pub fn add_audio(&mut self, audio_device: &mut CpalAudioDevice) -> Result<()> {
-        let audio_input_context = input(&self.input_path)?;
+        let audio_input_context = open_input(&self.input_path, &self.input_options)?;
         let audio_stream_indices = get_stream_indices_of_type(&audio_input_context, Type::Audio);
+        self.audio_tracks = track_infos(&audio_input_context, &audio_stream_indices);
 
         let audio_streamer = if !audio_stream_indices.is_empty() {
             let audio_decoder =
@@ -1133,13 +3602,14 @@ impl Player {
 
             let (audio_sample_producer, audio_sample_consumer) =
                 mpsc::channel::<StreamingAudioChunk>();
-            let audio_resampler = ffmpeg::software::resampling::context::Context::get2(
+            let taken_samples = Shared::new(0);
+            let audio_resampler = resampler::stereo_resampler(
                 audio_decoder.format(),
                 audio_decoder.ch_layout(),
                 audio_decoder.rate(),
                 audio_device.get_sample_format().to_sample(),
-                ChannelLayout::STEREO,
                 audio_device.get_sample_rate(),
+                &self.resampler_options,
             )?;
 
             audio_device
@@ -1149,10 +3619,26 @@ impl Player {
                 .push(AudioSampleStream {
                     sample_consumer: audio_sample_consumer,
                     audio_volume: self.options.audio_volume.clone(),
+                    audio_gain_db: self.options.audio_gain_db.clone(),
+                    normalization_gain_db: self.normalization_gain_db.clone(),
+                    audio_limiter: self.options.audio_limiter.clone(),
+                    fade_gain: self.audio_fade_gain.clone(),
+                    muted: self.audio_muted.clone(),
+                    output_gain: 1.,
+                    audio_fade_ms: self.options.audio_fade_ms.clone(),
+                    player_state: self.player_state.clone(),
+                    sample_rate: audio_device.get_sample_rate(),
+                    channels: audio_resampler.output().channel_layout.channels().max(1) as u32,
+                    ramp_step: None,
+                    ramp_gain: 0.,
+                    stale: false,
                     chunks: None,
+                    taken_samples: taken_samples.clone(),
+                    fatal: self.fatal.clone(),
                 });
 
             audio_device.callback.lock().device_time_ms = Some(self.audio_device_time_ms.clone());
+            audio_device.callback.lock().output_timing = Some(self.audio_output_timing.clone());
 
             audio_device.callback.lock().seeking = Some(self.seeking_signal.clone());
 
@@ -1169,7 +3655,32 @@ impl Player {
                 input_context: audio_input_context,
                 audio_decoder,
                 resampler: audio_resampler,
+                resampler_options: self.resampler_options,
                 audio_stream_indices,
+                timeshift: self.new_timeshift_buffer(false),
+                playback_speed: self.playback_speed.clone(),
+                time_stretch: self.time_stretch,
+                channel_map: self.channel_map.clone(),
+                audio_filter: None,
+                audio_filter_spec: None,
+                cut_filter: self.cut_bounds_ms.clone().map(CutFilter::new),
+                content_filters: self.content_filters.clone(),
+                health_monitor: self
+                    .health_options
+                    .clone()
+                    .map(|options| HealthMonitor::new(options, self.event_sender.clone())),
+                silence_detector: self
+                    .skip_silence
+                    .map(|options| SilenceDetector::new(&options, self.silent_spans.clone())),
+                paused_audio_reserve_ms: self.options.paused_audio_reserve_ms.clone(),
+                shuttle_silent: self.shuttle_silent.clone(),
+                sent_samples: Shared::new(0),
+                taken_samples,
+                decode_errors: DecodeErrors::new(
+                    self.decode_error_count.clone(),
+                    self.session_stats.clone(),
+                    self.event_sender.clone(),
+                ),
             })
         } else {
             None
@@ -1179,97 +3690,1958 @@ impl Player {
     }
 
     /// Initializes the subtitle stream (if there is one), required for making a [`Player`] display subtitles.
-    /// Will stop and reset the player's state.
+    /// Will stop and reset the player's state. Enabled with the `subtitles` feature.
+    #[cfg(feature = "subtitles")]
     pub fn add_subtitles(&mut self) -> Result<()> {
-        let subtitle_input_context = input(&self.input_path)?;
+        let subtitle_input_context = open_input(&self.input_path, &self.input_options)?;
         let subtitle_stream_indices =
             get_stream_indices_of_type(&subtitle_input_context, Type::Subtitle);
+        self.subtitle_tracks = track_infos(&subtitle_input_context, &subtitle_stream_indices);
 
         let subtitle_streamer = if !subtitle_stream_indices.is_empty() {
             let subtitle_decoder =
                 get_decoder_from_stream_index(&subtitle_input_context, subtitle_stream_indices[0])?
                     .subtitle()?;
-
-            self.stop();
-            self.subtitle_stream_info = StreamInfo::from_total(subtitle_stream_indices.len());
-            Some(SubtitleStreamer {
-                next_packet: None,
-                duration_ms: self.duration_ms,
-                player_state: self.player_state.clone(),
-                video_elapsed_ms: self.video_elapsed_ms.clone(),
-                _audio_elapsed_ms: self.audio_elapsed_ms.clone(),
-                subtitle_elapsed_ms: self.subtitle_elapsed_ms.clone(),
-                input_context: subtitle_input_context,
-                subtitles_queue: self.subtitles_queue.clone(),
+            let timeshift = self.new_timeshift_buffer(false);
+            Some(self.new_subtitle_streamer(
+                subtitle_input_context,
                 subtitle_decoder,
                 subtitle_stream_indices,
-            })
+                timeshift,
+                None,
+            ))
         } else {
             None
         };
         self.subtitle_streamer = subtitle_streamer.map(|s| Arc::new(Mutex::new(s)));
+        self.subtitle_file = None;
+        Ok(())
+    }
+
+    /// Show the subtitles of an external file (like an `.srt`, `.ass` or `.vtt` next to the video)
+    /// instead of the subtitle streams of the input. Text in legacy encodings is converted according
+    /// to `encoding`. Each file is kept as a track to switch back to with
+    /// [`Player::select_subtitle_file`]. Will stop and reset the player's state.
+    #[cfg(feature = "subtitles")]
+    pub fn add_subtitle_file(&mut self, path: &str, encoding: SubtitleEncoding) -> Result<()> {
+        let subtitle_input_context = open_input(path, &InputOptions::default())?;
+        let subtitle_stream_indices =
+            get_stream_indices_of_type(&subtitle_input_context, Type::Subtitle);
+        let Some(stream_index) = subtitle_stream_indices.front().copied() else {
+            anyhow::bail!("no subtitles in {path}");
+        };
+
+        let charset = encoding.charset_of(path)?;
+        let subtitle_decoder =
+            open_subtitle_decoder(&subtitle_input_context, stream_index, charset.as_deref())?;
+
+        let subtitle_streamer = self.new_subtitle_streamer(
+            subtitle_input_context,
+            subtitle_decoder,
+            subtitle_stream_indices,
+            None,
+            charset.clone(),
+        );
+        self.subtitle_streamer = Some(Arc::new(Mutex::new(subtitle_streamer)));
+        let index = match self.subtitle_files.iter().position(|(p, _)| p == path) {
+            Some(index) => {
+                self.subtitle_files[index].1 = charset;
+                index
+            }
+            None => {
+                self.subtitle_files.push((path.to_string(), charset));
+                self.subtitle_files.len() - 1
+            }
+        };
+        self.subtitle_file = Some((index, modified_time(path)));
+        Ok(())
+    }
+
+    /// The paths of the files added with [`Player::add_subtitle_file`], in the order they were added.
+    #[cfg(feature = "subtitles")]
+    pub fn subtitle_files(&self) -> impl Iterator<Item = &str> {
+        self.subtitle_files.iter().map(|(path, _)| path.as_str())
+    }
+
+    /// The index of the file in [`Player::subtitle_files`] being shown, if the subtitles come from
+    /// one.
+    #[cfg(feature = "subtitles")]
+    pub fn selected_subtitle_file(&self) -> Option<usize> {
+        self.subtitle_file.map(|(index, _)| index)
+    }
+
+    /// Switch to the subtitles of the file at `index` in [`Player::subtitle_files`], read again from
+    /// the current position without interrupting playback. [`Player::add_subtitles`] switches back
+    /// to the subtitle streams of the input. Fails, leaving playback as it is, when there is no
+    /// subtitle streamer to switch (after [`Player::add_subtitles`] on an input without subtitle
+    /// streams): [`Player::add_subtitle_file`] starts one, resetting the player.
+    #[cfg(feature = "subtitles")]
+    pub fn select_subtitle_file(&mut self, index: usize) -> Result<()> {
+        let (path, charset) = self
+            .subtitle_files
+            .get(index)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no subtitle file at {index}"))?;
+        let Some(subtitle_streamer) = self.subtitle_streamer.clone() else {
+            // starting a streamer stops the player, which is left to `add_subtitle_file`
+            anyhow::bail!(
+                "no subtitle streamer to switch to {path}, see `Player::add_subtitle_file`"
+            );
+        };
+        let input_context = open_input(&path, &InputOptions::default())?;
+        let subtitle_stream_indices = get_stream_indices_of_type(&input_context, Type::Subtitle);
+        if subtitle_stream_indices.is_empty() {
+            anyhow::bail!("no subtitles in {path}");
+        }
+        self.subtitle_stream_info = StreamInfo::from_total(subtitle_stream_indices.len());
+        self.subtitle_tracks = track_infos(&input_context, &subtitle_stream_indices);
+        self.subtitle_file = Some((index, modified_time(&path)));
+        let seek_frac = self.ms_to_seek_frac(self.current_frame_ms());
+        self.current_subtitles.clear();
+        std::thread::spawn(move || {
+            let mut subtitle_streamer = subtitle_streamer.lock();
+            subtitle_streamer.charset = charset;
+            subtitle_streamer.timeshift = None;
+            let _ = subtitle_streamer.reload(input_context, seek_frac);
+        });
         Ok(())
     }
 
-    fn cycle_stream<T: Streamer + 'static>(&self, mut streamer: Option<&Arc<Mutex<T>>>) {
-        if let Some(streamer) = streamer.take() {
+    /// Show or hide the subtitles of the input or subtitle file, keeping them decoded so they
+    /// reappear right away. Cues from [`Player::inject_subtitle`] are always shown.
+    #[cfg(feature = "subtitles")]
+    pub fn set_subtitles_enabled(&mut self, enabled: bool) {
+        self.subtitles_enabled = enabled;
+    }
+
+    /// Whether subtitles are shown, see [`Player::set_subtitles_enabled`].
+    #[cfg(feature = "subtitles")]
+    pub fn subtitles_enabled(&self) -> bool {
+        self.subtitles_enabled
+    }
+
+    #[cfg(feature = "subtitles")]
+    fn reload_changed_subtitle_file(&mut self) {
+        if !self.options.reload_subtitle_files
+            || self
+                .subtitle_file_checked
+                .is_some_and(|checked| checked.elapsed() < SUBTITLE_FILE_POLL_INTERVAL)
+        {
+            return;
+        }
+        self.subtitle_file_checked = Some(Instant::now());
+        let Some((index, loaded_modified)) = self.subtitle_file.as_mut() else {
+            return;
+        };
+        let path = &self.subtitle_files[*index].0;
+        let modified = modified_time(path);
+        if modified == *loaded_modified {
+            return;
+        }
+        // a file in the middle of being saved may not open yet, so it's retried on the next check
+        let Ok(input_context) = open_input(path, &InputOptions::default()) else {
+            return;
+        };
+        *loaded_modified = modified;
+        let Some(subtitle_streamer) = self.subtitle_streamer.clone() else {
+            return;
+        };
+        let seek_frac = self.ms_to_seek_frac(self.current_frame_ms());
+        self.current_subtitles.clear();
+        std::thread::spawn(move || {
+            let _ = subtitle_streamer.lock().reload(input_context, seek_frac);
+        });
+    }
+
+    #[cfg(feature = "subtitles")]
+    fn new_subtitle_streamer(
+        &mut self,
+        input_context: Input,
+        subtitle_decoder: ffmpeg::decoder::Subtitle,
+        subtitle_stream_indices: VecDeque<StreamIndex>,
+        timeshift: Option<TimeshiftBuffer>,
+        charset: Option<String>,
+    ) -> SubtitleStreamer {
+        self.stop();
+        self.subtitle_stream_info = StreamInfo::from_total(subtitle_stream_indices.len());
+        self.subtitle_tracks = track_infos(&input_context, &subtitle_stream_indices);
+        self.pending_font_attachments = FontAttachment::from_input(&input_context);
+        SubtitleStreamer {
+            next_packet: None,
+            duration_ms: self.duration_ms,
+            player_state: self.player_state.clone(),
+            video_elapsed_ms: self.video_elapsed_ms.clone(),
+            _audio_elapsed_ms: self.audio_elapsed_ms.clone(),
+            subtitle_elapsed_ms: self.subtitle_elapsed_ms.clone(),
+            input_context,
+            subtitles_queue: self.subtitles_queue.clone(),
+            ass_script: AssScript::from_decoder(&subtitle_decoder),
+            subtitle_decoder,
+            subtitle_stream_indices,
+            timeshift,
+            charset,
+            seek_preroll_ms: self.options.subtitle_seek_preroll_ms.clone(),
+            delay_ms: self.options.subtitle_delay_ms.clone(),
+        }
+    }
+
+    // switch `steps` streams ahead, opening only the decoder of the stream switched to
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
+    fn cycle_stream<T: Streamer + 'static>(
+        &mut self,
+        streamer: Option<Arc<Mutex<T>>>,
+        steps: usize,
+    ) {
+        if let Some(streamer) = streamer.filter(|_| steps > 0) {
             let message_sender = self.message_sender.clone();
-            let streamer = streamer.clone();
+            self.pending_stream_cycles += 1;
             std::thread::spawn(move || {
                 let mut streamer = streamer.lock();
-                streamer.cycle_stream();
-                message_sender.send(PlayerMessage::StreamCycled(streamer.stream_type()))
+                streamer.cycle_stream_by(steps);
+                let message = PlayerMessage::StreamCycled(streamer.stream_type(), steps);
+                message_sender.send(message)
             });
         };
     }
 
+    /// The audio tracks of the input, in the order [`Player::cycle_audio_stream`] goes through them.
+    /// Empty until [`Player::add_audio`] was called.
+    pub fn audio_tracks(&self) -> &[TrackInfo] {
+        &self.audio_tracks
+    }
+
+    /// The subtitle tracks of the input or subtitle file, in the order
+    /// [`Player::cycle_subtitle_stream`] goes through them. Empty until [`Player::add_subtitles`]
+    /// or [`Player::add_subtitle_file`] was called.
+    pub fn subtitle_tracks(&self) -> &[TrackInfo] {
+        &self.subtitle_tracks
+    }
+
+    /// The audio track playing.
+    pub fn current_audio_track(&self) -> Option<&TrackInfo> {
+        self.audio_tracks
+            .get(self.audio_stream_info.current_stream - 1)
+    }
+
+    /// The subtitle track showing, when subtitles are enabled.
+    #[cfg(feature = "subtitles")]
+    pub fn current_subtitle_track(&self) -> Option<&TrackInfo> {
+        self.subtitle_tracks
+            .get(self.subtitle_stream_info.current_stream - 1)
+            .filter(|_| self.subtitles_enabled)
+    }
+
+    /// Name tracks with `formatter` in the player controls and menus, instead of
+    /// [`TrackInfo::display_name`], like for naming languages in the language of the app.
+    pub fn set_track_name_formatter(
+        &mut self,
+        formatter: impl Fn(&TrackInfo) -> String + Send + 'static,
+    ) {
+        self.track_name_formatter = Some(Box::new(formatter));
+    }
+
+    /// The name of `track` in the player controls and menus, see
+    /// [`Player::set_track_name_formatter`].
+    pub fn track_name(&self, track: &TrackInfo) -> String {
+        match self.track_name_formatter.as_ref() {
+            Some(formatter) => formatter(track),
+            None => track.display_name(),
+        }
+    }
+
+    // the name of the current track of `stream_type` in the controls, after its number
+    fn stream_label(&self, stream_type: Type) -> String {
+        let (info, track) = match stream_type {
+            Type::Audio => (&self.audio_stream_info, self.current_audio_track()),
+            _ => (
+                &self.subtitle_stream_info,
+                self.subtitle_tracks
+                    .get(self.subtitle_stream_info.current_stream - 1),
+            ),
+        };
+        match track {
+            Some(track) => format!("{info} {}", self.track_name(track)),
+            None => info.to_string(),
+        }
+    }
+
     /// Switches to the next subtitle stream.
+    #[cfg(feature = "subtitles")]
     pub fn cycle_subtitle_stream(&mut self) {
-        self.cycle_stream(self.subtitle_streamer.as_ref());
+        self.cycle_stream(self.subtitle_streamer.clone(), 1);
     }
 
     /// Switches to the next audio stream.
+    #[cfg(feature = "audio")]
     pub fn cycle_audio_stream(&mut self) {
-        self.cycle_stream(self.audio_streamer.as_ref());
+        self.cycle_stream(self.audio_streamer.clone(), 1);
+    }
+
+    /// Switches to subtitle stream `stream`, counting from `1` in the order of
+    /// [`Player::subtitle_tracks`]. Streams the input doesn't have are ignored.
+    #[cfg(feature = "subtitles")]
+    pub fn select_subtitle_stream(&mut self, stream: usize) {
+        let steps = self.subtitle_stream_info.steps_to(stream);
+        self.cycle_stream(self.subtitle_streamer.clone(), steps);
+    }
+
+    /// Switches to audio stream `stream`, counting from `1` in the order of
+    /// [`Player::audio_tracks`]. Streams the input doesn't have are ignored.
+    #[cfg(feature = "audio")]
+    pub fn select_audio_stream(&mut self, stream: usize) {
+        let steps = self.audio_stream_info.steps_to(stream);
+        self.cycle_stream(self.audio_streamer.clone(), steps);
     }
 
     /// Enables using [`Player::add_audio`] with the builder pattern.
+    #[cfg(feature = "audio")]
     pub fn with_audio(mut self, audio_device: &mut CpalAudioDevice) -> Result<Self> {
         self.add_audio(audio_device)?;
         Ok(self)
     }
 
     /// Enables using [`Player::add_subtitles`] with the builder pattern.
+    #[cfg(feature = "subtitles")]
     pub fn with_subtitles(mut self) -> Result<Self> {
         self.add_subtitles()?;
         Ok(self)
     }
 
-    /// Create a new [`Player`].
-    pub fn new(ctx: &egui::Context, input_path: &String) -> Result<Self> {
-        let input_context = input(&input_path)?;
-        let video_stream = input_context
-            .streams()
-            .best(Type::Video)
-            .ok_or(ffmpeg::Error::StreamNotFound)?;
-        let video_stream_index = StreamIndex::from(video_stream.index());
+    /// Keep the most recent packets of a live source in memory, so that playback can be paused and rewound
+    /// within [`TimeshiftOptions::window_ms`]. The seekbar shows the buffered window instead of the duration.
+    /// Will stop and reset the player's state.
+    pub fn add_timeshift(&mut self, options: TimeshiftOptions) {
+        self.stop();
+        self.timeshift = Some((options, TimeshiftWindow::new()));
+        self.video_streamer.lock().timeshift = self.new_timeshift_buffer(true);
+        #[cfg(feature = "audio")]
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().timeshift = self.new_timeshift_buffer(false);
+        }
+        #[cfg(feature = "subtitles")]
+        if let Some(subtitle_streamer) = self.subtitle_streamer.as_ref() {
+            subtitle_streamer.lock().timeshift = self.new_timeshift_buffer(false);
+        }
+    }
 
-        let video_elapsed_ms = Shared::new(0);
-        let audio_elapsed_ms = Shared::new(0);
-        let audio_device_time_ms = Shared::new(0);
-        let seeking_signal = Shared::new(false);
-        let player_state = Shared::new(PlayerState::Stopped);
+    /// Enables using [`Player::add_timeshift`] with the builder pattern.
+    pub fn with_timeshift(mut self, options: TimeshiftOptions) -> Self {
+        self.add_timeshift(options);
+        self
+    }
+
+    /// The range of the live stream currently held by the timeshift buffer, in milliseconds. Only exists
+    /// if [`Player::add_timeshift`] has been called.
+    pub fn timeshift_window_ms(&self) -> Option<(i64, i64)> {
+        self.timeshift
+            .as_ref()
+            .map(|(_, window)| (window.start_ms.get(), window.end_ms.get()))
+    }
+
+    /// Jump back to the live edge of a timeshifted stream.
+    pub fn seek_to_live(&mut self) {
+        if self.timeshift.is_some() {
+            self.seek(1.);
+        }
+    }
+
+    /// The duration, position, buffered ranges, chapters and skip ranges of the player, taken
+    /// together in [`Player::process_state`], for drawing a custom seekbar.
+    pub fn timeline(&self) -> &TimelineModel {
+        &self.timeline
+    }
+
+    fn update_timeline(&mut self) {
+        let position_ms = self.elapsed_ms();
+        let window_ms = self.timeshift_window_ms();
+        let buffered_ms = match window_ms {
+            Some(window_ms) => vec![window_ms],
+            None => {
+                let vs = self.video_streamer.lock();
+                let buffered_end_ms = vs
+                    .frame_cache
+                    .back()
+                    .map(|(_, time_ms, duration_ms)| time_ms + duration_ms);
+                buffered_end_ms
+                    .filter(|&end_ms| end_ms > position_ms)
+                    .map(|end_ms| (position_ms, end_ms))
+                    .into_iter()
+                    .collect()
+            }
+        };
+        self.timeline = TimelineModel {
+            duration_ms: self.duration_ms,
+            position_ms,
+            seekable_ms: window_ms.unwrap_or((0, self.duration_ms)),
+            buffered_ms,
+            chapters: self.chapters.clone(),
+            markers: self.skip_ranges.clone(),
+            live_edge_ms: window_ms.map(|(_, end_ms)| end_ms),
+        };
+    }
+
+    /// Drain the events emitted since the last call, see [`PlayerEvent`].
+    pub fn events(&self) -> impl Iterator<Item = PlayerEvent> + '_ {
+        self.event_reciever.try_iter()
+    }
+
+    /// Drain the events like [`Player::events`], each along with the data set with
+    /// [`Player::set_user_data`], for apps handling the events of many players in one place.
+    pub fn tagged_events(&self) -> impl Iterator<Item = TaggedEvent> + '_ {
+        self.event_reciever.try_iter().map(|event| TaggedEvent {
+            event,
+            user_data: self.user_data.clone(),
+        })
+    }
+
+    /// Attach data of the app to the player, like the id of what it plays. It is passed along in
+    /// [`Player::tagged_events`] and [`PresentedFrame::user_data`], and cue point callbacks get it
+    /// from [`Player::user_data`].
+    pub fn set_user_data(&mut self, data: impl Any + Send + Sync) {
+        self.user_data = Some(UserData::new(data));
+    }
+
+    /// Attach data shared with other players or items, see [`Player::set_user_data`]. `None`
+    /// removes it.
+    pub fn set_shared_user_data(&mut self, data: Option<UserData>) {
+        self.user_data = data;
+    }
+
+    /// The data set with [`Player::set_user_data`], if it is a `T`.
+    pub fn user_data<T: Any>(&self) -> Option<&T> {
+        self.user_data.as_ref().and_then(|data| data.get())
+    }
+
+    /// Analyze decoded frames for motion and scene changes, emitting [`PlayerEvent::Motion`] and
+    /// [`PlayerEvent::SceneChange`].
+    pub fn enable_frame_analysis(&mut self, options: FrameAnalysisOptions) {
+        let mut video_streamer = self.video_streamer.lock();
+        match video_streamer.frame_analyzer.as_mut() {
+            Some(frame_analyzer) => frame_analyzer.set_options(options),
+            None => {
+                video_streamer.frame_analyzer = Some(FrameAnalyzer::new(
+                    options,
+                    self.size,
+                    self.event_sender.clone(),
+                ))
+            }
+        }
+    }
+
+    /// Stop analyzing frames. This also stops [`Player::capture_on`].
+    pub fn disable_frame_analysis(&mut self) {
+        self.video_streamer.lock().frame_analyzer = None;
+    }
+
+    /// Save frames into `dir` (as `.ppm` images) whenever frame analysis reports an event matching `filter`.
+    /// Enables frame analysis with the default [`FrameAnalysisOptions`] if it isn't already enabled. Captures
+    /// are limited by [`FrameAnalysisOptions::capture_cooldown_ms`], and each emits [`PlayerEvent::FrameCaptured`].
+    pub fn capture_on(&mut self, filter: CaptureFilter, dir: impl Into<PathBuf>) -> Result<()> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let mut video_streamer = self.video_streamer.lock();
+        video_streamer
+            .frame_analyzer
+            .get_or_insert_with(|| {
+                let options = FrameAnalysisOptions::default();
+                FrameAnalyzer::new(options, self.size, self.event_sender.clone())
+            })
+            .capture = Some((filter, dir));
+        Ok(())
+    }
+
+    /// Stop capturing frames started with [`Player::capture_on`]. Frame analysis stays enabled.
+    pub fn stop_capture(&mut self) {
+        if let Some(frame_analyzer) = self.video_streamer.lock().frame_analyzer.as_mut() {
+            frame_analyzer.capture = None;
+        }
+    }
 
-        let video_context =
-            ffmpeg::codec::context::Context::from_parameters(video_stream.parameters())?;
-        let video_decoder = video_context.decoder().video()?;
+    /// Why the video is decoded in software although [`InputOptions::with_hwaccel`] asked for the
+    /// hardware, `None` while it is decoded on the hardware (or software was asked for). Also
+    /// emitted as [`PlayerEvent::HwDecodingFallback`].
+    pub fn hw_fallback_reason(&self) -> Option<HwFallbackReason> {
+        self.hw_fallback.lock().clone()
+    }
+
+    /// How many packets and frames of the input couldn't be decoded or came out damaged so far,
+    /// each also emitted as [`PlayerEvent::DecodeError`].
+    pub fn decode_error_count(&self) -> u64 {
+        self.decode_error_count.get()
+    }
+
+    /// The thread that panicked and the message of the panic, if the pipeline of the player was
+    /// stopped by one, see [`PlayerEvent::Fatal`].
+    pub fn fatal_error(&self) -> Option<(&'static str, String)> {
+        self.fatal.get()
+    }
+
+    // after a panic in the pipeline, stop the timers and free the decoders, once
+    fn update_fatal(&mut self) -> bool {
+        if self.fatal_reported {
+            return true;
+        }
+        let Some((thread, error)) = self.fatal.get() else {
+            return false;
+        };
+        self.fatal_reported = true;
+        self.sleep_inhibitor = None;
+        if let Some(pool) = self.pool.as_ref() {
+            pool.deactivate();
+        }
+        self.video_thread = None;
+        self.synchro_thread = None;
+        #[cfg(feature = "audio")]
+        {
+            self.audio_thread = None;
+            self.audio_streamer = None;
+        }
+        #[cfg(feature = "subtitles")]
+        {
+            self.subtitle_thread = None;
+            self.subtitle_streamer = None;
+            self.current_subtitles.clear();
+        }
+        self.player_state.set(PlayerState::Stopped);
+        let _ = self.event_sender.send(PlayerEvent::Fatal { thread, error });
+        true
+    }
+
+    /// The buffering, dropped frames, source switches and errors of the session so far, for
+    /// quality of experience telemetry, see [`PlaybackStats::to_json`].
+    pub fn playback_stats(&self) -> PlaybackStats {
+        let mut stats = self.session_stats.lock().snapshot();
+        stats.display_refresh = self.display_refresh();
+        stats
+    }
+
+    // playback is buffering while it plays with no frames decoded ahead
+    fn update_session_stats(&mut self) {
+        let buffering = self.player_state.get() == PlayerState::Playing
+            && self.video_streamer.lock().frame_cache.is_empty();
+        let time_ms = self.current_frame_ms();
+        self.session_stats.lock().set_buffering(buffering, time_ms);
+    }
+
+    /// Watch the streams for anomalies of live broadcasts (corrupt packets, jitter, silence and
+    /// frozen video), emitting them as [`PlayerEvent`]s.
+    pub fn enable_health_monitoring(&mut self, options: HealthOptions) {
+        self.video_streamer.lock().health_monitor = Some(HealthMonitor::new(
+            options.clone(),
+            self.event_sender.clone(),
+        ));
+        #[cfg(feature = "audio")]
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().health_monitor = Some(HealthMonitor::new(
+                options.clone(),
+                self.event_sender.clone(),
+            ));
+        }
+        self.health_options = Some(options);
+    }
+
+    /// Stop watching the streams started with [`Player::enable_health_monitoring`].
+    pub fn disable_health_monitoring(&mut self) {
+        self.video_streamer.lock().health_monitor = None;
+        #[cfg(feature = "audio")]
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().health_monitor = None;
+        }
+        self.health_options = None;
+    }
+
+    /// Jump over the silent parts of the audio while playing, found as it is decoded, like for
+    /// listening to lectures and podcasts faster along with [`Player::set_playback_speed`].
+    /// Silences longer than the audio decoded ahead of playback are jumped over in steps. `None`
+    /// plays them again. Needs [`Player::add_audio`], before or after.
+    #[cfg(feature = "audio")]
+    pub fn set_skip_silence(&mut self, skip_silence: Option<SkipSilence>) {
+        self.skip_silence = skip_silence;
+        self.silent_spans.lock().clear();
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().silence_detector = skip_silence
+                .map(|options| SilenceDetector::new(&options, self.silent_spans.clone()));
+        }
+    }
+
+    /// The options set with [`Player::set_skip_silence`].
+    #[cfg(feature = "audio")]
+    pub fn skip_silence(&self) -> Option<SkipSilence> {
+        self.skip_silence
+    }
+
+    #[cfg(feature = "audio")]
+    fn update_silence_skip(&mut self) {
+        if self.player_state.get() != PlayerState::Playing {
+            return;
+        }
+        let Some(skip_silence) = self.skip_silence else {
+            return;
+        };
+        let time_ms = self.current_frame_ms();
+        let target_ms = {
+            let mut spans = self.silent_spans.lock();
+            while spans.front().is_some_and(|span| span.end_ms <= time_ms) {
+                spans.pop_front();
+            }
+            spans
+                .front()
+                .and_then(|span| skip_silence.jump_target(span, time_ms))
+        };
+        if let Some(target_ms) = target_ms {
+            self.seek_to_ms(target_ms);
+            // the spans found before the seek are dropped by the audio streamer too
+            self.silent_spans.lock().clear();
+            let _ = self.event_sender.send(PlayerEvent::SilenceSkipped {
+                range: TimeRange::new(time_ms, target_ms).with_label("silence"),
+            });
+        }
+    }
+
+    /// Set the playback speed, `1.0` being normal speed. Audio is time stretched to keep its pitch,
+    /// see [`Player::set_time_stretch`].
+    pub fn set_playback_speed(&mut self, speed: f32) {
+        self.playback_speed.set(speed.clamp(0.1, 16.));
+        self.save_settings();
+    }
+
+    /// The current playback speed.
+    pub fn playback_speed(&self) -> f32 {
+        self.playback_speed.get()
+    }
+
+    /// Shuttle forwards, faster on each call, or at the first speed when shuttling backwards,
+    /// see [`Shuttle`]. Uses the speeds of [`PlayerOptions::shuttle`], or the default ones.
+    pub fn shuttle_forward(&mut self) {
+        let shuttle = self.options.shuttle.clone().unwrap_or_default();
+        self.shuttle.press(1, &shuttle);
+        self.apply_shuttle(&shuttle);
+    }
+
+    /// Shuttle backwards, faster on each call, or at the first speed when shuttling forwards. Frames
+    /// are only decoded forwards, so this seeks back repeatedly, without audio.
+    pub fn shuttle_reverse(&mut self) {
+        let shuttle = self.options.shuttle.clone().unwrap_or_default();
+        self.shuttle.press(-1, &shuttle);
+        self.apply_shuttle(&shuttle);
+    }
+
+    /// Stop shuttling, pausing playback and restoring the speed from before.
+    pub fn shuttle_stop(&mut self) {
+        let shuttle = self.options.shuttle.clone().unwrap_or_default();
+        self.shuttle.level = 0;
+        self.shuttle.jog = 0;
+        self.apply_shuttle(&shuttle);
+    }
+
+    /// The speed shuttled at, negative backwards, `0` if not shuttling.
+    pub fn shuttle_speed(&self) -> f32 {
+        let shuttle = self.options.shuttle.clone().unwrap_or_default();
+        self.shuttle.speed(&shuttle)
+    }
+
+    // play at the speed of the shuttle, or pause for playing backwards and once it stops
+    fn apply_shuttle(&mut self, shuttle: &Shuttle) {
+        let speed = self.shuttle.speed(shuttle);
+        if self.shuttle.is_active() {
+            self.shuttle
+                .speed_before
+                .get_or_insert(self.playback_speed.get());
+        } else if let Some(speed_before) = self.shuttle.speed_before.take() {
+            self.playback_speed.set(speed_before);
+        }
+        let silent = speed < 0. || speed > shuttle.max_audible_speed;
+        if silent && !self.shuttle_silent.get() {
+            // the video clock takes over from where the audio is
+            self.video_clock.set(self.current_frame_ms());
+        }
+        self.shuttle_silent.set(silent);
+        self.shuttle.last_reverse_step = None;
+        if speed > 0. {
+            // not saved to the settings store, unlike `set_playback_speed`
+            self.playback_speed.set(speed.clamp(0.1, 16.));
+            self.play_automatically();
+        } else if speed < 0. || self.player_state.get() == PlayerState::Playing {
+            // playing backwards seeks a started, paused player
+            self.play_automatically();
+            self.pause_automatically();
+        }
+    }
+
+    // follow `PlayerOptions::shuttle` while the player is hovered. the keys are left to a text
+    // field that has the focus, which also ends jogging
+    fn update_shuttle_keys(&mut self, ui: &Ui, frame_response: &Response) {
+        let Some(shuttle) = self.options.shuttle.clone() else {
+            return;
+        };
+        let hovered = ui.rect_contains_pointer(frame_response.rect);
+        let typing = ui.ctx().wants_keyboard_input();
+        let (pause_down, forward_down, reverse_down, pressed) = ui.input(|i| {
+            if typing {
+                return (false, false, false, None);
+            }
+            let pressed = if i.key_pressed(shuttle.forward_key) {
+                Some(1)
+            } else if i.key_pressed(shuttle.reverse_key) {
+                Some(-1)
+            } else if i.key_pressed(shuttle.pause_key) {
+                Some(0)
+            } else {
+                None
+            };
+            (
+                i.key_down(shuttle.pause_key),
+                i.key_down(shuttle.forward_key),
+                i.key_down(shuttle.reverse_key),
+                pressed,
+            )
+        });
+        let jog = match (hovered && pause_down, forward_down, reverse_down) {
+            (true, true, false) => 1,
+            (true, false, true) => -1,
+            _ => 0,
+        };
+        if jog != 0 || self.shuttle.jog != 0 {
+            if jog != self.shuttle.jog {
+                self.shuttle.level = 0;
+                self.shuttle.jog = jog;
+                self.apply_shuttle(&shuttle);
+            }
+            return;
+        }
+        match pressed.filter(|_| hovered) {
+            Some(0) => self.shuttle_stop(),
+            Some(direction) => {
+                self.shuttle.press(direction, &shuttle);
+                self.apply_shuttle(&shuttle);
+            }
+            None => (),
+        }
+    }
+
+    // follow `PlayerOptions::inhibit_sleep`. seeks keep the state from before them, so
+    // seeking while playing doesn't release it
+    fn update_sleep_inhibition(&mut self) {
+        let playing = match self.player_state.get() {
+            PlayerState::Playing => true,
+            PlayerState::SeekingInProgress | PlayerState::SeekingFinished => {
+                self.preseek_player_state == Some(PlayerState::Playing)
+            }
+            _ => false,
+        };
+        let inhibit = self.options.inhibit_sleep && playing;
+        if inhibit != self.sleep_inhibitor.is_some() {
+            self.sleep_inhibitor = inhibit.then(SleepInhibitor::acquire);
+        }
+    }
+
+    // play backwards by seeking back by what the shuttle covered since the last step
+    fn update_shuttle(&mut self) {
+        if self.shuttle.level >= 0 && self.shuttle.jog >= 0 {
+            return;
+        }
+        let shuttle = self.options.shuttle.clone().unwrap_or_default();
+        match self.player_state.get() {
+            PlayerState::Paused => (),
+            // the previous step is still being seeked to
+            PlayerState::SeekingInProgress | PlayerState::SeekingFinished => return,
+            // the app played or stopped it
+            _ => {
+                if let Some(speed_before) = self.shuttle.speed_before.take() {
+                    self.playback_speed.set(speed_before);
+                }
+                self.shuttle = ShuttleState::default();
+                self.shuttle_silent.set(false);
+                return;
+            }
+        }
+        self.ctx_ref.request_repaint();
+        let current_ms = self.current_frame_ms();
+        let Some(target_ms) = self
+            .shuttle
+            .reverse_step(Instant::now(), current_ms, &shuttle)
+        else {
+            return;
+        };
+        if target_ms <= 0 {
+            self.shuttle_stop();
+            self.seek_to_ms(0);
+        } else {
+            self.seek_to_ms(target_ms);
+        }
+    }
+
+    /// Scale decoded frames down to fit in `max_size` pixels on their longer side before they are
+    /// converted and uploaded, for players shown small. `None` keeps the full resolution.
+    pub fn set_max_frame_size(&mut self, max_size: Option<u32>) {
+        self.max_frame_size.set(max_size.unwrap_or(0));
+    }
+
+    /// Only show every `step`th frame, lowering the frame rate. The other frames are still decoded
+    /// (later frames are predicted from them), but not converted or uploaded. `1` shows all frames.
+    pub fn set_frame_step(&mut self, step: u32) {
+        self.frame_step.set(step.max(1));
+    }
+
+    /// Apply the settings of `profile`, see [`Player::set_performance_settings`].
+    pub fn set_performance_profile(&mut self, profile: PerformanceProfile) {
+        self.set_performance_settings(profile.settings());
+        self.performance_profile = Some(profile);
+    }
+
+    /// The profile set with [`Player::set_performance_profile`], `None` after setting other
+    /// settings with [`Player::set_performance_settings`].
+    pub fn performance_profile(&self) -> Option<PerformanceProfile> {
+        self.performance_profile
+    }
+
+    /// Apply `settings`, trading quality for cpu and memory. The decode threads are only used when
+    /// the decoder is opened, so they apply to players built with
+    /// [`PlayerBuilder::with_performance_profile`] (or [`InputOptions::with_decode_threads`]).
+    pub fn set_performance_settings(&mut self, settings: PerformanceSettings) {
+        self.performance_profile = None;
+        self.set_max_frame_size(settings.max_frame_size);
+        self.set_upscale(settings.upscale);
+        self.set_texture_options(settings.texture_options);
+        self.set_review_cache_size(settings.review_cache);
+        let mut video_streamer = self.video_streamer.lock();
+        video_streamer.frame_buffer_size = settings.frame_buffer.max(1);
+        video_streamer.decoder_options.skip_non_reference_frames =
+            settings.skip_non_reference_frames;
+        video_streamer
+            .video_decoder
+            .skip_frame(hwaccel::skip_frame(settings.skip_non_reference_frames));
+    }
+
+    /// Remove noise from the video (like the grain of webcams and IP cameras in low light) before
+    /// it is shown. Takes effect on the next decoded frame. `None` turns it off, as does a filter
+    /// that isn't available (see [`DenoiseFilter::is_available`]).
+    pub fn set_denoise(&mut self, denoise: Option<Denoise>) {
+        self.video_streamer.lock().denoise = denoise;
+    }
+
+    /// Hash each decoded frame (xxh3 of its planes, size and pixel format, before it is converted
+    /// for the texture), for verifying that two files decode to identical frames without exporting
+    /// them. The hashes are in [`PresentedFrame::hash`] and [`Player::frame_hash`], so compare them
+    /// by presentation time. The planes are hashed after [`Player::set_denoise`] and the other
+    /// filters, and hardware decoding can output another pixel format than software decoding.
+    pub fn set_frame_hashing(&mut self, enabled: bool) {
+        self.video_streamer.lock().hash_frames = enabled;
+    }
+
+    /// The hash of the frame on screen, `None` without [`Player::set_frame_hashing`].
+    pub fn frame_hash(&self) -> Option<u64> {
+        self.frame_hash
+    }
+
+    /// The denoising set with [`Player::set_denoise`].
+    pub fn denoise(&self) -> Option<Denoise> {
+        self.video_streamer.lock().denoise
+    }
+
+    /// Convert the colors of the video for a display that isn't sRGB, like a wide gamut Display P3
+    /// monitor. Takes effect on the next decoded frame. `None` turns it off, as does a linked ffmpeg
+    /// without the filter (see [`DisplayColor::is_available`]).
+    pub fn set_display_color(&mut self, display_color: Option<DisplayColor>) {
+        self.video_streamer.lock().display_color = display_color;
+    }
+
+    /// The display colors set with [`Player::set_display_color`].
+    pub fn display_color(&self) -> Option<DisplayColor> {
+        self.video_streamer.lock().display_color
+    }
+
+    /// Convert the video to a higher frame rate by making up the frames in between, for smoother
+    /// motion. This takes a lot of cpu (more so at high resolutions), so only turn it on where it
+    /// can keep up. Takes effect on the next decoded frame, `None` turns it off.
+    pub fn set_interpolation(&mut self, interpolation: Option<Interpolation>) {
+        self.video_streamer.lock().interpolation = interpolation;
+    }
+
+    /// The interpolation set with [`Player::set_interpolation`].
+    pub fn interpolation(&self) -> Option<Interpolation> {
+        self.video_streamer.lock().interpolation
+    }
+
+    /// Remap the channels of the audio, see [`ChannelMap`]. Takes effect on the next decoded audio;
+    /// `None` plays the channels as they are.
+    #[cfg(feature = "audio")]
+    pub fn set_channel_map(&mut self, channel_map: Option<ChannelMap>) {
+        self.channel_map = channel_map.clone();
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().channel_map = channel_map;
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    /// The channel map set with [`Player::set_channel_map`].
+    pub fn channel_map(&self) -> Option<&ChannelMap> {
+        self.channel_map.as_ref()
+    }
+
+    /// Set the algorithm and quality used to stretch audio when the playback speed isn't `1.0`.
+    #[cfg(feature = "audio")]
+    pub fn set_time_stretch(&mut self, time_stretch: TimeStretch) {
+        self.time_stretch = time_stretch;
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer.lock().time_stretch = time_stretch;
+        }
+    }
+
+    /// Set how audio is resampled to the rate and format of the audio device. Takes effect right
+    /// away if audio is playing.
+    #[cfg(feature = "audio")]
+    pub fn set_resampler_options(&mut self, resampler_options: ResamplerOptions) -> Result<()> {
+        self.resampler_options = resampler_options;
+        if let Some(audio_streamer) = self.audio_streamer.as_ref() {
+            audio_streamer
+                .lock()
+                .set_resampler_options(resampler_options)?;
+        }
+        Ok(())
+    }
+
+    /// Call `callback` when playback crosses `time_ms` (in stream time), with the frame at or after
+    /// it on screen. Cue points are checked against every presented frame, so they fire once per pass
+    /// at any playback speed, also when frames are dropped. Seeking over a cue point doesn't fire it,
+    /// playing on from the seek target does. The callback is called from
+    /// [`Player::generate_frame_image`], on the ui thread, and can control the player, like seeking
+    /// to another branch of an interactive video.
+    pub fn add_cue_point(
+        &mut self,
+        time_ms: i64,
+        callback: impl FnMut(&mut Player) + Send + 'static,
+    ) -> CuePointId {
+        self.cue_points.add(time_ms, Box::new(callback))
+    }
+
+    /// Remove a cue point added with [`Player::add_cue_point`], returning whether it existed.
+    pub fn remove_cue_point(&mut self, id: CuePointId) -> bool {
+        self.cue_points.remove(id)
+    }
+
+    /// Remove all cue points.
+    pub fn clear_cue_points(&mut self) {
+        self.cue_points.clear();
+    }
+
+    /// Pause at `point` when playback crosses it (like a cue point, see [`Player::add_cue_point`])
+    /// and emit [`PlayerEvent::BranchReached`] with its choices, for the app to present. Playback
+    /// continues once one is passed to [`Player::choose_branch`]. Remove it with
+    /// [`Player::remove_cue_point`].
+    pub fn add_branch_point(&mut self, point: BranchPoint) -> CuePointId {
+        let time_ms = point.time_ms;
+        // the callback is created before its cue point has an id
+        let id = Arc::new(OnceLock::new());
+        let callback_id = id.clone();
+        let cue_point_id = self.add_cue_point(time_ms, move |player| {
+            let Some(&id) = callback_id.get() else {
+                return;
+            };
+            player.pause();
+            let _ = player.event_sender.send(PlayerEvent::BranchReached {
+                id,
+                choices: point.choices.clone(),
+            });
+            player.pending_branch = Some(PendingBranch {
+                id,
+                point: point.clone(),
+                reached: Instant::now(),
+            });
+        });
+        let _ = id.set(cue_point_id);
+        cue_point_id
+    }
+
+    /// The branch point playback paused at, waiting for [`Player::choose_branch`].
+    pub fn pending_branch(&self) -> Option<&BranchPoint> {
+        self.pending_branch.as_ref().map(|pending| &pending.point)
+    }
+
+    /// Take the choice at index `choice` of the pending branch point and emit
+    /// [`PlayerEvent::BranchChosen`]. Playback continues at a [`BranchTarget::Time`] right away,
+    /// a [`BranchTarget::Source`] is opened by the app.
+    pub fn choose_branch(&mut self, choice: usize) -> Result<()> {
+        let pending = self
+            .pending_branch
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no branch point reached"))?;
+        let target = pending
+            .point
+            .choices
+            .get(choice)
+            .ok_or_else(|| anyhow::anyhow!("no choice {choice}"))?
+            .target
+            .clone();
+        let id = pending.id;
+        self.pending_branch = None;
+        if let BranchTarget::Time(time_ms) = target {
+            self.seek_to_ms(time_ms.clamp(0, self.duration_ms));
+            self.resume();
+        }
+        let _ = self
+            .event_sender
+            .send(PlayerEvent::BranchChosen { id, choice, target });
+        Ok(())
+    }
+
+    // take the default choice of the pending branch point once its timeout passed
+    fn update_pending_branch(&mut self) {
+        let choice = self
+            .pending_branch
+            .as_ref()
+            .and_then(|pending| pending.timed_out_choice());
+        if let Some(choice) = choice {
+            if self.choose_branch(choice).is_err() {
+                // the default choice doesn't exist, so nothing can be taken
+                self.pending_branch = None;
+            }
+        }
+    }
+
+    // run the callbacks of the cue points crossed by presenting the frame at `time_ms`
+    fn fire_cue_points(&mut self, time_ms: i64) {
+        for id in self.cue_points.crossed(time_ms) {
+            // a callback seeking away leaves the cue points after it for playing through them
+            if !self.cue_points.is_tracking() {
+                break;
+            }
+            if let Some(mut callback) = self.cue_points.take_callback(id) {
+                callback(self);
+                self.cue_points.restore_callback(id, callback);
+            }
+        }
+    }
+
+    /// Set a callback that is called with each video frame when it is presented, along with the
+    /// [`FrameTimestamps`] of the frame. It is called from [`Player::generate_frame_image`], on the ui thread.
+    pub fn set_frame_callback(&mut self, callback: impl FnMut(&PresentedFrame) + Send + 'static) {
+        self.frame_callback = Some(Box::new(callback));
+    }
+
+    /// Keep the previous frame in a second texture, uploading each new frame into the texture of
+    /// the one before, for compositors and shaders doing motion-aware effects like motion blur
+    /// without copying frames out of the player. The current frame then alternates between the two
+    /// textures, so get it from [`Player::frame_textures`] every frame instead of keeping
+    /// [`Player::texture_handle`]. After a seek, the previous frame is the last one before it, so
+    /// compare the presentation times before blending them.
+    pub fn set_double_buffering(&mut self, enabled: bool) {
+        if !enabled {
+            self.spare_texture = None;
+            self.spare_frame_ms = None;
+        } else if self.spare_texture.is_none() {
+            self.spare_texture = Some(self.ctx_ref.load_texture(
+                "vidstream-previous",
+                ColorImage::example(),
+                self.options.texture_options,
+            ));
+        }
+    }
+
+    /// The textures of the frame on screen and, with [`Player::set_double_buffering`], of the frame
+    /// before it, with their presentation times.
+    pub fn frame_textures(&self) -> FrameTextures {
+        let frame_texture = |texture: &TextureHandle, presentation_time_ms| FrameTexture {
+            id: texture.id(),
+            size: texture.size(),
+            presentation_time_ms,
+        };
+        FrameTextures {
+            current: self
+                .texture_frame_ms
+                .map(|time_ms| frame_texture(&self.texture_handle, time_ms)),
+            previous: self
+                .spare_texture
+                .as_ref()
+                .zip(self.spare_frame_ms)
+                .map(|(texture, time_ms)| frame_texture(texture, time_ms)),
+        }
+    }
+
+    /// Set [`PlayerOptions::texture_options`], also applying them to the frame currently shown.
+    pub fn set_texture_options(&mut self, texture_options: TextureOptions) {
+        self.options.texture_options = texture_options;
+        self.refresh_frame();
+    }
+
+    /// Set [`PlayerOptions::review_cache`], dropping the least recently used frames that don't fit.
+    pub fn set_review_cache_size(&mut self, size: ReviewCacheSize) {
+        self.options.review_cache = size;
+        self.review_cache.evict(size);
+    }
+
+    /// Set [`PlayerOptions::subtitles`]. Subtitles already showing are laid out again.
+    #[cfg(feature = "subtitles")]
+    pub fn set_subtitle_options(&mut self, subtitle_options: SubtitleOptions) {
+        self.options.subtitles = subtitle_options;
+    }
+
+    /// Set [`PlayerOptions::tint`].
+    pub fn set_tint(&mut self, tint: Color32) {
+        self.options.tint = tint;
+    }
+
+    /// Set [`PlayerOptions::opacity`], clamped in `0.0..=1.0`.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.options.opacity = opacity.clamp(0., 1.);
+    }
+
+    /// Set [`PlayerOptions::corner_radius`].
+    pub fn set_corner_radius(&mut self, corner_radius: impl Into<CornerRadius>) {
+        self.options.corner_radius = corner_radius.into();
+    }
+
+    /// Set [`PlayerOptions::ambient_glow`].
+    pub fn set_ambient_glow(&mut self, ambient_glow: Option<AmbientGlow>) {
+        self.options.ambient_glow = ambient_glow;
+        if ambient_glow.is_none() {
+            self.glow_texture = None;
+        }
+        self.ambient_updated = None;
+        self.refresh_frame();
+    }
+
+    /// Set [`PlayerOptions::upscale`]. The frames already decoded keep their size.
+    pub fn set_upscale(&mut self, upscale: Option<UpscaleFilter>) {
+        self.options.upscale = upscale;
+    }
+
+    /// Set [`PlayerOptions::letterbox`].
+    pub fn set_letterbox(&mut self, letterbox: Option<LetterboxFill>) {
+        self.options.letterbox = letterbox;
+        if !matches!(letterbox, Some(LetterboxFill::Ambient { .. })) {
+            self.ambient_texture = None;
+        }
+        self.ambient_updated = None;
+        self.refresh_frame();
+    }
+
+    /// Clip the video to the opaque parts of `mask` (like a shape drawn into an image), stretched
+    /// over the video. Its alpha is multiplied with each frame before it is uploaded, so the
+    /// texture of the player holds the clipped frames. `None` shows the whole video again.
+    pub fn set_clip_mask(&mut self, mask: Option<ColorImage>) {
+        self.upload_stage.clip_mask = mask.map(Arc::new);
+        self.update_upload_stage();
+    }
+
+    /// Set [`PlayerOptions::transitions`].
+    pub fn set_transitions(&mut self, transitions: Transitions) {
+        self.options.transitions = transitions;
+    }
+
+    /// Set [`PlayerOptions::show_controls`].
+    pub fn set_show_controls(&mut self, show_controls: bool) {
+        self.options.show_controls = show_controls;
+    }
+
+    /// Drive the player's video clock with `clock` instead of wall time, e.g. a [`ManualClock`] in
+    /// tests. Only affects playback synchronized to the video clock (see [`PlayerOptions::sync_master`]):
+    /// decoding still happens in the background, but which frame is shown only depends on `clock`.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.video_clock.set_source(Arc::new(clock));
+    }
+
+    /// Switch to offline rendering, for driving the player from a non-realtime renderer (e.g. one
+    /// exporting composited video): frames only change on [`Player::advance`], exactly one per call,
+    /// regardless of wall time. Video is synchronized to the video clock, which stands still between
+    /// calls, and audio is muted without changing [`PlayerOptions::audio_volume`].
+    pub fn enable_offline_mode(&mut self) {
+        self.set_sync_master(SyncMaster::Video);
+        self.audio_muted.set(true);
+        self.offline = true;
+        self.offline_presented_ms = None;
+    }
+
+    /// Present the next frame of an offline player (see [`Player::enable_offline_mode`]). Doesn't
+    /// wait for it to be decoded: [`OfflineStep::Pending`] asks to call it again, like on the next
+    /// frame of the renderer.
+    pub fn advance(&mut self) -> OfflineStep {
+        if !self.offline {
+            return OfflineStep::Finished;
+        }
+        self.process_state();
+        let presented_ms = self.offline_presented_ms;
+        let next_ms = {
+            let vs = self.video_streamer.lock();
+            let mut upcoming = vs
+                .frame_cache
+                .iter()
+                .map(|(_, presentation_time_ms, _)| *presentation_time_ms)
+                .filter(|time_ms| presented_ms.is_none_or(|presented| *time_ms > presented));
+            // a frame is only shown once the frame after it is known
+            let next = upcoming.next();
+            next.filter(|_| upcoming.next().is_some())
+        };
+        match next_ms {
+            Some(next_ms) => {
+                self.video_clock.set(next_ms);
+                self.offline_presented_ms = Some(next_ms);
+                match self.present_frame() {
+                    true => OfflineStep::Presented,
+                    false => OfflineStep::Pending,
+                }
+            }
+            None if self.player_state.get() == PlayerState::Playing => OfflineStep::Pending,
+            None => OfflineStep::Finished,
+        }
+    }
+
+    /// Set the ranges of the stream that can be skipped, like the intro and credits. Entering one
+    /// during playback either skips it or shows a skip button, depending on [`PlayerOptions::skip_mode`].
+    /// Skips are reported with [`PlayerEvent::Skipped`].
+    pub fn set_skip_ranges(&mut self, skip_ranges: Vec<TimeRange>) {
+        self.skip_ranges = skip_ranges;
+        self.active_skip_range = None;
+    }
+
+    /// The ranges set with [`Player::set_skip_ranges`].
+    pub fn skip_ranges(&self) -> &[TimeRange] {
+        &self.skip_ranges
+    }
+
+    /// The skip range playback is currently inside of.
+    pub fn current_skip_range(&self) -> Option<&TimeRange> {
+        self.active_skip_range.map(|index| &self.skip_ranges[index])
+    }
+
+    /// Seek to the end of the current skip range, returning `false` if playback isn't inside one.
+    /// For apps drawing their own skip button instead of [`Player::render_skip_button`].
+    pub fn skip_current_range(&mut self) -> bool {
+        let Some(index) = self.active_skip_range else {
+            return false;
+        };
+        self.skip_range(index, false);
+        true
+    }
+
+    fn skip_range(&mut self, index: usize, automatic: bool) {
+        let range = self.skip_ranges[index].clone();
+        self.seek_to_ms(range.end_ms.min(self.duration_ms));
+        let _ = self
+            .event_sender
+            .send(PlayerEvent::Skipped { range, automatic });
+    }
+
+    fn update_skip_range(&mut self) {
+        let time_ms = self.current_frame_ms();
+        let current = self.skip_ranges.iter().position(|r| r.contains(time_ms));
+        if current == self.active_skip_range {
+            return;
+        }
+        self.active_skip_range = current;
+        if let Some(index) = current {
+            match self.options.skip_mode {
+                SkipMode::Auto => self.skip_range(index, true),
+                SkipMode::Prompt => {
+                    let range = self.skip_ranges[index].clone();
+                    let _ = self
+                        .event_sender
+                        .send(PlayerEvent::SkipRangeEntered { range });
+                }
+            }
+        }
+    }
+
+    /// Set the [`ContentFilter`]s muting, blanking, blurring or skipping parts of the stream. They are
+    /// checked for every frame and audio chunk, so seeking into a filtered range doesn't get around them.
+    pub fn set_content_filters(&mut self, content_filters: Vec<ContentFilter>) {
+        *self.content_filters.lock() = content_filters;
+        // the frames already prepared with the previous filters aren't used
+        self.upload_stage.generation += 1;
+        self.video_streamer.lock().upload_stage = self.upload_stage.clone();
+    }
+
+    /// The filters set with [`Player::set_content_filters`].
+    pub fn content_filters(&self) -> Vec<ContentFilter> {
+        self.content_filters.lock().clone()
+    }
+
+    fn apply_skip_filters(&mut self) {
+        let time_ms = self.current_frame_ms();
+        let skipped = self
+            .content_filters
+            .lock()
+            .iter()
+            // a range running to the end is only blanked and muted, seeking to its end would loop
+            .find(|f| {
+                f.action == ContentAction::Skip
+                    && f.range.contains(time_ms)
+                    && f.range.end_ms < self.duration_ms
+            })
+            .map(|f| f.range.clone());
+        if let Some(range) = skipped {
+            self.seek_to_ms(range.end_ms);
+            let _ = self.event_sender.send(PlayerEvent::Skipped {
+                range,
+                automatic: true,
+            });
+        }
+    }
+
+    /// Set the [`RegionFilter`]s blurring or pixelating parts of the video. They are applied to the
+    /// presented frames before [`Player::set_frame_callback`] sees them, and carried over to
+    /// [`Player::frame_server`] and to transcodes, so exported frames are filtered too.
+    pub fn set_region_filters(&mut self, region_filters: Vec<RegionFilter>) {
+        self.upload_stage.region_filters = region_filters;
+        self.update_upload_stage();
+    }
+
+    /// Show the video without the region filters while `bypassed`, for comparing the processed and
+    /// original frames. Takes effect immediately, on the current frame too, without rebuilding
+    /// anything. Content filters (see [`Player::set_content_filters`]) aren't bypassed. See also
+    /// [`PlayerOptions::bypass_filters_key`].
+    pub fn set_filters_bypassed(&mut self, bypassed: bool) {
+        if self.upload_stage.filters_bypassed != bypassed {
+            self.upload_stage.filters_bypassed = bypassed;
+            self.update_upload_stage();
+        }
+    }
+
+    /// Whether the region filters are bypassed, see [`Player::set_filters_bypassed`].
+    pub fn filters_bypassed(&self) -> bool {
+        self.upload_stage.filters_bypassed
+    }
+
+    /// The filters set with [`Player::set_region_filters`].
+    pub fn region_filters(&self) -> &[RegionFilter] {
+        &self.upload_stage.region_filters
+    }
+
+    /// Only show `crop` of the video, in video coordinates, or the whole frame if `None`. The frame
+    /// is cropped when it is drawn, so the size of the drawn image is up to the caller (see
+    /// [`Player::size`] for the full size).
+    pub fn set_crop(&mut self, crop: Option<Rect>) {
+        self.crop = crop;
+        self.save_settings();
+    }
+
+    /// The crop set with [`Player::set_crop`].
+    pub fn crop(&self) -> Option<Rect> {
+        self.crop
+    }
+
+    /// Identifies the input by the layout of its streams (their kind, codec and language), its
+    /// duration and the size of its file. See [`Player::set_settings_store`].
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Identifies the input only by the layout of its streams, so the episodes of a series made
+    /// the same way share it. See [`Player::set_settings_store`].
+    pub fn layout_fingerprint(&self) -> &str {
+        &self.layout_fingerprint
+    }
+
+    /// The settings of this player remembered by a [`SettingsStore`].
+    pub fn title_settings(&self) -> TitleSettings {
+        TitleSettings {
+            audio_stream: self.audio_stream_info.current_stream,
+            subtitle_stream: self.subtitle_stream_info.current_stream,
+            playback_speed: self.playback_speed(),
+            crop: self.crop,
+        }
+    }
+
+    /// Remember the selected audio and subtitle stream, playback speed and crop in `store`, keyed by
+    /// [`Player::fingerprint`] and [`Player::layout_fingerprint`]. Settings saved for the
+    /// fingerprint before, or else for the layout, are applied right away, so call this after
+    /// adding audio and subtitles. Changes are saved as they happen.
+    pub fn set_settings_store(&mut self, store: impl SettingsStore + 'static) {
+        self.use_settings_store(Box::new(store));
+    }
+
+    pub(crate) fn use_settings_store(&mut self, store: Box<dyn SettingsStore>) {
+        let saved = store
+            .load(&self.fingerprint)
+            .or_else(|| store.load(&self.layout_fingerprint));
+        if let Some(saved) = saved.as_ref() {
+            // applied before the store is set, so they are saved once the streams are selected
+            self.set_playback_speed(saved.playback_speed);
+            self.set_crop(saved.crop);
+        }
+        self.settings_store = Some(store);
+        if let Some(saved) = saved {
+            self.select_streams(saved.audio_stream, saved.subtitle_stream);
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn select_streams(&mut self, audio_stream: usize, subtitle_stream: usize) {
+        #[cfg(feature = "audio")]
+        self.select_audio_stream(audio_stream);
+        #[cfg(feature = "subtitles")]
+        self.select_subtitle_stream(subtitle_stream);
+    }
+
+    /// The current streams, speed, delays, crop, volume, filters, tint, upscaling and channel map,
+    /// for saving them or sending them along with a bug report. See [`PlaybackProfile`].
+    pub fn playback_profile(&self) -> PlaybackProfile {
+        let video_streamer = self.video_streamer.lock();
+        PlaybackProfile {
+            audio_stream: self.audio_stream_info.current_stream,
+            subtitle_stream: self.subtitle_stream_info.current_stream,
+            playback_speed: self.playback_speed(),
+            audio_delay_ms: self.options.audio_delay_ms,
+            crop: self.crop,
+            audio_volume: self.options.audio_volume.get(),
+            audio_gain_db: self.options.audio_gain_db.get(),
+            looping: self.options.looping,
+            denoise: video_streamer.denoise,
+            interpolation: video_streamer.interpolation,
+            display_color: video_streamer.display_color,
+            tint: self.options.tint,
+            upscale: self.options.upscale,
+            #[cfg(feature = "audio")]
+            channel_map: self.channel_map.clone(),
+        }
+    }
+
+    /// Play with the settings of `profile`, like one read with [`PlaybackProfile::parse`]. Streams
+    /// the input doesn't have are left as they are.
+    pub fn apply_playback_profile(&mut self, profile: &PlaybackProfile) {
+        self.playback_speed
+            .set(profile.playback_speed.clamp(0.1, 16.));
+        self.crop = profile.crop;
+        self.options.audio_delay_ms = profile.audio_delay_ms;
+        self.options.set_audio_volume(profile.audio_volume);
+        self.options.set_audio_gain_db(profile.audio_gain_db);
+        self.set_looping(profile.looping);
+        self.set_denoise(profile.denoise);
+        self.set_interpolation(profile.interpolation);
+        self.set_display_color(profile.display_color);
+        self.set_tint(profile.tint);
+        self.set_upscale(profile.upscale);
+        #[cfg(feature = "audio")]
+        self.set_channel_map(profile.channel_map.clone());
+        self.select_streams(profile.audio_stream, profile.subtitle_stream);
+        // saved once, after the streams are selected when they change
+        #[cfg(any(feature = "audio", feature = "subtitles"))]
+        let selecting = self.pending_stream_cycles > 0;
+        #[cfg(not(any(feature = "audio", feature = "subtitles")))]
+        let selecting = false;
+        if !selecting {
+            self.save_settings();
+        }
+    }
+
+    fn save_settings(&mut self) {
+        let settings = self.title_settings();
+        if let Some(store) = self.settings_store.as_mut() {
+            store.save(&self.fingerprint, &settings);
+            store.save(&self.layout_fingerprint, &settings);
+        }
+    }
+
+    /// Set [`PlayerOptions::looping`].
+    pub fn set_looping(&mut self, looping: bool) {
+        self.options.looping = looping;
+    }
+
+    /// Set [`PlayerOptions::sync_master`]. The video clock continues from the current clock, so
+    /// playback doesn't jump when switching.
+    pub fn set_sync_master(&mut self, sync_master: SyncMaster) {
+        self.video_clock.set(self.clock_ms());
+        self.options.sync_master = sync_master;
+    }
+
+    fn new_timeshift_buffer(&self, is_primary: bool) -> Option<TimeshiftBuffer> {
+        self.timeshift
+            .as_ref()
+            .map(|(options, window)| TimeshiftBuffer::new(*options, window.clone(), is_primary))
+    }
+
+    /// The presentation times of the keyframes of the video stream, in milliseconds. The index is built
+    /// in the background on the first call by reading the whole input, so it stays empty until that
+    /// is done (and for live sources, which never end).
+    pub fn keyframes(&mut self) -> &[i64] {
+        if !self.keyframe_index_requested && self.timeshift.is_none() {
+            self.keyframe_index_requested = true;
+            let keyframe_index = self.keyframe_index.clone();
+            let input_path = self.input_path.clone();
+            let input_options = self.input_options.clone();
+            std::thread::spawn(move || {
+                if let Ok(keyframes) = keyframes::scan_keyframes(&input_path, &input_options) {
+                    let _ = keyframe_index.set(keyframes);
+                }
+            });
+        }
+        self.keyframe_index
+            .get()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Measure the integrated loudness of the audio in the background, for
+    /// [`PlayerOptions::loudness_target_lufs`]. The input is decoded once more on its own thread;
+    /// for a playlist, start it as soon as the next item is created so it's done before it plays.
+    /// Live sources are never measured, since they don't end.
+    #[cfg(feature = "audio")]
+    pub fn scan_loudness(&mut self) {
+        if self.loudness_scan_requested || self.timeshift.is_some() {
+            return;
+        }
+        self.loudness_scan_requested = true;
+        let loudness_lufs = self.loudness_lufs.clone();
+        let input_path = self.input_path.clone();
+        let input_options = self.input_options.clone();
+        std::thread::spawn(move || {
+            if let Ok(loudness) = loudness::measure_loudness(&input_path, &input_options) {
+                let _ = loudness_lufs.set(loudness);
+            }
+        });
+    }
+
+    #[cfg(feature = "audio")]
+    /// The integrated loudness of the audio in LUFS, once [`Player::scan_loudness`] has measured it.
+    pub fn loudness_lufs(&self) -> Option<f32> {
+        self.loudness_lufs.get().copied()
+    }
+
+    #[cfg(feature = "audio")]
+    fn apply_loudness_normalization(&mut self) {
+        let target_lufs = self.options.loudness_target_lufs;
+        if target_lufs == self.normalized_to_lufs {
+            return;
+        }
+        if let (Some(target_lufs), Some(loudness_lufs)) = (target_lufs, self.loudness_lufs()) {
+            let gain_db = loudness::normalization_gain_db(loudness_lufs, target_lufs);
+            self.normalization_gain_db.set(gain_db);
+            self.normalized_to_lufs = Some(target_lufs);
+        } else if target_lufs.is_none() {
+            self.normalization_gain_db.set(0.);
+            self.normalized_to_lufs = None;
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    /// The gain [`PlayerOptions::loudness_target_lufs`] applies on top of
+    /// [`PlayerOptions::audio_gain_db`], in decibels. `0.0` until the loudness is measured.
+    pub fn normalization_gain_db(&self) -> f32 {
+        self.normalization_gain_db.get()
+    }
+
+    /// Pause playback once it reaches `time_ms` of stream time, fading the audio out over the
+    /// `fade_out` before it (`Duration::ZERO` for none), and emit [`PlayerEvent::SleepTimerFired`].
+    /// The fade follows the playback speed, and seeking past `time_ms` doesn't fire the timer.
+    /// Replaces any earlier sleep timer.
+    pub fn stop_at(&mut self, time_ms: i64, fade_out: std::time::Duration) {
+        self.set_sleep_timer(SleepTimer::at_stream_time(time_ms, fade_out));
+    }
+
+    /// Like [`Player::stop_at`], but pause after `duration` of wall time, however playback is seeked
+    /// or paused in between.
+    pub fn stop_after(&mut self, duration: std::time::Duration, fade_out: std::time::Duration) {
+        self.set_sleep_timer(SleepTimer::after(duration, fade_out));
+    }
+
+    /// Cancel the sleep timer, restoring the volume if it was fading out.
+    pub fn cancel_sleep_timer(&mut self) {
+        self.sleep_timer = None;
+        self.audio_fade_gain.set(1.);
+    }
+
+    /// The wall time left until the sleep timer pauses playback, at the current playback speed.
+    pub fn sleep_timer_remaining(&self) -> Option<std::time::Duration> {
+        self.sleep_timer
+            .as_ref()?
+            .remaining(self.current_frame_ms(), self.playback_speed.get())
+    }
+
+    fn set_sleep_timer(&mut self, sleep_timer: SleepTimer) {
+        self.cancel_sleep_timer();
+        self.sleep_timer = Some(sleep_timer);
+    }
+
+    fn update_sleep_timer(&mut self) {
+        let time_ms = self.current_frame_ms();
+        let speed = self.playback_speed.get();
+        let playing = self.player_state.get() == PlayerState::Playing;
+        let Some(sleep_timer) = self.sleep_timer.as_mut() else {
+            return;
+        };
+        // the stream isn't heading towards the deadline while paused
+        let remaining = if playing {
+            sleep_timer.update(time_ms, speed)
+        } else {
+            sleep_timer.remaining(time_ms, speed)
+        };
+        match remaining {
+            Some(remaining) if remaining.is_zero() => {
+                self.cancel_sleep_timer();
+                self.pause();
+                let _ = self.event_sender.send(PlayerEvent::SleepTimerFired {
+                    elapsed_ms: time_ms,
+                });
+            }
+            Some(remaining) => self.audio_fade_gain.set(sleep_timer.fade_gain(remaining)),
+            None => self.audio_fade_gain.set(1.),
+        }
+    }
+
+    /// The chapters of the input: the ones stored in it (as in matroska, mp4 and M4B audiobooks,
+    /// with their chapter images), or else the tracks of a cue sheet with the same name next to it
+    /// (with the `chapters` feature).
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    /// Replace the chapters with the tracks of the cue sheet at `path`. Enabled with the `chapters`
+    /// feature.
+    #[cfg(feature = "chapters")]
+    pub fn add_cue_sheet(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.chapters = chapters::read_cue_sheet(path.as_ref(), self.duration_ms)?;
+        Ok(())
+    }
+
+    /// The index of the chapter being played.
+    pub fn current_chapter(&self) -> Option<usize> {
+        let time_ms = self.current_frame_ms();
+        self.chapters
+            .iter()
+            .rposition(|chapter| chapter.start_ms <= time_ms)
+    }
+
+    /// Seek to the start of the chapter at `index`, returning `false` if there is none.
+    pub fn seek_to_chapter(&mut self, index: usize) -> bool {
+        let Some(chapter) = self.chapters.get(index) else {
+            return false;
+        };
+        self.seek_to_ms(chapter.start_ms);
+        true
+    }
+
+    /// Seek to the keyframe before the current frame. Returns `false` if there is none, or the
+    /// keyframe index isn't built yet (see [`Player::keyframes`]).
+    pub fn seek_to_prev_keyframe(&mut self) -> bool {
+        let current_ms = self.current_frame_ms();
+        let target_ms = self
+            .keyframes()
+            .iter()
+            .rev()
+            .find(|k| **k < current_ms && !millisec_approx_eq(**k, current_ms))
+            .copied();
+        let Some(target_ms) = target_ms else {
+            return false;
+        };
+        self.seek_to_ms(target_ms);
+        true
+    }
+
+    /// Seek to the keyframe after the current frame. Returns `false` if there is none, or the
+    /// keyframe index isn't built yet (see [`Player::keyframes`]).
+    pub fn seek_to_next_keyframe(&mut self) -> bool {
+        let current_ms = self.current_frame_ms();
+        let target_ms = self
+            .keyframes()
+            .iter()
+            .find(|k| **k > current_ms && !millisec_approx_eq(**k, current_ms))
+            .copied();
+        let Some(target_ms) = target_ms else {
+            return false;
+        };
+        self.seek_to_ms(target_ms);
+        true
+    }
+
+    // the time of the frame on screen. `elapsed_ms` follows the decoders, which run ahead of it.
+    fn current_frame_ms(&self) -> i64 {
+        self.video_elapsed_ms_override
+            .unwrap_or_else(|| self.clock_ms())
+    }
+
+    fn seek_to_ms(&mut self, time_ms: i64) {
+        self.seek(self.ms_to_seek_frac(time_ms));
+    }
+
+    fn ms_to_seek_frac(&self, time_ms: i64) -> f32 {
+        // live inputs seek within the buffered window, which `seek` maps the fraction onto
+        if let Some((_, window)) = self.timeshift.as_ref() {
+            return window.ms_to_frac(time_ms);
+        }
+        if self.duration_ms <= 0 {
+            return 0.;
+        }
+        // aim half a millisecond late, so rounding doesn't land before a keyframe
+        ((time_ms as f64 + 0.5) / self.duration_ms as f64) as f32
+    }
+
+    /// Pause on exactly the frame shown at `time_ms` of stream time, like for jumping to a frame to
+    /// inspect it. While playing towards `time_ms`, playback continues until that frame is on screen.
+    /// Otherwise the frame is decoded forward from the keyframe before it (see [`Accuracy::Exact`])
+    /// and shown right away, and playback resumes from there. Blocks while decoding.
+    pub fn pause_at(&mut self, time_ms: i64) -> Result<()> {
+        let time_ms = time_ms.clamp(0, self.duration_ms);
+        let state = self.player_state.get();
+        if state == PlayerState::Playing && time_ms > self.current_frame_ms() {
+            self.pause_at_ms = Some(time_ms);
+            return Ok(());
+        }
+        anyhow::ensure!(
+            self.timeshift.is_none(),
+            "live sources can only pause at frames ahead"
+        );
+        let frame_server = match self.exact_frame_server.as_mut() {
+            Some(frame_server) => frame_server,
+            None => self.exact_frame_server.insert(self.frame_server()?),
+        };
+        let (frame_ms, mut image) = frame_server.decoded_frame_at(time_ms, Accuracy::Exact)?;
+        match state {
+            PlayerState::Stopped | PlayerState::EndOfFile => self.start(),
+            PlayerState::SeekingInProgress | PlayerState::SeekingFinished => {
+                self.preseek_player_state = Some(PlayerState::Paused)
+            }
+            _ => (),
+        }
+        self.pause();
+        self.pause_at_ms = None;
+        content_filter::filter_image(&self.content_filters.lock(), frame_ms, &mut image);
+        self.show_frame(frame_ms, Arc::new(image));
+        // like a seek shown from the review cache
+        self.video_elapsed_ms_override = Some(time_ms);
+        self.deferred_seek_frac = Some(self.ms_to_seek_frac(time_ms));
+        Ok(())
+    }
+
+    /// Properties of the video stream, like its pixel aspect ratio, rotation and codec parameters.
+    pub fn media_info(&self) -> &MediaInfo {
+        &self.media_info
+    }
+
+    /// Open a [`FrameServer`] on the input of this player, for accessing frames at arbitrary times
+    /// without affecting playback.
+    pub fn frame_server(&self) -> Result<FrameServer> {
+        let mut frame_server =
+            FrameServer::new_with_input_options(&self.input_path, &self.input_options)?;
+        if let Some(keyframes) = self.keyframe_index.get() {
+            frame_server.keyframes = Some(keyframes.clone());
+        }
+        frame_server.set_region_filters(self.upload_stage.region_filters.clone());
+        Ok(frame_server)
+    }
+
+    /// Transcode the input of this player to `output_path` in the background (like making a
+    /// lower resolution proxy while the original plays). The input is opened again, since the
+    /// demuxer of the player seeks with playback. The video is filtered with the region filters
+    /// and the blanking and blurring content filters of the player (skipped ranges are blanked),
+    /// before burning in; copied audio isn't muted. Enabled with the `export` and
+    /// `experimental` features.
+    #[cfg(all(feature = "export", feature = "experimental"))]
+    pub fn transcode(
+        &self,
+        output_path: impl Into<String>,
+        options: TranscodeOptions,
+    ) -> Transcode {
+        let input = TranscodeInput {
+            path: self.input_path.clone(),
+            options: self.input_options.clone(),
+            duration_ms: self.duration_ms,
+            filters: TranscodeFilters {
+                region_filters: self.upload_stage.region_filters.clone(),
+                content_filters: self.content_filters.lock().clone(),
+                video_size: self.size,
+            },
+        };
+        Transcode::start(input, output_path.into(), options)
+    }
+
+    /// Associate a proxy with the input: a low resolution copy of it (like one made with
+    /// [`Player::transcode`]) that decodes faster while editing. See [`Player::use_proxy`].
+    /// `None` removes it, switching back to the input if the proxy was playing.
+    pub fn set_proxy(&mut self, proxy_path: Option<String>) -> Result<()> {
+        let proxy_active = self.proxy_active;
+        if proxy_active {
+            self.use_proxy(false)?;
+        }
+        self.proxy_path = proxy_path;
+        if proxy_active && self.proxy_path.is_some() {
+            self.use_proxy(true)?;
+        }
+        Ok(())
+    }
+
+    /// The proxy set with [`Player::set_proxy`].
+    pub fn proxy_path(&self) -> Option<&str> {
+        self.proxy_path.as_deref()
+    }
+
+    /// Play the video of the proxy instead of the input (or the other way around), continuing at
+    /// the same time. Only the video switches; audio and subtitles keep coming from the input, as
+    /// do [`Player::frame_server`], [`Player::transcode`] and the scans, so exports always use the
+    /// original.
+    pub fn use_proxy(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.proxy_active {
+            return Ok(());
+        }
+        if self.timeshift.is_some() || self.cut_bounds_ms.is_some() {
+            anyhow::bail!("proxies need a single seekable input");
+        }
+        let input_context = if enabled {
+            let proxy_path = self
+                .proxy_path
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("no proxy set"))?;
+            open_input(proxy_path, &InputOptions::default())?
+        } else {
+            open_input(&self.input_path, &self.input_options)?
+        };
+        let time_ms = self.current_frame_ms();
+        self.video_streamer.lock().replace_input(input_context)?;
+        self.proxy_active = enabled;
+        self.session_stats.lock().source_switched(time_ms, enabled);
+        // the cached frames are from the other file
+        self.review_cache = ReviewCache::default();
+        if self.player_state.get() != PlayerState::Stopped {
+            self.seek_to_ms(time_ms);
+        }
+        Ok(())
+    }
+
+    /// Whether the proxy is playing, see [`Player::use_proxy`].
+    pub fn proxy_active(&self) -> bool {
+        self.proxy_active
+    }
+
+    /// Start building a [`Player`] for `input_path`, see [`PlayerBuilder`].
+    pub fn builder<'a>(input_path: impl Into<String>) -> PlayerBuilder<'a> {
+        PlayerBuilder::new(input_path)
+    }
+
+    /// Create a muted [`Player`] for a small preview of `input_path`, with the default
+    /// [`PreviewProfile`].
+    pub fn preview(ctx: &egui::Context, input_path: impl Into<String>) -> Result<Self> {
+        Self::builder(input_path)
+            .with_preview_profile(PreviewProfile::default())
+            .build(ctx)
+    }
+
+    /// Create a [`Player`] showing a generated test pattern: SMPTE color bars with a burnt-in
+    /// timecode (if ffmpeg has the `drawtext` filter), and a 1 kHz tone when audio is added. Useful for
+    /// diagnosing sync, frame pacing and color issues without a media file. The pattern is a live
+    /// source, so it is played with a one minute timeshift window.
+    pub fn test_pattern(ctx: &egui::Context, resolution: [u32; 2], fps: u32) -> Result<Self> {
+        let [width, height] = resolution;
+        let mut video = format!("smptehdbars=size={width}x{height}:rate={fps}");
+        if ffmpeg::filter::find("drawtext").is_some() {
+            video += &format!(
+                ",drawtext=timecode='00\\:00\\:00\\:00':rate={fps}:fontsize={}:fontcolor=white\
+                 :box=1:boxcolor=black@0.6:x=(w-tw)/2:y=h*0.8",
+                height / 12
+            );
+        }
+        let graph = format!("{video}[out0];sine=frequency=1000:sample_rate=48000[out1]");
+        let input_options = InputOptions::new().with_format("lavfi");
+        Ok(Self::new_with_input_options(ctx, &graph, input_options)?
+            .with_timeshift(TimeshiftOptions::minutes(1)))
+    }
+
+    /// Create a [`Player`] for measuring A/V sync: it plays a white flash and a beep at the start of
+    /// every second, and compares when each flash is presented to when its beep leaves the audio
+    /// device (using the output latency reported by the audio backend). Add audio with
+    /// [`Player::with_audio`], then read [`Player::sync_test_result`] after a few seconds.
+    /// The latency of the display itself can't be queried, so it isn't included.
+    pub fn sync_test(ctx: &egui::Context, resolution: [u32; 2], fps: u32) -> Result<Self> {
+        let graph = SyncTest::graph(resolution, fps);
+        let input_options = InputOptions::new().with_format("lavfi");
+        let mut player = Self::new_with_input_options(ctx, &graph, input_options)?
+            .with_timeshift(TimeshiftOptions::minutes(1));
+        player.sync_test = Some(SyncTest::new(fps));
+        Ok(player)
+    }
+
+    /// The measurements of a player created with [`Player::sync_test`], once a flash has been shown
+    /// with audio playing.
+    pub fn sync_test_result(&self) -> Option<SyncTestResult> {
+        self.sync_test
+            .as_ref()
+            .and_then(|t| t.result(self.options.audio_delay_ms))
+    }
+
+    /// Create a new [`Player`].
+    pub fn new(ctx: &egui::Context, input_path: &str) -> Result<Self> {
+        Self::new_with_input_options(ctx, input_path, InputOptions::default())
+    }
+
+    /// Create a new [`Player`] playing `input_paths` back to back as one continuous timeline, like
+    /// the consecutive segment files of a dashcam recording. The files should share their codecs and
+    /// stream layout. Each file is opened once up front to read its duration, so the seekbar covers
+    /// the whole sequence.
+    pub fn new_concatenated(ctx: &egui::Context, input_paths: &[impl AsRef<str>]) -> Result<Self> {
+        let segments = input_paths
+            .iter()
+            .map(|path| Segment::new(path.as_ref()))
+            .collect::<Vec<_>>();
+        let input = concat_input(&segments)?;
+        Self::new_with_input_options(ctx, &input.input_path, input.input_options)
+    }
+
+    /// Create a new [`Player`] playing an edit decision list: the [`Cut`]s are played back to back
+    /// as one timeline. Frames outside of each cut are dropped, so cut points are frame accurate even
+    /// when an in point isn't on a keyframe.
+    pub fn new_edit(ctx: &egui::Context, cuts: &[Cut]) -> Result<Self> {
+        let segments = cuts.iter().map(Segment::from).collect::<Vec<_>>();
+        let input = concat_input(&segments)?;
+        let mut player = Self::new_with_input_options(ctx, &input.input_path, input.input_options)?;
+        player.video_streamer.lock().cut_filter = Some(CutFilter::new(input.bounds_ms.clone()));
+        player.cut_bounds_ms = Some(input.bounds_ms);
+        Ok(player)
+    }
+
+    /// Create a new [`Player`], passing [`InputOptions`] to ffmpeg when opening the input. The same
+    /// options are used when opening the audio and subtitle streams.
+    pub fn new_with_input_options(
+        ctx: &egui::Context,
+        input_path: &str,
+        input_options: InputOptions,
+    ) -> Result<Self> {
+        let input_context = open_input(input_path, &input_options)?;
+        let video_stream = input_context
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let video_stream_index = StreamIndex::from(video_stream.index());
+
+        let video_elapsed_ms = Shared::new(0);
+        let audio_elapsed_ms = Shared::new(0);
+        let audio_device_time_ms = Shared::new(0);
+        let seeking_signal = Shared::new(false);
+        let player_state = Shared::new(PlayerState::Stopped);
+
+        let (video_decoder, hw_decoding, hw_fallback_reason) = hwaccel::open_video_decoder(
+            video_stream.parameters(),
+            &input_options.decoder_options(),
+        )?;
+        let (event_sender, event_reciever) = std::sync::mpsc::channel();
+        if let Some(reason) = hw_fallback_reason.clone() {
+            let _ = event_sender.send(PlayerEvent::HwDecodingFallback { reason });
+        }
+        let hw_fallback = Arc::new(Mutex::new(hw_fallback_reason));
+        let decode_error_count = Shared::new(0);
+        let session_stats = Arc::new(Mutex::new(StatsRecorder::new()));
         let framerate = (video_stream.avg_frame_rate().numerator() as f64)
             / video_stream.avg_frame_rate().denominator() as f64;
 
-        let (width, height) = (video_decoder.width(), video_decoder.height());
-        let size = Vec2::new(width as f32, height as f32);
+        let media_info = MediaInfo::new(input_path, &video_stream, &video_decoder);
+        let size = media_info.display_size;
+        let fingerprint = settings::fingerprint(&input_context, input_path);
+        let layout_fingerprint = settings::layout_fingerprint(&input_context);
         let duration_ms = timestamp_to_millisec(input_context.duration(), AV_TIME_BASE_RATIONAL); // in sec
+        #[cfg_attr(not(feature = "chapters"), allow(unused_mut))]
+        let mut chapters = chapters::read_chapters(&input_context, input_path, &input_options);
+        // audio CD rips keep their tracks in a cue sheet next to the file
+        #[cfg(feature = "chapters")]
+        {
+            let cue_sheet = std::path::Path::new(input_path).with_extension("cue");
+            if chapters.is_empty() && cue_sheet.is_file() {
+                chapters = chapters::read_cue_sheet(&cue_sheet, duration_ms).unwrap_or_default();
+            }
+        }
 
+        let max_frame_size = Shared::new(0);
+        let frame_step = Shared::new(1);
+        let upscale_filter = Shared::new(UpscaleFilter::default());
+        let upscale_to = Shared::new(0);
+        let fit_to = Shared::new(0);
+        let content_filters = Arc::new(Mutex::new(Vec::new()));
         let stream_decoder = VideoStreamer {
             apply_video_frame_fn: None,
             duration_ms,
@@ -1279,46 +5651,212 @@ impl Player {
             video_elapsed_ms: video_elapsed_ms.clone(),
             input_context,
             player_state: player_state.clone(),
-            frame_cache: VecDeque::<(ColorImage, i64, i64)>::default(),
+            frame_cache: VecDeque::default(),
+            timeshift: None,
+            frame_analyzer: None,
+            health_monitor: None,
+            content_filters: content_filters.clone(),
+            upload_stage: UploadStage::new(size),
+            decoder_options: input_options.decoder_options(),
+            frame_buffer_size: 50,
+            max_video_lead_ms: None,
+            hw_decoding,
+            hw_fallback: hw_fallback.clone(),
+            event_sender: event_sender.clone(),
+            decode_errors: DecodeErrors::new(
+                decode_error_count.clone(),
+                session_stats.clone(),
+                event_sender.clone(),
+            ),
+            media_info: media_info.clone(),
+            cut_filter: None,
+            packet_receive_times: VecDeque::new(),
+            max_frame_size: max_frame_size.clone(),
+            frame_step: frame_step.clone(),
+            upscale_filter: upscale_filter.clone(),
+            upscale_to: upscale_to.clone(),
+            fit_to: fit_to.clone(),
+            frames_to_skip: 0,
+            denoise: None,
+            interpolation: None,
+            display_color: None,
+            video_filter: None,
+            video_filter_config: None,
+            filtered_frames: VecDeque::new(),
+            hash_frames: false,
         };
         let options = PlayerOptions::default();
         let texture_handle =
             ctx.load_texture("vidstream", ColorImage::example(), options.texture_options);
+        #[cfg(any(feature = "audio", feature = "subtitles"))]
         let (message_sender, message_reciever) = std::sync::mpsc::channel();
         let mut streamer = Self {
-            input_path: input_path.clone(),
+            input_path: input_path.to_string(),
+            input_options,
+            proxy_path: None,
+            proxy_active: false,
+            timeshift: None,
+            #[cfg(feature = "audio")]
             audio_streamer: None,
+            #[cfg(feature = "subtitles")]
             subtitle_streamer: None,
             video_streamer: Arc::new(Mutex::new(stream_decoder)),
             subtitle_stream_info: StreamInfo::new(),
             audio_stream_info: StreamInfo::new(),
             framerate,
             video_timer: Timer::new(),
+            #[cfg(feature = "audio")]
             audio_timer: Timer::new(),
             synchro_timer: Timer::new(),
+            #[cfg(feature = "subtitles")]
             subtitle_timer: Timer::new(),
+            #[cfg(feature = "subtitles")]
             subtitle_elapsed_ms: Shared::new(0),
             preseek_player_state: None,
             video_thread: None,
+            #[cfg(feature = "subtitles")]
             subtitle_thread: None,
             synchro_thread: None,
+            #[cfg(feature = "audio")]
             audio_thread: None,
+            fatal: FatalError::default(),
+            fatal_reported: false,
             texture_handle,
             player_state,
+            #[cfg(any(feature = "audio", feature = "subtitles"))]
             message_sender,
+            #[cfg(any(feature = "audio", feature = "subtitles"))]
             message_reciever,
+            #[cfg(any(feature = "audio", feature = "subtitles"))]
+            pending_stream_cycles: 0,
+            event_sender,
+            event_reciever,
+            health_options: None,
+            #[cfg(feature = "audio")]
+            skip_silence: None,
+            #[cfg(feature = "audio")]
+            silent_spans: SilentSpans::default(),
+            hw_fallback,
+            decode_error_count,
+            session_stats,
             video_elapsed_ms,
             audio_elapsed_ms,
             audio_device_time_ms,
+            audio_output_timing: Arc::new(Mutex::new(None)),
+            sync_test: None,
             seeking_signal,
             size,
             last_seek_ms: None,
             duration_ms,
             options,
+            media_info,
             video_elapsed_ms_override: None,
             ctx_ref: ctx.clone(),
+            #[cfg(feature = "subtitles")]
             subtitles_queue: Arc::new(Mutex::new(VecDeque::new())),
+            #[cfg(feature = "subtitles")]
             current_subtitles: Vec::new(),
+            #[cfg(feature = "subtitles")]
+            injected_subtitles: Vec::new(),
+            #[cfg(feature = "subtitles")]
+            subtitle_files: Vec::new(),
+            #[cfg(feature = "subtitles")]
+            subtitle_file: None,
+            #[cfg(feature = "subtitles")]
+            subtitle_file_checked: None,
+            #[cfg(feature = "subtitles")]
+            subtitles_enabled: true,
+            #[cfg(feature = "subtitles")]
+            pending_font_attachments: Vec::new(),
+            frame_callback: None,
+            user_data: None,
+            cue_points: CuePoints::default(),
+            audio_tracks: Vec::new(),
+            subtitle_tracks: Vec::new(),
+            track_name_formatter: None,
+            up_next: None,
+            performance_profile: None,
+            spare_texture: None,
+            texture_frame_ms: None,
+            spare_frame_ms: None,
+            pending_branch: None,
+            last_frame: None,
+            review_cache: ReviewCache::default(),
+            keyframe_index: Arc::new(OnceLock::new()),
+            keyframe_index_requested: false,
+            #[cfg(feature = "audio")]
+            loudness_lufs: Arc::new(OnceLock::new()),
+            #[cfg(feature = "audio")]
+            loudness_scan_requested: false,
+            #[cfg(feature = "audio")]
+            normalized_to_lufs: None,
+            #[cfg(feature = "audio")]
+            normalization_gain_db: Shared::new(0.),
+            deferred_seek_frac: None,
+            video_clock: PlaybackClock::new(Arc::new(SystemClock::new())),
+            offline: false,
+            offline_presented_ms: None,
+            cut_bounds_ms: None,
+            skip_ranges: Vec::new(),
+            active_skip_range: None,
+            content_filters,
+            upload_stage: UploadStage::new(size),
+            ambient_texture: None,
+            glow_texture: None,
+            ambient_updated: None,
+            ambient_pending: None,
+            bypass_key_held: false,
+            hover_started: None,
+            hover_playing: false,
+            first_frame_pending: false,
+            backoff: None,
+            video_lead_limit: None,
+            damage_overlay: None,
+            drawn_size: 0,
+            resized_at: None,
+            frame_damage: None,
+            damage_highlighted: None,
+            frame_hash: None,
+            backoff_presented: None,
+            repaint_paused: Shared::new(false),
+            fade_in_started: None,
+            crossfade: None,
+            visibility_playing: false,
+            pool: None,
+            shuttle: ShuttleState::default(),
+            sleep_inhibitor: None,
+            shuttle_silent: Shared::new(false),
+            suspend_requested: Shared::new(false),
+            suspended: None,
+            pool_visible_pass: None,
+            idle_since: None,
+            drawn_visible: None,
+            reclaimed: None,
+            refresh_meter: RefreshMeter::default(),
+            display_refresh_hz: None,
+            crop: None,
+            chapters,
+            timeline: TimelineModel::default(),
+            sleep_timer: None,
+            pause_at_ms: None,
+            exact_frame_server: None,
+            audio_fade_gain: Shared::new(1.),
+            audio_muted: Shared::new(false),
+            fingerprint,
+            layout_fingerprint,
+            settings_store: None,
+            playback_speed: Shared::new(1.),
+            #[cfg(feature = "audio")]
+            time_stretch: TimeStretch::default(),
+            #[cfg(feature = "audio")]
+            channel_map: None,
+            #[cfg(feature = "audio")]
+            resampler_options: ResamplerOptions::default(),
+            max_frame_size,
+            frame_step,
+            upscale_filter,
+            upscale_to,
+            fit_to,
             #[cfg(feature = "from_bytes")]
             temp_file: None,
         };
@@ -1337,7 +5875,7 @@ impl Player {
             Ok(first_frame) => {
                 let texture_handle = self.ctx_ref.load_texture(
                     "vidstream",
-                    first_frame.0,
+                    first_frame.0.upload.image,
                     self.options.texture_options,
                 );
                 let texture_handle_clone = texture_handle.clone();
@@ -1349,6 +5887,173 @@ impl Player {
     }
 }
 
+// lay out the text of `subtitle` over the video at `video_rect`, returning the rect the galley is
+// drawn in. `placed` are the rects of the subtitles drawn before it.
+#[cfg(feature = "subtitles")]
+fn layout_text_subtitle(
+    fonts: &Fonts,
+    subtitle: &mut Subtitle,
+    options: &SubtitleOptions,
+    video_rect: Rect,
+    safe_area: Rect,
+    in_band: bool,
+    placed: &[(i32, Rect)],
+) -> (Rect, Arc<Galley>) {
+    // text subtitles are positioned and sized in the coordinates of their script, so they
+    // scale with the rendered video
+    let script_size = subtitle.play_res.unwrap_or(DEFAULT_PLAY_RES);
+    let transform =
+        RectTransform::from_to(Rect::from_min_size(Pos2::ZERO, script_size), video_rect);
+    let scale = video_rect.size() / script_size;
+    // the margins of the subtitle push its area further inside the safe area
+    let mut area = Rect::from_min_max(
+        Pos2::new(
+            safe_area
+                .left()
+                .max(video_rect.left() + subtitle.margin_left.unwrap_or(0.) * scale.x),
+            safe_area
+                .top()
+                .max(video_rect.top() + subtitle.margin_vertical.unwrap_or(0.) * scale.y),
+        ),
+        Pos2::new(
+            safe_area
+                .right()
+                .min(video_rect.right() - subtitle.margin_right.unwrap_or(0.) * scale.x),
+            safe_area
+                .bottom()
+                .min(video_rect.bottom() - subtitle.margin_vertical.unwrap_or(0.) * scale.y),
+        ),
+    );
+    // vertical margins are relative to the video, so they don't apply inside the band
+    if in_band {
+        area.set_top(safe_area.top());
+        area.set_bottom(safe_area.bottom());
+    }
+    let font_size = subtitle.font_size * scale.y * options.font_scale;
+    let font_family = subtitle
+        .font_name
+        .as_ref()
+        .map(|name| FontFamily::Name(name.as_str().into()))
+        .filter(|family| fonts.families().contains(family))
+        .unwrap_or(FontFamily::Proportional);
+    let wrap_style = subtitle.wrap_style.unwrap_or(options.wrap_style);
+    // static cues keep their galley, instead of shaping the text again every frame
+    let font_id = FontId::new(font_size, font_family);
+    let atlas = fonts.texture_atlas();
+    let galley = match &subtitle.layout {
+        Some(cached) if cached.matches(&font_id, wrap_style, area.width(), &atlas) => {
+            cached.galley.clone()
+        }
+        _ => {
+            let galley = layout_subtitle(
+                fonts,
+                &subtitle.text,
+                font_id.clone(),
+                subtitle.primary_fill,
+                subtitle.alignment,
+                wrap_style,
+                area.width(),
+            );
+            subtitle.layout = Some(CachedLayout {
+                font_id,
+                wrap_style,
+                max_width: area.width(),
+                galley: galley.clone(),
+                atlas: Arc::downgrade(&atlas),
+            });
+            galley
+        }
+    };
+    let text_rect = match subtitle.position {
+        // explicitly positioned subtitles never move out of the way
+        Some(position) => subtitle
+            .alignment
+            .anchor_size(transform.transform_pos(position), galley.size()),
+        None => avoid_collisions(
+            subtitle
+                .alignment
+                .anchor_size(subtitle.alignment.pos_in_rect(&area), galley.size()),
+            subtitle.alignment,
+            subtitle.layer,
+            placed,
+        ),
+    };
+    (text_rect, galley)
+}
+
+// lay out the text of a subtitle, wrapping it at `max_width` according to `wrap_style`.
+#[cfg(feature = "subtitles")]
+fn layout_subtitle(
+    fonts: &Fonts,
+    text: &str,
+    font_id: FontId,
+    color: Color32,
+    alignment: Align2,
+    wrap_style: WrapStyle,
+    max_width: f32,
+) -> Arc<Galley> {
+    let layout = |wrap_width: f32| {
+        let mut job = LayoutJob::simple(text.to_string(), font_id.clone(), color, wrap_width);
+        job.halign = alignment.x();
+        fonts.layout_job(job)
+    };
+    if wrap_style == WrapStyle::NoWrap {
+        return layout(f32::INFINITY);
+    }
+    let galley = layout(max_width);
+    if wrap_style == WrapStyle::EndOfLine || galley.rows.len() < 2 {
+        return galley;
+    }
+    // smart wrapping: narrow the wrap width until the rows are about equally long, without
+    // adding rows.
+    let unwrapped_width = layout(f32::INFINITY).size().x;
+    let balanced_width = (unwrapped_width / galley.rows.len() as f32 * 1.1).min(max_width);
+    let balanced = layout(balanced_width);
+    let (mut galley, mut fits) = if balanced.rows.len() <= galley.rows.len() {
+        (balanced, balanced_width)
+    } else {
+        (galley, max_width)
+    };
+    if wrap_style == WrapStyle::SmartLowerWider {
+        // narrow it further, down to the narrowest width that doesn't add rows, so the words move
+        // down and the lower rows end up the wider ones
+        let rows = galley.rows.len();
+        let mut too_narrow = unwrapped_width / rows as f32;
+        for _ in 0..8 {
+            let width = (too_narrow + fits) / 2.;
+            let narrower = layout(width);
+            if narrower.rows.len() <= rows {
+                (galley, fits) = (narrower, width);
+            } else {
+                too_narrow = width;
+            }
+        }
+    }
+    galley
+}
+
+// move `rect` away from the rects already placed in the same layer: bottom aligned subtitles
+// stack upwards, all others downwards.
+#[cfg(feature = "subtitles")]
+fn avoid_collisions(mut rect: Rect, alignment: Align2, layer: i32, placed: &[(i32, Rect)]) -> Rect {
+    // every move passes a placed rect, so this settles after at most `placed.len()` moves
+    for _ in 0..=placed.len() {
+        let Some((_, other)) = placed
+            .iter()
+            .find(|(l, other)| *l == layer && other.intersects(rect))
+        else {
+            break;
+        };
+        if alignment.y() == Align::Max {
+            rect = rect.translate(vec2(0., other.top() - rect.bottom()));
+        } else {
+            rect = rect.translate(vec2(0., other.bottom() - rect.top()));
+        }
+    }
+    rect
+}
+
+#[cfg(any(feature = "audio", feature = "subtitles"))]
 fn get_stream_indices_of_type(
     input_context: &Input,
     stream_type: ffmpeg::media::Type,
@@ -1361,6 +6066,19 @@ fn get_stream_indices_of_type(
         .collect::<VecDeque<_>>()
 }
 
+#[cfg(any(feature = "audio", feature = "subtitles"))]
+fn track_infos(input_context: &Input, stream_indices: &VecDeque<StreamIndex>) -> Vec<TrackInfo> {
+    stream_indices
+        .iter()
+        .enumerate()
+        .filter_map(|(index, stream_index)| {
+            let stream = input_context.stream(**stream_index)?;
+            Some(TrackInfo::new(&stream, index + 1))
+        })
+        .collect()
+}
+
+#[cfg(any(feature = "audio", feature = "subtitles"))]
 fn get_decoder_from_stream_index(
     input_context: &Input,
     stream_index: StreamIndex,
@@ -1371,6 +6089,29 @@ fn get_decoder_from_stream_index(
     Ok(context.decoder())
 }
 
+// the last modification time of the file at `path`, `None` if it can't be read.
+#[cfg(feature = "subtitles")]
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// open a subtitle decoder, converting text from `charset` (if given) to utf-8.
+#[cfg(feature = "subtitles")]
+fn open_subtitle_decoder(
+    input_context: &Input,
+    stream_index: StreamIndex,
+    charset: Option<&str>,
+) -> Result<ffmpeg::decoder::Subtitle> {
+    let decoder = get_decoder_from_stream_index(input_context, stream_index)?;
+    let Some(charset) = charset else {
+        return Ok(decoder.subtitle()?);
+    };
+    let mut options = ffmpeg::Dictionary::new();
+    options.set("sub_charenc", charset);
+    let codec = ffmpeg::decoder::find(decoder.id()).ok_or(ffmpeg::Error::DecoderNotFound)?;
+    Ok(decoder.open_as_with(codec, options)?.subtitle()?)
+}
+
 #[derive(PartialEq, Clone, Copy)]
 /// The index of the stream.
 pub struct StreamIndex(usize);
@@ -1403,14 +6144,24 @@ impl StreamInfo {
             total_streams: 0,
         }
     }
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
     fn from_total(total: usize) -> Self {
         let mut slf = Self::new();
         slf.total_streams = total;
         slf
     }
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
     fn cycle(&mut self) {
         self.current_stream = ((self.current_stream + 1) % (self.total_streams + 1)).max(1);
     }
+    // how many times to cycle to get to `stream`, none for a stream that doesn't exist
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
+    fn steps_to(&self, stream: usize) -> usize {
+        match (1..=self.total_streams).contains(&stream) {
+            true => (stream + self.total_streams - self.current_stream) % self.total_streams,
+            false => 0,
+        }
+    }
     fn is_cyclable(&self) -> bool {
         self.total_streams > 1
     }
@@ -1430,7 +6181,10 @@ pub trait Streamer: Send {
     type ProcessedFrame;
     /// Seek to a location within the stream.
     fn seek(&mut self, seek_frac: f32) {
-        let target_ms = (seek_frac as f64 * self.duration_ms() as f64) as i64;
+        let target_ms = match self.timeshift() {
+            Some(timeshift) => timeshift.window().frac_to_ms(seek_frac),
+            None => (seek_frac as f64 * self.duration_ms() as f64) as i64,
+        };
         let seek_completed = millisec_approx_eq(target_ms, self.elapsed_ms().get());
         // stop seeking near target so we dont waste cpu cycles
         if !seek_completed {
@@ -1440,9 +6194,14 @@ pub trait Streamer: Send {
             let seeking_backwards = target_ms < self.elapsed_ms().get();
             let target_ts = millisec_to_timestamp(target_ms, rescale::TIME_BASE);
 
-            // TODO: propogate error
-            if self.input_context().seek(target_ts, ..target_ts).is_ok() {
+            let seeked = match self.timeshift() {
+                Some(timeshift) => timeshift.seek(target_ms),
+                // TODO: propogate error
+                None => self.input_context().seek(target_ts, ..target_ts).is_ok(),
+            };
+            if seeked {
                 self.decoder().flush();
+                self.on_seek();
                 let mut previous_elapsed_ms = self.elapsed_ms().get();
 
                 // this drop frame loop lets us refresh until current_ts is accurate
@@ -1490,6 +6249,14 @@ pub trait Streamer: Send {
     fn stream_index(&self) -> StreamIndex;
     /// Move to the next stream index, if possible, and return the new_stream_index.
     fn cycle_stream(&mut self) -> StreamIndex;
+    /// Move `steps` stream indices ahead, like [`Streamer::cycle_stream`] that many times.
+    fn cycle_stream_by(&mut self, steps: usize) -> StreamIndex {
+        let mut stream_index = self.stream_index();
+        for _ in 0..steps {
+            stream_index = self.cycle_stream();
+        }
+        stream_index
+    }
     /// The elapsed time of this streamer, in milliseconds.
     fn elapsed_ms(&self) -> &Shared<i64>;
     /// The elapsed time of the primary streamer, in milliseconds.
@@ -1502,6 +6269,66 @@ pub trait Streamer: Send {
     fn input_context(&mut self) -> &mut ffmpeg::format::context::Input;
     /// The streamer's state.
     fn player_state(&self) -> &Shared<PlayerState>;
+    /// The streamer's timeshift buffer, if [`Player::add_timeshift`] has been called.
+    fn timeshift(&mut self) -> Option<&mut TimeshiftBuffer> {
+        None
+    }
+    /// Whether enough is decoded ahead of playback that decoding waits.
+    fn is_buffered_ahead(&self) -> bool {
+        false
+    }
+    /// Whether to keep decoding while paused, to have a reserve for resuming.
+    fn needs_paused_reserve(&self) -> bool {
+        false
+    }
+    /// Report a packet or frame at `elapsed_ms` that couldn't be decoded, or came out damaged.
+    fn report_decode_error(&mut self, _elapsed_ms: i64, _error: String, _dropped: bool) {}
+    /// Read the next packet of the input, along with its stream index and time base. Replays from the
+    /// timeshift buffer when playback is behind the live edge.
+    fn read_packet(&mut self) -> Option<Result<(usize, Rational, Packet)>> {
+        let stream_index = *self.stream_index();
+        if self.timeshift().is_some_and(|t| !t.is_live()) {
+            if let Err(e) = self.buffer_live_packet() {
+                return Some(Err(e));
+            }
+            if let Some((time_base, packet)) = self.timeshift().and_then(|t| t.next_buffered()) {
+                return Some(Ok((stream_index, time_base, packet)));
+            }
+        }
+        let (index, time_base, packet) = match self.input_context().packets().next()? {
+            Ok((stream, packet)) => (stream.index(), stream.time_base(), packet),
+            Err(e) => return Some(Err(e.into())),
+        };
+        if index == stream_index {
+            if let Some(timeshift) = self.timeshift() {
+                timeshift.push(time_base, packet.clone());
+            }
+        }
+        Some(Ok((index, time_base, packet)))
+    }
+    /// Read packets from the live source into the timeshift buffer, until one belonging to this stream
+    /// has been buffered.
+    fn buffer_live_packet(&mut self) -> Result<()> {
+        let stream_index = *self.stream_index();
+        if let Some(timeshift) = self.timeshift() {
+            timeshift.hold();
+        }
+        while let Some(packet) = self.input_context().packets().next() {
+            let (stream, packet) = packet?;
+            if stream.index() == stream_index {
+                let time_base = stream.time_base();
+                if let Some(timeshift) = self.timeshift() {
+                    timeshift.push(time_base, packet);
+                }
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Called when a packet of this stream has been read from the input, before it is sent to the decoder.
+    fn on_packet_received(&mut self, _packet: &Packet) {}
+    /// Called after the input was seeked and the decoder flushed.
+    fn on_seek(&mut self) {}
     /// Output a frame from the decoder.
     fn decode_frame(&mut self) -> Result<Self::Frame>;
     /// Ignore the remainder of this packet.
@@ -1514,11 +6341,11 @@ pub trait Streamer: Send {
     }
     /// Recieve the next packet of the stream.
     fn recieve_next_packet(&mut self) -> Result<()> {
-        if let Some(packet) = self.input_context().packets().next() {
-            let (stream, packet) = packet?;
-            let time_base = stream.time_base();
-            if stream.index() == *self.stream_index() {
-                self.decoder().send_packet(&packet)?;
+        if let Some(packet) = self.read_packet() {
+            let (stream_index, time_base, packet) = packet?;
+            if stream_index == *self.stream_index() {
+                self.on_packet_received(&packet);
+                let sent = self.decoder().send_packet(&packet);
                 match packet.dts() {
                     // Don't try to set elasped time off of undefined timestamp values
                     Some(ffmpeg::ffi::AV_NOPTS_VALUE) => (),
@@ -1528,6 +6355,14 @@ pub trait Streamer: Send {
                     }
                     _ => (),
                 }
+                match sent {
+                    // a damaged packet is dropped, and playback goes on with the next one
+                    Err(error) if decode_errors::is_data_error(&error) => {
+                        let elapsed_ms = self.elapsed_ms().get();
+                        self.report_decode_error(elapsed_ms, error.to_string(), true);
+                    }
+                    sent => sent?,
+                }
             }
         } else {
             self.decoder().send_eof()?;
@@ -1540,6 +6375,7 @@ pub trait Streamer: Send {
         let beginning_seek = beginning.rescale((1, 1), rescale::TIME_BASE);
         let _ = self.input_context().seek(beginning_seek, ..beginning_seek);
         self.decoder().flush();
+        self.on_seek();
     }
     /// Keep recieving packets until a frame can be decoded.
     fn recieve_next_packet_until_frame(&mut self) -> Result<(Self::ProcessedFrame, i64, i64)> {
@@ -1567,14 +6403,150 @@ pub trait Streamer: Send {
     fn recieve_next_frame(&mut self) -> Result<(Self::ProcessedFrame, i64, i64)> {
         match self.decode_frame() {
             Ok(decoded_frame) => self.process_frame(decoded_frame),
-            Err(e) => Err(e),
+            Err(e) => match e.downcast_ref::<ffmpeg::Error>() {
+                // decoding goes on with the next packet
+                Some(error) if decode_errors::is_data_error(error) => {
+                    let elapsed_ms = self.elapsed_ms().get();
+                    self.report_decode_error(elapsed_ms, error.to_string(), true);
+                    Err(ffmpeg::Error::from(AVERROR(EAGAIN)).into())
+                }
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+impl VideoStreamer {
+    // replace the input with `input_context`, the same video in another file, from its start
+    fn replace_input(&mut self, input_context: Input) -> Result<()> {
+        let video_stream = input_context
+            .streams()
+            .best(Type::Video)
+            .ok_or(ffmpeg::Error::StreamNotFound)?;
+        let video_stream_index = StreamIndex::from(video_stream.index());
+        let (video_decoder, hw_decoding, hw_fallback_reason) =
+            hwaccel::open_video_decoder(video_stream.parameters(), &self.decoder_options)?;
+        self.video_decoder = video_decoder;
+        self.hw_decoding = hw_decoding;
+        *self.hw_fallback.lock() = None;
+        if let Some(reason) = hw_fallback_reason {
+            self.report_hw_fallback(reason);
+        }
+        self.video_stream_index = video_stream_index;
+        self.input_context = input_context;
+        self.frame_cache.clear();
+        self.packet_receive_times.clear();
+        self.frames_to_skip = 0;
+        self.reset_video_filter();
+        // the new input is at its start, so a seek back to the current time isn't skipped
+        self.video_elapsed_ms.set(0);
+        Ok(())
+    }
+
+    fn video_filter_spec(&self, frame: &Video) -> Option<String> {
+        let filters: Vec<String> = [
+            self.denoise.map(|denoise| denoise.filter_spec()),
+            self.display_color
+                .and_then(|display_color| display_color.filter_spec(frame)),
+            self.interpolation
+                .map(|interpolation| interpolation.filter_spec()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+
+    // rebuild the filter graph whenever its filters or the format of the frames changed. When the
+    // linked ffmpeg doesn't have a filter, all of them are turned off.
+    fn update_video_filter(&mut self, frame: &Video) {
+        let config = self
+            .video_filter_spec(frame)
+            .map(|spec| (spec, frame.width(), frame.height(), frame.format()));
+        if config == self.video_filter_config {
+            return;
+        }
+        let time_base = self
+            .input_context
+            .stream(*self.video_stream_index)
+            .unwrap()
+            .time_base();
+        self.video_filter = config.as_ref().and_then(|(spec, width, height, format)| {
+            VideoFilter::new(spec, *format, *width, *height, time_base).ok()
+        });
+        if self.video_filter.is_none() {
+            self.denoise = None;
+            self.display_color = None;
+            self.interpolation = None;
+        }
+        self.video_filter_config = self.video_filter.as_ref().and(config);
+    }
+
+    // receive a frame from the decoder, moving decoding to software when the hardware can't go on
+    fn receive_decoded_frame(&mut self, frame: &mut Video) -> Result<()> {
+        let received = self.video_decoder.receive_frame(frame);
+        let Some(hw_decoding) = self.hw_decoding.as_mut() else {
+            return Ok(received?);
+        };
+        let (result, reason) = match received {
+            Err(error) => (Err(error), hw_decoding.fallback_for(&error)),
+            Ok(()) => match hw_decoding.transfer(std::mem::replace(frame, Video::empty())) {
+                Ok(transferred) => {
+                    hw_decoding.decoded();
+                    *frame = transferred;
+                    (Ok(()), hw_decoding.fallback())
+                }
+                Err(reason) => (Err(ffmpeg::Error::from(AVERROR(EAGAIN))), Some(reason)),
+            },
+        };
+        if let Some(reason) = reason {
+            // the decoder is opened again, as it keeps the hardware after moving to software itself
+            let parameters = self
+                .input_context
+                .stream(*self.video_stream_index)
+                .unwrap()
+                .parameters();
+            let software = DecoderOptions {
+                hwaccel: None,
+                ..self.decoder_options
+            };
+            (self.video_decoder, _, _) = hwaccel::open_video_decoder(parameters, &software)?;
+            self.hw_decoding = None;
+            self.report_hw_fallback(reason);
         }
+        Ok(result?)
+    }
+
+    // the time of the decoded `frame`, or of the last packet without one
+    fn frame_time_ms(&self, frame: &Video) -> i64 {
+        let time_base = self
+            .input_context
+            .stream(*self.video_stream_index)
+            .unwrap()
+            .time_base();
+        frame.pts().map_or(self.video_elapsed_ms.get(), |pts| {
+            timestamp_to_millisec(pts, time_base)
+        })
+    }
+
+    fn report_hw_fallback(&mut self, reason: HwFallbackReason) {
+        *self.hw_fallback.lock() = Some(reason.clone());
+        let _ = self
+            .event_sender
+            .send(PlayerEvent::HwDecodingFallback { reason });
+    }
+
+    // drop the frames buffered in the filter graph, like the temporal state of `hqdn3d`
+    fn reset_video_filter(&mut self) {
+        self.video_filter = None;
+        self.video_filter_config = None;
+        self.filtered_frames.clear();
     }
 }
 
 impl Streamer for VideoStreamer {
     type Frame = Video;
-    type ProcessedFrame = ColorImage;
+    type ProcessedFrame = VideoFrame;
     fn stream_type(&self) -> Type {
         Type::Video
     }
@@ -1605,41 +6577,239 @@ impl Streamer for VideoStreamer {
     fn player_state(&self) -> &Shared<PlayerState> {
         &self.player_state
     }
+    fn timeshift(&mut self) -> Option<&mut TimeshiftBuffer> {
+        self.timeshift.as_mut()
+    }
+    fn report_decode_error(&mut self, elapsed_ms: i64, error: String, dropped: bool) {
+        self.decode_errors
+            .report(*self.video_stream_index, elapsed_ms, error, dropped);
+    }
+    fn is_buffered_ahead(&self) -> bool {
+        // the frames before the playback position are dropped when presenting, so the queue starts
+        // about at it
+        match (
+            self.max_video_lead_ms,
+            self.frame_cache.front(),
+            self.frame_cache.back(),
+        ) {
+            (Some(max_lead_ms), Some(first), Some(last)) => last.1 - first.1 >= max_lead_ms,
+            _ => false,
+        }
+    }
     fn decode_frame(&mut self) -> Result<Self::Frame> {
         let mut decoded_frame = Video::empty();
-        self.video_decoder.receive_frame(&mut decoded_frame)?;
-        Ok(decoded_frame)
+        loop {
+            if let Some(filtered_frame) = self.filtered_frames.pop_front() {
+                return Ok(filtered_frame);
+            }
+            self.receive_decoded_frame(&mut decoded_frame)?;
+            if FrameDamage::of(&decoded_frame).is_some() {
+                let dropped = self.decoder_options.drop_corrupt_frames;
+                let elapsed_ms = self.frame_time_ms(&decoded_frame);
+                self.report_decode_error(elapsed_ms, "the frame is damaged".to_string(), dropped);
+                if dropped {
+                    continue;
+                }
+            }
+            if self.frames_to_skip > 0 {
+                self.frames_to_skip -= 1;
+                continue;
+            }
+            self.frames_to_skip = self.frame_step.get().max(1) - 1;
+            self.update_video_filter(&decoded_frame);
+            match self.video_filter.as_mut() {
+                Some(video_filter) => self
+                    .filtered_frames
+                    .extend(video_filter.run(&decoded_frame)?),
+                None => return Ok(decoded_frame),
+            }
+        }
     }
     fn apply_frame(&mut self, frame: (Self::ProcessedFrame, i64, i64)) -> bool {
+        if let Some(cut_filter) = self.cut_filter.as_mut() {
+            if !cut_filter.accept(frame.1, &mut self.frame_cache) {
+                return false;
+            }
+        }
         // some logic here to deal with synchronization
         // store all frames in the cache, display current frame until audio device is more near a future frame
         self.frame_cache.push_back(frame);
 
-        // full after like 50 frames
-        self.frame_cache.len() >= 50
+        self.frame_cache.len() >= self.frame_buffer_size
+    }
+    fn on_seek(&mut self) {
+        if let Some(cut_filter) = self.cut_filter.as_mut() {
+            cut_filter.reset();
+        }
+        self.frames_to_skip = 0;
+        self.reset_video_filter();
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            health_monitor.reset();
+        }
+    }
+    fn on_packet_received(&mut self, packet: &Packet) {
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            let stream_index = *self.video_stream_index;
+            let time_base = self.input_context.stream(stream_index).unwrap().time_base();
+            health_monitor.on_packet(packet, stream_index, time_base);
+        }
+        if let Some(pts) = packet.pts() {
+            self.packet_receive_times.push_back((pts, Instant::now()));
+            if self.packet_receive_times.len() > 128 {
+                self.packet_receive_times.pop_front();
+            }
+        }
     }
     fn process_frame(&mut self, frame: Self::Frame) -> Result<(Self::ProcessedFrame, i64, i64)> {
+        let decode = Instant::now();
         let mut rgb_frame = Video::empty();
+        // the smaller of the two limits, where `0` is none
+        let max_size = match (self.max_frame_size.get(), self.fit_to.get()) {
+            (0, fit_to) => fit_to,
+            (max_size, 0) => max_size,
+            (max_size, fit_to) => max_size.min(fit_to),
+        };
+        let (mut width, mut height) = fit_frame_size(frame.width(), frame.height(), max_size);
+        let mut flags = Flags::BILINEAR;
+        if self.max_frame_size.get() == 0 && (width, height) == (frame.width(), frame.height()) {
+            if let Some(upscaled) = upscale::upscaled_size(width, height, self.upscale_to.get()) {
+                (width, height) = upscaled;
+                flags = self.upscale_filter.get().flags();
+            }
+        }
         let mut scaler = Context::get(
             frame.format(),
             frame.width(),
             frame.height(),
             Pixel::RGBA, // destination should match the buffer output, this is much faster
-            frame.width(),
-            frame.height(),
-            Flags::BILINEAR,
+            width,
+            height,
+            flags,
         )?;
         scaler.run(&frame, &mut rgb_frame)?;
 
-        let presentation_time_ms = frame.pts().unwrap_or(0);
-        let duration = unsafe { (*frame.as_ptr()).duration };
+        let time_base = match self.video_filter.as_ref() {
+            Some(video_filter) => video_filter.time_base(),
+            None => self
+                .input_context
+                .stream(*self.video_stream_index)
+                .unwrap()
+                .time_base(),
+        };
+        let pts = frame.pts().unwrap_or(0);
+        let presentation_time_ms = timestamp_to_millisec(pts, time_base);
+        let duration = timestamp_to_millisec(unsafe { (*frame.as_ptr()).duration }, time_base);
+        let damage = FrameDamage::of(&frame);
+        let hash = self.hash_frames.then(|| frame_hash::hash_frame(&frame));
         //println!("writing video chunk : pts {} duration {}", presentation_time_ms, duration);
 
-        let image = video_frame_to_image(rgb_frame);
-        Ok((image, presentation_time_ms, duration))
+        let image = self.media_info.to_display(video_frame_to_image(rgb_frame));
+        if let Some(frame_analyzer) = self.frame_analyzer.as_mut() {
+            frame_analyzer.analyze(&image, presentation_time_ms);
+        }
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            health_monitor.on_video_frame(&image, presentation_time_ms);
+        }
+
+        let receive = self
+            .packet_receive_times
+            .iter()
+            .find(|(packet_pts, _)| *packet_pts == pts)
+            .map(|(_, instant)| *instant);
+        // only sources with a wall clock reference (like rtcp sender reports) know when frames were captured
+        let capture = unsafe {
+            let input = &*self.input_context.as_ptr();
+            (input.start_time_realtime != ffmpeg::ffi::AV_NOPTS_VALUE).then(|| {
+                let start_ms = if input.start_time == ffmpeg::ffi::AV_NOPTS_VALUE {
+                    0
+                } else {
+                    timestamp_to_millisec(input.start_time, AV_TIME_BASE_RATIONAL)
+                };
+                UNIX_EPOCH
+                    + std::time::Duration::from_micros(input.start_time_realtime as u64)
+                    + std::time::Duration::from_millis(
+                        (presentation_time_ms - start_ms).max(0) as u64
+                    )
+            })
+        };
+
+        // prepared here, so the ui thread only sets the texture to it
+        let upload =
+            self.upload_stage
+                .prepare(&image, presentation_time_ms, &self.content_filters.lock());
+        let frame = VideoFrame {
+            image,
+            capture,
+            receive,
+            decode,
+            damage,
+            hash,
+            upload,
+        };
+        Ok((frame, presentation_time_ms, duration))
+    }
+}
+
+#[cfg(feature = "audio")]
+impl AudioStreamer {
+    fn set_resampler_options(&mut self, resampler_options: ResamplerOptions) -> Result<()> {
+        self.resampler = resampler::stereo_resampler(
+            self.audio_decoder.format(),
+            self.audio_decoder.ch_layout(),
+            self.audio_decoder.rate(),
+            self.resampler.output().format,
+            self.resampler.output().rate,
+            &resampler_options,
+        )?;
+        self.resampler_options = resampler_options;
+        Ok(())
+    }
+    fn audio_filter_spec(&self) -> Option<String> {
+        let speed = self.playback_speed.get();
+        let layout = self.audio_decoder.ch_layout();
+        let filters: Vec<String> = [
+            self.channel_map.as_ref().and_then(|channel_map| {
+                channel_map.filter_spec(&layout.description(), layout.channels() as usize)
+            }),
+            (speed != 1.).then(|| self.time_stretch.filter_spec(speed)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+    // rebuild the filter graph whenever its configuration changed
+    fn update_audio_filter(&mut self) -> Result<()> {
+        let spec = self.audio_filter_spec();
+        if spec != self.audio_filter_spec {
+            let time_base = self
+                .input_context
+                .stream(*self.stream_index())
+                .unwrap()
+                .time_base();
+            self.audio_filter = spec
+                .as_ref()
+                .map(|spec| {
+                    AudioFilter::new(
+                        spec,
+                        self.audio_decoder.format(),
+                        self.audio_decoder.rate(),
+                        &self.audio_decoder.ch_layout(),
+                        time_base,
+                    )
+                })
+                .transpose()?;
+            self.audio_filter_spec = spec;
+        }
+        Ok(())
+    }
+    fn reset_audio_filter(&mut self) {
+        self.audio_filter = None;
+        self.audio_filter_spec = None;
     }
 }
 
+#[cfg(feature = "audio")]
 impl Streamer for AudioStreamer {
     type Frame = Audio;
     type ProcessedFrame = ();
@@ -1653,23 +6823,28 @@ impl Streamer for AudioStreamer {
         self.audio_stream_indices[0]
     }
     fn cycle_stream(&mut self) -> StreamIndex {
-        self.audio_stream_indices.rotate_right(1);
+        self.cycle_stream_by(1)
+    }
+    fn cycle_stream_by(&mut self, steps: usize) -> StreamIndex {
+        let len = self.audio_stream_indices.len();
+        self.audio_stream_indices.rotate_right(steps % len.max(1));
         let new_stream_index = self.stream_index();
         let new_decoder = get_decoder_from_stream_index(&self.input_context, new_stream_index)
             .unwrap()
             .audio()
             .unwrap();
-        let new_resampler = ffmpeg::software::resampling::context::Context::get2(
+        let new_resampler = resampler::stereo_resampler(
             new_decoder.format(),
             new_decoder.ch_layout(),
             new_decoder.rate(),
             self.resampler.output().format,
-            ChannelLayout::STEREO,
             self.resampler.output().rate,
+            &self.resampler_options,
         )
         .unwrap();
         self.audio_decoder = new_decoder;
         self.resampler = new_resampler;
+        self.reset_audio_filter();
         new_stream_index
     }
     fn decoder(&mut self) -> &mut ffmpeg::decoder::Opened {
@@ -1690,30 +6865,139 @@ impl Streamer for AudioStreamer {
     fn player_state(&self) -> &Shared<PlayerState> {
         &self.player_state
     }
+    fn timeshift(&mut self) -> Option<&mut TimeshiftBuffer> {
+        self.timeshift.as_mut()
+    }
+    fn needs_paused_reserve(&self) -> bool {
+        let output = self.resampler.output();
+        let channels = output.channel_layout.channels().max(1) as u64;
+        let samples_per_ms = output.rate as u64 * channels / 1000;
+        let reserve = self.paused_audio_reserve_ms.get() as u64 * samples_per_ms;
+        let queued = self
+            .sent_samples
+            .get()
+            .saturating_sub(self.taken_samples.get());
+        queued < reserve
+    }
+    fn on_seek(&mut self) {
+        if let Some(cut_filter) = self.cut_filter.as_mut() {
+            cut_filter.reset();
+        }
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            health_monitor.reset();
+        }
+        if let Some(silence_detector) = self.silence_detector.as_mut() {
+            silence_detector.reset();
+        }
+    }
+    fn on_packet_received(&mut self, packet: &Packet) {
+        let stream_index = *self.stream_index();
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            let time_base = self.input_context.stream(stream_index).unwrap().time_base();
+            health_monitor.on_packet(packet, stream_index, time_base);
+        }
+    }
+    fn report_decode_error(&mut self, elapsed_ms: i64, error: String, dropped: bool) {
+        self.decode_errors
+            .report(*self.stream_index(), elapsed_ms, error, dropped);
+    }
     fn decode_frame(&mut self) -> Result<Self::Frame> {
         let mut decoded_frame = Audio::empty();
         self.audio_decoder.receive_frame(&mut decoded_frame)?;
+        if FrameDamage::of(&decoded_frame).is_some() {
+            let elapsed_ms = self.audio_elapsed_ms.get();
+            self.report_decode_error(elapsed_ms, "the frame is damaged".to_string(), false);
+        }
         Ok(decoded_frame)
     }
     fn process_frame(&mut self, frame: Self::Frame) -> Result<(Self::ProcessedFrame, i64, i64)> {
-        let mut resampled_frame = ffmpeg::frame::Audio::empty();
-        self.resampler.run(&frame, &mut resampled_frame)?;
-        let audio_samples = if resampled_frame.is_packed() {
-            packed(&resampled_frame)
-        } else {
-            resampled_frame.plane(0)
+        let time_base = self
+            .input_context
+            .stream(*self.stream_index())
+            .unwrap()
+            .time_base();
+        let pts = timestamp_to_millisec(frame.pts().unwrap_or(0), time_base);
+        let duration = timestamp_to_millisec(unsafe { (*frame.as_ptr()).duration }, time_base);
+        if let Some(cut_filter) = self.cut_filter.as_mut() {
+            if !cut_filter.accept::<()>(pts, &mut VecDeque::new()) {
+                return Ok(((), pts, duration));
+            }
+        }
+        // shuttling too fast to be heard
+        if self.shuttle_silent.get() {
+            return Ok(((), pts, duration));
+        }
+        self.update_audio_filter()?;
+        let filtered_frames = match self.audio_filter.as_mut() {
+            Some(audio_filter) => audio_filter.run(&frame)?,
+            None => vec![frame],
         };
-        let mut chunk = StreamingAudioChunk::new(audio_samples.to_vec());
-        let pts = frame.pts().unwrap_or(0);
-        let duration = unsafe { (*frame.as_ptr()).duration };
+        let mut audio_samples = vec![];
+        for filtered_frame in filtered_frames {
+            let mut resampled_frame = ffmpeg::frame::Audio::empty();
+            self.resampler.run(&filtered_frame, &mut resampled_frame)?;
+            audio_samples.extend_from_slice(if resampled_frame.is_packed() {
+                packed(&resampled_frame)
+            } else {
+                resampled_frame.plane(0)
+            });
+        }
+        // before muting, which is no silence of the source
+        if let Some(health_monitor) = self.health_monitor.as_mut() {
+            health_monitor.on_audio(&audio_samples, pts);
+        }
+        if let Some(silence_detector) = self.silence_detector.as_mut() {
+            silence_detector.on_audio(&audio_samples, pts, duration);
+        }
+        let muted = self
+            .content_filters
+            .lock()
+            .iter()
+            .any(|f| f.mutes() && f.overlaps(pts, pts + duration));
+        if muted {
+            audio_samples.fill(0.);
+        }
+        let mut chunk = StreamingAudioChunk::new(audio_samples);
         chunk.presentation_time_ms = pts;
         chunk.duration = duration;
         //println!("writing audio chunk : pts {} duration {}", chunk.presentation_time_ms, chunk.duration);
+        self.sent_samples
+            .set(self.sent_samples.get() + chunk.data.len() as u64);
         let _ = self.audio_sample_producer.send(chunk);
         Ok(((), pts, duration))
     }
 }
 
+#[cfg(feature = "subtitles")]
+impl SubtitleStreamer {
+    // replace the input with `input_context`, the same file after it changed, and catch up to
+    // `seek_frac`
+    fn reload(&mut self, input_context: Input, seek_frac: f32) -> Result<()> {
+        let mut stream_indices = get_stream_indices_of_type(&input_context, Type::Subtitle);
+        // keep the selected stream, if the file still has it
+        if let Some(position) = stream_indices
+            .iter()
+            .position(|index| *index == self.stream_index())
+        {
+            stream_indices.rotate_left(position);
+        }
+        let Some(stream_index) = stream_indices.front().copied() else {
+            anyhow::bail!("no subtitles left in the file");
+        };
+        let subtitle_decoder =
+            open_subtitle_decoder(&input_context, stream_index, self.charset.as_deref())?;
+        self.ass_script = AssScript::from_decoder(&subtitle_decoder);
+        self.subtitle_decoder = subtitle_decoder;
+        self.input_context = input_context;
+        self.subtitle_stream_indices = stream_indices;
+        self.next_packet = None;
+        self.subtitles_queue.lock().clear();
+        self.seek(seek_frac);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "subtitles")]
 impl Streamer for SubtitleStreamer {
     type Frame = (ffmpeg::codec::subtitle::Subtitle, i64, Option<i64>);
     type ProcessedFrame = Subtitle;
@@ -1727,19 +7011,32 @@ impl Streamer for SubtitleStreamer {
         self.subtitle_stream_indices[0]
     }
     fn cycle_stream(&mut self) -> StreamIndex {
-        self.subtitle_stream_indices.rotate_right(1);
+        self.cycle_stream_by(1)
+    }
+    fn cycle_stream_by(&mut self, steps: usize) -> StreamIndex {
+        let len = self.subtitle_stream_indices.len();
+        self.subtitle_stream_indices
+            .rotate_right(steps % len.max(1));
         self.subtitle_decoder.flush();
         let new_stream_index = self.stream_index();
-        let new_decoder = get_decoder_from_stream_index(&self.input_context, new_stream_index)
-            .unwrap()
-            .subtitle()
-            .unwrap();
+        let new_decoder = open_subtitle_decoder(
+            &self.input_context,
+            new_stream_index,
+            self.charset.as_deref(),
+        )
+        .unwrap();
         self.next_packet = None;
+        self.ass_script = AssScript::from_decoder(&new_decoder);
+        self.subtitle_decoder = new_decoder;
+        self.subtitles_queue.lock().clear();
         // bandaid: subtitle decoder is always ahead of video decoder, so we need to seek it back to the
         // video decoder's location in order so that we don't miss possible subtitles when switching streams
-        self.seek(self.primary_elapsed_ms().get() as f32 / self.duration_ms as f32);
-        self.subtitles_queue.lock().clear();
-        self.subtitle_decoder = new_decoder;
+        let primary_elapsed_ms = self.primary_elapsed_ms().get();
+        let seek_frac = match self.timeshift() {
+            Some(timeshift) => timeshift.window().ms_to_frac(primary_elapsed_ms),
+            None => primary_elapsed_ms as f32 / self.duration_ms as f32,
+        };
+        self.seek(seek_frac);
         new_stream_index
     }
     fn decoder(&mut self) -> &mut ffmpeg::decoder::Opened {
@@ -1760,13 +7057,61 @@ impl Streamer for SubtitleStreamer {
     fn player_state(&self) -> &Shared<PlayerState> {
         &self.player_state
     }
+    fn timeshift(&mut self) -> Option<&mut TimeshiftBuffer> {
+        self.timeshift.as_mut()
+    }
+    fn seek(&mut self, seek_frac: f32) {
+        let target_ms = match self.timeshift() {
+            Some(timeshift) => timeshift.window().frac_to_ms(seek_frac),
+            None => (seek_frac as f64 * self.duration_ms as f64) as i64,
+        };
+        let delay_ms = self.delay_ms.get();
+        // start reading before the target, so the cues still showing at it are found
+        let start_ms = (target_ms - delay_ms - self.seek_preroll_ms.get().max(0)).max(0);
+        let seeked = match self.timeshift() {
+            Some(timeshift) => timeshift.seek(start_ms),
+            None => {
+                let start_ts = millisec_to_timestamp(start_ms, rescale::TIME_BASE);
+                self.input_context.seek(start_ts, ..start_ts).is_ok()
+            }
+        };
+        if !seeked {
+            return;
+        }
+        self.subtitle_decoder.flush();
+        self.next_packet = None;
+        self.subtitle_elapsed_ms.set(start_ms + delay_ms);
+        while let Some(Ok((stream_index, time_base, packet))) = self.read_packet() {
+            if stream_index != *self.stream_index() {
+                continue;
+            }
+            let dts_ms = packet
+                .dts()
+                .map(|dts| timestamp_to_millisec(dts, time_base) + delay_ms);
+            if let Some(dts_ms) = dts_ms {
+                self.subtitle_elapsed_ms.set(dts_ms);
+            }
+            self.next_packet = Some(packet);
+            if dts_ms.is_some_and(|dts_ms| dts_ms >= target_ms) {
+                // left for playback to decode
+                break;
+            }
+            let Ok((subtitle, _, _)) = self.recieve_next_frame() else {
+                continue;
+            };
+            // keep the cues that started before the target and are still showing
+            if !subtitle.has_ended(target_ms) {
+                self.subtitles_queue.lock().push_back(subtitle);
+            }
+        }
+    }
     fn recieve_next_packet(&mut self) -> Result<()> {
-        if let Some(packet) = self.input_context().packets().next() {
-            let (stream, packet) = packet?;
-            let time_base = stream.time_base();
-            if stream.index() == *self.stream_index() {
+        if let Some(packet) = self.read_packet() {
+            let (stream_index, time_base, packet) = packet?;
+            if stream_index == *self.stream_index() {
                 if let Some(dts) = packet.dts() {
-                    self.elapsed_ms().set(timestamp_to_millisec(dts, time_base));
+                    let dts_ms = timestamp_to_millisec(dts, time_base) + self.delay_ms.get();
+                    self.elapsed_ms().set(dts_ms);
                 }
                 self.next_packet = Some(packet);
             }
@@ -1779,7 +7124,18 @@ impl Streamer for SubtitleStreamer {
         if let Some(packet) = self.next_packet.take() {
             let mut decoded_frame = ffmpeg::Subtitle::new();
             self.subtitle_decoder.decode(&packet, &mut decoded_frame)?;
-            Ok((decoded_frame, packet.duration(), packet.pts()))
+            let time_base = self
+                .input_context
+                .stream(*self.stream_index())
+                .unwrap()
+                .time_base();
+            Ok((
+                decoded_frame,
+                timestamp_to_millisec(packet.duration(), time_base),
+                packet
+                    .pts()
+                    .map(|pts| timestamp_to_millisec(pts, time_base) + self.delay_ms.get()),
+            ))
         } else {
             Err(ffmpeg::Error::from(AVERROR(EAGAIN)).into())
         }
@@ -1788,15 +7144,16 @@ impl Streamer for SubtitleStreamer {
         // TODO: manage the case when frame rects len > 1
         let (frame, duration, pts) = frame;
         if let Some(rect) = frame.rects().next() {
-            Subtitle::from_ffmpeg_rect(rect).map(|s| {
+            Subtitle::from_ffmpeg_rect(rect, &self.ass_script).map(|s| {
                 if pts.is_some() {
+                    let duration_ms = if duration > 0 { duration } else { 3000 };
                     (
                         s.with_presentation_time_ms(pts.unwrap())
-                            .with_duration_ms(3000),
+                            .with_duration_ms(duration_ms),
                         pts.unwrap(),
                         duration,
                     )
-                } else if s.remaining_duration_ms == 0 {
+                } else if s.duration_ms == 0 {
                     (s.with_duration_ms(duration), pts.unwrap(), duration)
                 } else {
                     (s, pts.unwrap(), duration)
@@ -1815,12 +7172,16 @@ impl Streamer for SubtitleStreamer {
     }
 }
 
+#[cfg(feature = "audio")]
 type FfmpegAudioFormat = ffmpeg::format::Sample;
+#[cfg(feature = "audio")]
 type FfmpegAudioFormatType = ffmpeg::format::sample::Type;
+#[cfg(feature = "audio")]
 trait AsFfmpegSample {
     fn to_sample(&self) -> FfmpegAudioFormat;
 }
 
+#[cfg(feature = "audio")]
 impl AsFfmpegSample for cpal::SampleFormat {
     fn to_sample(&self) -> FfmpegAudioFormat {
         match self {
@@ -1841,17 +7202,23 @@ impl AsFfmpegSample for cpal::SampleFormat {
 
 /// Pipes audio samples to cpal.
 #[derive(Default)]
+#[cfg(feature = "audio")]
 pub struct AudioDeviceCallback {
     sample_streams: Vec<AudioSampleStream>,
+    // the streams mixed, before converting to the samples of the device
+    mix: Vec<f32>,
     device_time_ms: Option<Shared<i64>>,
+    output_timing: Option<AudioOutputTiming>,
     seeking: Option<Shared<bool>>,
 }
 
+#[cfg(feature = "audio")]
 struct ChunkSampler {
     chunk: StreamingAudioChunk,
     processed: usize,
 }
 
+#[cfg(feature = "audio")]
 impl ChunkSampler {
     fn new(chunk: StreamingAudioChunk) -> Self {
         ChunkSampler {
@@ -1874,17 +7241,117 @@ impl ChunkSampler {
     }
 }
 
+#[cfg(feature = "audio")]
 struct AudioSampleStream {
     sample_consumer: AudioSampleConsumer,
     audio_volume: Shared<f32>,
+    audio_gain_db: Shared<f32>,
+    normalization_gain_db: Shared<f32>,
+    audio_limiter: Shared<bool>,
+    fade_gain: Shared<f32>,
+    muted: Shared<bool>,
+    // volume and gain combined, updated once per callback
+    output_gain: f32,
+    audio_fade_ms: Shared<u32>,
+    player_state: Shared<PlayerState>,
+    sample_rate: u32,
+    // of the resampled audio, whose samples are interleaved
+    channels: u32,
+    // how far the fade moves per sample, if fading is on (updated once per callback), and where it is
+    ramp_step: Option<f32>,
+    ramp_gain: f32,
+    // the current chunk is from before a seek, and is dropped once faded out
+    stale: bool,
     chunks: Option<ChunkSampler>,
+    // the samples taken from `sample_consumer`, see `AudioStreamer::needs_paused_reserve`
+    taken_samples: Shared<u64>,
+    // of the player, a panic while mixing the stream is reported as its own
+    fatal: FatalError,
+}
+
+// samples above this level are gradually compressed by the limiter
+#[cfg(feature = "audio")]
+const LIMITER_THRESHOLD: f32 = 0.8;
+
+#[inline]
+#[cfg(feature = "audio")]
+fn soft_limit(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= LIMITER_THRESHOLD {
+        sample
+    } else {
+        let headroom = 1. - LIMITER_THRESHOLD;
+        sample.signum()
+            * (LIMITER_THRESHOLD + headroom * ((magnitude - LIMITER_THRESHOLD) / headroom).tanh())
+    }
 }
 
+#[cfg(feature = "audio")]
 impl AudioSampleStream {
+    fn update_output_gain(&mut self) {
+        self.output_gain = match self.muted.get() {
+            true => 0.,
+            false => {
+                self.audio_volume.get()
+                    * 10f32
+                        .powf((self.audio_gain_db.get() + self.normalization_gain_db.get()) / 20.)
+                    * self.fade_gain.get()
+            }
+        };
+        self.ramp_step = match self.audio_fade_ms.get() {
+            0 => None,
+            fade_ms => {
+                let samples_per_ms = self.sample_rate as f32 * self.channels as f32 / 1000.;
+                Some(1. / (fade_ms as f32 * samples_per_ms))
+            }
+        };
+        if self.ramp_step.is_none() {
+            self.stale = false;
+        }
+    }
+    fn get_output_sample(&mut self) -> f32 {
+        let mut sample = match self.ramp_step {
+            Some(ramp_step) => {
+                let fading_out = matches!(
+                    self.player_state.get(),
+                    PlayerState::Paused
+                        | PlayerState::SeekingInProgress
+                        | PlayerState::SeekingFinished
+                );
+                if fading_out && self.ramp_gain == 0. {
+                    // hold the rest of the chunk for resuming
+                    if self.stale {
+                        self.chunks = None;
+                        self.stale = false;
+                    }
+                    return 0.;
+                }
+                self.ramp_gain = if fading_out {
+                    (self.ramp_gain - ramp_step).max(0.)
+                } else {
+                    (self.ramp_gain + ramp_step).min(1.)
+                };
+                self.get_sample() * self.ramp_gain
+            }
+            // hold the reserve decoded while paused for resuming
+            None if self.player_state.get() == PlayerState::Paused => return 0.,
+            None => self.get_sample(),
+        };
+        sample *= self.output_gain;
+        if self.audio_limiter.get() {
+            soft_limit(sample)
+        } else {
+            sample
+        }
+    }
     fn get_sample(&mut self) -> f32 {
         if self.chunks.is_none() || self.chunks.as_ref().unwrap().finished() {
             match self.sample_consumer.try_recv() {
-                Ok(result) => self.chunks = Some(ChunkSampler::new(result)),
+                Ok(result) => {
+                    self.taken_samples
+                        .set(self.taken_samples.get() + result.data.len() as u64);
+                    self.chunks = Some(ChunkSampler::new(result))
+                }
                 Err(_) => (),
             }
         }
@@ -1896,16 +7363,21 @@ impl AudioSampleStream {
     }
 }
 
+#[cfg(feature = "audio")]
 impl AudioDeviceCallback {
     fn callback<T>(&mut self, output: &mut [T])
     where
         T: cpal::Sample + cpal::FromSample<f32> + std::iter::Sum<f32>,
     {
         if self.seeking.is_some() && self.seeking.as_ref().unwrap().get() {
-            for stream in self.sample_streams.iter() {
+            for stream in self.sample_streams.iter_mut() {
+                stream.stale = true;
                 // clear until there's nothing left
-                while let Ok(_) = stream.sample_consumer.try_recv() {
+                while let Ok(chunk) = stream.sample_consumer.try_recv() {
                     //println!("draining audio receiver...");
+                    stream
+                        .taken_samples
+                        .set(stream.taken_samples.get() + chunk.data.len() as u64);
                 }
             }
             self.seeking.as_ref().unwrap().set(false);
@@ -1930,12 +7402,24 @@ impl AudioDeviceCallback {
             }
         }
 
-        for x in output.iter_mut() {
-            *x = self
-                .sample_streams
-                .iter_mut()
-                .map(|s| s.get_sample() * s.audio_volume.get())
-                .sum()
+        for stream in self.sample_streams.iter_mut() {
+            stream.update_output_gain();
+        }
+
+        let mix = &mut self.mix;
+        mix.clear();
+        mix.resize(output.len(), 0.);
+        // a panic while mixing a stream drops it, so the other players on the device play on
+        self.sample_streams.retain_mut(|stream| {
+            let mixed = stream.fatal.clone().contain("audio", || {
+                for sample in mix.iter_mut() {
+                    *sample += stream.get_output_sample();
+                }
+            });
+            mixed.is_some()
+        });
+        for (x, sample) in output.iter_mut().zip(mix.iter()) {
+            *x = T::from_sample(*sample);
         }
     }
 }
@@ -1943,6 +7427,7 @@ impl AudioDeviceCallback {
 #[inline]
 // Thanks https://github.com/zmwangx/rust-ffmpeg/issues/72 <3
 // Interpret the audio frame's data as packed (alternating channels, 12121212, as opposed to planar 11112222)
+#[cfg(feature = "audio")]
 fn packed<T: ffmpeg::frame::audio::Sample>(frame: &ffmpeg::frame::Audio) -> &[T] {
     if !frame.is_packed() {
         panic!("data is not packed");
@@ -1977,6 +7462,32 @@ fn is_ffmpeg_incomplete_error(error: &anyhow::Error) -> bool {
     )
 }
 
+// replace the image of `texture`, loading it with linear filtering the first time.
+fn set_linear_texture(
+    ctx: &egui::Context,
+    texture: &mut Option<TextureHandle>,
+    name: &str,
+    image: ColorImage,
+) {
+    match texture.as_mut() {
+        Some(texture) => texture.set(image, TextureOptions::LINEAR),
+        None => *texture = Some(ctx.load_texture(name, image, TextureOptions::LINEAR)),
+    }
+}
+
+// the size of a `width` by `height` frame scaled down to fit in `max_size` on its longer side.
+fn fit_frame_size(width: u32, height: u32, max_size: u32) -> (u32, u32) {
+    let longer_side = width.max(height);
+    if max_size == 0 || longer_side <= max_size {
+        return (width, height);
+    }
+    let scale = max_size as f64 / longer_side as f64;
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
 fn video_frame_to_image(frame: Video) -> ColorImage {
     let size = [frame.width() as usize, frame.height() as usize];
     let data = frame.data(0);
@@ -1997,3 +7508,17 @@ fn video_frame_to_image(frame: Video) -> ColorImage {
 
     ColorImage { size, pixels }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_frames_fit_in_the_max_size() {
+        assert_eq!(fit_frame_size(1920, 1080, 960), (960, 540));
+        assert_eq!(fit_frame_size(1080, 1920, 960), (540, 960));
+        // smaller frames, and no max size, are left as they are
+        assert_eq!(fit_frame_size(640, 360, 960), (640, 360));
+        assert_eq!(fit_frame_size(1920, 1080, 0), (1920, 1080));
+    }
+}
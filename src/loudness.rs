@@ -0,0 +1,80 @@
+use crate::decode_errors;
+use crate::filter::AudioFilter;
+use crate::input::{open_input, InputOptions};
+use anyhow::Result;
+use ffmpeg::error::EAGAIN;
+use ffmpeg::frame::Audio;
+use ffmpeg::media::Type;
+
+// normalization never boosts or cuts more than this, so silent or broken items don't blow up.
+const MAX_GAIN_DB: f32 = 20.;
+
+/// Decode the first audio stream of `input_path` and measure its integrated loudness (EBU R128),
+/// in LUFS. Reads the whole input as fast as it decodes, so run it in the background.
+pub(crate) fn measure_loudness(input_path: &str, input_options: &InputOptions) -> Result<f32> {
+    let mut input_context = open_input(input_path, input_options)?;
+    let stream = input_context
+        .streams()
+        .best(Type::Audio)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let stream_index = stream.index();
+    let time_base = stream.time_base();
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+        .decoder()
+        .audio()?;
+    let mut filter = AudioFilter::new(
+        "ebur128=metadata=1",
+        decoder.format(),
+        decoder.rate(),
+        &decoder.ch_layout(),
+        time_base,
+    )?;
+
+    // every filtered frame carries the integrated loudness up to it
+    let mut loudness = None;
+    let mut measure = |decoder: &mut ffmpeg::decoder::Audio| -> Result<()> {
+        let mut decoded = Audio::empty();
+        loop {
+            match decoder.receive_frame(&mut decoded) {
+                Ok(()) => (),
+                Err(ffmpeg::Error::Eof) => return Ok(()),
+                Err(ffmpeg::Error::Other { errno }) if errno == EAGAIN => return Ok(()),
+                // a damaged frame is left out of the measurement
+                Err(e) if decode_errors::is_data_error(&e) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+            for filtered in filter.run(&decoded)? {
+                if let Some(value) = filtered.metadata().get("lavfi.r128.I") {
+                    loudness = value.parse::<f32>().ok().or(loudness);
+                }
+            }
+        }
+    };
+    for packet in input_context.packets() {
+        let (stream, packet) = match packet {
+            Ok(packet) => packet,
+            // damaged packets are skipped, like while playing
+            Err(ffmpeg::Error::InvalidData) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if stream.index() == stream_index {
+            match decoder.send_packet(&packet) {
+                Err(e) if decode_errors::is_data_error(&e) => continue,
+                sent => sent?,
+            }
+            measure(&mut decoder)?;
+        }
+    }
+    decoder.send_eof()?;
+    measure(&mut decoder)?;
+    loudness.ok_or_else(|| anyhow::anyhow!("no audio to measure in {input_path}"))
+}
+
+/// The gain bringing audio at `loudness_lufs` to `target_lufs`, in decibels.
+pub(crate) fn normalization_gain_db(loudness_lufs: f32, target_lufs: f32) -> f32 {
+    // silence measures as -70 LUFS (the absolute gate)
+    if loudness_lufs <= -70. {
+        return 0.;
+    }
+    (target_lufs - loudness_lufs).clamp(-MAX_GAIN_DB, MAX_GAIN_DB)
+}
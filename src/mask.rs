@@ -0,0 +1,20 @@
+use egui::ColorImage;
+
+/// Multiply the alpha of `image` with the alpha of `mask`, stretched over it, so only the opaque
+/// parts of the mask stay visible.
+pub(crate) fn apply_clip_mask(image: &mut ColorImage, mask: &ColorImage) {
+    let [width, height] = image.size;
+    let [mask_width, mask_height] = mask.size;
+    if mask_width == 0 || mask_height == 0 {
+        return;
+    }
+    for y in 0..height {
+        let mask_y = y * mask_height / height;
+        let mask_row = &mask.pixels[mask_y * mask_width..(mask_y + 1) * mask_width];
+        let row = &mut image.pixels[y * width..(y + 1) * width];
+        for (x, pixel) in row.iter_mut().enumerate() {
+            // the colors are premultiplied, so all channels are scaled
+            *pixel = pixel.gamma_multiply_u8(mask_row[x * mask_width / width].a());
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use egui::{vec2, Color32, ColorImage, Pos2, Rect, Vec2};
+use ffmpeg::codec::packet::side_data::Type as SideDataType;
+use ffmpeg::color;
+use ffmpeg::format::stream::Stream;
+use ffmpeg::Rational;
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+// the `moov` box is read into memory to find the clean aperture, unless it is larger than this.
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Properties of the video stream of a [`crate::Player`], see [`crate::Player::media_info`].
+#[derive(Clone, Debug)]
+pub struct MediaInfo {
+    /// The size of the decoded frames, in pixels.
+    pub coded_size: [u32; 2],
+    /// The width of a pixel relative to its height (the sample aspect ratio), `1.0` for square pixels.
+    pub pixel_aspect_ratio: f32,
+    /// The part of the decoded frames meant to be shown (the clean aperture of MOV/MP4 files), in
+    /// pixels. Everything outside of it is cropped away. `None` if the whole frame is shown.
+    pub clean_aperture: Option<Rect>,
+    /// How far frames are rotated clockwise for display, in degrees: `0`, `90`, `180` or `270`.
+    pub rotation: u32,
+    /// The size of the displayed picture: the clean aperture, stretched by the pixel aspect ratio and
+    /// rotated. This is the size of [`crate::Player::size`].
+    pub display_size: Vec2,
+    /// The aspect ratio (width / height) of the displayed picture.
+    pub display_aspect_ratio: f32,
+    /// The name of the codec, e.g. `h264`.
+    pub codec: String,
+    /// The profile of the codec, e.g. `High` or `Main 10`.
+    pub profile: Option<String>,
+    /// The level of the codec as coded, e.g. `41` for H.264 level 4.1.
+    pub level: Option<i32>,
+    /// The number of bits per color component of the decoded pixels.
+    pub bit_depth: Option<u8>,
+    /// The chroma subsampling of the decoded pixels, e.g. `4:2:0`, or `unknown` for layouts
+    /// without a name.
+    pub chroma_subsampling: Option<&'static str>,
+    /// The color primaries, e.g. `bt709` or `bt2020`.
+    pub color_primaries: Option<&'static str>,
+    /// The transfer function, e.g. `bt709` or `smpte2084` (PQ).
+    pub transfer_characteristic: Option<&'static str>,
+    /// Whether the video is interlaced, and which field comes first.
+    pub field_order: FieldOrder,
+}
+
+/// The field order of a video stream, see [`MediaInfo::field_order`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FieldOrder {
+    /// The stream doesn't say.
+    #[default]
+    Unknown,
+    /// Frames aren't interlaced.
+    Progressive,
+    /// Interlaced, with the top field displayed first.
+    TopFieldFirst,
+    /// Interlaced, with the bottom field displayed first.
+    BottomFieldFirst,
+}
+
+impl From<ffmpeg::FieldOrder> for FieldOrder {
+    fn from(field_order: ffmpeg::FieldOrder) -> Self {
+        match field_order {
+            ffmpeg::FieldOrder::Unknown => Self::Unknown,
+            ffmpeg::FieldOrder::Progressive => Self::Progressive,
+            // named by coded order, then display order
+            ffmpeg::FieldOrder::TT | ffmpeg::FieldOrder::BT => Self::TopFieldFirst,
+            ffmpeg::FieldOrder::BB | ffmpeg::FieldOrder::TB => Self::BottomFieldFirst,
+        }
+    }
+}
+
+impl MediaInfo {
+    pub(crate) fn new(input_path: &str, stream: &Stream, decoder: &ffmpeg::decoder::Video) -> Self {
+        let coded_size = [decoder.width(), decoder.height()];
+        // like `av_guess_sample_aspect_ratio`, the container takes precedence over the codec
+        let stream_aspect_ratio = Rational::from(unsafe { (*stream.as_ptr()).sample_aspect_ratio });
+        let pixel_aspect_ratio = [stream_aspect_ratio, decoder.aspect_ratio()]
+            .into_iter()
+            .find(|r| r.numerator() > 0 && r.denominator() > 0)
+            .map_or(1., |r| r.numerator() as f32 / r.denominator() as f32);
+        let clean_aperture = read_clean_aperture(input_path, coded_size);
+        let rotation = stream
+            .side_data()
+            .find(|side_data| side_data.kind() == SideDataType::DisplayMatrix)
+            .and_then(|side_data| display_matrix_rotation(side_data.data()))
+            .unwrap_or(0);
+
+        let visible_size = clean_aperture.map_or(
+            vec2(coded_size[0] as f32, coded_size[1] as f32),
+            |aperture| aperture.size(),
+        );
+        let mut display_size = vec2(visible_size.x * pixel_aspect_ratio, visible_size.y);
+        if rotation % 180 == 90 {
+            display_size = vec2(display_size.y, display_size.x);
+        }
+
+        let parameters = unsafe { &*stream.parameters().as_ptr() };
+        // ffmpeg uses -99 for unknown profiles and levels
+        let profile = (parameters.profile != -99)
+            .then(|| unsafe {
+                let name =
+                    ffmpeg::ffi::avcodec_profile_name(decoder.id().into(), parameters.profile);
+                (!name.is_null()).then(|| CStr::from_ptr(name).to_string_lossy().into_owned())
+            })
+            .flatten();
+        let level = (parameters.level > 0).then_some(parameters.level);
+        let pixel_descriptor = decoder.format().descriptor();
+        let bit_depth = pixel_descriptor
+            .map(|descriptor| unsafe { (*descriptor.as_ptr()).comp[0].depth as u8 });
+        let chroma_subsampling = pixel_descriptor.map(|descriptor| {
+            if descriptor.nb_components() < 3 {
+                return "4:0:0";
+            }
+            match (descriptor.log2_chroma_w(), descriptor.log2_chroma_h()) {
+                (0, 0) => "4:4:4",
+                (1, 0) => "4:2:2",
+                (1, 1) => "4:2:0",
+                (0, 1) => "4:4:0",
+                (2, 0) => "4:1:1",
+                (2, 2) => "4:1:0",
+                _ => "unknown",
+            }
+        });
+        let color_primaries = decoder.color_primaries();
+        let transfer_characteristic = decoder.color_transfer_characteristic();
+
+        Self {
+            coded_size,
+            pixel_aspect_ratio,
+            clean_aperture,
+            rotation,
+            display_size,
+            display_aspect_ratio: display_size.x / display_size.y,
+            codec: decoder.id().name().to_string(),
+            profile,
+            level,
+            bit_depth,
+            chroma_subsampling,
+            color_primaries: (color_primaries != color::Primaries::Unspecified)
+                .then(|| color_primaries.name())
+                .flatten(),
+            transfer_characteristic: (transfer_characteristic
+                != color::TransferCharacteristic::Unspecified)
+                .then(|| transfer_characteristic.name())
+                .flatten(),
+            field_order: FieldOrder::from(ffmpeg::FieldOrder::from(parameters.field_order)),
+        }
+    }
+
+    /// Crop a decoded frame to the clean aperture and rotate it upright. Frames scaled down from
+    /// the coded size are cropped to the scaled aperture.
+    pub(crate) fn to_display(&self, image: ColorImage) -> ColorImage {
+        let image = match self.clean_aperture {
+            Some(aperture) => {
+                let [coded_width, coded_height] = self.coded_size;
+                let scale = vec2(
+                    image.size[0] as f32 / coded_width.max(1) as f32,
+                    image.size[1] as f32 / coded_height.max(1) as f32,
+                );
+                let aperture = Rect::from_min_max(
+                    (aperture.min.to_vec2() * scale).to_pos2(),
+                    (aperture.max.to_vec2() * scale).to_pos2(),
+                );
+                crop(image, aperture)
+            }
+            None => image,
+        };
+        rotate(image, self.rotation)
+    }
+}
+
+fn crop(image: ColorImage, rect: Rect) -> ColorImage {
+    let [width, height] = image.size;
+    let min_x = (rect.min.x.round().max(0.) as usize).min(width);
+    let min_y = (rect.min.y.round().max(0.) as usize).min(height);
+    let max_x = (rect.max.x.round().max(0.) as usize).clamp(min_x, width);
+    let max_y = (rect.max.y.round().max(0.) as usize).clamp(min_y, height);
+    if [min_x, min_y, max_x, max_y] == [0, 0, width, height] {
+        return image;
+    }
+    let mut pixels = Vec::with_capacity((max_x - min_x) * (max_y - min_y));
+    for y in min_y..max_y {
+        pixels.extend_from_slice(&image.pixels[y * width + min_x..y * width + max_x]);
+    }
+    ColorImage {
+        size: [max_x - min_x, max_y - min_y],
+        pixels,
+    }
+}
+
+fn rotate(image: ColorImage, rotation: u32) -> ColorImage {
+    let [width, height] = image.size;
+    let pixel = |x: usize, y: usize| image.pixels[y * width + x];
+    let (size, pixels): ([usize; 2], Vec<Color32>) = match rotation {
+        90 => (
+            [height, width],
+            (0..width)
+                .flat_map(|y| (0..height).map(move |x| (x, y)))
+                .map(|(x, y)| pixel(y, height - 1 - x))
+                .collect(),
+        ),
+        180 => (
+            [width, height],
+            image.pixels.iter().rev().copied().collect(),
+        ),
+        270 => (
+            [height, width],
+            (0..width)
+                .flat_map(|y| (0..height).map(move |x| (x, y)))
+                .map(|(x, y)| pixel(width - 1 - y, x))
+                .collect(),
+        ),
+        _ => return image,
+    };
+    ColorImage { size, pixels }
+}
+
+// the clockwise rotation of a display matrix (nine 16.16 fixed point values), rounded to a quarter
+// turn. Skews and flips are ignored.
+fn display_matrix_rotation(data: &[u8]) -> Option<u32> {
+    if data.len() < 9 * 4 {
+        return None;
+    }
+    let m: Vec<f64> = data
+        .chunks_exact(4)
+        .map(|value| i32::from_ne_bytes(value.try_into().unwrap()) as f64 / 65536.)
+        .collect();
+    let scale = [m[0].hypot(m[3]), m[1].hypot(m[4])];
+    if scale[0] == 0. || scale[1] == 0. {
+        return None;
+    }
+    // the opposite of `av_display_rotation_get`, which is counterclockwise
+    let degrees = (m[1] / scale[1]).atan2(m[0] / scale[0]).to_degrees();
+    Some(((degrees / 90.).round() as i64 * 90).rem_euclid(360) as u32)
+}
+
+// the boxes in an ISO BMFF (MOV/MP4) byte range, as (type, contents).
+fn boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> + '_ {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        if rest.len() < 8 {
+            return None;
+        }
+        let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+        let (header_size, size) = match u32::from_be_bytes(rest[0..4].try_into().unwrap()) {
+            0 => (8, rest.len()),
+            1 if rest.len() >= 16 => (
+                16,
+                u64::from_be_bytes(rest[8..16].try_into().unwrap()) as usize,
+            ),
+            1 => return None,
+            size => (8, size as usize),
+        };
+        if size < header_size || size > rest.len() {
+            return None;
+        }
+        let contents = &rest[header_size..size];
+        rest = &rest[size..];
+        Some((kind, contents))
+    })
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data)
+        .find(|(k, _)| k == kind)
+        .map(|(_, contents)| contents)
+}
+
+// ffmpeg's mov demuxer doesn't export the `clap` box, so it's read from the file directly. Inputs
+// that aren't local MOV/MP4 files return `None`.
+fn read_clean_aperture(input_path: &str, coded_size: [u32; 2]) -> Option<Rect> {
+    let moov = read_moov(input_path)?;
+    let aperture = boxes(&moov)
+        .filter(|(kind, _)| kind == b"trak")
+        .find_map(|(_, trak)| {
+            let mdia = child(trak, b"mdia")?;
+            // version and flags, pre-defined, then the handler type
+            let handler = child(mdia, b"hdlr")?.get(8..12)?;
+            if handler != b"vide" {
+                return None;
+            }
+            let stsd = child(child(child(mdia, b"minf")?, b"stbl")?, b"stsd")?;
+            // version and flags, entry count, then the sample entries
+            let (_, sample_entry) = boxes(stsd.get(8..)?).next()?;
+            // the fields of a visual sample entry take 78 bytes, followed by its boxes
+            let clap = child(sample_entry.get(78..)?, b"clap")?;
+            let value = |index: usize| -> Option<[u8; 4]> {
+                clap.get(index * 4..index * 4 + 4)?.try_into().ok()
+            };
+            let fraction = |index: usize| -> Option<f32> {
+                let numerator = i32::from_be_bytes(value(index)?) as f32;
+                let denominator = u32::from_be_bytes(value(index + 1)?) as f32;
+                (denominator > 0.).then(|| numerator / denominator)
+            };
+            let size = vec2(fraction(0)?, fraction(2)?);
+            let offset = vec2(fraction(4)?, fraction(6)?);
+            let center = Pos2::new(coded_size[0] as f32 / 2., coded_size[1] as f32 / 2.) + offset;
+            let aperture = Rect::from_center_size(center, size);
+            let full =
+                Rect::from_min_size(Pos2::ZERO, vec2(coded_size[0] as f32, coded_size[1] as f32));
+            (aperture.width() >= 1. && aperture.height() >= 1. && aperture != full)
+                .then(|| aperture.intersect(full))
+        });
+    aperture
+}
+
+fn read_moov(input_path: &str) -> Option<Vec<u8>> {
+    let mut file = File::open(input_path).ok()?;
+    let mut first = true;
+    loop {
+        let mut header = [0; 8];
+        file.read_exact(&mut header).ok()?;
+        let kind: [u8; 4] = header[4..8].try_into().unwrap();
+        // bail out early on anything that doesn't start like an ISO BMFF file
+        if first
+            && !matches!(
+                &kind,
+                b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip" | b"pnot"
+            )
+        {
+            return None;
+        }
+        first = false;
+        let (header_size, size) = match u32::from_be_bytes(header[0..4].try_into().unwrap()) {
+            0 => return None,
+            1 => {
+                let mut large_size = [0; 8];
+                file.read_exact(&mut large_size).ok()?;
+                (16, u64::from_be_bytes(large_size))
+            }
+            size => (8, size as u64),
+        };
+        let contents_size = size.checked_sub(header_size)?;
+        if &kind == b"moov" {
+            if contents_size > MAX_MOOV_SIZE {
+                return None;
+            }
+            let mut moov = vec![0; contents_size as usize];
+            file.read_exact(&mut moov).ok()?;
+            return Some(moov);
+        }
+        file.seek(SeekFrom::Current(contents_size as i64)).ok()?;
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{ReviewCacheSize, UpscaleFilter};
+use egui::{TextureFilter, TextureOptions};
+use std::time::Duration;
+
+/// A bundle of settings trading quality for cpu and memory, for offering users a single quality
+/// toggle, see [`crate::Player::set_performance_profile`]. Frames that are late are dropped with
+/// every profile, there is no separate frame-drop policy: `Low` only decodes fewer frames, see
+/// [`PerformanceSettings::skip_non_reference_frames`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PerformanceProfile {
+    /// For low-end machines: frames are kept to 720p, fewer of them are buffered, and frames
+    /// other frames don't depend on are skipped.
+    Low,
+    /// Frames are kept to 1080p.
+    Medium,
+    /// Full resolution, upscaled to the size the video is drawn at, with mipmapped textures and a
+    /// review cache of 256 MB.
+    High,
+}
+
+impl PerformanceProfile {
+    /// The settings of this profile.
+    pub fn settings(&self) -> PerformanceSettings {
+        match self {
+            Self::Low => PerformanceSettings {
+                decode_threads: 2,
+                frame_buffer: 15,
+                max_frame_size: Some(1280),
+                upscale: None,
+                skip_non_reference_frames: true,
+                texture_options: TextureOptions::LINEAR,
+                review_cache: ReviewCacheSize::Frames(0),
+            },
+            Self::Medium => PerformanceSettings {
+                decode_threads: 0,
+                frame_buffer: 30,
+                max_frame_size: Some(1920),
+                upscale: None,
+                skip_non_reference_frames: false,
+                texture_options: TextureOptions::LINEAR,
+                review_cache: ReviewCacheSize::Frames(0),
+            },
+            Self::High => PerformanceSettings {
+                decode_threads: 0,
+                frame_buffer: 50,
+                max_frame_size: None,
+                upscale: Some(UpscaleFilter::default()),
+                skip_non_reference_frames: false,
+                texture_options: TextureOptions {
+                    mipmap_mode: Some(TextureFilter::Linear),
+                    ..TextureOptions::LINEAR
+                },
+                review_cache: ReviewCacheSize::Megabytes(256),
+            },
+        }
+    }
+}
+
+/// The settings bundled by a [`PerformanceProfile`], for tuning one before applying it with
+/// [`crate::Player::set_performance_settings`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PerformanceSettings {
+    /// The threads decoding the video, `0` for one per cpu core. Only used when the decoder is
+    /// opened, see [`crate::InputOptions::with_decode_threads`].
+    pub decode_threads: usize,
+    /// How many decoded frames are buffered ahead of the one shown.
+    pub frame_buffer: usize,
+    /// See [`crate::Player::set_max_frame_size`].
+    pub max_frame_size: Option<u32>,
+    /// See [`crate::PlayerOptions::upscale`].
+    pub upscale: Option<UpscaleFilter>,
+    /// Skip decoding frames no other frame is predicted from (like most B-frames), lowering the
+    /// frame rate of videos that have them. Frames that are late are dropped either way.
+    pub skip_non_reference_frames: bool,
+    /// See [`crate::PlayerOptions::texture_options`].
+    pub texture_options: TextureOptions,
+    /// See [`crate::PlayerOptions::review_cache`].
+    pub review_cache: ReviewCacheSize,
+}
+
+/// How a [`crate::Player`] backs off while the window is unfocused, like when it plays in a
+/// background window, see [`crate::PlayerOptions::unfocused_backoff`]. Full rate returns once the
+/// window is focused again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnfocusedBackoff {
+    /// Show at most this many frames per second, and skip decoding frames no other frame is
+    /// predicted from.
+    MaxFps(f32),
+    /// Only decode keyframes and show none of them, so just the audio plays. The picture catches up
+    /// at the first keyframe after the window is focused again.
+    AudioOnly,
+}
+
+impl UnfocusedBackoff {
+    // how often the ui is repainted while backing off
+    pub(crate) fn repaint_interval(&self) -> Duration {
+        match self {
+            Self::MaxFps(fps) => Duration::from_secs_f32(1. / fps.max(0.1)),
+            Self::AudioOnly => Duration::from_secs(1),
+        }
+    }
+
+    pub(crate) fn skip_frame(&self) -> ffmpeg::Discard {
+        match self {
+            Self::MaxFps(_) => ffmpeg::Discard::NonReference,
+            Self::AudioOnly => ffmpeg::Discard::NonKey,
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::Shared;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// Caps how many [`crate::Player`]s run their pipeline (the decoding threads) at once, like for a
+/// gallery of hundreds of video cards. Players join it with [`crate::Player::join_pool`]. When one
+/// more starts than `max_active` allows, the player drawn visible least recently is suspended: its
+/// threads stop, showing the last frame as a poster, until it is scrolled back into view or
+/// resumed, seeked or started. Suspending saves the cpu of decoding, not memory: the input, the
+/// decoders and the buffered frames stay open for waking up quickly (see
+/// [`crate::PlayerOptions::reclaim_idle_after`] for freeing the frames). Cloning it shares the
+/// pool.
+#[derive(Clone)]
+pub struct PlayerPool(Arc<Mutex<PoolState>>);
+
+struct PoolState {
+    max_active: usize,
+    next_id: u64,
+    // counts the visits, as the time of the last one
+    tick: u64,
+    members: Vec<Member>,
+}
+
+struct Member {
+    id: u64,
+    last_visible: u64,
+    active: bool,
+    suspend: Shared<bool>,
+}
+
+impl PlayerPool {
+    /// Create a pool running at most `max_active` pipelines at once, at least one.
+    pub fn new(max_active: usize) -> Self {
+        Self(Arc::new(Mutex::new(PoolState {
+            max_active: max_active.max(1),
+            next_id: 0,
+            tick: 0,
+            members: Vec::new(),
+        })))
+    }
+
+    /// Change how many pipelines run at once, suspending the players drawn visible least recently
+    /// if more run already.
+    pub fn set_max_active(&self, max_active: usize) {
+        let mut state = self.0.lock();
+        state.max_active = max_active.max(1);
+        state.suspend_over_limit(None);
+    }
+
+    /// How many pipelines run at once at most.
+    pub fn max_active(&self) -> usize {
+        self.0.lock().max_active
+    }
+
+    /// How many pipelines run now.
+    pub fn active_count(&self) -> usize {
+        self.0.lock().active_count()
+    }
+
+    /// How many players are in the pool, suspended and stopped ones included.
+    pub fn len(&self) -> usize {
+        self.0.lock().members.len()
+    }
+
+    /// Whether no player is in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().members.is_empty()
+    }
+
+    pub(crate) fn join(&self, suspend: Shared<bool>) -> PoolMembership {
+        let mut state = self.0.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.members.push(Member {
+            id,
+            last_visible: 0,
+            active: false,
+            suspend,
+        });
+        PoolMembership {
+            pool: self.clone(),
+            id,
+        }
+    }
+}
+
+impl PoolState {
+    fn active_count(&self) -> usize {
+        self.members.iter().filter(|member| member.active).count()
+    }
+
+    fn member(&mut self, id: u64) -> Option<&mut Member> {
+        self.members.iter_mut().find(|member| member.id == id)
+    }
+
+    // suspend the least recently visible players until the active ones fit, but `keep`
+    fn suspend_over_limit(&mut self, keep: Option<u64>) {
+        while self.active_count() > self.max_active {
+            let Some(member) = self
+                .members
+                .iter_mut()
+                .filter(|member| member.active && Some(member.id) != keep)
+                .min_by_key(|member| member.last_visible)
+            else {
+                return;
+            };
+            member.active = false;
+            member.suspend.set(true);
+        }
+    }
+}
+
+/// The place of a player in a [`PlayerPool`], left when dropped.
+pub(crate) struct PoolMembership {
+    pool: PlayerPool,
+    id: u64,
+}
+
+impl PoolMembership {
+    /// The player was drawn visible.
+    pub(crate) fn visible(&self) {
+        let mut state = self.pool.0.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some(member) = state.member(self.id) {
+            member.last_visible = tick;
+        }
+    }
+
+    /// The pipeline of the player runs, suspending another one if that is one too many.
+    pub(crate) fn activate(&self) {
+        let mut state = self.pool.0.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        if let Some(member) = state.member(self.id) {
+            member.active = true;
+            member.last_visible = tick;
+            member.suspend.set(false);
+        }
+        state.suspend_over_limit(Some(self.id));
+    }
+
+    /// The pipeline of the player stopped.
+    pub(crate) fn deactivate(&self) {
+        if let Some(member) = self.pool.0.lock().member(self.id) {
+            member.active = false;
+        }
+    }
+}
+
+impl Drop for PoolMembership {
+    fn drop(&mut self) {
+        self.pool
+            .0
+            .lock()
+            .members
+            .retain(|member| member.id != self.id);
+    }
+}
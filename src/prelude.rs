@@ -0,0 +1,16 @@
+//! The stable core of the crate: opening a video, showing it, controlling playback and receiving
+//! its events. Apps using only this (and the methods of these types for it: [`Player::new`],
+//! [`Player::builder`], [`Player::ui`], [`Player::start`], [`Player::pause`], [`Player::resume`],
+//! [`Player::stop`], [`Player::seek`], [`PlayerOptions::set_audio_volume`] and [`Player::events`]) keep
+//! compiling across minor releases, as these only change with a major version. [`PlayerEvent`] and
+//! [`PlayerOptions`] are `#[non_exhaustive]`, so events and options can be added to them. The rest
+//! of the crate may still change between minor releases, and its newest subsystems need the
+//! `experimental` feature on top of their own. [`CpalAudioDevice`] needs the `audio` feature, which is on by default.
+//!
+//! ```ignore
+//! use egui_video::prelude::*;
+//! ```
+
+#[cfg(feature = "audio")]
+pub use crate::CpalAudioDevice;
+pub use crate::{Player, PlayerBuilder, PlayerEvent, PlayerOptions, PlayerState};
@@ -0,0 +1,18 @@
+/// A profile for small, muted previews (like hovering a file in a grid), where many players run
+/// at once and none needs full quality, see [`crate::PlayerBuilder::with_preview_profile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreviewProfile {
+    /// See [`crate::Player::set_max_frame_size`].
+    pub max_frame_size: u32,
+    /// See [`crate::Player::set_frame_step`].
+    pub frame_step: u32,
+}
+
+impl Default for PreviewProfile {
+    fn default() -> Self {
+        Self {
+            max_frame_size: 320,
+            frame_step: 3,
+        }
+    }
+}
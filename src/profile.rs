@@ -0,0 +1,387 @@
+#[cfg(feature = "audio")]
+use crate::ChannelMap;
+use crate::{
+    Denoise, DenoiseFilter, DenoiseStrength, DisplayColor, DisplayPrimaries, DisplayTransfer,
+    Interpolation, InterpolationMode, UpscaleFilter,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use egui::{pos2, vec2, Color32, Rect};
+use std::fmt::Write;
+
+/// A snapshot of how a [`crate::Player`] plays (the selected streams, speed, delays, crop, volume,
+/// filters, tint, upscaling and channel map), see [`crate::Player::playback_profile`]. It is written as an mpv style config with
+/// [`PlaybackProfile::to_config`] or command line with [`PlaybackProfile::to_args`], for saving it
+/// or sending it along with a bug report, and read back with [`PlaybackProfile::parse`].
+///
+/// Content and region filters (see [`crate::Player::set_content_filters`] and
+/// [`crate::Player::set_region_filters`]) aren't part of it: they belong to the input they were
+/// made for rather than to how it plays, and are saved along with it by the app.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaybackProfile {
+    /// The selected audio stream, counting from `1` in the order of the input.
+    pub audio_stream: usize,
+    /// The selected subtitle stream, counting from `1` in the order of the input.
+    pub subtitle_stream: usize,
+    /// See [`crate::Player::set_playback_speed`].
+    pub playback_speed: f32,
+    /// See [`crate::PlayerOptions::audio_delay_ms`].
+    pub audio_delay_ms: i64,
+    /// See [`crate::Player::set_crop`].
+    pub crop: Option<Rect>,
+    /// See [`crate::PlayerOptions::audio_volume`].
+    pub audio_volume: f32,
+    /// See [`crate::PlayerOptions::audio_gain_db`].
+    pub audio_gain_db: f32,
+    /// See [`crate::PlayerOptions::looping`].
+    pub looping: bool,
+    /// See [`crate::Player::set_denoise`].
+    pub denoise: Option<Denoise>,
+    /// See [`crate::Player::set_interpolation`].
+    pub interpolation: Option<Interpolation>,
+    /// See [`crate::Player::set_display_color`].
+    pub display_color: Option<DisplayColor>,
+    /// See [`crate::PlayerOptions::tint`].
+    pub tint: Color32,
+    /// See [`crate::PlayerOptions::upscale`].
+    pub upscale: Option<UpscaleFilter>,
+    /// See [`crate::Player::set_channel_map`].
+    #[cfg(feature = "audio")]
+    pub channel_map: Option<ChannelMap>,
+}
+
+impl Default for PlaybackProfile {
+    fn default() -> Self {
+        Self {
+            audio_stream: 1,
+            subtitle_stream: 1,
+            playback_speed: 1.,
+            audio_delay_ms: 0,
+            crop: None,
+            audio_volume: 0.5,
+            audio_gain_db: 0.,
+            looping: false,
+            denoise: None,
+            interpolation: None,
+            display_color: None,
+            tint: Color32::WHITE,
+            upscale: None,
+            #[cfg(feature = "audio")]
+            channel_map: None,
+        }
+    }
+}
+
+const DENOISE: [Denoise; 6] = {
+    use DenoiseFilter::*;
+    use DenoiseStrength::*;
+    [
+        Denoise {
+            filter: Hqdn3d,
+            strength: Light,
+        },
+        Denoise {
+            filter: Hqdn3d,
+            strength: Medium,
+        },
+        Denoise {
+            filter: Hqdn3d,
+            strength: Strong,
+        },
+        Denoise {
+            filter: Nlmeans,
+            strength: Light,
+        },
+        Denoise {
+            filter: Nlmeans,
+            strength: Medium,
+        },
+        Denoise {
+            filter: Nlmeans,
+            strength: Strong,
+        },
+    ]
+};
+
+fn primaries_name(primaries: DisplayPrimaries) -> &'static str {
+    match primaries {
+        DisplayPrimaries::Srgb => "bt.709",
+        DisplayPrimaries::DisplayP3 => "display-p3",
+        DisplayPrimaries::DciP3 => "dci-p3",
+        DisplayPrimaries::Bt2020 => "bt.2020",
+    }
+}
+
+fn upscale_name(upscale: UpscaleFilter) -> &'static str {
+    match upscale {
+        UpscaleFilter::Bicubic => "bicubic",
+        UpscaleFilter::Lanczos => "lanczos",
+        UpscaleFilter::Spline => "spline36",
+    }
+}
+
+fn transfer_name(transfer: DisplayTransfer) -> &'static str {
+    match transfer {
+        DisplayTransfer::Srgb => "srgb",
+        DisplayTransfer::Gamma22 => "gamma2.2",
+        DisplayTransfer::Bt709 => "bt.1886",
+    }
+}
+
+impl PlaybackProfile {
+    /// The options of the profile, as `(name, value)` in the order they are written. The names
+    /// and values are the ones of mpv where it has the option.
+    pub fn options(&self) -> Vec<(&'static str, String)> {
+        let mut options = vec![
+            ("aid", self.audio_stream.to_string()),
+            ("sid", self.subtitle_stream.to_string()),
+            ("speed", self.playback_speed.to_string()),
+            // mpv delays the audio, this delays the video for an audio device playing late
+            (
+                "audio-delay",
+                (-self.audio_delay_ms as f64 / 1000.).to_string(),
+            ),
+            ("volume", (self.audio_volume * 100.).to_string()),
+            ("volume-gain", self.audio_gain_db.to_string()),
+            (
+                "loop-file",
+                if self.looping { "inf" } else { "no" }.to_string(),
+            ),
+        ];
+        if let Some(crop) = self.crop {
+            let (min, size) = (crop.min.round(), crop.size().round());
+            let crop = format!("{}x{}+{}+{}", size.x, size.y, min.x, min.y);
+            options.push(("video-crop", crop));
+        }
+        if let Some(denoise) = self.denoise {
+            options.push(("vf-add", format!("lavfi=[{}]", denoise.filter_spec())));
+        }
+        if let Some(interpolation) = self.interpolation {
+            options.push(("vf-add", format!("lavfi=[{}]", interpolation.filter_spec())));
+        }
+        if let Some(display_color) = self.display_color {
+            options.push((
+                "target-prim",
+                primaries_name(display_color.primaries).into(),
+            ));
+            options.push(("target-trc", transfer_name(display_color.transfer).into()));
+        }
+        if let Some(upscale) = self.upscale {
+            options.push(("scale", upscale_name(upscale).into()));
+        }
+        // mpv has no tint, nor a channel map independent of the layout
+        if self.tint != Color32::WHITE {
+            let [r, g, b, a] = self.tint.to_array();
+            options.push(("tint", format!("{r:02x}{g:02x}{b:02x}{a:02x}")));
+        }
+        #[cfg(feature = "audio")]
+        if let Some(channel_map) = self.channel_map.as_ref() {
+            let rows = channel_map.matrix.iter().map(|row| {
+                let gains = row.iter().map(f32::to_string);
+                gains.collect::<Vec<_>>().join(",")
+            });
+            options.push(("channel-map", rows.collect::<Vec<_>>().join(";")));
+        }
+        options
+    }
+
+    /// The profile as an mpv config, one `name=value` per line.
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+        for (name, value) in self.options() {
+            let _ = writeln!(config, "{name}={value}");
+        }
+        config
+    }
+
+    /// The profile as mpv command line arguments, `--name=value` each.
+    pub fn to_args(&self) -> Vec<String> {
+        let options = self.options().into_iter();
+        options
+            .map(|(name, value)| format!("--{name}={value}"))
+            .collect()
+    }
+
+    /// Read a profile written by [`PlaybackProfile::to_config`] or [`PlaybackProfile::to_args`]
+    /// (joined with spaces). Options it doesn't have keep their default, `#` starts a comment and
+    /// unknown options are an error, so nothing of the profile is dropped silently.
+    pub fn parse(profile: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut primaries = None;
+        let mut transfer = None;
+        let options = profile.lines().flat_map(|line| {
+            line.split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+        });
+        for option in options {
+            let option = option.strip_prefix("--").unwrap_or(option);
+            let (name, value) = option
+                .split_once('=')
+                .ok_or_else(|| anyhow!("option {option} has no value"))?;
+            let invalid = || format!("invalid value {value} of {name}");
+            match name {
+                "aid" => parsed.audio_stream = value.parse().with_context(invalid)?,
+                "sid" => parsed.subtitle_stream = value.parse().with_context(invalid)?,
+                "speed" => parsed.playback_speed = value.parse().with_context(invalid)?,
+                "audio-delay" => {
+                    let seconds: f64 = value.parse().with_context(invalid)?;
+                    parsed.audio_delay_ms = (-seconds * 1000.).round() as i64;
+                }
+                "volume" => {
+                    parsed.audio_volume = value.parse::<f32>().with_context(invalid)? / 100.;
+                }
+                "volume-gain" => parsed.audio_gain_db = value.parse().with_context(invalid)?,
+                "loop-file" => {
+                    parsed.looping = match value {
+                        "inf" | "yes" => true,
+                        "no" => false,
+                        _ => bail!(invalid()),
+                    }
+                }
+                "video-crop" => parsed.crop = Some(parse_crop(value).with_context(invalid)?),
+                "vf-add" => {
+                    let spec = value
+                        .strip_prefix("lavfi=[")
+                        .and_then(|spec| spec.strip_suffix(']'))
+                        .with_context(invalid)?;
+                    if let Some(denoise) = DENOISE.iter().find(|d| d.filter_spec() == spec) {
+                        parsed.denoise = Some(*denoise);
+                    } else if let Some(interpolation) = parse_interpolation(spec) {
+                        parsed.interpolation = Some(interpolation);
+                    } else {
+                        bail!("unknown filter {spec}");
+                    }
+                }
+                "target-prim" => {
+                    use DisplayPrimaries::*;
+                    let all = [Srgb, DisplayP3, DciP3, Bt2020];
+                    let found = all.into_iter().find(|p| primaries_name(*p) == value);
+                    primaries = Some(found.with_context(invalid)?);
+                }
+                "target-trc" => {
+                    use DisplayTransfer::*;
+                    let all = [Srgb, Gamma22, Bt709];
+                    let found = all.into_iter().find(|t| transfer_name(*t) == value);
+                    transfer = Some(found.with_context(invalid)?);
+                }
+                "scale" => {
+                    use UpscaleFilter::*;
+                    let all = [Bicubic, Lanczos, Spline];
+                    let found = all.into_iter().find(|u| upscale_name(*u) == value);
+                    parsed.upscale = Some(found.with_context(invalid)?);
+                }
+                "tint" => parsed.tint = parse_tint(value).with_context(invalid)?,
+                #[cfg(feature = "audio")]
+                "channel-map" => {
+                    let matrix = value
+                        .split(';')
+                        .map(|row| row.split(',').map(str::parse).collect())
+                        .collect::<Result<_, _>>()
+                        .with_context(invalid)?;
+                    parsed.channel_map = Some(ChannelMap::new(matrix));
+                }
+                _ => bail!("unknown option {name}"),
+            }
+        }
+        if primaries.is_some() || transfer.is_some() {
+            parsed.display_color = Some(DisplayColor {
+                primaries: primaries.unwrap_or_default(),
+                transfer: transfer.unwrap_or_default(),
+            });
+        }
+        Ok(parsed)
+    }
+}
+
+// `WxH+X+Y`
+fn parse_crop(value: &str) -> Option<Rect> {
+    let (size, offset) = value.split_once('+')?;
+    let (width, height) = size.split_once('x')?;
+    let (x, y) = offset.split_once('+')?;
+    let min = pos2(x.parse().ok()?, y.parse().ok()?);
+    Some(Rect::from_min_size(
+        min,
+        vec2(width.parse().ok()?, height.parse().ok()?),
+    ))
+}
+
+// `RRGGBBAA`, premultiplied like `Color32`
+fn parse_tint(value: &str) -> Option<Color32> {
+    let rgba = u32::from_str_radix(value, 16)
+        .ok()
+        .filter(|_| value.len() == 8)?;
+    let [r, g, b, a] = rgba.to_be_bytes();
+    Some(Color32::from_rgba_premultiplied(r, g, b, a))
+}
+
+// `minterpolate=fps=60:mi_mode=mci`
+fn parse_interpolation(spec: &str) -> Option<Interpolation> {
+    let options = spec.strip_prefix("minterpolate=")?;
+    let (fps, mode) = options.split_once(':')?;
+    let mode = match mode.strip_prefix("mi_mode=")? {
+        "blend" => InterpolationMode::Blend,
+        "mci" => InterpolationMode::MotionCompensated,
+        _ => return None,
+    };
+    Some(Interpolation {
+        fps: fps.strip_prefix("fps=")?.parse().ok()?,
+        mode,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trips() {
+        let profiles = [
+            PlaybackProfile::default(),
+            PlaybackProfile {
+                audio_stream: 2,
+                subtitle_stream: 3,
+                playback_speed: 1.25,
+                audio_delay_ms: -120,
+                crop: Some(Rect::from_min_size(pos2(10., 20.), vec2(640., 360.))),
+                audio_volume: 0.75,
+                audio_gain_db: -3.5,
+                looping: true,
+                denoise: Some(DENOISE[4]),
+                interpolation: Some(Interpolation {
+                    fps: 120,
+                    mode: InterpolationMode::Blend,
+                }),
+                display_color: Some(DisplayColor {
+                    primaries: DisplayPrimaries::DisplayP3,
+                    transfer: DisplayTransfer::Gamma22,
+                }),
+                tint: Color32::from_rgba_premultiplied(200, 180, 160, 220),
+                upscale: Some(UpscaleFilter::Spline),
+                #[cfg(feature = "audio")]
+                channel_map: Some(ChannelMap::new(vec![vec![1., 0., 0.7], vec![0., 1., -0.5]])),
+            },
+        ];
+        for profile in profiles {
+            assert_eq!(
+                PlaybackProfile::parse(&profile.to_config()).unwrap(),
+                profile
+            );
+            let args = profile.to_args().join(" ");
+            assert_eq!(PlaybackProfile::parse(&args).unwrap(), profile);
+        }
+    }
+
+    #[test]
+    fn unknown_options_are_errors() {
+        for config in [
+            "speed",
+            "bogus=1",
+            "loop-file=sometimes",
+            "tint=fff",
+            "scale=nearest",
+        ] {
+            assert!(PlaybackProfile::parse(config).is_err(), "{config}");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use egui::Vec2;
+use std::collections::VecDeque;
+
+// the frame intervals measured, about two seconds at 60Hz
+const MAX_SAMPLES: usize = 120;
+// the samples needed before a rate is reported
+const MIN_SAMPLES: usize = 30;
+
+// the rates of common displays, measured rates near one are snapped to it
+const COMMON_RATES: [f32; 17] = [
+    23.976, 24., 25., 29.97, 30., 48., 50., 59.94, 60., 72., 75., 90., 100., 120., 144., 165., 240.,
+];
+
+/// How the frames of the video fit the refreshes of the display, see [`DisplayRefresh::pacing`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FramePacing {
+    /// Every frame is shown for `refreshes` refreshes, like 30fps at 60Hz. Motion is smooth.
+    Even {
+        /// The refreshes per frame.
+        refreshes: u32,
+    },
+    /// Frames are shown for the number of refreshes of `pattern` in turn, like the 3:2 pulldown of
+    /// 24fps at 60Hz. Pans have a slight, regular judder most viewers are used to.
+    Pulldown {
+        /// The refreshes of each frame of the repeating pattern.
+        pattern: Vec<u32>,
+    },
+    /// Only every `every`th frame is shown, like 60fps at 30Hz. Smooth, at the rate of the display.
+    Decimated {
+        /// One frame is shown out of this many.
+        every: u32,
+    },
+    /// The rates don't fit a short pattern, like 25fps at 60Hz: frames are shown for an uneven
+    /// number of refreshes or skipped unevenly, which judders visibly on pans. Setting the display
+    /// to a multiple of the frame rate or [`crate::Player::set_interpolation`] avoid it.
+    Uneven,
+}
+
+/// The refresh rate of the display a player is drawn on, and how the frames of the video fit it,
+/// see [`crate::Player::display_refresh`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayRefresh {
+    /// The refresh rate, in Hz.
+    pub hz: f32,
+    /// Whether the rate was measured from the intervals between frames of egui, rather than set
+    /// with [`crate::Player::set_display_refresh_rate`].
+    pub measured: bool,
+    /// The frame rate of the video.
+    pub video_fps: f64,
+    /// How the frames of the video fit the refreshes.
+    pub pacing: FramePacing,
+}
+
+impl DisplayRefresh {
+    pub(crate) fn new(hz: f32, measured: bool, video_fps: f64) -> Self {
+        Self {
+            hz,
+            measured,
+            video_fps,
+            pacing: pacing(hz as f64, video_fps),
+        }
+    }
+
+    /// Whether motion visibly judders, as the frames don't fit the refreshes evenly, see
+    /// [`FramePacing::Uneven`].
+    pub fn judder_risk(&self) -> bool {
+        self.pacing == FramePacing::Uneven
+    }
+
+    /// How much earlier than their time frames are presented with
+    /// [`crate::PlayerOptions::cadence_pacing`], in milliseconds. With a cadence, frames are put on
+    /// the refresh nearest their time, so timing noise doesn't break the cadence up.
+    pub(crate) fn pacing_lead_ms(&self) -> i64 {
+        match self.pacing {
+            FramePacing::Uneven => 0,
+            _ => (500. / self.hz).round() as i64,
+        }
+    }
+}
+
+// whether `value` is within half a percent of a whole number, which is returned
+fn near_whole(value: f64) -> Option<u32> {
+    let whole = value.round();
+    (whole >= 1. && (value - whole).abs() < whole * 0.005).then_some(whole as u32)
+}
+
+fn pacing(hz: f64, fps: f64) -> FramePacing {
+    if hz <= 0. || fps <= 0. {
+        return FramePacing::Uneven;
+    }
+    let refreshes_per_frame = hz / fps;
+    if let Some(refreshes) = near_whole(refreshes_per_frame) {
+        return FramePacing::Even { refreshes };
+    }
+    if refreshes_per_frame < 1. {
+        return match near_whole(fps / hz) {
+            Some(every) => FramePacing::Decimated { every },
+            None => FramePacing::Uneven,
+        };
+    }
+    // two frames over a whole number of refreshes, like 3:2, is the cadence of film on video
+    match near_whole(refreshes_per_frame * 2.) {
+        Some(refreshes) => {
+            let longer = refreshes.div_ceil(2);
+            FramePacing::Pulldown {
+                pattern: vec![longer, refreshes - longer],
+            }
+        }
+        None => FramePacing::Uneven,
+    }
+}
+
+/// Measures the refresh rate from the intervals between the frames egui draws while playing, which
+/// follow the refreshes with vsync. The measurement starts over on another monitor.
+#[derive(Default)]
+pub(crate) struct RefreshMeter {
+    intervals: VecDeque<f32>,
+    monitor_size: Option<Vec2>,
+}
+
+impl RefreshMeter {
+    /// Add the interval since the last frame, `dt` seconds, drawn on a monitor of `monitor_size`.
+    pub(crate) fn record(&mut self, dt: f32, monitor_size: Option<Vec2>) {
+        if monitor_size != self.monitor_size {
+            self.monitor_size = monitor_size;
+            self.intervals.clear();
+        }
+        // leave out stalls, like the app being in the background
+        if dt > 0. && dt < 0.1 {
+            if self.intervals.len() == MAX_SAMPLES {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(dt);
+        }
+    }
+
+    /// The measured refresh rate, `None` until enough frames were measured or without vsync.
+    pub(crate) fn hz(&self) -> Option<f32> {
+        if self.intervals.len() < MIN_SAMPLES {
+            return None;
+        }
+        // the median, as frames taking too long skip a refresh
+        let mut intervals = self.intervals.iter().copied().collect::<Vec<_>>();
+        intervals.sort_by(f32::total_cmp);
+        let hz = 1. / intervals[intervals.len() / 2];
+        if hz > 500. {
+            // no vsync, frames are drawn as fast as they can be
+            return None;
+        }
+        let common = COMMON_RATES
+            .iter()
+            .copied()
+            .min_by(|a, b| (a - hz).abs().total_cmp(&(b - hz).abs()))?;
+        Some(if (common - hz).abs() < common * 0.03 {
+            common
+        } else {
+            hz.round()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacing_of_common_rates() {
+        let cases = [
+            (60., 30., FramePacing::Even { refreshes: 2 }),
+            (60., 60., FramePacing::Even { refreshes: 1 }),
+            (59.94, 29.97, FramePacing::Even { refreshes: 2 }),
+            (
+                60.,
+                24.,
+                FramePacing::Pulldown {
+                    pattern: vec![3, 2],
+                },
+            ),
+            (
+                59.94,
+                23.976,
+                FramePacing::Pulldown {
+                    pattern: vec![3, 2],
+                },
+            ),
+            (60., 25., FramePacing::Uneven),
+            (30., 60., FramePacing::Decimated { every: 2 }),
+            (60., 0., FramePacing::Uneven),
+        ];
+        for (hz, fps, expected) in cases {
+            assert_eq!(pacing(hz, fps), expected, "{fps}fps at {hz}Hz");
+        }
+    }
+
+    #[test]
+    fn lead_only_with_a_cadence() {
+        assert_eq!(DisplayRefresh::new(60., true, 24.).pacing_lead_ms(), 8);
+        assert_eq!(DisplayRefresh::new(60., true, 25.).pacing_lead_ms(), 0);
+        assert!(DisplayRefresh::new(60., true, 25.).judder_risk());
+    }
+
+    #[test]
+    fn measured_rates_snap_to_common_ones() {
+        let mut meter = RefreshMeter::default();
+        for i in 0..MIN_SAMPLES {
+            assert_eq!(meter.hz(), None);
+            // jitter around 59.94Hz, and a stall that is left out
+            let dt = 1. / 59.94 + if i % 2 == 0 { 0.0002 } else { -0.0002 };
+            meter.record(dt, None);
+            meter.record(0.5, None);
+        }
+        assert_eq!(meter.hz(), Some(59.94));
+        // another monitor starts over
+        meter.record(1. / 144., Some(Vec2::new(2560., 1440.)));
+        assert_eq!(meter.hz(), None);
+    }
+}
@@ -0,0 +1,125 @@
+use ffmpeg::software::resampling::Context;
+use ffmpeg::{format, ChannelLayout};
+
+/// The library doing the resampling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResamplerEngine {
+    /// ffmpeg's own resampler. Always available.
+    #[default]
+    Swr,
+    /// The SoX resampler, with very low aliasing. Only available if ffmpeg was built with
+    /// `libsoxr`, otherwise adding audio fails.
+    Soxr,
+}
+
+/// How the quantization noise of the resampled audio is shaped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DitherMethod {
+    /// No dithering.
+    #[default]
+    None,
+    /// Rectangular (uniform) dither.
+    Rectangular,
+    /// Triangular dither.
+    Triangular,
+    /// Triangular dither with a high pass.
+    TriangularHighPass,
+    /// Lipshitz noise shaping.
+    Lipshitz,
+    /// Shibata noise shaping.
+    Shibata,
+    /// High Shibata noise shaping, for 48kHz and above.
+    HighShibata,
+}
+
+impl DitherMethod {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::None => "0",
+            Self::Rectangular => "rectangular",
+            Self::Triangular => "triangular",
+            Self::TriangularHighPass => "triangular_hp",
+            Self::Lipshitz => "lipshitz",
+            Self::Shibata => "shibata",
+            Self::HighShibata => "high_shibata",
+        }
+    }
+}
+
+/// Configures the resampler converting audio to the rate and format of the audio device, see
+/// [`crate::Player::set_resampler_options`]. The defaults are ffmpeg's, which favour speed; use
+/// [`ResamplerOptions::high_quality`] when downsampling high resolution masters.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResamplerOptions {
+    /// The resampling library.
+    pub engine: ResamplerEngine,
+    /// The length of the interpolation filter, ffmpeg's `filter_size` (32 by default). Only used
+    /// by [`ResamplerEngine::Swr`].
+    pub filter_size: Option<u32>,
+    /// The number of filter phases, as a power of two, ffmpeg's `phase_shift` (10 by default).
+    /// Only used by [`ResamplerEngine::Swr`].
+    pub phase_shift: Option<u32>,
+    /// Interpolate linearly between filter phases. Only used by [`ResamplerEngine::Swr`].
+    pub linear_interp: bool,
+    /// The cutoff frequency, relative to the Nyquist frequency of the lower rate (in `0.0..=1.0`).
+    pub cutoff: Option<f64>,
+    /// The dithering applied when reducing the bit depth.
+    pub dither: DitherMethod,
+}
+
+impl ResamplerOptions {
+    /// A long filter with interpolated phases and a cutoff close to Nyquist, at a higher cpu cost.
+    pub fn high_quality() -> Self {
+        Self {
+            engine: ResamplerEngine::Swr,
+            filter_size: Some(64),
+            phase_shift: Some(14),
+            linear_interp: true,
+            cutoff: Some(0.97),
+            dither: DitherMethod::TriangularHighPass,
+        }
+    }
+
+    fn to_dictionary(self) -> ffmpeg::Dictionary<'static> {
+        let mut dictionary = ffmpeg::Dictionary::new();
+        if self.engine == ResamplerEngine::Soxr {
+            dictionary.set("resampler", "soxr");
+        }
+        if let Some(filter_size) = self.filter_size {
+            dictionary.set("filter_size", &filter_size.to_string());
+        }
+        if let Some(phase_shift) = self.phase_shift {
+            dictionary.set("phase_shift", &phase_shift.to_string());
+        }
+        if self.linear_interp {
+            dictionary.set("linear_interp", "1");
+        }
+        if let Some(cutoff) = self.cutoff {
+            dictionary.set("cutoff", &cutoff.to_string());
+        }
+        if self.dither != DitherMethod::None {
+            dictionary.set("dither_method", self.dither.name());
+        }
+        dictionary
+    }
+}
+
+/// A resampler from the decoded audio to stereo audio in the device format.
+pub(crate) fn stereo_resampler(
+    src_format: format::Sample,
+    src_channel_layout: ChannelLayout,
+    src_rate: u32,
+    dst_format: format::Sample,
+    dst_rate: u32,
+    options: &ResamplerOptions,
+) -> Result<Context, ffmpeg::Error> {
+    Context::get_with2(
+        src_format,
+        src_channel_layout,
+        src_rate,
+        dst_format,
+        ChannelLayout::STEREO,
+        dst_rate,
+        options.to_dictionary(),
+    )
+}
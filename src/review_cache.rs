@@ -0,0 +1,95 @@
+use egui::ColorImage;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// How many recently displayed frames a [`crate::Player`] keeps, see [`crate::PlayerOptions::review_cache`].
+/// Frames are kept as the decoded image, 4 bytes per pixel, so a 1080p frame takes about 8 MB.
+/// The default keeps none.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewCacheSize {
+    /// Keep at most this many frames.
+    Frames(usize),
+    /// Keep frames until they take up this many megabytes.
+    Megabytes(usize),
+}
+
+impl Default for ReviewCacheSize {
+    fn default() -> Self {
+        Self::Frames(0)
+    }
+}
+
+struct CachedFrame {
+    time_ms: i64,
+    duration_ms: i64,
+    image: Arc<ColorImage>,
+}
+
+impl CachedFrame {
+    fn size_bytes(&self) -> usize {
+        self.image.pixels.len() * std::mem::size_of::<egui::Color32>()
+    }
+}
+
+/// A least recently used cache of displayed frames, so seeking back to them while paused doesn't
+/// need the decoders.
+#[derive(Default)]
+pub(crate) struct ReviewCache {
+    // least recently used first.
+    frames: VecDeque<CachedFrame>,
+    size_bytes: usize,
+}
+
+impl ReviewCache {
+    pub(crate) fn insert(
+        &mut self,
+        time_ms: i64,
+        duration_ms: i64,
+        image: Arc<ColorImage>,
+        size: ReviewCacheSize,
+    ) {
+        if let Some(index) = self.frames.iter().position(|f| f.time_ms == time_ms) {
+            self.remove(index);
+        }
+        let frame = CachedFrame {
+            time_ms,
+            duration_ms: duration_ms.max(1),
+            image,
+        };
+        self.size_bytes += frame.size_bytes();
+        self.frames.push_back(frame);
+        self.evict(size);
+    }
+
+    /// The frame shown at `time_ms`, marking it as recently used.
+    pub(crate) fn get(&mut self, time_ms: i64) -> Option<Arc<ColorImage>> {
+        let index = self
+            .frames
+            .iter()
+            .position(|f| (f.time_ms..f.time_ms + f.duration_ms).contains(&time_ms))?;
+        let frame = self.frames.remove(index)?;
+        let image = frame.image.clone();
+        self.frames.push_back(frame);
+        Some(image)
+    }
+
+    fn remove(&mut self, index: usize) {
+        if let Some(frame) = self.frames.remove(index) {
+            self.size_bytes -= frame.size_bytes();
+        }
+    }
+
+    /// Drop the least recently used frames until the cache fits in `size`.
+    pub(crate) fn evict(&mut self, size: ReviewCacheSize) {
+        loop {
+            let full = match size {
+                ReviewCacheSize::Frames(frames) => self.frames.len() > frames,
+                ReviewCacheSize::Megabytes(megabytes) => self.size_bytes > megabytes * 1024 * 1024,
+            };
+            if !full {
+                break;
+            }
+            self.remove(0);
+        }
+    }
+}
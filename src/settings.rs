@@ -0,0 +1,62 @@
+use crate::{timestamp_to_millisec, AV_TIME_BASE_RATIONAL};
+use egui::Rect;
+use ffmpeg::format::context::input::Input;
+use ffmpeg::media::Type;
+use std::fmt::Write;
+
+/// The settings chosen for a title, remembered by a [`SettingsStore`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TitleSettings {
+    /// The selected audio stream, counting from `1` in the order of the input.
+    pub audio_stream: usize,
+    /// The selected subtitle stream, counting from `1` in the order of the input.
+    pub subtitle_stream: usize,
+    /// See [`crate::Player::set_playback_speed`].
+    pub playback_speed: f32,
+    /// See [`crate::Player::set_crop`].
+    pub crop: Option<Rect>,
+}
+
+/// Persists [`TitleSettings`] between sessions, see [`crate::Player::set_settings_store`]. Settings
+/// are saved under the content fingerprint of the input ([`crate::Player::fingerprint`]) and under
+/// the layout of its streams ([`crate::Player::layout_fingerprint`]). An input without settings of
+/// its own starts with the ones of its layout, so the episodes of a series usually share them.
+pub trait SettingsStore: Send {
+    /// The settings saved for `fingerprint`, if any.
+    fn load(&self, fingerprint: &str) -> Option<TitleSettings>;
+    /// Save `settings` for `fingerprint`, called whenever one of them changes.
+    fn save(&mut self, fingerprint: &str, settings: &TitleSettings);
+}
+
+/// Identify `input`, opened from `input_path`, by the layout of its streams, its duration and the
+/// size of its file.
+pub(crate) fn fingerprint(input: &Input, input_path: &str) -> String {
+    let mut fingerprint = layout_fingerprint(input);
+    let duration_ms = timestamp_to_millisec(input.duration(), AV_TIME_BASE_RATIONAL);
+    let _ = write!(fingerprint, ";duration:{duration_ms}");
+    if let Ok(metadata) = std::fs::metadata(input_path) {
+        let _ = write!(fingerprint, ";size:{}", metadata.len());
+    }
+    fingerprint
+}
+
+/// Describe the streams of `input` (their kind, codec and language), not its contents.
+pub(crate) fn layout_fingerprint(input: &Input) -> String {
+    let streams = input.streams().map(|stream| {
+        let parameters = stream.parameters();
+        let kind = match parameters.medium() {
+            Type::Video => "video",
+            Type::Audio => "audio",
+            Type::Subtitle => "subtitle",
+            Type::Attachment => "attachment",
+            _ => "data",
+        };
+        let language = stream
+            .metadata()
+            .get("language")
+            .unwrap_or("und")
+            .to_string();
+        format!("{kind}:{}:{language}", parameters.id().name())
+    });
+    streams.collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,138 @@
+use egui::Key;
+use std::time::Instant;
+
+/// J/K/L shuttle controls for reviewing footage, see [`crate::PlayerOptions::shuttle`]. Each press
+/// of the forward or reverse key plays faster in that direction, pressing the other one turns
+/// around at the first speed, and the pause key stops. Holding the pause key along with the
+/// forward or reverse key jogs slowly while they are held. The keys work while the pointer is on
+/// the video and no text field has the keyboard focus, [`crate::Player::shuttle_forward`] and the
+/// other methods drive it from the app.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shuttle {
+    /// Plays backwards, faster with each press.
+    pub reverse_key: Key,
+    /// Stops shuttling, pausing playback.
+    pub pause_key: Key,
+    /// Plays forwards, faster with each press.
+    pub forward_key: Key,
+    /// The speeds the presses step through, in each direction.
+    pub speeds: Vec<f32>,
+    /// The speed of jogging with the pause key held.
+    pub jog_speed: f32,
+    /// Audio plays along, time stretched, up to this speed. Faster shuttling is silent, so the
+    /// audio doesn't hold back the decoding of the frames. Playing backwards is always silent.
+    pub max_audible_speed: f32,
+}
+
+impl Default for Shuttle {
+    fn default() -> Self {
+        Self {
+            reverse_key: Key::J,
+            pause_key: Key::K,
+            forward_key: Key::L,
+            speeds: vec![1., 2., 4., 8., 16.],
+            jog_speed: 0.25,
+            max_audible_speed: 2.,
+        }
+    }
+}
+
+/// Where the shuttle of a player is.
+#[derive(Default)]
+pub(crate) struct ShuttleState {
+    // the number of presses in the direction of its sign, `0` if not shuttling
+    pub(crate) level: i32,
+    // the direction jogged in while the pause key is held, `0` if not jogging
+    pub(crate) jog: i32,
+    // the playback speed before shuttling started, restored once it stops
+    pub(crate) speed_before: Option<f32>,
+    // when playing backwards last seeked
+    pub(crate) last_reverse_step: Option<Instant>,
+}
+
+impl ShuttleState {
+    pub(crate) fn is_active(&self) -> bool {
+        self.level != 0 || self.jog != 0
+    }
+
+    /// The speed shuttled at, negative backwards.
+    pub(crate) fn speed(&self, shuttle: &Shuttle) -> f32 {
+        if self.jog != 0 {
+            return self.jog as f32 * shuttle.jog_speed;
+        }
+        let Some(index) = (self.level.unsigned_abs() as usize).checked_sub(1) else {
+            return 0.;
+        };
+        let speed = shuttle
+            .speeds
+            .get(index.min(shuttle.speeds.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or(1.);
+        speed * self.level.signum() as f32
+    }
+
+    /// Press the key of `direction`, `1` forwards and `-1` backwards.
+    pub(crate) fn press(&mut self, direction: i32, shuttle: &Shuttle) {
+        self.jog = 0;
+        self.level = if self.level.signum() == direction {
+            (self.level + direction)
+                .clamp(-(shuttle.speeds.len() as i32), shuttle.speeds.len() as i32)
+        } else {
+            direction
+        };
+    }
+
+    /// Where playing backwards seeks to at `now` from `current_ms`: back by what the shuttle
+    /// covered since the last step. `None` on the first step, which only starts the timing.
+    pub(crate) fn reverse_step(
+        &mut self,
+        now: Instant,
+        current_ms: i64,
+        shuttle: &Shuttle,
+    ) -> Option<i64> {
+        let last_step = self.last_reverse_step.replace(now)?;
+        let step_ms = (now - last_step).as_secs_f32() * 1000. * -self.speed(shuttle);
+        Some(current_ms - step_ms as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn presses_step_through_the_speeds() {
+        let shuttle = Shuttle::default();
+        let mut state = ShuttleState::default();
+        // forward, forward, forward, reverse, reverse, then past the fastest speed
+        let presses = [1, 1, 1, -1, -1, -1, -1, -1, -1];
+        let speeds = [1., 2., 4., -1., -2., -4., -8., -16., -16.];
+        for (direction, speed) in presses.into_iter().zip(speeds) {
+            state.press(direction, &shuttle);
+            assert_eq!(state.speed(&shuttle), speed, "after pressing {direction}");
+        }
+        state.jog = 1;
+        assert_eq!(state.speed(&shuttle), shuttle.jog_speed);
+        state.press(1, &shuttle);
+        assert_eq!((state.jog, state.speed(&shuttle)), (0, 1.));
+    }
+
+    #[test]
+    fn reverse_steps_cover_the_elapsed_time() {
+        let shuttle = Shuttle::default();
+        let mut state = ShuttleState::default();
+        state.press(-1, &shuttle);
+        state.press(-1, &shuttle);
+        let start = Instant::now();
+        assert_eq!(state.reverse_step(start, 10_000, &shuttle), None);
+        // 100ms at 2x backwards
+        let next = start + Duration::from_millis(100);
+        assert_eq!(state.reverse_step(next, 10_000, &shuttle), Some(9_800));
+        // jogging backwards at a quarter of the speed
+        state.level = 0;
+        state.jog = -1;
+        let next = next + Duration::from_millis(400);
+        assert_eq!(state.reverse_step(next, 9_800, &shuttle), Some(9_700));
+    }
+}
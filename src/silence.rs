@@ -0,0 +1,130 @@
+use crate::TimeRange;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Jumps over the silent parts of the audio while playing, like the pauses of a lecture or a
+/// podcast, see [`crate::Player::set_skip_silence`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkipSilence {
+    /// The level below which audio counts as silent, in dBFS.
+    pub threshold_db: f32,
+    /// How long audio has to stay silent to be skipped, in milliseconds. Shorter pauses are kept,
+    /// so speech keeps its rhythm.
+    pub min_duration_ms: i64,
+    /// How much of each skipped silence is still played before and after the jump, in
+    /// milliseconds, so words aren't cut off.
+    pub padding_ms: i64,
+}
+
+impl Default for SkipSilence {
+    fn default() -> Self {
+        Self {
+            threshold_db: -45.,
+            min_duration_ms: 800,
+            padding_ms: 200,
+        }
+    }
+}
+
+impl SkipSilence {
+    /// Skip silences below `threshold_db` lasting at least `min_duration_ms`.
+    pub fn new(threshold_db: f32, min_duration_ms: i64) -> Self {
+        Self {
+            threshold_db,
+            min_duration_ms,
+            ..Default::default()
+        }
+    }
+
+    /// Set [`SkipSilence::padding_ms`].
+    pub fn with_padding_ms(mut self, padding_ms: i64) -> Self {
+        self.padding_ms = padding_ms;
+        self
+    }
+
+    // where playback at `time_ms` jumps to for the silent `span`, if it is worth a seek
+    pub(crate) fn jump_target(&self, span: &TimeRange, time_ms: i64) -> Option<i64> {
+        let target_ms = span.end_ms - self.padding_ms;
+        (span.end_ms - span.start_ms >= self.min_duration_ms
+            && time_ms >= span.start_ms + self.padding_ms
+            && target_ms - time_ms >= MIN_JUMP_MS)
+            .then_some(target_ms)
+    }
+}
+
+// jumps shorter than this cost more in seeking than they save
+const MIN_JUMP_MS: i64 = 250;
+
+/// The silent spans found in the decoded audio, ahead of playback. The last one grows while
+/// the audio stays silent.
+pub(crate) type SilentSpans = Arc<Mutex<VecDeque<TimeRange>>>;
+
+/// Finds the silent spans of the audio as it is decoded.
+pub(crate) struct SilenceDetector {
+    threshold_db: f32,
+    spans: SilentSpans,
+    // whether the last span is still growing
+    in_silence: bool,
+}
+
+impl SilenceDetector {
+    pub(crate) fn new(options: &SkipSilence, spans: SilentSpans) -> Self {
+        Self {
+            threshold_db: options.threshold_db,
+            spans,
+            in_silence: false,
+        }
+    }
+
+    /// Forget the span being found, as the audio after a seek doesn't continue it.
+    pub(crate) fn reset(&mut self) {
+        self.in_silence = false;
+        self.spans.lock().clear();
+    }
+
+    pub(crate) fn on_audio(&mut self, samples: &[f32], start_ms: i64, duration_ms: i64) {
+        let mean_square = samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32;
+        let silent = 10. * mean_square.max(1e-12).log10() < self.threshold_db;
+        if !silent {
+            self.in_silence = false;
+            return;
+        }
+        let end_ms = start_ms + duration_ms;
+        let mut spans = self.spans.lock();
+        match spans.back_mut() {
+            Some(span) if self.in_silence => span.end_ms = end_ms,
+            _ => spans.push_back(TimeRange::new(start_ms, end_ms).with_label("silence")),
+        }
+        self.in_silence = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_silences_are_jumped_over() {
+        let skip = SkipSilence::default();
+        let silence = TimeRange::new(1000, 3000);
+        assert_eq!(skip.jump_target(&silence, 1200), Some(2800));
+        // still in the padding, or too close to the end to be worth a seek
+        assert_eq!(skip.jump_target(&silence, 1100), None);
+        assert_eq!(skip.jump_target(&silence, 2600), None);
+        assert_eq!(skip.jump_target(&TimeRange::new(1000, 1700), 1200), None);
+    }
+
+    #[test]
+    fn silent_audio_extends_the_span() {
+        let spans = SilentSpans::default();
+        let mut detector = SilenceDetector::new(&SkipSilence::default(), spans.clone());
+        for (i, level) in [0.5, 0., 0.001, 0.5, 0.].into_iter().enumerate() {
+            detector.on_audio(&[level; 480], i as i64 * 10, 10);
+        }
+        let spans = spans.lock();
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].start_ms, spans[0].end_ms), (10, 30));
+        assert_eq!((spans[1].start_ms, spans[1].end_ms), (40, 50));
+    }
+}
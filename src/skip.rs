@@ -0,0 +1,51 @@
+/// A span of stream time that can be skipped, like an intro or the credits, see
+/// [`crate::Player::set_skip_ranges`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    /// Where the range starts, in milliseconds.
+    pub start_ms: i64,
+    /// Where the range ends (exclusive) and playback continues after skipping it, in milliseconds.
+    pub end_ms: i64,
+    /// What the range is, shown on the skip button as "Skip {label}".
+    pub label: Option<String>,
+}
+
+impl TimeRange {
+    /// The range from `start_ms` up to `end_ms`.
+    pub fn new(start_ms: i64, end_ms: i64) -> Self {
+        Self {
+            start_ms,
+            end_ms,
+            label: None,
+        }
+    }
+
+    /// Set [`TimeRange::label`].
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Whether `time_ms` is inside the range.
+    pub fn contains(&self, time_ms: i64) -> bool {
+        (self.start_ms..self.end_ms).contains(&time_ms)
+    }
+
+    pub(crate) fn button_text(&self) -> String {
+        match &self.label {
+            Some(label) => format!("Skip {label}"),
+            None => "Skip".to_string(),
+        }
+    }
+}
+
+/// What happens when playback enters one of the skip ranges of a [`crate::Player`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SkipMode {
+    /// Seek to the end of the range right away.
+    Auto,
+    /// Show a skip button over the video while inside the range, see
+    /// [`crate::Player::render_skip_button`].
+    #[default]
+    Prompt,
+}
@@ -0,0 +1,122 @@
+use parking_lot::Mutex;
+
+// the players inhibiting sleep, and the inhibition of the system they share
+static INHIBITION: Mutex<(usize, Option<SystemInhibition>)> = Mutex::new((0, None));
+
+/// Keeps the system from sleeping while a player plays, see
+/// [`crate::PlayerOptions::inhibit_sleep`]. Players share one inhibition of the system, released
+/// once none holds it any more.
+pub(crate) struct SleepInhibitor(());
+
+impl SleepInhibitor {
+    pub(crate) fn acquire() -> Self {
+        let mut inhibition = INHIBITION.lock();
+        inhibition.0 += 1;
+        if inhibition.1.is_none() {
+            inhibition.1 = SystemInhibition::start();
+        }
+        Self(())
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        let mut inhibition = INHIBITION.lock();
+        inhibition.0 -= 1;
+        if inhibition.0 == 0 {
+            // dropping it releases the system
+            inhibition.1 = None;
+        }
+    }
+}
+
+// logind and caffeinate hold the inhibition as long as their child process runs. on linux the
+// child reads the pipe kept here, so it exits when this process does, even if it crashes
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+struct SystemInhibition(std::process::Child);
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl SystemInhibition {
+    fn start() -> Option<Self> {
+        use std::process::{Command, Stdio};
+        #[cfg(target_os = "linux")]
+        let mut command = {
+            let mut command = Command::new("systemd-inhibit");
+            command.args([
+                "--what=idle:sleep",
+                "--who=egui-video",
+                "--why=playing video",
+                "--mode=block",
+                "cat",
+            ]);
+            command.stdin(Stdio::piped());
+            command
+        };
+        #[cfg(target_os = "macos")]
+        let mut command = {
+            let mut command = Command::new("caffeinate");
+            // until this process exits, should the child outlive it
+            command.args(["-d", "-i", "-w", &std::process::id().to_string()]);
+            command.stdin(Stdio::null());
+            command
+        };
+        // without the tool, playback goes on without the inhibition
+        command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+            .map(Self)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+impl Drop for SystemInhibition {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    pub(super) const ES_CONTINUOUS: u32 = 0x8000_0000;
+    pub(super) const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    pub(super) const ES_DISPLAY_REQUIRED: u32 = 0x0000_0002;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub(super) fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+}
+
+// the execution state belongs to the thread setting it, which is the ui thread players are
+// processed on
+#[cfg(target_os = "windows")]
+struct SystemInhibition;
+
+#[cfg(target_os = "windows")]
+impl SystemInhibition {
+    fn start() -> Option<Self> {
+        use windows::*;
+        let flags = ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED;
+        (unsafe { SetThreadExecutionState(flags) } != 0).then_some(Self)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for SystemInhibition {
+    fn drop(&mut self) {
+        unsafe { windows::SetThreadExecutionState(windows::ES_CONTINUOUS) };
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+struct SystemInhibition;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+impl SystemInhibition {
+    fn start() -> Option<Self> {
+        None
+    }
+}
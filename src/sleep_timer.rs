@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug)]
+enum Deadline {
+    // a stream time, so seeks and speed changes move the deadline with the stream
+    StreamTime(i64),
+    WallClock(Instant),
+}
+
+/// Pauses playback at a deadline, see [`crate::Player::stop_at`] and [`crate::Player::stop_after`].
+pub(crate) struct SleepTimer {
+    deadline: Deadline,
+    fade_out: Duration,
+    // the stream time playback was last seen at, cleared by seeks so jumping over the deadline
+    // doesn't count as playing through it
+    last_ms: Option<i64>,
+}
+
+impl SleepTimer {
+    pub(crate) fn at_stream_time(time_ms: i64, fade_out: Duration) -> Self {
+        Self {
+            deadline: Deadline::StreamTime(time_ms),
+            fade_out,
+            last_ms: None,
+        }
+    }
+
+    pub(crate) fn after(duration: Duration, fade_out: Duration) -> Self {
+        Self {
+            deadline: Deadline::WallClock(Instant::now() + duration),
+            fade_out,
+            last_ms: None,
+        }
+    }
+
+    pub(crate) fn on_seek(&mut self) {
+        self.last_ms = None;
+    }
+
+    /// The wall time left until the deadline, playing at `speed` from `time_ms`. `None` once a
+    /// stream time deadline has been passed without playing into it.
+    pub(crate) fn remaining(&self, time_ms: i64, speed: f32) -> Option<Duration> {
+        match self.deadline {
+            Deadline::StreamTime(deadline_ms) if time_ms >= deadline_ms => self
+                .last_ms
+                .is_some_and(|last_ms| last_ms < deadline_ms)
+                .then_some(Duration::ZERO),
+            Deadline::StreamTime(deadline_ms) => {
+                let remaining_ms = (deadline_ms - time_ms) as f32 / speed.max(f32::EPSILON);
+                Some(Duration::from_secs_f32(remaining_ms / 1000.))
+            }
+            Deadline::WallClock(deadline) => {
+                Some(deadline.saturating_duration_since(Instant::now()))
+            }
+        }
+    }
+
+    /// Check the timer while playing at `time_ms`, returning the remaining time.
+    pub(crate) fn update(&mut self, time_ms: i64, speed: f32) -> Option<Duration> {
+        let remaining = self.remaining(time_ms, speed);
+        self.last_ms = Some(time_ms);
+        remaining
+    }
+
+    /// The volume during the fade out, in `0.0..=1.0`.
+    pub(crate) fn fade_gain(&self, remaining: Duration) -> f32 {
+        if self.fade_out.is_zero() {
+            return 1.;
+        }
+        (remaining.as_secs_f32() / self.fade_out.as_secs_f32()).min(1.)
+    }
+}
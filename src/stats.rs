@@ -0,0 +1,262 @@
+use crate::DisplayRefresh;
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// the timeline keeps the most recent events, so a long session doesn't grow without bound
+const MAX_TIMELINE_EVENTS: usize = 1000;
+// shorter stalls are the decoder catching up between two frames, not buffering
+const MIN_BUFFERING: Duration = Duration::from_millis(100);
+
+/// What happened during a playback session, see [`PlaybackStats::timeline`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionEventKind {
+    /// Playback waited for frames, as none were decoded in time.
+    Buffering {
+        /// How long it waited, in milliseconds.
+        duration_ms: u64,
+    },
+    /// Frames were decoded too late and skipped without being shown.
+    FramesDropped {
+        /// How many frames were skipped.
+        count: u64,
+    },
+    /// The video switched between the input and its proxy, see [`crate::Player::use_proxy`]. This
+    /// is the only switch of the bitrate the player makes, as it doesn't do adaptive streaming.
+    SourceSwitched {
+        /// Whether the proxy plays now.
+        proxy: bool,
+    },
+    /// A packet couldn't be decoded or a frame came out damaged, see
+    /// [`crate::PlayerEvent::DecodeError`].
+    Error {
+        /// The index of the stream in the input.
+        stream_index: usize,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+/// An entry of [`PlaybackStats::timeline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionEvent {
+    /// When it happened, since the session started.
+    pub at: Duration,
+    /// The stream time it happened at, in milliseconds.
+    pub elapsed_ms: i64,
+    /// What happened.
+    pub kind: SessionEventKind,
+}
+
+/// The quality of experience of a playback session (the lifetime of a [`crate::Player`]), for
+/// telemetry, see [`crate::Player::playback_stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaybackStats {
+    /// When the session started.
+    pub started: SystemTime,
+    /// How long the session has lasted.
+    pub duration: Duration,
+    /// How many frames were shown.
+    pub frames_presented: u64,
+    /// How many frames were skipped, as they were decoded too late.
+    pub frames_dropped: u64,
+    /// How often playback waited for frames.
+    pub buffering_count: u64,
+    /// How long playback waited for frames in total.
+    pub buffering_duration: Duration,
+    /// How many decode errors there were.
+    pub errors: u64,
+    /// The buffering, dropped frames, source switches and errors, oldest first. Only the last
+    /// 1000 are kept, the counts above cover the whole session.
+    pub timeline: Vec<SessionEvent>,
+    /// The refresh rate of the display and how the video fits it, see
+    /// [`crate::Player::display_refresh`].
+    pub display_refresh: Option<DisplayRefresh>,
+}
+
+impl PlaybackStats {
+    /// The stats as a JSON object, the durations in milliseconds and `started` in milliseconds
+    /// since the unix epoch, for shipping them to a telemetry backend.
+    pub fn to_json(&self) -> String {
+        let started_ms = self
+            .started
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let (display_hz, judder_risk) = match &self.display_refresh {
+            Some(refresh) => (refresh.hz.to_string(), refresh.judder_risk().to_string()),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let mut json = format!(
+            "{{\"started_ms\":{started_ms},\"duration_ms\":{},\"frames_presented\":{},\
+             \"frames_dropped\":{},\"buffering_count\":{},\"buffering_ms\":{},\"errors\":{},\
+             \"display_hz\":{display_hz},\"judder_risk\":{judder_risk},\"timeline\":[",
+            self.duration.as_millis(),
+            self.frames_presented,
+            self.frames_dropped,
+            self.buffering_count,
+            self.buffering_duration.as_millis(),
+            self.errors,
+        );
+        for (i, event) in self.timeline.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"at_ms\":{},\"elapsed_ms\":{},",
+                event.at.as_millis(),
+                event.elapsed_ms
+            );
+            let _ = match &event.kind {
+                SessionEventKind::Buffering { duration_ms } => {
+                    write!(
+                        json,
+                        "\"type\":\"buffering\",\"duration_ms\":{duration_ms}}}"
+                    )
+                }
+                SessionEventKind::FramesDropped { count } => {
+                    write!(json, "\"type\":\"frames_dropped\",\"count\":{count}}}")
+                }
+                SessionEventKind::SourceSwitched { proxy } => {
+                    write!(json, "\"type\":\"source_switched\",\"proxy\":{proxy}}}")
+                }
+                SessionEventKind::Error {
+                    stream_index,
+                    message,
+                } => write!(
+                    json,
+                    "\"type\":\"error\",\"stream_index\":{stream_index},\"message\":{}}}",
+                    json_string(message)
+                ),
+            };
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Collects the [`PlaybackStats`] of a player. Shared with the decoders, which report the errors.
+pub(crate) type SessionStats = Arc<Mutex<StatsRecorder>>;
+
+pub(crate) struct StatsRecorder {
+    started: Instant,
+    stats: PlaybackStats,
+    // when playback started waiting for frames, and the stream time it waited at
+    buffering_since: Option<(Instant, i64)>,
+}
+
+impl StatsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            stats: PlaybackStats {
+                started: SystemTime::now(),
+                duration: Duration::ZERO,
+                frames_presented: 0,
+                frames_dropped: 0,
+                buffering_count: 0,
+                buffering_duration: Duration::ZERO,
+                errors: 0,
+                timeline: Vec::new(),
+                display_refresh: None,
+            },
+            buffering_since: None,
+        }
+    }
+
+    fn push(&mut self, elapsed_ms: i64, kind: SessionEventKind) {
+        if self.stats.timeline.len() >= MAX_TIMELINE_EVENTS {
+            self.stats.timeline.remove(0);
+        }
+        self.stats.timeline.push(SessionEvent {
+            at: self.started.elapsed(),
+            elapsed_ms,
+            kind,
+        });
+    }
+
+    pub(crate) fn frame_presented(&mut self) {
+        self.stats.frames_presented += 1;
+    }
+
+    pub(crate) fn frames_dropped(&mut self, elapsed_ms: i64, count: u64) {
+        self.stats.frames_dropped += count;
+        self.push(elapsed_ms, SessionEventKind::FramesDropped { count });
+    }
+
+    pub(crate) fn source_switched(&mut self, elapsed_ms: i64, proxy: bool) {
+        self.push(elapsed_ms, SessionEventKind::SourceSwitched { proxy });
+    }
+
+    pub(crate) fn error(&mut self, stream_index: usize, elapsed_ms: i64, message: String) {
+        self.stats.errors += 1;
+        self.push(
+            elapsed_ms,
+            SessionEventKind::Error {
+                stream_index,
+                message,
+            },
+        );
+    }
+
+    /// Track whether playback at `elapsed_ms` is waiting for frames, recording the wait once it ends.
+    pub(crate) fn set_buffering(&mut self, buffering: bool, elapsed_ms: i64) {
+        match (buffering, self.buffering_since) {
+            (true, None) => self.buffering_since = Some((Instant::now(), elapsed_ms)),
+            (false, Some((since, since_ms))) => {
+                self.buffering_since = None;
+                let duration = since.elapsed();
+                if duration >= MIN_BUFFERING {
+                    self.stats.buffering_count += 1;
+                    self.stats.buffering_duration += duration;
+                    let duration_ms = duration.as_millis() as u64;
+                    self.push(since_ms, SessionEventKind::Buffering { duration_ms });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> PlaybackStats {
+        PlaybackStats {
+            duration: self.started.elapsed(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages_are_escaped() {
+        assert_eq!(
+            json_string("can't open \"C:\\in.mp4\"\n"),
+            r#""can't open \"C:\\in.mp4\"\n""#
+        );
+        assert_eq!(json_string("\u{1}ü"), r#""\u0001ü""#);
+    }
+}
@@ -1,5 +1,5 @@
-use anyhow::{anyhow, bail, Context, Result};
-use egui::{Align2, Color32, Pos2};
+use anyhow::{anyhow, Context, Result};
+use egui::{vec2, Align2, Color32, Pos2, Vec2};
 use nom::branch::alt;
 use nom::bytes::complete::{is_not, tag, take_till, take_until, take_while_m_n};
 use nom::character::complete::{char, digit0, digit1};
@@ -10,7 +10,7 @@ use nom::number::complete::double;
 use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::{AsChar, IResult};
 
-use super::{FadeEffect, Subtitle, SubtitleField};
+use super::{FadeEffect, Subtitle, SubtitleField, WrapStyle};
 
 fn num_list(i: &str) -> IResult<&str, Vec<f64>> {
     delimited(char('('), separated_list0(char(','), double), char(')'))(i)
@@ -54,23 +54,45 @@ fn t(i: &str) -> IResult<&str, SubtitleField> {
 fn an(i: &str) -> IResult<&str, SubtitleField> {
     preceded(
         tag(r"\an"),
-        map_res(digit1, |s: &str| match s.parse::<i64>() {
-            Ok(1) => Ok(SubtitleField::Alignment(Align2::LEFT_BOTTOM)),
-            Ok(2) => Ok(SubtitleField::Alignment(Align2::CENTER_BOTTOM)),
-            Ok(3) => Ok(SubtitleField::Alignment(Align2::RIGHT_BOTTOM)),
-
-            Ok(4) => Ok(SubtitleField::Alignment(Align2::LEFT_CENTER)),
-            Ok(5) => Ok(SubtitleField::Alignment(Align2::CENTER_CENTER)),
-            Ok(6) => Ok(SubtitleField::Alignment(Align2::RIGHT_CENTER)),
-
-            Ok(7) => Ok(SubtitleField::Alignment(Align2::LEFT_TOP)),
-            Ok(8) => Ok(SubtitleField::Alignment(Align2::CENTER_TOP)),
-            Ok(9) => Ok(SubtitleField::Alignment(Align2::RIGHT_TOP)),
-            _ => bail!("invalid alignment"),
+        map_res(digit1, |s: &str| {
+            s.parse::<i64>()
+                .ok()
+                .and_then(numpad_alignment)
+                .map(SubtitleField::Alignment)
+                .context("invalid alignment")
         }),
     )(i)
 }
 
+// alignment as laid out on a numpad, used by `\an` and v4+ styles.
+fn numpad_alignment(value: i64) -> Option<Align2> {
+    match value {
+        1 => Some(Align2::LEFT_BOTTOM),
+        2 => Some(Align2::CENTER_BOTTOM),
+        3 => Some(Align2::RIGHT_BOTTOM),
+
+        4 => Some(Align2::LEFT_CENTER),
+        5 => Some(Align2::CENTER_CENTER),
+        6 => Some(Align2::RIGHT_CENTER),
+
+        7 => Some(Align2::LEFT_TOP),
+        8 => Some(Align2::CENTER_TOP),
+        9 => Some(Align2::RIGHT_TOP),
+        _ => None,
+    }
+}
+
+// alignment of v4 styles: 1 to 3 for the bottom row, plus 4 for the top row or 8 for the middle row.
+fn legacy_alignment(value: i64) -> Option<Align2> {
+    let row = match value & !3 {
+        0 => 0,
+        4 => 6,
+        8 => 3,
+        _ => return None,
+    };
+    numpad_alignment((value & 3) + row).filter(|_| value & 3 != 0)
+}
+
 fn pos(i: &str) -> IResult<&str, SubtitleField> {
     preceded(
         tag(r"\pos"),
@@ -80,6 +102,19 @@ fn pos(i: &str) -> IResult<&str, SubtitleField> {
     )(i)
 }
 
+fn q(i: &str) -> IResult<&str, SubtitleField> {
+    preceded(
+        tag(r"\q"),
+        map_res(digit1, |s: &str| {
+            s.parse::<i64>()
+                .ok()
+                .and_then(WrapStyle::from_ass)
+                .map(SubtitleField::WrapStyle)
+                .context("invalid wrap style")
+        }),
+    )(i)
+}
+
 // color parsing credit: example on https://github.com/rust-bakery/nom/tree/main
 fn from_hex(i: &str) -> Result<u8> {
     Ok(u8::from_str_radix(i, 16)?)
@@ -104,32 +139,19 @@ fn undefined(i: &str) -> IResult<&str, SubtitleField> {
         SubtitleField::Undefined,
     )(i)
 }
-fn parse_style(i: &str) -> IResult<&str, Subtitle> {
-    let (i, subtitle_style_components) = delimited(
+fn parse_style(i: &str) -> IResult<&str, Vec<SubtitleField>> {
+    delimited(
         char('{'),
-        many0(alt((t, fad, an, pos, c, undefined))),
+        many0(alt((t, fad, an, pos, c, q, undefined))),
         tuple((take_until("}"), char('}'))),
-    )(i)?;
-
-    let mut subtitle = Subtitle::default();
-
-    for component in subtitle_style_components {
-        match component {
-            SubtitleField::Fade(fade) => subtitle.fade = fade,
-            SubtitleField::Alignment(alignment) => subtitle.alignment = alignment,
-            SubtitleField::PrimaryFill(primary_fill) => subtitle.primary_fill = primary_fill,
-            SubtitleField::Position(position) => subtitle.position = Some(position),
-            SubtitleField::Undefined(_) => (),
-        }
-    }
-    Ok((i, subtitle))
+    )(i)
 }
 
-fn text_field(i: &str) -> IResult<&str, Subtitle> {
-    let (i, (subtitle, subtitle_text)) = preceded(opt_comma, pair(opt(parse_style), rest))(i)?;
-    let mut subtitle = subtitle.unwrap_or_default();
-    subtitle.text = subtitle_text.replace(r"\N", "\n");
-    Ok((i, subtitle))
+fn text_field(i: &str) -> IResult<&str, (Vec<SubtitleField>, &str)> {
+    preceded(
+        opt_comma,
+        pair(map(opt(parse_style), Option::unwrap_or_default), rest),
+    )(i)
 }
 
 fn not_comma(i: &str) -> IResult<&str, &str> {
@@ -153,20 +175,238 @@ fn num_field(i: &str) -> IResult<&str, i32> {
     preceded(opt_comma, map_res(digit0, str::parse))(i)
 }
 
-pub(crate) fn parse_ass_subtitle(i: &str) -> Result<Subtitle> {
-    let (_i, (_layer, _start, _style, _name, _margin_l, _margin_r, _margin_v, _effect, subtitle)) =
-        tuple((
-            context("layer", num_field),
-            context("start", num_field),
-            context("style", string_field),
-            context("name", string_field),
-            context("margin_l", num_field),
-            context("margin_r", num_field),
-            context("margin_v", num_field),
-            context("effect", string_field),
-            context("style override + text", text_field),
-        ))(i)
-        .map_err(|e| anyhow!(format!("subtitle parse failed: {e}")))?;
+pub(crate) fn parse_ass_subtitle(i: &str, script: &AssScript) -> Result<Subtitle> {
+    let (
+        _i,
+        (_read_order, layer, style, _name, margin_l, margin_r, margin_v, _effect, (fields, text)),
+    ) = tuple((
+        context("read order", num_field),
+        context("layer", num_field),
+        context("style", string_field),
+        context("name", string_field),
+        context("margin_l", num_field),
+        context("margin_r", num_field),
+        context("margin_v", num_field),
+        context("effect", string_field),
+        context("style override + text", text_field),
+    ))(i)
+    .map_err(|e| anyhow!(format!("subtitle parse failed: {e}")))?;
 
+    let mut subtitle = script.subtitle_with_style(style.as_deref());
+    // a margin of 0 means the margin of the style is used
+    let margin = |m: i32, style_margin: Option<f32>| (m != 0).then_some(m as f32).or(style_margin);
+    subtitle.layer = layer;
+    subtitle.margin_left = margin(margin_l, subtitle.margin_left);
+    subtitle.margin_right = margin(margin_r, subtitle.margin_right);
+    subtitle.margin_vertical = margin(margin_v, subtitle.margin_vertical);
+
+    for field in fields {
+        match field {
+            SubtitleField::Fade(fade) => subtitle.fade = fade,
+            SubtitleField::Alignment(alignment) => subtitle.alignment = alignment,
+            SubtitleField::PrimaryFill(primary_fill) => subtitle.primary_fill = primary_fill,
+            SubtitleField::Position(position) => subtitle.position = Some(position),
+            SubtitleField::WrapStyle(wrap_style) => subtitle.wrap_style = Some(wrap_style),
+            SubtitleField::Undefined(_) => (),
+        }
+    }
+    subtitle.text = text.replace(r"\N", "\n");
     Ok(subtitle)
 }
+
+/// The `[Script Info]` and `[V4+ Styles]` sections of an ASS header, which events refer to.
+#[derive(Debug, Default)]
+pub(crate) struct AssScript {
+    play_res: Option<Vec2>,
+    wrap_style: Option<WrapStyle>,
+    styles: Vec<AssStyle>,
+}
+
+#[derive(Debug, Default)]
+struct AssStyle {
+    name: String,
+    font_name: Option<String>,
+    font_size: Option<f32>,
+    primary_fill: Option<Color32>,
+    alignment: Option<Align2>,
+    margin_left: Option<f32>,
+    margin_right: Option<f32>,
+    margin_vertical: Option<f32>,
+}
+
+impl AssScript {
+    /// Read the header ffmpeg generates (or copies from the codec extradata) for text subtitles.
+    pub(crate) fn from_decoder(decoder: &ffmpeg::decoder::Subtitle) -> Self {
+        let header = unsafe {
+            let context = decoder.as_ptr();
+            if (*context).subtitle_header.is_null() {
+                return Self::default();
+            }
+            std::slice::from_raw_parts(
+                (*context).subtitle_header,
+                (*context).subtitle_header_size as usize,
+            )
+        };
+        Self::parse(&String::from_utf8_lossy(header))
+    }
+
+    pub(crate) fn parse(header: &str) -> Self {
+        let mut script = Self::default();
+        let mut section = "";
+        let mut format: Vec<&str> = Vec::new();
+        let (mut play_res_x, mut play_res_y) = (None, None);
+        for line in header.lines().map(str::trim) {
+            if line.starts_with('[') {
+                section = line;
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match (section, key.trim()) {
+                ("[Script Info]", "PlayResX") => play_res_x = value.parse::<f32>().ok(),
+                ("[Script Info]", "PlayResY") => play_res_y = value.parse::<f32>().ok(),
+                ("[Script Info]", "WrapStyle") => {
+                    script.wrap_style = value.parse().ok().and_then(WrapStyle::from_ass)
+                }
+                ("[V4+ Styles]" | "[V4 Styles]", "Format") => {
+                    format = value.split(',').map(str::trim).collect()
+                }
+                ("[V4+ Styles]", "Style") => {
+                    script.styles.push(AssStyle::parse(&format, value, true))
+                }
+                ("[V4 Styles]", "Style") => {
+                    script.styles.push(AssStyle::parse(&format, value, false))
+                }
+                _ => (),
+            }
+        }
+        // like libass, a missing resolution is derived from the other one
+        script.play_res = match (play_res_x, play_res_y) {
+            (Some(x), Some(y)) => Some(vec2(x, y)),
+            (Some(x), None) if x == 1280. => Some(vec2(x, 1024.)),
+            (Some(x), None) => Some(vec2(x, x * 3. / 4.)),
+            (None, Some(y)) if y == 1024. => Some(vec2(1280., y)),
+            (None, Some(y)) => Some(vec2(y * 4. / 3., y)),
+            (None, None) => None,
+        };
+        script
+    }
+
+    // events with an unknown style fall back to the "Default" style, then to the first one.
+    fn style(&self, name: Option<&str>) -> Option<&AssStyle> {
+        let name = name.map(|n| n.trim_start_matches('*'));
+        self.styles
+            .iter()
+            .find(|s| Some(s.name.as_str()) == name)
+            .or_else(|| self.styles.iter().find(|s| s.name == "Default"))
+            .or_else(|| self.styles.first())
+    }
+
+    /// A subtitle with the defaults of the script and the named style.
+    pub(crate) fn subtitle_with_style(&self, name: Option<&str>) -> Subtitle {
+        let mut subtitle = Subtitle {
+            play_res: self.play_res,
+            wrap_style: self.wrap_style,
+            ..Default::default()
+        };
+        if let Some(style) = self.style(name) {
+            subtitle.font_name = style.font_name.clone();
+            subtitle.font_size = style.font_size.unwrap_or(subtitle.font_size);
+            subtitle.primary_fill = style.primary_fill.unwrap_or(subtitle.primary_fill);
+            subtitle.alignment = style.alignment.unwrap_or(subtitle.alignment);
+            subtitle.margin_left = style.margin_left;
+            subtitle.margin_right = style.margin_right;
+            subtitle.margin_vertical = style.margin_vertical;
+        }
+        subtitle
+    }
+}
+
+impl AssStyle {
+    fn parse(format: &[&str], value: &str, is_v4_plus: bool) -> Self {
+        let mut style = Self::default();
+        for (field, value) in format.iter().zip(value.splitn(format.len().max(1), ',')) {
+            let value = value.trim();
+            match *field {
+                "Name" => style.name = value.to_string(),
+                "Fontname" => style.font_name = Some(value.to_string()),
+                "Fontsize" => style.font_size = value.parse().ok(),
+                "PrimaryColour" => style.primary_fill = parse_ass_color(value),
+                "Alignment" => {
+                    style.alignment = value.parse().ok().and_then(if is_v4_plus {
+                        numpad_alignment
+                    } else {
+                        legacy_alignment
+                    })
+                }
+                "MarginL" => style.margin_left = value.parse().ok(),
+                "MarginR" => style.margin_right = value.parse().ok(),
+                "MarginV" => style.margin_vertical = value.parse().ok(),
+                _ => (),
+            }
+        }
+        style
+    }
+}
+
+// style colors are `&HAABBGGRR` (or decimal), where an alpha of 0 is opaque.
+fn parse_ass_color(value: &str) -> Option<Color32> {
+    let value = value.trim_end_matches('&');
+    let abgr = match value
+        .strip_prefix("&H")
+        .or_else(|| value.strip_prefix("&h"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => value.parse::<i64>().ok()? as u32,
+    };
+    let [r, g, b, a] = abgr.to_le_bytes();
+    Some(Color32::from_rgba_unmultiplied(r, g, b, 255 - a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "[Script Info]
+PlayResX: 1920
+
+[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, Alignment, MarginL, MarginR, MarginV
+Style: Default,Arial,48,&H00FFFFFF,2,10,10,20
+Style: Sign,Verdana,32,&H8000FFFF,8,0,0,40
+";
+
+    #[test]
+    fn colors_are_abgr() {
+        assert_eq!(parse_ass_color("&H000000FF&"), Some(Color32::RED));
+        assert_eq!(parse_ass_color("255"), Some(Color32::RED));
+        assert_eq!(
+            parse_ass_color("&H80FF0000"),
+            Some(Color32::from_rgba_unmultiplied(0, 0, 255, 127))
+        );
+        assert_eq!(parse_ass_color("red"), None);
+    }
+
+    #[test]
+    fn events_use_their_style() {
+        let script = AssScript::parse(HEADER);
+        assert_eq!(script.play_res, Some(vec2(1920., 1440.)));
+        let sign = parse_ass_subtitle("0,0,Sign,,0,0,0,,Exit", &script).unwrap();
+        assert_eq!(sign.font_name.as_deref(), Some("Verdana"));
+        assert_eq!(sign.alignment, Align2::CENTER_TOP);
+        assert_eq!(sign.margin_vertical, Some(40.));
+        // overrides win over the style, unknown styles fall back to the default one
+        let event = r"1,1,Karaoke,,5,0,0,,{\an7\pos(100,200)\c&H0000FF&}Top\Nleft";
+        let subtitle = parse_ass_subtitle(event, &script).unwrap();
+        assert_eq!(subtitle.text, "Top\nleft");
+        assert_eq!(subtitle.font_name.as_deref(), Some("Arial"));
+        assert_eq!(subtitle.alignment, Align2::LEFT_TOP);
+        assert_eq!(subtitle.position, Some(Pos2::new(100., 200.)));
+        assert_eq!(subtitle.primary_fill, Color32::RED);
+        assert_eq!(subtitle.layer, 1);
+        assert_eq!(subtitle.margin_left, Some(5.));
+        assert_eq!(subtitle.margin_right, Some(10.));
+    }
+}
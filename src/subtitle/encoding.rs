@@ -0,0 +1,36 @@
+use anyhow::Result;
+use chardetng::EncodingDetector;
+
+/// The character encoding of an external subtitle file, see [`crate::Player::add_subtitle_file`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SubtitleEncoding {
+    /// Guess the encoding from the contents of the file. Files starting with a byte order mark are
+    /// always read as the unicode encoding it marks.
+    #[default]
+    Detect,
+    /// Use this encoding, by any name `iconv` understands (e.g. `windows-1250`, `windows-1256` or `GBK`).
+    Named(String),
+}
+
+impl SubtitleEncoding {
+    /// The encoding ffmpeg has to convert the file at `path` from, or `None` if it is already read correctly.
+    pub(crate) fn charset_of(&self, path: &str) -> Result<Option<String>> {
+        match self {
+            Self::Named(name) => Ok(Some(name.clone())),
+            Self::Detect => {
+                let data = std::fs::read(path)?;
+                // ffmpeg converts utf-16 text with a byte order mark by itself
+                let has_bom = [&[0xEF, 0xBB, 0xBF][..], &[0xFF, 0xFE], &[0xFE, 0xFF]]
+                    .iter()
+                    .any(|bom| data.starts_with(bom));
+                if has_bom {
+                    return Ok(None);
+                }
+                let mut detector = EncodingDetector::new();
+                detector.feed(&data, true);
+                let encoding = detector.guess(None, true).name();
+                Ok((encoding != "UTF-8").then(|| encoding.to_string()))
+            }
+        }
+    }
+}
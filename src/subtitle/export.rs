@@ -0,0 +1,295 @@
+use crate::input::{open_input, InputOptions};
+use crate::{open_subtitle_decoder, timestamp_to_millisec, StreamIndex};
+use anyhow::Result;
+use ffmpeg::media::Type;
+use ffmpeg::Rational;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+// how long cues without a duration are shown, as when playing them
+const DEFAULT_CUE_DURATION_MS: i64 = 3000;
+
+/// The format subtitle tracks are written in, see [`export_subtitle_tracks`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaptionFormat {
+    /// SubRip, the text of each cue without its styling.
+    #[default]
+    Srt,
+    /// Advanced SubStation Alpha, keeping the styles and override tags of ASS tracks. Other text
+    /// tracks get the default header of their ffmpeg decoder.
+    Ass,
+}
+
+impl CaptionFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Ass => "ass",
+        }
+    }
+}
+
+/// A subtitle track written by [`export_subtitle_tracks`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportedTrack {
+    /// The index of the stream in the input.
+    pub stream_index: usize,
+    /// The language of the track, if the input tags it.
+    pub language: Option<String>,
+    /// The file the track was written to.
+    pub path: PathBuf,
+    /// How many cues it has.
+    pub cues: usize,
+}
+
+// a decoded cue, with the fields of its ass event after the read order (or just the text)
+struct Cue {
+    start_ms: i64,
+    end_ms: i64,
+    event: String,
+    is_ass: bool,
+}
+
+struct Track {
+    stream_index: usize,
+    time_base: Rational,
+    decoder: ffmpeg::decoder::Subtitle,
+    header: String,
+    cues: Vec<Cue>,
+}
+
+/// Write every text subtitle track of the input at `input_path` to a file of its own in
+/// `output_dir`, named like `movie.3.eng.srt` after the input, the stream index and the language.
+/// The input is read once for all tracks. Bitmap tracks (like PGS and DVD subtitles) have no
+/// text, so they are left out, as are tracks without any cues. Enabled with the `export`
+/// and `experimental` features.
+pub fn export_subtitle_tracks(
+    input_path: &str,
+    input_options: &InputOptions,
+    output_dir: impl AsRef<Path>,
+    format: CaptionFormat,
+) -> Result<Vec<ExportedTrack>> {
+    let mut input = open_input(input_path, input_options)?;
+    let mut tracks = Vec::new();
+    for stream in input.streams() {
+        if stream.parameters().medium() != Type::Subtitle {
+            continue;
+        }
+        let Ok(decoder) = open_subtitle_decoder(&input, StreamIndex::from(stream.index()), None)
+        else {
+            continue;
+        };
+        let header = unsafe {
+            let context = decoder.as_ptr();
+            if (*context).subtitle_header.is_null() {
+                String::new()
+            } else {
+                String::from_utf8_lossy(std::slice::from_raw_parts(
+                    (*context).subtitle_header,
+                    (*context).subtitle_header_size as usize,
+                ))
+                .into_owned()
+            }
+        };
+        tracks.push(Track {
+            stream_index: stream.index(),
+            time_base: stream.time_base(),
+            decoder,
+            header,
+            cues: Vec::new(),
+        });
+    }
+
+    for (stream, packet) in input.packets().filter_map(Result::ok) {
+        let Some(track) = tracks
+            .iter_mut()
+            .find(|track| track.stream_index == stream.index())
+        else {
+            continue;
+        };
+        let mut subtitle = ffmpeg::Subtitle::new();
+        // a damaged cue is left out, the others are still exported
+        if !track
+            .decoder
+            .decode(&packet, &mut subtitle)
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        let Some(pts) = packet.pts() else {
+            continue;
+        };
+        let start_ms = timestamp_to_millisec(pts, track.time_base) + subtitle.start() as i64;
+        let duration_ms = match timestamp_to_millisec(packet.duration(), track.time_base) {
+            0 if subtitle.end() > subtitle.start() => (subtitle.end() - subtitle.start()) as i64,
+            0 => DEFAULT_CUE_DURATION_MS,
+            duration_ms => duration_ms,
+        };
+        for rect in subtitle.rects() {
+            let (event, is_ass) = match rect {
+                ffmpeg::subtitle::Rect::Ass(ass) => match ass.get().split_once(',') {
+                    Some((_read_order, event)) => (event.to_string(), true),
+                    None => continue,
+                },
+                ffmpeg::subtitle::Rect::Text(text) => (text.get().to_string(), false),
+                _ => continue,
+            };
+            track.cues.push(Cue {
+                start_ms,
+                end_ms: start_ms + duration_ms,
+                event,
+                is_ass,
+            });
+        }
+    }
+
+    let output_dir = output_dir.as_ref();
+    let stem = Path::new(input_path)
+        .file_stem()
+        .map_or("subtitles".into(), |stem| stem.to_string_lossy());
+    let mut exported = Vec::new();
+    for mut track in tracks {
+        if track.cues.is_empty() {
+            continue;
+        }
+        track.cues.sort_by_key(|cue| cue.start_ms);
+        let language = input
+            .stream(track.stream_index)
+            .and_then(|stream| stream.metadata().get("language").map(str::to_string));
+        let mut name = format!("{stem}.{}", track.stream_index);
+        if let Some(language) = language.as_ref() {
+            let _ = write!(name, ".{language}");
+        }
+        let path = output_dir.join(format!("{name}.{}", format.extension()));
+        let contents = match format {
+            CaptionFormat::Srt => to_srt(&track.cues),
+            CaptionFormat::Ass => to_ass(&track.header, &track.cues),
+        };
+        std::fs::write(&path, contents)?;
+        exported.push(ExportedTrack {
+            stream_index: track.stream_index,
+            language,
+            path,
+            cues: track.cues.len(),
+        });
+    }
+    Ok(exported)
+}
+
+// the text of an ass event: the fields after layer, style, name, the margins and the effect
+fn event_text(event: &str) -> &str {
+    event.splitn(8, ',').nth(7).unwrap_or_default()
+}
+
+fn to_srt(cues: &[Cue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let text = match cue.is_ass {
+            true => plain_text(event_text(&cue.event)),
+            false => cue.event.trim_end().to_string(),
+        };
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{text}\n\n",
+            i + 1,
+            srt_time(cue.start_ms),
+            srt_time(cue.end_ms),
+        );
+    }
+    srt
+}
+
+fn to_ass(header: &str, cues: &[Cue]) -> String {
+    let mut ass = header.trim_end().to_string();
+    ass.push('\n');
+    for cue in cues {
+        let (start, end) = (ass_time(cue.start_ms), ass_time(cue.end_ms));
+        match cue.is_ass {
+            true => {
+                let (layer, rest) = cue.event.split_once(',').unwrap_or(("0", &cue.event));
+                let _ = writeln!(ass, "Dialogue: {layer},{start},{end},{rest}");
+            }
+            false => {
+                let text = cue.event.trim_end().replace('\n', r"\N");
+                let _ = writeln!(ass, "Dialogue: 0,{start},{end},Default,,0,0,0,,{text}");
+            }
+        }
+    }
+    ass
+}
+
+// the text of an ass event without its override blocks, with its line breaks
+fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_override = false;
+    for c in text.chars() {
+        match c {
+            '{' => in_override = true,
+            '}' if in_override => in_override = false,
+            c if !in_override => plain.push(c),
+            _ => (),
+        }
+    }
+    plain
+        .replace(r"\N", "\n")
+        .replace(r"\n", "\n")
+        .replace(r"\h", " ")
+}
+
+fn srt_time(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+fn ass_time(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000 / 10
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cues_are_exported() {
+        assert_eq!(srt_time(3_723_456), "01:02:03,456");
+        assert_eq!(ass_time(3_723_456), "1:02:03.45");
+        let cues = [
+            Cue {
+                start_ms: 1000,
+                end_ms: 2500,
+                event: r"0,Default,,0,0,0,,{\b1}Bold,\Nand more".to_string(),
+                is_ass: true,
+            },
+            Cue {
+                start_ms: 3000,
+                end_ms: 4000,
+                event: "Plain\n".to_string(),
+                is_ass: false,
+            },
+        ];
+        assert_eq!(
+            to_srt(&cues),
+            "1\n00:00:01,000 --> 00:00:02,500\nBold,\nand more\n\n\
+             2\n00:00:03,000 --> 00:00:04,000\nPlain\n\n"
+        );
+        assert_eq!(
+            to_ass("[Events]\n", &cues),
+            "[Events]\n\
+             Dialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\b1}Bold,\\Nand more\n\
+             Dialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Plain\n"
+        );
+    }
+}
@@ -1,10 +1,29 @@
 use anyhow::Result;
-use egui::{Align2, Color32, Margin, Pos2, TextureHandle};
+use egui::epaint::TextureAtlas;
+use egui::mutex::Mutex;
+use egui::{
+    Align2, Color32, FontData, FontDefinitions, FontFamily, FontId, Galley, Pos2, Rect,
+    TextureHandle, Vec2,
+};
+use ffmpeg::format::context::input::Input;
+use ffmpeg::media::Type;
 use std::fmt;
+use std::sync::{Arc, Weak};
 
 use self::ass::parse_ass_subtitle;
+pub(crate) use self::ass::AssScript;
+pub use self::encoding::SubtitleEncoding;
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub use self::export::{export_subtitle_tracks, CaptionFormat, ExportedTrack};
+pub use self::snapshot::render_subtitle_image;
+#[cfg(all(feature = "export", feature = "experimental"))]
+pub(crate) use self::snapshot::{paint_shape, MAX_TEXTURE_SIDE};
 
 mod ass;
+mod encoding;
+#[cfg(all(feature = "export", feature = "experimental"))]
+mod export;
+mod snapshot;
 
 #[derive(Default)]
 pub struct SubtitleBitmap {
@@ -28,6 +47,9 @@ impl fmt::Debug for SubtitleBitmap {
     }
 }
 
+/// The script resolution of subtitles without one, the same as libass uses.
+pub(crate) const DEFAULT_PLAY_RES: Vec2 = Vec2::new(384., 288.);
+
 #[derive(Debug)]
 pub struct Subtitle {
     pub text: String,
@@ -36,11 +58,228 @@ pub struct Subtitle {
     pub primary_fill: Color32,
     pub position: Option<Pos2>,
     pub font_size: f32,
-    pub margin: Margin,
-    pub remaining_duration_ms: i64,
+    pub font_name: Option<String>,
+    pub play_res: Option<Vec2>,
+    pub wrap_style: Option<WrapStyle>,
+    pub layer: i32,
+    pub margin_left: Option<f32>,
+    pub margin_right: Option<f32>,
+    pub margin_vertical: Option<f32>,
+    pub duration_ms: i64,
     pub presentation_time_ms: Option<i64>,
     pub showing: bool,
     pub bitmap: SubtitleBitmap,
+    pub(crate) layout: Option<CachedLayout>,
+}
+
+/// The galley of a text subtitle, reused by the following frames while it is laid out the same.
+#[derive(Debug)]
+pub(crate) struct CachedLayout {
+    pub(crate) font_id: FontId,
+    pub(crate) wrap_style: WrapStyle,
+    pub(crate) max_width: f32,
+    pub(crate) galley: Arc<Galley>,
+    // egui starts a new font atlas when it fills up or the scale changes, leaving the glyphs of
+    // the galley pointing nowhere
+    pub(crate) atlas: Weak<Mutex<TextureAtlas>>,
+}
+
+impl CachedLayout {
+    pub(crate) fn matches(
+        &self,
+        font_id: &FontId,
+        wrap_style: WrapStyle,
+        max_width: f32,
+        atlas: &Arc<Mutex<TextureAtlas>>,
+    ) -> bool {
+        self.font_id == *font_id
+            && self.wrap_style == wrap_style
+            && self.max_width == max_width
+            && self.atlas.upgrade().is_some_and(|a| Arc::ptr_eq(&a, atlas))
+    }
+}
+
+/// How long lines of text subtitles are broken, matching the ASS `WrapStyle` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapStyle {
+    /// Wrap at word boundaries, keeping the lines about equally long (`0`).
+    #[default]
+    Smart,
+    /// Wrap at word boundaries once a line is full (`1`).
+    EndOfLine,
+    /// Never wrap, only break lines at explicit `\N` (`2`).
+    NoWrap,
+    /// Same as [`WrapStyle::Smart`], but the lower line is wider (`3`).
+    SmartLowerWider,
+}
+
+impl WrapStyle {
+    pub(crate) fn from_ass(value: i64) -> Option<Self> {
+        match value {
+            0 => Some(Self::Smart),
+            1 => Some(Self::EndOfLine),
+            2 => Some(Self::NoWrap),
+            3 => Some(Self::SmartLowerWider),
+            _ => None,
+        }
+    }
+}
+
+/// The margins of the area text subtitles are kept inside, as fractions of the video size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SafeArea {
+    /// Fraction of the video width left free on the left.
+    pub left: f32,
+    /// Fraction of the video width left free on the right.
+    pub right: f32,
+    /// Fraction of the video height left free at the top.
+    pub top: f32,
+    /// Fraction of the video height left free at the bottom.
+    pub bottom: f32,
+}
+
+impl Default for SafeArea {
+    fn default() -> Self {
+        Self {
+            left: 0.05,
+            right: 0.05,
+            top: 0.05,
+            bottom: 0.08,
+        }
+    }
+}
+
+impl SafeArea {
+    /// The same margin on every side.
+    pub fn same(margin: f32) -> Self {
+        Self {
+            left: margin,
+            right: margin,
+            top: margin,
+            bottom: margin,
+        }
+    }
+
+    /// Shrink the rect of the video to the safe area.
+    pub fn apply(&self, video_rect: Rect) -> Rect {
+        let size = video_rect.size();
+        Rect::from_min_max(
+            video_rect.min + egui::vec2(self.left * size.x, self.top * size.y),
+            video_rect.max - egui::vec2(self.right * size.x, self.bottom * size.y),
+        )
+    }
+}
+
+/// Configures how text subtitles are laid out, see [`crate::PlayerOptions::subtitles`].
+#[derive(Clone, Copy, Debug)]
+pub struct SubtitleOptions {
+    /// The area subtitles are wrapped and positioned in.
+    pub safe_area: SafeArea,
+    /// The wrap style used when a subtitle doesn't set one with `\q`.
+    pub wrap_style: WrapStyle,
+    /// Whether subtitles are drawn over the video or in a band below it.
+    pub placement: SubtitlePlacement,
+    /// Multiplies the font size of text subtitles. Font sizes are relative to the height of the
+    /// rendered video, so `1.0` keeps the size intended by the subtitle script at any widget size.
+    pub font_scale: f32,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            safe_area: SafeArea::default(),
+            wrap_style: WrapStyle::default(),
+            placement: SubtitlePlacement::default(),
+            font_scale: 1.,
+        }
+    }
+}
+
+/// Where text subtitles are drawn, see [`SubtitleOptions::placement`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SubtitlePlacement {
+    /// Over the picture, inside the safe area of the video.
+    #[default]
+    OverVideo,
+    /// In a black band below the video. The video is shrunk so that the video and the band fit
+    /// the size given to the player. Subtitles positioned with `\pos` are still drawn over the video.
+    BelowVideo {
+        /// The height of the band, as a fraction of the total height.
+        band_height: f32,
+    },
+}
+
+/// A cue pushed by the app with [`crate::Player::inject_subtitle`], like a translation or a debug
+/// overlay. It is drawn like the cues of the subtitle stream, following [`SubtitleOptions`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubtitleCue {
+    /// The text of the cue.
+    pub text: String,
+    /// When the cue is first shown, in milliseconds of stream time.
+    pub start_ms: i64,
+    /// When the cue is no longer shown (exclusive), in milliseconds of stream time.
+    pub end_ms: i64,
+    /// Where the cue is anchored in the safe area.
+    pub alignment: Align2,
+    /// The color of the text.
+    pub color: Color32,
+    /// The font size, in the coordinates of a 384x288 script (the rendered size scales with the video).
+    pub font_size: f32,
+    /// Cues of higher layers are drawn above lower ones.
+    pub layer: i32,
+}
+
+impl SubtitleCue {
+    /// Show `text` from `start_ms` up to `end_ms`.
+    pub fn new(text: impl Into<String>, start_ms: i64, end_ms: i64) -> Self {
+        Self {
+            text: text.into(),
+            start_ms,
+            end_ms,
+            alignment: Align2::CENTER_BOTTOM,
+            color: Color32::WHITE,
+            font_size: 16.,
+            layer: 0,
+        }
+    }
+
+    /// Set [`SubtitleCue::alignment`].
+    pub fn with_alignment(mut self, alignment: Align2) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Set [`SubtitleCue::color`].
+    pub fn with_color(mut self, color: Color32) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set [`SubtitleCue::font_size`].
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Set [`SubtitleCue::layer`].
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+impl From<SubtitleCue> for Subtitle {
+    fn from(cue: SubtitleCue) -> Self {
+        Self {
+            alignment: cue.alignment,
+            primary_fill: cue.color,
+            font_size: cue.font_size,
+            layer: cue.layer,
+            ..Subtitle::from_text(&cue.text)
+                .with_presentation_time_ms(cue.start_ms)
+                .with_duration_ms(cue.end_ms - cue.start_ms)
+        }
+    }
 }
 
 // todo, among others
@@ -56,6 +295,7 @@ enum SubtitleField<'a> {
     Alignment(Align2),
     PrimaryFill(Color32),
     Position(Pos2),
+    WrapStyle(WrapStyle),
     #[allow(unused)]
     Undefined(&'a str),
 }
@@ -74,15 +314,22 @@ impl Default for Subtitle {
                 _fade_in_ms: 0,
                 _fade_out_ms: 0,
             },
-            remaining_duration_ms: 0,
-            font_size: 30.,
-            margin: Margin::same(85),
-            alignment: Align2::CENTER_CENTER,
+            duration_ms: 0,
+            font_size: 16.,
+            font_name: None,
+            play_res: None,
+            wrap_style: None,
+            layer: 0,
+            margin_left: None,
+            margin_right: None,
+            margin_vertical: None,
+            alignment: Align2::CENTER_BOTTOM,
             primary_fill: Color32::WHITE,
             position: None,
             presentation_time_ms: None,
             showing: false,
             bitmap: SubtitleBitmap::default(),
+            layout: None,
         }
     }
 }
@@ -96,9 +343,24 @@ impl Subtitle {
         self
     }
     pub(crate) fn with_duration_ms(mut self, duration_ms: i64) -> Self {
-        self.remaining_duration_ms = duration_ms;
+        self.duration_ms = duration_ms;
         self
     }
+    /// Whether the cue is shown on the frame at `time_ms`.
+    pub(crate) fn is_shown_at(&self, time_ms: i64) -> bool {
+        match self.presentation_time_ms {
+            Some(start_ms) => (start_ms..start_ms + self.duration_ms).contains(&time_ms),
+            None => true,
+        }
+    }
+    /// Whether the cue is a bitmap, or the empty cue clearing one, rather than text.
+    pub(crate) fn is_bitmap(&self) -> bool {
+        self.text.is_empty()
+    }
+    pub(crate) fn has_ended(&self, time_ms: i64) -> bool {
+        self.presentation_time_ms
+            .is_some_and(|start_ms| start_ms + self.duration_ms <= time_ms)
+    }
     pub(crate) fn with_presentation_time_ms(mut self, pts: i64) -> Self {
         self.presentation_time_ms = Some(pts);
         self
@@ -131,9 +393,12 @@ impl Subtitle {
         }
         subtitle
     }
-    pub(crate) fn from_ffmpeg_rect(rect: ffmpeg::subtitle::Rect) -> Result<Self> {
+    pub(crate) fn from_ffmpeg_rect(
+        rect: ffmpeg::subtitle::Rect,
+        script: &AssScript,
+    ) -> Result<Self> {
         match rect {
-            ffmpeg::subtitle::Rect::Ass(ass) => parse_ass_subtitle(ass.get()),
+            ffmpeg::subtitle::Rect::Ass(ass) => parse_ass_subtitle(ass.get(), script),
             ffmpeg::subtitle::Rect::Bitmap(bitmap) => Ok(Subtitle::from_bitmap(&bitmap)),
             ffmpeg::subtitle::Rect::None(_none) => anyhow::bail!("no subtitle"),
             ffmpeg::subtitle::Rect::Text(text) => Ok(Subtitle::from_text(text.get())),
@@ -146,3 +411,76 @@ impl FadeEffect {
         self._fade_in_ms == 0 && self._fade_out_ms == 0
     }
 }
+
+/// A font attached to the input, as matroska files do for the fonts used by their ASS subtitles.
+pub(crate) struct FontAttachment {
+    family: String,
+    file_name: String,
+    data: Vec<u8>,
+}
+
+impl FontAttachment {
+    pub(crate) fn from_input(input: &Input) -> Vec<Self> {
+        input
+            .streams()
+            .filter(|stream| stream.parameters().medium() == Type::Attachment)
+            .filter_map(|stream| {
+                let file_name = stream.metadata().get("filename")?.to_string();
+                let data = unsafe {
+                    let parameters = stream.parameters();
+                    let parameters = parameters.as_ptr();
+                    if (*parameters).extradata.is_null() {
+                        return None;
+                    }
+                    std::slice::from_raw_parts(
+                        (*parameters).extradata,
+                        (*parameters).extradata_size as usize,
+                    )
+                    .to_vec()
+                };
+                // attachments that aren't fonts (like cover art) fail to parse and are skipped
+                let family = font_family_name(&data)?;
+                Some(Self {
+                    family,
+                    file_name,
+                    data,
+                })
+            })
+            .collect()
+    }
+
+    /// Add the fonts to `definitions` as families named after them, which ASS styles refer to.
+    /// Glyphs missing from a font (like emoji) fall back to the proportional fonts.
+    pub(crate) fn register(attachments: Vec<Self>, definitions: &mut FontDefinitions) {
+        let fallbacks = definitions
+            .families
+            .get(&FontFamily::Proportional)
+            .cloned()
+            .unwrap_or_default();
+        for attachment in attachments {
+            let name = format!("attachment:{}", attachment.file_name);
+            if definitions.font_data.contains_key(&name) {
+                continue;
+            }
+            definitions.font_data.insert(
+                name.clone(),
+                Arc::new(FontData::from_owned(attachment.data)),
+            );
+            let fonts = definitions
+                .families
+                .entry(FontFamily::Name(attachment.family.into()))
+                .or_default();
+            fonts.retain(|font| !fallbacks.contains(font));
+            fonts.push(name);
+            fonts.extend(fallbacks.iter().cloned());
+        }
+    }
+}
+
+fn font_family_name(data: &[u8]) -> Option<String> {
+    let face = ttf_parser::Face::parse(data, 0).ok()?;
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == ttf_parser::name_id::FAMILY && name.is_unicode())
+        .find_map(|name| name.to_string())
+}
@@ -0,0 +1,197 @@
+use super::{Subtitle, SubtitleCue, SubtitleOptions};
+use egui::epaint::text::Fonts;
+use egui::epaint::{Mesh, Shape, TessellationOptions, Tessellator, Vertex};
+use egui::{Color32, ColorImage, FontDefinitions, Pos2, Rect, Vec2};
+
+// the largest font atlas the cue is laid out with, the same as most gpus allow
+pub(crate) const MAX_TEXTURE_SIDE: usize = 8192;
+
+/// Draw `cue` the way [`crate::Player`] draws it over a video of `video_size` pixels with `style`,
+/// into a transparent image of that size. Meant for golden-image tests that pin the subtitle
+/// rendering across upgrades of the crate, so the text is laid out with only `fonts` (the same
+/// fonts give the same image) and rasterized on the cpu, at one pixel per point.
+///
+/// With [`crate::SubtitlePlacement::BelowVideo`] the cue is laid out as in the band, but the band
+/// itself is left out: the image covers `video_size` only.
+pub fn render_subtitle_image(
+    cue: &SubtitleCue,
+    style: &SubtitleOptions,
+    video_size: [usize; 2],
+    fonts: FontDefinitions,
+) -> ColorImage {
+    let fonts = Fonts::new(1., MAX_TEXTURE_SIDE, fonts);
+    let video_rect = Rect::from_min_size(
+        Pos2::ZERO,
+        Vec2::new(video_size[0] as f32, video_size[1] as f32),
+    );
+    let mut subtitle = Subtitle::from(cue.clone());
+    let in_band = matches!(style.placement, crate::SubtitlePlacement::BelowVideo { .. });
+    let (text_rect, galley) = crate::layout_text_subtitle(
+        &fonts,
+        &mut subtitle,
+        style,
+        video_rect,
+        style.safe_area.apply(video_rect),
+        in_band,
+        &[],
+    );
+    let shape = Shape::galley(
+        text_rect.min - galley.rect.min.to_vec2(),
+        galley,
+        subtitle.primary_fill,
+    );
+    let mut image = ColorImage::new(video_size, Color32::TRANSPARENT);
+    paint_shape(&mut image, &fonts, shape);
+    image
+}
+
+/// Rasterize `shape` over `image` on the cpu, at one pixel per point, with the glyphs of the text
+/// laid out with `fonts`.
+pub(crate) fn paint_shape(image: &mut ColorImage, fonts: &Fonts, shape: Shape) {
+    // the glyphs are only added to the atlas while laying out, so it is read afterwards
+    let mut mesh = Mesh::default();
+    Tessellator::new(
+        1.,
+        TessellationOptions::default(),
+        fonts.font_image_size(),
+        vec![],
+    )
+    .tessellate_shape(shape, &mut mesh);
+    let atlas = fonts.image();
+    let atlas_pixels: Vec<Color32> = atlas.srgba_pixels(None).collect();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+        fill_triangle(image, [a, b, c], &atlas_pixels, atlas.size);
+    }
+}
+
+// blend the triangle `vertices` over `image`, sampling the font atlas at the pixel centers
+fn fill_triangle(
+    image: &mut ColorImage,
+    vertices: [Vertex; 3],
+    atlas: &[Color32],
+    atlas_size: [usize; 2],
+) {
+    let [a, b, c] = vertices;
+    let area = edge(a.pos, b.pos, c.pos);
+    if area == 0. {
+        return;
+    }
+    let [width, height] = image.size;
+    let min_x = a.pos.x.min(b.pos.x).min(c.pos.x).floor().max(0.) as usize;
+    let min_y = a.pos.y.min(b.pos.y).min(c.pos.y).floor().max(0.) as usize;
+    let max_x = (a.pos.x.max(b.pos.x).max(c.pos.x).ceil().max(0.) as usize).min(width);
+    let max_y = (a.pos.y.max(b.pos.y).max(c.pos.y).ceil().max(0.) as usize).min(height);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let center = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(b.pos, c.pos, center) / area,
+                edge(c.pos, a.pos, center) / area,
+                edge(a.pos, b.pos, center) / area,
+            ];
+            if weights.iter().any(|w| *w < 0.) {
+                continue;
+            }
+            let interpolate = |value: fn(&Vertex) -> f32| {
+                weights[0] * value(&a) + weights[1] * value(&b) + weights[2] * value(&c)
+            };
+            let u = interpolate(|v| v.uv.x);
+            let v = interpolate(|v| v.uv.y);
+            let texel_x = ((u * atlas_size[0] as f32) as usize).min(atlas_size[0] - 1);
+            let texel_y = ((v * atlas_size[1] as f32) as usize).min(atlas_size[1] - 1);
+            let texel = atlas[texel_y * atlas_size[0] + texel_x].to_array();
+            let color = [
+                interpolate(|v| v.color.r() as f32),
+                interpolate(|v| v.color.g() as f32),
+                interpolate(|v| v.color.b() as f32),
+                interpolate(|v| v.color.a() as f32),
+            ];
+            // both are premultiplied, so the source is blended over with its alpha
+            let source: [f32; 4] =
+                std::array::from_fn(|i| color[i] * texel[i] as f32 / 255. / 255.);
+            let pixel = &mut image.pixels[y * width + x];
+            let destination = pixel.to_array();
+            let [r, g, b, alpha] = std::array::from_fn(|i| {
+                let blended = source[i] + destination[i] as f32 / 255. * (1. - source[3]);
+                (blended * 255.).round().clamp(0., 255.) as u8
+            });
+            *pixel = Color32::from_rgba_premultiplied(r, g, b, alpha);
+        }
+    }
+}
+
+// twice the signed area of the triangle `a`, `b`, `p`
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::Align2;
+
+    // the smallest rect holding the pixels that aren't transparent
+    fn painted_rect(image: &ColorImage) -> Option<Rect> {
+        let [width, _] = image.size;
+        image
+            .pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, pixel)| pixel.a() > 0)
+            .map(|(i, _)| Pos2::new((i % width) as f32, (i / width) as f32))
+            .fold(None, |rect: Option<Rect>, pos| {
+                Some(rect.map_or(Rect::from_min_max(pos, pos), |r| {
+                    r.union(Rect::from_min_max(pos, pos))
+                }))
+            })
+    }
+
+    #[test]
+    fn same_cue_same_image() {
+        let cue = SubtitleCue::new("golden", 0, 1000);
+        let render = || {
+            render_subtitle_image(
+                &cue,
+                &SubtitleOptions::default(),
+                [320, 180],
+                FontDefinitions::default(),
+            )
+        };
+        let image = render();
+        assert_eq!(image.size, [320, 180]);
+        assert_eq!(image.pixels, render().pixels);
+    }
+
+    #[test]
+    fn cue_is_drawn_at_its_alignment() {
+        let style = SubtitleOptions::default();
+        let fonts = FontDefinitions::default;
+        let bottom = SubtitleCue::new("bottom", 0, 1000);
+        let image = render_subtitle_image(&bottom, &style, [320, 180], fonts());
+        let rect = painted_rect(&image).expect("the text is drawn");
+        assert!(rect.min.y > 90.);
+        assert!((rect.center().x - 160.).abs() < 16.);
+
+        let top = SubtitleCue::new("top", 0, 1000).with_alignment(Align2::LEFT_TOP);
+        let image = render_subtitle_image(&top, &style, [320, 180], fonts());
+        let rect = painted_rect(&image).expect("the text is drawn");
+        assert!(rect.max.y < 90.);
+        assert!(rect.max.x < 160.);
+    }
+
+    #[test]
+    fn empty_cue_is_transparent() {
+        let cue = SubtitleCue::new("", 0, 1000);
+        let image = render_subtitle_image(
+            &cue,
+            &SubtitleOptions::default(),
+            [64, 36],
+            FontDefinitions::default(),
+        );
+        assert!(image
+            .pixels
+            .iter()
+            .all(|pixel| *pixel == Color32::TRANSPARENT));
+    }
+}
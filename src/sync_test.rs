@@ -0,0 +1,78 @@
+/// The result of an A/V sync self-test, see [`crate::Player::sync_test`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncTestResult {
+    /// How many flashes have been measured.
+    pub measurements: usize,
+    /// The mean time the beep was heard after its flash was presented, in milliseconds. Positive
+    /// values mean audio is late.
+    pub mean_offset_ms: f64,
+    /// The standard deviation of the offset, in milliseconds.
+    pub jitter_ms: f64,
+    /// The [`crate::PlayerOptions::audio_delay_ms`] that puts the beeps on the flashes.
+    pub suggested_audio_delay_ms: i64,
+}
+
+// one flash and beep every second
+const PERIOD_MS: i64 = 1000;
+
+pub(crate) struct SyncTest {
+    flash_ms: i64,
+    last_flash: Option<i64>,
+    offsets_ms: Vec<f64>,
+}
+
+impl SyncTest {
+    pub(crate) fn new(fps: u32) -> Self {
+        Self {
+            flash_ms: 1000 / fps.max(1) as i64,
+            last_flash: None,
+            offsets_ms: Vec::new(),
+        }
+    }
+
+    /// The lavfi graph producing the pattern: a white frame at the start of every period, and a
+    /// beep of the same length.
+    pub(crate) fn graph(resolution: [u32; 2], fps: u32) -> String {
+        let [width, height] = resolution;
+        let flash_s = 1. / fps.max(1) as f64;
+        let period_s = PERIOD_MS as f64 / 1000.;
+        format!(
+            "color=c=black:size={width}x{height}:rate={fps},\
+             drawbox=color=white:t=fill:enable='lt(mod(t,{period_s}),{flash_s})'[out0];\
+             sine=frequency=1000:sample_rate=48000,\
+             volume=volume=0:enable='gte(mod(t,{period_s}),{flash_s})'[out1]"
+        )
+    }
+
+    /// Measure a presented frame, if it is the first frame of a flash. `audio_heard_ms` is the
+    /// stream time of the audio coming out of the speakers at the moment of presentation.
+    pub(crate) fn frame_presented(&mut self, presentation_time_ms: i64, audio_heard_ms: f64) {
+        let period = presentation_time_ms.div_euclid(PERIOD_MS);
+        let is_flash = presentation_time_ms.rem_euclid(PERIOD_MS) < self.flash_ms;
+        if is_flash && self.last_flash != Some(period) {
+            self.last_flash = Some(period);
+            self.offsets_ms
+                .push((period * PERIOD_MS) as f64 - audio_heard_ms);
+        }
+    }
+
+    pub(crate) fn result(&self, audio_delay_ms: i64) -> Option<SyncTestResult> {
+        if self.offsets_ms.is_empty() {
+            return None;
+        }
+        let count = self.offsets_ms.len() as f64;
+        let mean = self.offsets_ms.iter().sum::<f64>() / count;
+        let variance = self
+            .offsets_ms
+            .iter()
+            .map(|o| (o - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some(SyncTestResult {
+            measurements: self.offsets_ms.len(),
+            mean_offset_ms: mean,
+            jitter_ms: variance.sqrt(),
+            suggested_audio_delay_ms: audio_delay_ms + mean.round() as i64,
+        })
+    }
+}
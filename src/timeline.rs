@@ -0,0 +1,39 @@
+use crate::{Chapter, TimeRange};
+
+/// Everything a seekbar shows, taken at once for each frame, see [`crate::Player::timeline`].
+#[derive(Clone, Debug, Default)]
+pub struct TimelineModel {
+    /// The duration of the input, in milliseconds.
+    pub duration_ms: i64,
+    /// The time of the frame on screen (or the seek target while seeking), in milliseconds.
+    pub position_ms: i64,
+    /// The range [`crate::Player::seek`] maps `0.0..=1.0` onto, in milliseconds. The whole input,
+    /// or the timeshift window of a live source.
+    pub seekable_ms: (i64, i64),
+    /// The ranges that play without waiting for the input, in milliseconds: the decoded frames
+    /// ahead of the position, or the timeshift window.
+    pub buffered_ms: Vec<(i64, i64)>,
+    /// See [`crate::Player::chapters`].
+    pub chapters: Vec<Chapter>,
+    /// The ranges set with [`crate::Player::set_skip_ranges`].
+    pub markers: Vec<TimeRange>,
+    /// The newest time of a live source, if it is timeshifted.
+    pub live_edge_ms: Option<i64>,
+}
+
+impl TimelineModel {
+    /// Where `time_ms` is in [`TimelineModel::seekable_ms`], as passed to [`crate::Player::seek`].
+    pub fn frac(&self, time_ms: i64) -> f32 {
+        let (start_ms, end_ms) = self.seekable_ms;
+        if end_ms <= start_ms {
+            return 0.;
+        }
+        ((time_ms - start_ms) as f32 / (end_ms - start_ms) as f32).clamp(0., 1.)
+    }
+
+    /// The time at `frac` of [`TimelineModel::seekable_ms`], in milliseconds.
+    pub fn time_ms(&self, frac: f32) -> i64 {
+        let (start_ms, end_ms) = self.seekable_ms;
+        start_ms + (frac.clamp(0., 1.) as f64 * (end_ms - start_ms) as f64) as i64
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{timestamp_to_millisec, Shared};
+use ffmpeg::{Packet, Rational};
+use std::collections::VecDeque;
+
+/// Configures the timeshift buffer of a [`crate::Player`] playing a live source.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeshiftOptions {
+    /// How far back playback can be rewound, in milliseconds.
+    pub window_ms: i64,
+    /// The most memory the buffered packets of each stream take up, in bytes. The oldest packets
+    /// are dropped beyond it, shortening the window. `None` only limits the buffer by
+    /// [`TimeshiftOptions::window_ms`], which can take a lot of memory for high bitrate streams.
+    pub max_bytes: Option<usize>,
+}
+
+impl TimeshiftOptions {
+    /// A timeshift window of `minutes` minutes.
+    pub fn minutes(minutes: i64) -> Self {
+        Self {
+            window_ms: minutes * 60 * 1000,
+            max_bytes: None,
+        }
+    }
+
+    /// Set [`TimeshiftOptions::max_bytes`].
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+}
+
+/// The range of time currently held by a timeshift buffer, shared with the [`crate::Player`] so
+/// the seekbar can show it.
+#[derive(Clone, Debug)]
+pub(crate) struct TimeshiftWindow {
+    pub(crate) start_ms: Shared<i64>,
+    pub(crate) end_ms: Shared<i64>,
+}
+
+impl TimeshiftWindow {
+    pub(crate) fn new() -> Self {
+        Self {
+            start_ms: Shared::new(0),
+            end_ms: Shared::new(0),
+        }
+    }
+    pub(crate) fn len_ms(&self) -> i64 {
+        (self.end_ms.get() - self.start_ms.get()).max(0)
+    }
+    pub(crate) fn frac_to_ms(&self, frac: f32) -> i64 {
+        self.start_ms.get() + (frac as f64 * self.len_ms() as f64) as i64
+    }
+    pub(crate) fn ms_to_frac(&self, ms: i64) -> f32 {
+        if self.len_ms() == 0 {
+            1.
+        } else {
+            ((ms - self.start_ms.get()) as f32 / self.len_ms() as f32).clamp(0., 1.)
+        }
+    }
+}
+
+struct BufferedPacket {
+    time_base: Rational,
+    time_ms: i64,
+    packet: Packet,
+}
+
+/// Keeps the most recent packets of a live stream in memory, so that playback can be paused and
+/// rewound. Packets keep being read from the live source while the player is behind the live edge.
+pub struct TimeshiftBuffer {
+    window_ms: i64,
+    max_bytes: Option<usize>,
+    packets: VecDeque<BufferedPacket>,
+    size_bytes: usize,
+    // index of the next packet to replay, or `None` when playing at the live edge.
+    cursor: Option<usize>,
+    window: TimeshiftWindow,
+    // only the primary streamer's buffer updates the shared window.
+    is_primary: bool,
+}
+
+impl TimeshiftBuffer {
+    pub(crate) fn new(
+        options: TimeshiftOptions,
+        window: TimeshiftWindow,
+        is_primary: bool,
+    ) -> Self {
+        Self {
+            window_ms: options.window_ms,
+            max_bytes: options.max_bytes,
+            packets: VecDeque::new(),
+            size_bytes: 0,
+            cursor: None,
+            window,
+            is_primary,
+        }
+    }
+
+    /// Whether playback is at the live edge of the stream.
+    pub(crate) fn is_live(&self) -> bool {
+        self.cursor.is_none()
+    }
+
+    pub(crate) fn window(&self) -> &TimeshiftWindow {
+        &self.window
+    }
+
+    pub(crate) fn push(&mut self, time_base: Rational, packet: Packet) {
+        let time_ms = match packet.dts().or(packet.pts()) {
+            Some(ffmpeg::ffi::AV_NOPTS_VALUE) | None => {
+                self.packets.back().map(|p| p.time_ms).unwrap_or_default()
+            }
+            Some(ts) => timestamp_to_millisec(ts, time_base),
+        };
+        self.size_bytes += packet.size();
+        self.packets.push_back(BufferedPacket {
+            time_base,
+            time_ms,
+            packet,
+        });
+        let mut dropped_cursor = false;
+        while self.packets.len() > 1
+            && self.packets.front().is_some_and(|p| {
+                p.time_ms < time_ms - self.window_ms
+                    || self.max_bytes.is_some_and(|max| self.size_bytes > max)
+            })
+        {
+            if let Some(front) = self.packets.pop_front() {
+                self.size_bytes -= front.packet.size();
+            }
+            match self.cursor.as_mut() {
+                Some(0) => dropped_cursor = true,
+                Some(cursor) => *cursor -= 1,
+                None => (),
+            }
+        }
+        // replaying from a packet that isn't a keyframe would decode broken frames until the next
+        // one, so replay continues at the first keyframe left (or the live edge without one)
+        if dropped_cursor {
+            self.cursor = self.packets.iter().position(|p| p.packet.is_key());
+        }
+        if self.is_primary {
+            if let Some(front) = self.packets.front() {
+                self.window.start_ms.set(front.time_ms);
+            }
+            self.window.end_ms.set(time_ms);
+        }
+    }
+
+    /// Stop following the live edge, so that packets pushed from now on are replayed in order.
+    pub(crate) fn hold(&mut self) {
+        if self.cursor.is_none() {
+            self.cursor = Some(self.packets.len());
+        }
+    }
+
+    /// The next packet to replay, if playback is behind the live edge.
+    pub(crate) fn next_buffered(&mut self) -> Option<(Rational, Packet)> {
+        let cursor = self.cursor?;
+        let Some(buffered) = self.packets.get(cursor) else {
+            self.cursor = None;
+            return None;
+        };
+        let next = (buffered.time_base, buffered.packet.clone());
+        self.cursor = (cursor + 1 < self.packets.len()).then_some(cursor + 1);
+        Some(next)
+    }
+
+    /// Move the replay position to the last keyframe at or before `target_ms`. Returns `false`
+    /// if no such keyframe is buffered.
+    pub(crate) fn seek(&mut self, target_ms: i64) -> bool {
+        if target_ms >= self.window.end_ms.get() {
+            self.cursor = None;
+            return true;
+        }
+        let keyframe = self
+            .packets
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.packet.is_key())
+            .take_while(|(_, p)| p.time_ms <= target_ms)
+            .last()
+            .map(|(i, _)| i)
+            .or_else(|| self.packets.iter().position(|p| p.packet.is_key()));
+        if keyframe.is_some() {
+            self.cursor = keyframe;
+        }
+        keyframe.is_some()
+    }
+}
@@ -0,0 +1,289 @@
+#[cfg(any(feature = "audio", feature = "subtitles"))]
+use ffmpeg::format::stream::{Disposition, Stream};
+#[cfg(any(feature = "audio", feature = "subtitles"))]
+use ffmpeg::media::Type;
+
+/// An audio or subtitle track of a [`crate::Player`], as listed by [`crate::Player::audio_tracks`]
+/// and [`crate::Player::subtitle_tracks`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrackInfo {
+    /// The number of the track among the tracks of its type, starting at `1`, in the order the
+    /// tracks are cycled through.
+    pub number: usize,
+    /// The language tag of the track, like `eng`, `de` or `pt-BR`. `None` when it's not tagged or
+    /// tagged as undetermined.
+    pub language: Option<String>,
+    /// The title of the track, like `Director's commentary`.
+    pub title: Option<String>,
+    /// The name of the codec, like `eac3` or `subrip`.
+    pub codec: String,
+    /// The number of audio channels, `None` for subtitles.
+    pub channels: Option<u16>,
+    /// The track is meant to be chosen by default.
+    pub default: bool,
+    /// Subtitles only translating foreign dialogue and signs.
+    pub forced: bool,
+    /// Subtitles for the deaf and hard of hearing, describing sounds as well.
+    pub hearing_impaired: bool,
+    /// Audio describing the picture, for the blind and visually impaired.
+    pub audio_description: bool,
+    /// A commentary track.
+    pub commentary: bool,
+}
+
+impl TrackInfo {
+    #[cfg(any(feature = "audio", feature = "subtitles"))]
+    pub(crate) fn new(stream: &Stream, number: usize) -> Self {
+        let metadata = stream.metadata();
+        let language = metadata
+            .get("language")
+            .filter(|language| !matches!(*language, "und" | "zxx" | "mis" | ""))
+            .map(str::to_string);
+        let title = metadata
+            .get("title")
+            .filter(|title| !title.trim().is_empty())
+            .map(str::to_string);
+        let parameters = stream.parameters();
+        let channels = (parameters.medium() == Type::Audio).then(|| {
+            let channels = unsafe { (*parameters.as_ptr()).ch_layout.nb_channels };
+            channels.max(0) as u16
+        });
+        let disposition = stream.disposition();
+        Self {
+            number,
+            language,
+            title,
+            codec: parameters.id().name().to_string(),
+            channels,
+            default: disposition.contains(Disposition::DEFAULT),
+            forced: disposition.contains(Disposition::FORCED),
+            hearing_impaired: disposition.contains(Disposition::HEARING_IMPAIRED),
+            audio_description: disposition.contains(Disposition::VISUAL_IMPAIRED),
+            commentary: disposition.contains(Disposition::COMMENT),
+        }
+    }
+
+    /// The name of the language of the track in English, like `Portuguese` for `pt-BR`, or the tag
+    /// itself for languages this doesn't know.
+    pub fn language_name(&self) -> Option<String> {
+        let language = self.language.as_deref()?;
+        Some(language_name(language).map_or_else(|| language.to_string(), str::to_string))
+    }
+
+    /// A name for showing the track in a menu, like `English (Dolby Digital 5.1)`,
+    /// `Director's commentary (English)` or `Spanish (SDH)`: the language or else the title or
+    /// number, followed by the title when it says more, and the format and dispositions. See
+    /// [`crate::Player::set_track_name_formatter`] for naming them differently, like in the
+    /// language of the app.
+    pub fn display_name(&self) -> String {
+        let language = self.language_name();
+        let mut details = Vec::new();
+        let name = match (language, self.title.as_deref()) {
+            (Some(language), Some(title)) if !title.eq_ignore_ascii_case(&language) => {
+                // titles like `English (SDH)` already say what the track is
+                if title.to_lowercase().contains(&language.to_lowercase()) {
+                    return title.to_string();
+                }
+                details.push(language);
+                title.to_string()
+            }
+            (Some(language), _) => language,
+            (None, Some(title)) => title.to_string(),
+            (None, None) => format!("Track {}", self.number),
+        };
+        if let Some(channels) = self.channels {
+            let format = match codec_name(&self.codec) {
+                Some(codec) => format!("{codec} {}", channel_layout_name(channels)),
+                None => channel_layout_name(channels),
+            };
+            details.push(format);
+        }
+        if self.hearing_impaired {
+            details.push("SDH".to_string());
+        }
+        if self.forced {
+            details.push("Forced".to_string());
+        }
+        if self.audio_description {
+            details.push("Audio Description".to_string());
+        }
+        if self.commentary && !name.to_lowercase().contains("commentary") {
+            details.push("Commentary".to_string());
+        }
+        if details.is_empty() {
+            name
+        } else {
+            format!("{name} ({})", details.join(", "))
+        }
+    }
+}
+
+// the name of a codec as shown on discs and streaming services, for the ones people know by name
+fn codec_name(codec: &str) -> Option<&'static str> {
+    Some(match codec {
+        "ac3" => "Dolby Digital",
+        "eac3" => "Dolby Digital Plus",
+        "truehd" => "Dolby TrueHD",
+        "dts" => "DTS",
+        "aac" => "AAC",
+        "flac" => "FLAC",
+        "opus" => "Opus",
+        "mp3" => "MP3",
+        _ => return None,
+    })
+}
+
+fn channel_layout_name(channels: u16) -> String {
+    match channels {
+        1 => "Mono".to_string(),
+        2 => "Stereo".to_string(),
+        6 => "5.1".to_string(),
+        8 => "7.1".to_string(),
+        channels => format!("{channels}ch"),
+    }
+}
+
+/// The English name of the language with the ISO 639 or BCP 47 tag `tag`, like `German` for `ger`,
+/// `deu`, `de` or `de-AT`.
+pub fn language_name(tag: &str) -> Option<&'static str> {
+    let primary = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+    Some(match primary.as_str() {
+        "en" | "eng" => "English",
+        "fr" | "fre" | "fra" => "French",
+        "de" | "ger" | "deu" => "German",
+        "es" | "spa" => "Spanish",
+        "it" | "ita" => "Italian",
+        "pt" | "por" => "Portuguese",
+        "nl" | "dut" | "nld" => "Dutch",
+        "sv" | "swe" => "Swedish",
+        "no" | "nb" | "nn" | "nor" | "nob" | "nno" => "Norwegian",
+        "da" | "dan" => "Danish",
+        "fi" | "fin" => "Finnish",
+        "is" | "ice" | "isl" => "Icelandic",
+        "pl" | "pol" => "Polish",
+        "cs" | "cze" | "ces" => "Czech",
+        "sk" | "slo" | "slk" => "Slovak",
+        "sl" | "slv" => "Slovenian",
+        "hu" | "hun" => "Hungarian",
+        "ro" | "rum" | "ron" => "Romanian",
+        "bg" | "bul" => "Bulgarian",
+        "hr" | "hrv" => "Croatian",
+        "sr" | "srp" => "Serbian",
+        "el" | "gre" | "ell" => "Greek",
+        "tr" | "tur" => "Turkish",
+        "ru" | "rus" => "Russian",
+        "uk" | "ukr" => "Ukrainian",
+        "et" | "est" => "Estonian",
+        "lv" | "lav" => "Latvian",
+        "lt" | "lit" => "Lithuanian",
+        "ca" | "cat" => "Catalan",
+        "eu" | "baq" | "eus" => "Basque",
+        "gl" | "glg" => "Galician",
+        "ar" | "ara" => "Arabic",
+        "he" | "heb" => "Hebrew",
+        "fa" | "per" | "fas" => "Persian",
+        "hi" | "hin" => "Hindi",
+        "bn" | "ben" => "Bengali",
+        "ta" | "tam" => "Tamil",
+        "te" | "tel" => "Telugu",
+        "ja" | "jpn" => "Japanese",
+        "ko" | "kor" => "Korean",
+        "zh" | "chi" | "zho" => "Chinese",
+        "th" | "tha" => "Thai",
+        "vi" | "vie" => "Vietnamese",
+        "id" | "ind" => "Indonesian",
+        "ms" | "may" | "msa" => "Malay",
+        "tl" | "fil" | "tgl" => "Filipino",
+        "la" | "lat" => "Latin",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(language: Option<&str>, title: Option<&str>) -> TrackInfo {
+        TrackInfo {
+            number: 2,
+            language: language.map(str::to_string),
+            title: title.map(str::to_string),
+            codec: "subrip".to_string(),
+            channels: None,
+            default: false,
+            forced: false,
+            hearing_impaired: false,
+            audio_description: false,
+            commentary: false,
+        }
+    }
+
+    #[test]
+    fn language_tags_are_named() {
+        let cases = [
+            ("eng", Some("English")),
+            ("de", Some("German")),
+            ("deu", Some("German")),
+            ("pt-BR", Some("Portuguese")),
+            ("zh_Hant", Some("Chinese")),
+            ("JPN", Some("Japanese")),
+            ("tlh", None),
+            ("", None),
+        ];
+        for (tag, expected) in cases {
+            assert_eq!(language_name(tag), expected, "{tag}");
+        }
+        assert_eq!(
+            track(Some("tlh"), None).language_name().as_deref(),
+            Some("tlh")
+        );
+        assert_eq!(track(None, None).language_name(), None);
+    }
+
+    #[test]
+    fn tracks_are_named_for_menus() {
+        let audio = |codec: &str, channels| TrackInfo {
+            codec: codec.to_string(),
+            channels: Some(channels),
+            ..track(Some("eng"), None)
+        };
+        let cases = [
+            (track(Some("eng"), None), "English"),
+            (track(None, None), "Track 2"),
+            (track(None, Some("Signs")), "Signs"),
+            (track(Some("eng"), Some("English")), "English"),
+            (track(Some("eng"), Some("English (SDH)")), "English (SDH)"),
+            (
+                track(Some("eng"), Some("Director's commentary")),
+                "Director's commentary (English)",
+            ),
+            (
+                TrackInfo {
+                    hearing_impaired: true,
+                    forced: true,
+                    ..track(Some("spa"), None)
+                },
+                "Spanish (SDH, Forced)",
+            ),
+            (
+                TrackInfo {
+                    commentary: true,
+                    ..track(None, Some("Commentary with the cast"))
+                },
+                "Commentary with the cast",
+            ),
+            (audio("ac3", 6), "English (Dolby Digital 5.1)"),
+            (audio("vorbis", 2), "English (Stereo)"),
+            (
+                TrackInfo {
+                    audio_description: true,
+                    ..audio("aac", 3)
+                },
+                "English (AAC 3ch, Audio Description)",
+            ),
+        ];
+        for (track, expected) in cases {
+            assert_eq!(track.display_name(), expected, "{track:?}");
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use crate::burn_in::BurnInSource;
+use crate::content_filter::{self, ContentFilter, RegionFilter};
+use crate::filter::VideoFilter;
+use crate::input::{open_input, InputOptions};
+use crate::{timestamp_to_millisec, video_frame_to_image, BurnIn, Shared};
+use anyhow::{bail, Context as _, Result};
+use egui::{ColorImage, Vec2};
+use ffmpeg::codec::{self, Parameters};
+use ffmpeg::format::{self, context::Output, Pixel};
+use ffmpeg::media::Type;
+use ffmpeg::software::scaling::{context::Context, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+use ffmpeg::{encoder, Packet, Rational};
+use std::path::Path;
+use std::thread::JoinHandle;
+
+/// Configures a transcode started with [`crate::Player::transcode`]. The defaults make a proxy
+/// of the video at 540 lines, with the audio copied as it is.
+#[derive(Clone, Debug)]
+pub struct TranscodeOptions {
+    /// The video encoder, by its ffmpeg name (like `libx264` or `mpeg4`). `None` uses the default
+    /// video codec of the output format, guessed from the file name.
+    pub video_encoder: Option<String>,
+    /// Scale the video down to at most this many lines, keeping its aspect ratio.
+    pub max_height: Option<u32>,
+    /// The video bit rate, in bits per second. `None` leaves it to the encoder.
+    pub video_bit_rate: Option<usize>,
+    /// Copy the best audio stream to the output without encoding it again.
+    pub copy_audio: bool,
+    /// Text burned into every frame, like the timecode and file name of review copies. Drawn after
+    /// scaling, so it is sized for the output.
+    pub burn_in: Vec<BurnIn>,
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        Self {
+            video_encoder: None,
+            max_height: Some(540),
+            video_bit_rate: None,
+            copy_audio: true,
+            burn_in: Vec::new(),
+        }
+    }
+}
+
+/// The input of the player transcoded.
+pub(crate) struct TranscodeInput {
+    pub(crate) path: String,
+    pub(crate) options: InputOptions,
+    pub(crate) duration_ms: i64,
+    pub(crate) filters: TranscodeFilters,
+}
+
+/// The filters of the player the video is transcoded with, so they hide the same parts of it.
+pub(crate) struct TranscodeFilters {
+    pub(crate) region_filters: Vec<RegionFilter>,
+    pub(crate) content_filters: Vec<ContentFilter>,
+    // the size of the video the region filters are in the coordinates of
+    pub(crate) video_size: Vec2,
+}
+
+impl TranscodeFilters {
+    fn changes_frames(&self) -> bool {
+        !self.region_filters.is_empty() || self.content_filters.iter().any(|f| f.changes_frames())
+    }
+
+    fn apply(&self, time_ms: i64, image: &mut ColorImage) {
+        content_filter::filter_image(&self.content_filters, time_ms, image);
+        content_filter::filter_regions(&self.region_filters, time_ms, self.video_size, image);
+    }
+}
+
+/// A transcode running in the background, see [`crate::Player::transcode`].
+pub struct Transcode {
+    progress: Shared<f32>,
+    cancelled: Shared<bool>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl Transcode {
+    pub(crate) fn start(
+        input: TranscodeInput,
+        output_path: String,
+        options: TranscodeOptions,
+    ) -> Self {
+        let progress = Shared::new(0.);
+        let cancelled = Shared::new(false);
+        let thread = {
+            let progress = progress.clone();
+            let cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                transcode(input, &output_path, &options, &progress, &cancelled)
+            })
+        };
+        Self {
+            progress,
+            cancelled,
+            thread: Some(thread),
+        }
+    }
+
+    /// How much of the input has been transcoded, in `0.0..=1.0`.
+    pub fn progress(&self) -> f32 {
+        self.progress.get()
+    }
+
+    /// Stop transcoding. The output is finished up to where it got, so it stays playable.
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    /// Whether the transcode is done, was cancelled or failed.
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Block until the transcode is done, returning its error if it failed.
+    pub fn wait(mut self) -> Result<()> {
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .map_err(|_| anyhow::anyhow!("transcode thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+// decodes, scales, filters, burns in and encodes the video stream.
+struct VideoTranscoder {
+    decoder: ffmpeg::decoder::Video,
+    scaler: Context,
+    // with filters, the scaler converts to rgba for them and this to the format of the encoder
+    filters: Option<(TranscodeFilters, Context)>,
+    burn_in: Option<VideoFilter>,
+    encoder: encoder::video::Encoder,
+    time_base: Rational,
+    output_index: usize,
+}
+
+impl VideoTranscoder {
+    fn send_packet(&mut self, packet: &Packet, output: &mut Output) -> Result<Option<i64>> {
+        self.decoder.send_packet(packet)?;
+        self.encode_decoded(output)
+    }
+
+    fn finish(&mut self, output: &mut Output) -> Result<()> {
+        self.decoder.send_eof()?;
+        self.encode_decoded(output)?;
+        self.encoder.send_eof()?;
+        self.write_encoded(output)
+    }
+
+    // returns the time of the last frame, in milliseconds
+    fn encode_decoded(&mut self, output: &mut Output) -> Result<Option<i64>> {
+        let mut decoded = Video::empty();
+        let mut time_ms = None;
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            let mut scaled = Video::empty();
+            self.scaler.run(&decoded, &mut scaled)?;
+            let pts = decoded.timestamp();
+            if let Some((filters, to_encoder)) = self.filters.as_mut() {
+                let time_ms = timestamp_to_millisec(pts.unwrap_or(0), self.time_base);
+                let mut image = video_frame_to_image(scaled);
+                filters.apply(time_ms, &mut image);
+                scaled = Video::empty();
+                to_encoder.run(&image_to_frame(&image), &mut scaled)?;
+            }
+            scaled.set_pts(pts);
+            let frames = match self.burn_in.as_mut() {
+                Some(burn_in) => burn_in.run(&scaled)?,
+                None => vec![scaled],
+            };
+            for frame in frames {
+                self.encoder.send_frame(&frame)?;
+                self.write_encoded(output)?;
+            }
+            time_ms = pts.map(|pts| timestamp_to_millisec(pts, self.time_base));
+        }
+        Ok(time_ms)
+    }
+
+    fn write_encoded(&mut self, output: &mut Output) -> Result<()> {
+        let output_time_base = output
+            .stream(self.output_index)
+            .context("no video output stream")?
+            .time_base();
+        let mut encoded = Packet::empty();
+        while self.encoder.receive_packet(&mut encoded).is_ok() {
+            encoded.set_stream(self.output_index);
+            encoded.rescale_ts(self.time_base, output_time_base);
+            encoded.write_interleaved(output)?;
+        }
+        Ok(())
+    }
+}
+
+fn image_to_frame(image: &ColorImage) -> Video {
+    let [width, height] = image.size;
+    let mut frame = Video::new(Pixel::RGBA, width as u32, height as u32);
+    let stride = frame.stride(0);
+    let data = frame.data_mut(0);
+    for (line, pixels) in image.pixels.chunks_exact(width).enumerate() {
+        let begin = line * stride;
+        data[begin..begin + width * 4].copy_from_slice(bytemuck::cast_slice(pixels));
+    }
+    frame
+}
+
+// the size of the video scaled down to at most `max_height` lines, in even numbers as most
+// encoders need them.
+fn scaled_size(width: u32, height: u32, max_height: Option<u32>) -> (u32, u32) {
+    let (width, height) = match max_height {
+        Some(max_height) if max_height < height => {
+            let scaled_width = (width as f64 * max_height as f64 / height as f64).round() as u32;
+            (scaled_width, max_height)
+        }
+        _ => (width, height),
+    };
+    ((width & !1).max(2), (height & !1).max(2))
+}
+
+fn pixel_format_name(format: Pixel) -> Result<&'static str> {
+    Ok(format
+        .descriptor()
+        .ok_or(ffmpeg::Error::InvalidData)?
+        .name())
+}
+
+fn transcode(
+    input: TranscodeInput,
+    output_path: &str,
+    options: &TranscodeOptions,
+    progress: &Shared<f32>,
+    cancelled: &Shared<bool>,
+) -> Result<()> {
+    let TranscodeInput {
+        path: input_path,
+        options: input_options,
+        duration_ms,
+        filters,
+    } = input;
+    let input_path = input_path.as_str();
+    let mut input = open_input(input_path, &input_options)?;
+    let video_stream = input
+        .streams()
+        .best(Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let video_index = video_stream.index();
+    let time_base = video_stream.time_base();
+    let decoder = codec::context::Context::from_parameters(video_stream.parameters())?
+        .decoder()
+        .video()?;
+    let frame_rate = video_stream.avg_frame_rate();
+    let burn_in_source = (!options.burn_in.is_empty()).then(|| {
+        let filename = Path::new(input_path)
+            .file_name()
+            .map_or(input_path.into(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+        // editing and camera formats keep the timecode on the stream or the container
+        let metadata = video_stream.metadata();
+        let input_metadata = input.metadata();
+        let timecode = metadata
+            .get("timecode")
+            .or_else(|| input_metadata.get("timecode"));
+        BurnInSource::new(
+            filename,
+            frame_rate,
+            timecode,
+            video_stream.start_time(),
+            time_base,
+        )
+    });
+    let audio = options
+        .copy_audio
+        .then(|| input.streams().best(Type::Audio))
+        .flatten()
+        .map(|stream| (stream.index(), stream.time_base(), stream.parameters()));
+
+    let mut output = format::output(&output_path)?;
+    let codec = match options.video_encoder.as_deref() {
+        Some(name) => encoder::find_by_name(name),
+        None => encoder::find(output.format().codec(&output_path, Type::Video)),
+    }
+    .context("video encoder not found")?;
+    let pixel_format = codec
+        .video()?
+        .formats()
+        .and_then(|mut formats| formats.next())
+        .unwrap_or(Pixel::YUV420P);
+    let (width, height) = scaled_size(decoder.width(), decoder.height(), options.max_height);
+
+    let mut video_encoder = codec::context::Context::new().encoder().video()?;
+    video_encoder.set_width(width);
+    video_encoder.set_height(height);
+    video_encoder.set_format(pixel_format);
+    video_encoder.set_time_base(time_base);
+    video_encoder.set_frame_rate(Some(frame_rate));
+    if let Some(bit_rate) = options.video_bit_rate {
+        video_encoder.set_bit_rate(bit_rate);
+    }
+    if output
+        .format()
+        .flags()
+        .contains(format::Flags::GLOBAL_HEADER)
+    {
+        video_encoder.set_flags(codec::Flags::GLOBAL_HEADER);
+    }
+    let video_encoder = video_encoder.open_as(codec)?;
+    let video_output_index = {
+        let mut stream = output.add_stream(codec)?;
+        stream.set_parameters(&video_encoder);
+        stream.index()
+    };
+    let audio = match audio {
+        Some((index, time_base, parameters)) => {
+            let mut stream = output.add_stream(encoder::find(codec::Id::None))?;
+            stream.set_parameters(parameters);
+            // the tag of the input container may not exist in the output one
+            let mut parameters: Parameters = stream.parameters();
+            unsafe { (*parameters.as_mut_ptr()).codec_tag = 0 };
+            Some((index, time_base, stream.index()))
+        }
+        None => None,
+    };
+    output.write_header()?;
+
+    let filters = match filters.changes_frames() {
+        true => {
+            let to_encoder = Context::get(
+                Pixel::RGBA,
+                width,
+                height,
+                pixel_format,
+                width,
+                height,
+                Flags::BILINEAR,
+            )?;
+            Some((filters, to_encoder))
+        }
+        false => None,
+    };
+    let scaler = Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        match filters {
+            Some(_) => Pixel::RGBA,
+            None => pixel_format,
+        },
+        width,
+        height,
+        Flags::BILINEAR,
+    )?;
+    let burn_in = match burn_in_source {
+        Some(source) => {
+            if !BurnIn::is_available() {
+                bail!("burning in text needs the drawtext filter of ffmpeg");
+            }
+            let filters = options.burn_in.iter();
+            let mut spec = filters
+                .map(|burn_in| burn_in.filter_spec(height, &source))
+                .collect::<Vec<_>>()
+                .join(",");
+            // back to the format of the encoder, drawtext may pick another one
+            spec += &format!(",format=pix_fmts={}", pixel_format_name(pixel_format)?);
+            Some(VideoFilter::new(
+                &spec,
+                pixel_format,
+                width,
+                height,
+                time_base,
+            )?)
+        }
+        None => None,
+    };
+    let mut video = VideoTranscoder {
+        decoder,
+        scaler,
+        filters,
+        burn_in,
+        encoder: video_encoder,
+        time_base,
+        output_index: video_output_index,
+    };
+    for packet in input.packets() {
+        if cancelled.get() {
+            break;
+        }
+        let (stream, mut packet) = packet?;
+        if stream.index() == video_index {
+            if let Some(time_ms) = video.send_packet(&packet, &mut output)? {
+                progress.set((time_ms as f32 / duration_ms.max(1) as f32).clamp(0., 1.));
+            }
+        } else if let Some((_, time_base, output_index)) =
+            audio.filter(|(index, _, _)| *index == stream.index())
+        {
+            let output_time_base = output
+                .stream(output_index)
+                .context("no audio output stream")?
+                .time_base();
+            packet.rescale_ts(time_base, output_time_base);
+            packet.set_position(-1);
+            packet.set_stream(output_index);
+            packet.write_interleaved(&mut output)?;
+        }
+    }
+    video.finish(&mut output)?;
+    output.write_trailer()?;
+    if !cancelled.get() {
+        progress.set(1.);
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+/// How a [`Transition`] progresses over its duration.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// At a constant rate.
+    #[default]
+    Linear,
+    /// Starting slowly.
+    EaseIn,
+    /// Ending slowly.
+    EaseOut,
+    /// Starting and ending slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// The eased progress at `t` of the transition, both in `0.0..=1.0`.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1. - (1. - t) * (1. - t),
+            Self::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+/// A fade of the video, see [`Transitions`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// How long the fade takes.
+    pub duration: Duration,
+    /// How the fade progresses.
+    pub easing: Easing,
+}
+
+impl Default for Transition {
+    /// A fade of 300 milliseconds, eased like [`Transition::new`].
+    fn default() -> Self {
+        Self::new(Duration::from_millis(300))
+    }
+}
+
+impl Transition {
+    /// A fade taking `duration`, with [`Easing::EaseInOut`].
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            easing: Easing::EaseInOut,
+        }
+    }
+
+    /// How far along the fade is after `elapsed`, eased, in `0.0..=1.0`.
+    pub(crate) fn progress(&self, elapsed: Duration) -> f32 {
+        if self.duration.is_zero() {
+            return 1.;
+        }
+        self.easing
+            .apply(elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+}
+
+/// Fades of the video drawn by the [`crate::Player`], see [`crate::PlayerOptions::transitions`].
+/// The video is drawn with less opacity, so whatever is behind it shows through.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transitions {
+    /// Fade the video in when playback starts. Until then it is hidden.
+    pub fade_in: Option<Transition>,
+    /// Fade the video out over the end of the input, in stream time: the fade follows seeks and
+    /// pauses, and plays out at the playback speed. Live inputs and ones without a known duration
+    /// don't fade out.
+    pub fade_out: Option<Transition>,
+    /// Dissolve into this player from the one it replaces, see [`crate::Player::dissolve_from`].
+    pub dissolve: Option<Transition>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_progress_eased() {
+        let fade = Transition::new(Duration::from_millis(400));
+        assert_eq!(fade.progress(Duration::ZERO), 0.);
+        assert_eq!(fade.progress(Duration::from_millis(100)), 0.15625);
+        assert_eq!(fade.progress(Duration::from_secs(1)), 1.);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Transition::new(Duration::ZERO).progress(Duration::ZERO), 1.);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::UserData;
+use std::any::Any;
+use std::time::Duration;
+
+/// An "up next" prompt counting down over the final seconds of an item, for playing the items of a
+/// playlist one after another, see [`crate::Player::set_up_next`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpNext {
+    /// The title of the next item.
+    pub title: String,
+    /// How long before the end the prompt appears and counts down.
+    pub countdown: Duration,
+    /// Draw the prompt over the video in [`crate::Player::ui`]. Apps drawing their own, or none,
+    /// turn it off and follow the events instead.
+    pub show_overlay: bool,
+    /// The data of the next item, read from [`crate::Player::up_next`] on
+    /// [`crate::PlayerEvent::PlayNext`].
+    pub user_data: Option<UserData>,
+}
+
+impl UpNext {
+    /// A prompt for the item called `title`, counting down over the last 10 seconds.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            countdown: Duration::from_secs(10),
+            show_overlay: true,
+            user_data: None,
+        }
+    }
+
+    /// Count down over the last `countdown` instead.
+    pub fn with_countdown(mut self, countdown: Duration) -> Self {
+        self.countdown = countdown;
+        self
+    }
+
+    /// Attach data of the app to the next item, like its id in the playlist.
+    pub fn with_user_data(mut self, data: impl Any + Send + Sync) -> Self {
+        self.user_data = Some(UserData::new(data));
+        self
+    }
+
+    /// Don't draw the prompt, see [`UpNext::show_overlay`].
+    pub fn without_overlay(mut self) -> Self {
+        self.show_overlay = false;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UpNextState {
+    // before the countdown
+    Waiting,
+    Counting,
+    // cancelled until playback goes back before the countdown
+    Cancelled,
+    // the next item was asked for
+    Done,
+}
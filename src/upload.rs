@@ -0,0 +1,77 @@
+use crate::content_filter::{self, ContentFilter, RegionFilter};
+use crate::mask;
+use egui::{ColorImage, Vec2};
+use std::sync::Arc;
+
+/// What the frames go through between decoding and the texture: the region filters and the clip
+/// mask. The decoding thread gets a copy from [`crate::Player`] whenever they change, to prepare
+/// the frames with, so the ui thread only hands the finished image to egui.
+#[derive(Clone, Default)]
+pub(crate) struct UploadStage {
+    // counts the changes, so frames prepared before the last one are told apart
+    pub(crate) generation: u64,
+    // the size of the video the region filters are in the coordinates of
+    pub(crate) video_size: Vec2,
+    pub(crate) region_filters: Vec<RegionFilter>,
+    pub(crate) clip_mask: Option<Arc<ColorImage>>,
+    pub(crate) filters_bypassed: bool,
+}
+
+/// A frame made ready for the texture while decoding, see [`UploadStage::prepare`].
+pub(crate) struct PreparedUpload {
+    pub(crate) generation: u64,
+    // the frame with the content filters applied
+    pub(crate) source: Arc<ColorImage>,
+    // `source` with the region filters and the clip mask applied, what the texture is set to
+    pub(crate) image: Arc<ColorImage>,
+}
+
+impl UploadStage {
+    pub(crate) fn new(video_size: Vec2) -> Self {
+        Self {
+            video_size,
+            ..Default::default()
+        }
+    }
+
+    /// `source` with the region filters (unless bypassed) and the clip mask applied, `source`
+    /// itself without either.
+    pub(crate) fn apply(&self, time_ms: i64, source: &Arc<ColorImage>) -> Arc<ColorImage> {
+        let filtered =
+            !self.filters_bypassed && self.region_filters.iter().any(|f| f.is_active(time_ms));
+        if !filtered && self.clip_mask.is_none() {
+            return source.clone();
+        }
+        let mut image = (**source).clone();
+        if filtered {
+            let (filters, video_size) = (&self.region_filters, self.video_size);
+            content_filter::filter_regions(filters, time_ms, video_size, &mut image);
+        }
+        if let Some(clip_mask) = self.clip_mask.as_ref() {
+            mask::apply_clip_mask(&mut image, clip_mask);
+        }
+        Arc::new(image)
+    }
+
+    /// Apply the `content_filters` and this stage to the decoded `image`, which is left as it is.
+    pub(crate) fn prepare(
+        &self,
+        image: &ColorImage,
+        time_ms: i64,
+        content_filters: &[ContentFilter],
+    ) -> PreparedUpload {
+        let mut source = image.clone();
+        if content_filters
+            .iter()
+            .any(|f| f.range.contains(time_ms) && f.changes_frames())
+        {
+            content_filter::filter_image(content_filters, time_ms, &mut source);
+        }
+        let source = Arc::new(source);
+        PreparedUpload {
+            generation: self.generation,
+            image: self.apply(time_ms, &source),
+            source,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use bytemuck::NoUninit;
+use ffmpeg::software::scaling::flag::Flags;
+
+/// The scaling filter for upscaling frames to the size they are drawn at, see
+/// [`crate::PlayerOptions::upscale`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, NoUninit)]
+#[repr(u8)]
+pub enum UpscaleFilter {
+    /// Bicubic interpolation. The fastest of the three.
+    Bicubic,
+    /// Lanczos resampling. Sharp, with slight ringing at hard edges.
+    #[default]
+    Lanczos,
+    /// Spline interpolation. About as sharp as lanczos, with less ringing.
+    Spline,
+}
+
+impl UpscaleFilter {
+    pub(crate) fn flags(&self) -> Flags {
+        match self {
+            Self::Bicubic => Flags::BICUBIC,
+            Self::Lanczos => Flags::LANCZOS,
+            Self::Spline => Flags::SPLINE,
+        }
+    }
+}
+
+// frames are only upscaled when drawn at least this much larger, below that the texture filtering
+// looks the same
+const MIN_UPSCALE: f64 = 1.25;
+// and at most by this much, as the decoded frames waiting to be shown grow with it
+const MAX_UPSCALE: f64 = 2.;
+
+/// The size to upscale a `width` by `height` frame to, drawn `drawn_size` pixels wide on its
+/// longer side. `None` when it isn't drawn much larger.
+pub(crate) fn upscaled_size(width: u32, height: u32, drawn_size: u32) -> Option<(u32, u32)> {
+    let longer_side = width.max(height) as f64;
+    let scale = (drawn_size as f64 / longer_side).min(MAX_UPSCALE);
+    (longer_side > 0. && scale >= MIN_UPSCALE).then(|| {
+        (
+            (width as f64 * scale).round() as u32,
+            (height as f64 * scale).round() as u32,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_frames_drawn_much_larger_are_upscaled() {
+        assert_eq!(upscaled_size(640, 360, 1280), Some((1280, 720)));
+        assert_eq!(upscaled_size(640, 360, 700), None);
+        // at most twice as large
+        assert_eq!(upscaled_size(640, 360, 3840), Some((1280, 720)));
+        assert_eq!(upscaled_size(0, 0, 1280), None);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::PlayerEvent;
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+/// Data of the app attached to a player or an up next item, like the id of the domain object it
+/// plays, so events can be told apart without a map from players to them, see
+/// [`crate::Player::set_user_data`]. Cloning it shares the data.
+#[derive(Clone)]
+pub struct UserData(Arc<dyn Any + Send + Sync>);
+
+impl UserData {
+    /// Wrap `data`.
+    pub fn new(data: impl Any + Send + Sync) -> Self {
+        Self(Arc::new(data))
+    }
+
+    /// The data, if it is a `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+impl fmt::Debug for UserData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UserData(..)")
+    }
+}
+
+/// Equal if it is the same data, not a copy of it.
+impl PartialEq for UserData {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+/// An event along with the user data of the player that emitted it, see
+/// [`crate::Player::tagged_events`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaggedEvent {
+    /// The event.
+    pub event: PlayerEvent,
+    /// The data set with [`crate::Player::set_user_data`] when the event was drained.
+    pub user_data: Option<UserData>,
+}
+
+impl TaggedEvent {
+    /// The user data, if it is a `T`.
+    pub fn user_data<T: Any>(&self) -> Option<&T> {
+        self.user_data.as_ref().and_then(|data| data.get())
+    }
+}
@@ -0,0 +1,323 @@
+//! Menus for the common player settings, driven by the state of a [`Player`]. Enabled with the
+//! `widgets` feature.
+
+use crate::{PerformanceProfile, Player};
+use egui::{Color32, CornerRadius, FontId, PopupCloseBehavior, Response, Slider, Ui, Widget};
+#[cfg(any(feature = "audio", feature = "subtitles"))]
+use ffmpeg::media::Type;
+
+// the speeds offered by `SettingsMenu`
+const SPEEDS: [f32; 7] = [0.25, 0.5, 0.75, 1., 1.25, 1.5, 2.];
+
+/// The look of the widgets in [`crate::widgets`]. The defaults match the player controls.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetStyle {
+    /// The background of the buttons and menus.
+    pub background: Color32,
+    /// The color of the text and icons.
+    pub text: Color32,
+    /// The color of the selected entries and the filled part of the volume slider.
+    pub accent: Color32,
+    /// The corner radius of the buttons and menus.
+    pub corner_radius: CornerRadius,
+    /// The font of the text and icons.
+    pub font: FontId,
+}
+
+impl Default for WidgetStyle {
+    fn default() -> Self {
+        Self {
+            background: Color32::from_black_alpha(180),
+            text: Color32::WHITE,
+            accent: Color32::from_rgb(220, 220, 220),
+            corner_radius: CornerRadius::same(5),
+            font: FontId::proportional(16.),
+        }
+    }
+}
+
+impl WidgetStyle {
+    fn apply(&self, ui: &mut Ui) {
+        ui.style_mut().override_font_id = Some(self.font.clone());
+        let visuals = ui.visuals_mut();
+        visuals.override_text_color = Some(self.text);
+        visuals.window_fill = self.background;
+        visuals.panel_fill = self.background;
+        visuals.window_corner_radius = self.corner_radius;
+        visuals.menu_corner_radius = self.corner_radius;
+        visuals.selection.bg_fill = self.accent.gamma_multiply(0.5);
+        visuals.slider_trailing_fill = true;
+        for widget in [
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+        ] {
+            widget.weak_bg_fill = self.background;
+            widget.corner_radius = self.corner_radius;
+        }
+        visuals.widgets.hovered.weak_bg_fill = self.accent.gamma_multiply(0.3);
+    }
+}
+
+/// A speaker button opening a volume slider with a mute toggle.
+///
+/// ```ignore
+/// ui.add(VolumePopup::new(&mut player));
+/// ```
+pub struct VolumePopup<'a> {
+    player: &'a mut Player,
+    style: WidgetStyle,
+}
+
+impl<'a> VolumePopup<'a> {
+    /// A volume popup for `player`.
+    pub fn new(player: &'a mut Player) -> Self {
+        Self {
+            player,
+            style: WidgetStyle::default(),
+        }
+    }
+
+    /// Draw it with `style`.
+    pub fn style(mut self, style: WidgetStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Widget for VolumePopup<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let options = &mut self.player.options;
+        let volume_frac = options.audio_volume.get() / options.max_audio_volume;
+        let icon = if volume_frac > 0.7 {
+            "🔊"
+        } else if volume_frac > 0.4 {
+            "🔉"
+        } else if volume_frac > 0. {
+            "🔈"
+        } else {
+            "🔇"
+        };
+        ui.scope(|ui| {
+            self.style.apply(ui);
+            let response = ui.button(icon);
+            let popup_id = response.id.with("volume_popup");
+            if response.clicked() {
+                ui.memory_mut(|m| m.toggle_popup(popup_id));
+            }
+            egui::popup_below_widget(
+                ui,
+                popup_id,
+                &response,
+                PopupCloseBehavior::CloseOnClickOutside,
+                |ui| {
+                    self.style.apply(ui);
+                    let mut volume = options.audio_volume.get();
+                    let slider = Slider::new(&mut volume, 0.0..=options.max_audio_volume)
+                        .vertical()
+                        .show_value(false);
+                    if ui.add(slider).changed() {
+                        options.set_audio_volume(volume);
+                    }
+                    // the volume before muting, restored when unmuting
+                    let unmuted_id = popup_id.with("unmuted_volume");
+                    let muted = options.audio_volume.get() == 0.;
+                    if ui.selectable_label(muted, "mute").clicked() {
+                        if muted {
+                            let volume = ui
+                                .data(|d| d.get_temp::<f32>(unmuted_id))
+                                .filter(|volume| *volume > 0.)
+                                .unwrap_or(options.max_audio_volume / 2.);
+                            options.set_audio_volume(volume);
+                        } else {
+                            ui.data_mut(|d| d.insert_temp(unmuted_id, options.audio_volume.get()));
+                            options.audio_volume.set(0.);
+                        }
+                    }
+                },
+            );
+            response
+        })
+        .inner
+    }
+}
+
+/// A menu for choosing the audio track, and the subtitle track or file or turning subtitles off.
+pub struct TrackMenu<'a> {
+    player: &'a mut Player,
+    style: WidgetStyle,
+}
+
+impl<'a> TrackMenu<'a> {
+    /// A track menu for `player`.
+    pub fn new(player: &'a mut Player) -> Self {
+        Self {
+            player,
+            style: WidgetStyle::default(),
+        }
+    }
+
+    /// Draw it with `style`.
+    pub fn style(mut self, style: WidgetStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Widget for TrackMenu<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        #[cfg_attr(
+            not(any(feature = "audio", feature = "subtitles")),
+            allow(unused_variables)
+        )]
+        let player = self.player;
+        ui.scope(|ui| {
+            self.style.apply(ui);
+            ui.menu_button("💬", |ui| {
+                self.style.apply(ui);
+                #[cfg(feature = "audio")]
+                if player.audio_stream_info.is_cyclable() {
+                    ui.label("audio");
+                    for track in 1..=player.audio_stream_info.total_streams {
+                        let selected = player.audio_stream_info.current_stream == track;
+                        let label = track_label(player, Type::Audio, track);
+                        if ui.selectable_label(selected, label).clicked() {
+                            player.select_audio_stream(track);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                }
+                #[cfg(feature = "subtitles")]
+                subtitle_entries(ui, player);
+            })
+            .response
+        })
+        .inner
+    }
+}
+
+// the off entry, the subtitle streams of the input and the subtitle files
+#[cfg(feature = "subtitles")]
+fn subtitle_entries(ui: &mut Ui, player: &mut Player) {
+    ui.label("subtitles");
+    let enabled = player.subtitles_enabled();
+    if ui.selectable_label(!enabled, "off").clicked() {
+        player.set_subtitles_enabled(false);
+        ui.close_menu();
+    }
+    let from_file = player.selected_subtitle_file();
+    if player.subtitle_streamer.is_some() && from_file.is_none() {
+        for track in 1..=player.subtitle_stream_info.total_streams {
+            let selected = enabled && player.subtitle_stream_info.current_stream == track;
+            let label = track_label(player, Type::Subtitle, track);
+            if ui.selectable_label(selected, label).clicked() {
+                player.select_subtitle_stream(track);
+                player.set_subtitles_enabled(true);
+                ui.close_menu();
+            }
+        }
+    }
+    let files: Vec<String> = player.subtitle_files().map(file_name).collect();
+    for (index, name) in files.into_iter().enumerate() {
+        let selected = enabled && from_file == Some(index);
+        if ui.selectable_label(selected, name).clicked() {
+            if player.select_subtitle_file(index).is_ok() {
+                player.set_subtitles_enabled(true);
+            }
+            ui.close_menu();
+        }
+    }
+}
+
+// the name of the track numbered `track`, see `Player::track_name`
+#[cfg(any(feature = "audio", feature = "subtitles"))]
+fn track_label(player: &Player, stream_type: Type, track: usize) -> String {
+    let tracks = match stream_type {
+        Type::Audio => player.audio_tracks(),
+        _ => player.subtitle_tracks(),
+    };
+    match tracks.get(track - 1) {
+        Some(info) => player.track_name(info),
+        None => format!("Track {track}"),
+    }
+}
+
+#[cfg(feature = "subtitles")]
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map_or(path.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// A gear menu for the playback speed, the [`PerformanceProfile`] and showing subtitles.
+pub struct SettingsMenu<'a> {
+    player: &'a mut Player,
+    style: WidgetStyle,
+}
+
+impl<'a> SettingsMenu<'a> {
+    /// A settings menu for `player`.
+    pub fn new(player: &'a mut Player) -> Self {
+        Self {
+            player,
+            style: WidgetStyle::default(),
+        }
+    }
+
+    /// Draw it with `style`.
+    pub fn style(mut self, style: WidgetStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Widget for SettingsMenu<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let player = self.player;
+        let style = &self.style;
+        ui.scope(|ui| {
+            style.apply(ui);
+            ui.menu_button("⚙", |ui| {
+                style.apply(ui);
+                ui.menu_button("speed", |ui| {
+                    style.apply(ui);
+                    let current = player.playback_speed();
+                    for speed in SPEEDS {
+                        let selected = (current - speed).abs() < 0.01;
+                        if ui.selectable_label(selected, format!("{speed}x")).clicked() {
+                            player.set_playback_speed(speed);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.menu_button("quality", |ui| {
+                    style.apply(ui);
+                    let current = player.performance_profile();
+                    for (profile, label) in [
+                        (PerformanceProfile::Low, "low"),
+                        (PerformanceProfile::Medium, "medium"),
+                        (PerformanceProfile::High, "high"),
+                    ] {
+                        if ui
+                            .selectable_label(current == Some(profile), label)
+                            .clicked()
+                        {
+                            player.set_performance_profile(profile);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                #[cfg(feature = "subtitles")]
+                {
+                    let mut subtitles = player.subtitles_enabled();
+                    if ui.checkbox(&mut subtitles, "subtitles").changed() {
+                        player.set_subtitles_enabled(subtitles);
+                    }
+                }
+            })
+            .response
+        })
+        .inner
+    }
+}